@@ -0,0 +1,217 @@
+//! Scales the keyboard backlight, and optionally the screen brightness, with the
+//! ambient light reported by an iio light sensor. The daemon runs this on every
+//! fan refresh.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const IIO_DEVICES: &str = "/sys/bus/iio/devices";
+const LEDS: &str = "/sys/class/leds";
+const BACKLIGHTS: &str = "/sys/class/backlight";
+/// Weight of a new reading in the smoothed light level, so a passing shadow
+/// doesn't flash the keyboard.
+const SMOOTHING: f64 = 0.3;
+
+/// Brightness (percent of the device's maximum) at an ambient light level.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LightPoint {
+    pub lux: f64,
+    pub percent: u8,
+}
+
+/// `ambient_light` in the config, globally or per profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AmbientLightConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Keyboard backlight by ambient light, sorted by `lux`.
+    #[serde(default = "default_keyboard_curve")]
+    pub keyboard: Vec<LightPoint>,
+    /// Screen brightness by ambient light; the screen is left alone without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<Vec<LightPoint>>,
+}
+
+fn default_keyboard_curve() -> Vec<LightPoint> {
+    vec![
+        LightPoint { lux: 0.0, percent: 100 },
+        LightPoint { lux: 20.0, percent: 66 },
+        LightPoint { lux: 80.0, percent: 33 },
+        LightPoint { lux: 200.0, percent: 0 },
+    ]
+}
+
+impl Default for AmbientLightConfig {
+    fn default() -> Self {
+        Self { enabled: false, keyboard: default_keyboard_curve(), screen: None }
+    }
+}
+
+/// Brightness at `lux`, interpolated between the curve's points and held at its ends.
+pub fn percent_at(curve: &[LightPoint], lux: f64) -> Option<u8> {
+    let first = curve.first()?;
+    if lux <= first.lux {
+        return Some(first.percent.min(100));
+    }
+    for pair in curve.windows(2) {
+        let (low, high) = (pair[0], pair[1]);
+        if lux <= high.lux {
+            let span = (high.lux - low.lux).max(f64::EPSILON);
+            let t = (lux - low.lux) / span;
+            let percent = low.percent as f64 + t * (high.percent as f64 - low.percent as f64);
+            return Some(percent.round().clamp(0.0, 100.0) as u8);
+        }
+    }
+    curve.last().map(|point| point.percent.min(100))
+}
+
+fn read_number(path: &Path) -> Option<f64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The first iio device with an illuminance channel.
+pub fn find_sensor() -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = fs::read_dir(IIO_DEVICES).ok()?.flatten().map(|entry| entry.path()).collect();
+    devices.sort();
+    devices
+        .into_iter()
+        .find(|device| device.join("in_illuminance_input").exists() || device.join("in_illuminance_raw").exists())
+}
+
+/// Ambient light in lux, from a processed channel if the sensor has one.
+pub fn read_lux(sensor: &Path) -> Option<f64> {
+    if let Some(lux) = read_number(&sensor.join("in_illuminance_input")) {
+        return Some(lux);
+    }
+    let raw = read_number(&sensor.join("in_illuminance_raw"))?;
+    let scale = read_number(&sensor.join("in_illuminance_scale")).unwrap_or(1.0);
+    let offset = read_number(&sensor.join("in_illuminance_offset")).unwrap_or(0.0);
+    Some(((raw + offset) * scale).max(0.0))
+}
+
+/// A sysfs brightness control (LED or backlight).
+#[derive(Debug, Clone)]
+struct Brightness {
+    dir: PathBuf,
+    max: u32,
+    /// Value last written, to notice when someone else changes it.
+    written: Option<u32>,
+    /// Target the user overrode by hand; kept until the target moves on.
+    overridden: Option<u32>,
+}
+
+impl Brightness {
+    fn open(dir: PathBuf) -> Option<Self> {
+        let max = read_number(&dir.join("max_brightness"))? as u32;
+        (max > 0).then_some(Self { dir, max, written: None, overridden: None })
+    }
+
+    fn keyboard() -> Option<Self> {
+        let mut leds: Vec<PathBuf> = fs::read_dir(LEDS).ok()?.flatten().map(|entry| entry.path()).collect();
+        leds.sort();
+        let dir = leds.into_iter().find(|led| led.file_name().is_some_and(|name| name.to_string_lossy().ends_with("::kbd_backlight")))?;
+        Self::open(dir)
+    }
+
+    /// The preferred backlight: firmware interfaces over platform ones over raw.
+    fn screen() -> Option<Self> {
+        let mut backlights: Vec<PathBuf> = fs::read_dir(BACKLIGHTS).ok()?.flatten().map(|entry| entry.path()).collect();
+        let rank = |dir: &PathBuf| match fs::read_to_string(dir.join("type")).unwrap_or_default().trim() {
+            "firmware" => 0,
+            "platform" => 1,
+            _ => 2,
+        };
+        backlights.sort_by_key(|dir| (rank(dir), dir.clone()));
+        Self::open(backlights.into_iter().next()?)
+    }
+
+    fn set_percent(&mut self, percent: u8, name: &str) {
+        let target = (percent as u32 * self.max + 50) / 100;
+        let current = read_number(&self.dir.join("brightness")).map(|value| value as u32);
+        if let (Some(current), Some(written)) = (current, self.written)
+            && current != written
+            && self.overridden.is_none()
+        {
+            log::info!("{} brightness changed by hand, leaving it until the light changes", name);
+            self.overridden = Some(target);
+        }
+        match self.overridden {
+            Some(held) if held == target => return,
+            Some(_) => self.overridden = None,
+            None => {}
+        }
+        if current == Some(target) {
+            self.written = Some(target);
+            return;
+        }
+        match fs::write(self.dir.join("brightness"), target.to_string()) {
+            Ok(()) => {
+                log::debug!("Ambient light: {} brightness {}/{}", name, target, self.max);
+                self.written = Some(target);
+            }
+            Err(e) => log::warn!("Failed to set {} brightness: {}", name, e),
+        }
+    }
+}
+
+/// State kept across daemon ticks.
+#[derive(Debug, Default)]
+pub struct AmbientLight {
+    lux: Option<f64>,
+    keyboard: Option<Brightness>,
+    screen: Option<Brightness>,
+}
+
+impl AmbientLight {
+    pub fn update(&mut self, config: &AmbientLightConfig) {
+        if !config.enabled {
+            *self = Self::default();
+            return;
+        }
+        let Some(sensor) = find_sensor() else {
+            return;
+        };
+        let Some(reading) = read_lux(&sensor) else {
+            return;
+        };
+        let lux = match self.lux {
+            Some(previous) => previous + SMOOTHING * (reading - previous),
+            None => reading,
+        };
+        self.lux = Some(lux);
+
+        if let Some(percent) = percent_at(&config.keyboard, lux) {
+            if self.keyboard.is_none() {
+                self.keyboard = Brightness::keyboard();
+            }
+            if let Some(ref mut keyboard) = self.keyboard {
+                keyboard.set_percent(percent, "Keyboard");
+            }
+        }
+        if let Some(percent) = config.screen.as_deref().and_then(|curve| percent_at(curve, lux)) {
+            if self.screen.is_none() {
+                self.screen = Brightness::screen();
+            }
+            if let Some(ref mut screen) = self.screen {
+                // Never switch the panel fully off.
+                screen.set_percent(percent.max(1), "Screen");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curve_interpolates_and_holds_at_the_ends() {
+        let curve = default_keyboard_curve();
+        assert_eq!(percent_at(&curve, 0.0), Some(100));
+        assert_eq!(percent_at(&curve, 10.0), Some(83));
+        assert_eq!(percent_at(&curve, 50.0), Some(50));
+        assert_eq!(percent_at(&curve, 5000.0), Some(0));
+        assert_eq!(percent_at(&[], 10.0), None);
+    }
+}
@@ -0,0 +1,373 @@
+use crate::config::AppConfig;
+use crate::system;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const TRIGGERS_FILE: &str = "automation-triggers.json";
+
+/// Profiles the daemon switches to automatically. Unset roles are skipped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AutomationConfig {
+    #[serde(default)]
+    pub on_ac: Option<String>,
+    #[serde(default)]
+    pub on_battery: Option<String>,
+    #[serde(default)]
+    pub on_low_battery: Option<String>,
+    /// Battery percentage at or below which `on_low_battery` takes over.
+    #[serde(default = "default_low_battery_threshold")]
+    pub low_battery_threshold: u8,
+    #[serde(default)]
+    pub while_gaming: Option<String>,
+    /// Executable names (or the second argument, e.g. Steam's `SteamLaunch`) that count as a game.
+    #[serde(default = "default_gaming_processes")]
+    pub gaming_processes: Vec<String>,
+    /// Suspends all roles without forgetting them.
+    #[serde(default)]
+    pub paused: bool,
+    /// Unix time at which a timed pause ends; `None` pauses until resumed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_until: Option<i64>,
+    /// Roles switched off without forgetting their profile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disabled: Vec<Role>,
+}
+
+fn default_low_battery_threshold() -> u8 {
+    20
+}
+
+fn default_gaming_processes() -> Vec<String> {
+    vec!["gamescope".to_string(), "SteamLaunch".to_string()]
+}
+
+impl Default for AutomationConfig {
+    fn default() -> Self {
+        Self {
+            on_ac: None,
+            on_battery: None,
+            on_low_battery: None,
+            low_battery_threshold: default_low_battery_threshold(),
+            while_gaming: None,
+            gaming_processes: default_gaming_processes(),
+            paused: false,
+            paused_until: None,
+            disabled: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Role {
+    OnAc,
+    OnBattery,
+    OnLowBattery,
+    WhileGaming,
+}
+
+impl Role {
+    /// In order of precedence, as `select` checks them.
+    pub const ALL: [Role; 4] = [Role::WhileGaming, Role::OnLowBattery, Role::OnBattery, Role::OnAc];
+
+    /// Name on the command line and in the config.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Role::OnAc => "on-ac",
+            Role::OnBattery => "on-battery",
+            Role::OnLowBattery => "on-low-battery",
+            Role::WhileGaming => "while-gaming",
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Role::ALL
+            .into_iter()
+            .find(|role| role.id() == s.to_lowercase().replace('_', "-"))
+            .ok_or_else(|| format!("Unknown rule '{}'. Use: on-ac, on-battery, on-low-battery, while-gaming", s))
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Role::OnAc => write!(f, "On AC"),
+            Role::OnBattery => write!(f, "On battery"),
+            Role::OnLowBattery => write!(f, "On low battery"),
+            Role::WhileGaming => write!(f, "While gaming"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PowerState {
+    pub on_ac: bool,
+    /// Lowest capacity of all batteries, if there are any.
+    pub battery_percent: Option<u8>,
+}
+
+impl PowerState {
+    pub fn read() -> Self {
+        let mut state = PowerState { on_ac: true, battery_percent: None };
+        let Ok(entries) = fs::read_dir("/sys/class/power_supply") else {
+            return state;
+        };
+
+        let mut saw_mains = false;
+        let mut any_online = false;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let read = |name: &str| fs::read_to_string(path.join(name)).map(|s| s.trim().to_string()).ok();
+            match read("type").as_deref() {
+                Some("Mains") => {
+                    saw_mains = true;
+                    any_online |= read("online").as_deref() == Some("1");
+                }
+                Some("Battery") => {
+                    if let Some(capacity) = read("capacity").and_then(|c| c.parse::<u8>().ok()) {
+                        state.battery_percent = Some(state.battery_percent.map_or(capacity, |p| p.min(capacity)));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Desktops and VMs without a mains supply entry are treated as plugged in.
+        state.on_ac = !saw_mains || any_online;
+        state
+    }
+}
+
+/// Parses durations such as `90s`, `45m`, `2h` or `1h30m`; a bare number is minutes.
+/// A zero duration is rejected, since it would end the pause or override as it starts.
+pub fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    if let Ok(minutes) = s.parse::<u64>()
+        && minutes > 0
+    {
+        return Ok(Duration::from_secs(minutes * 60));
+    }
+
+    let mut total = 0;
+    let mut number = String::new();
+    for c in s.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let value: u64 = number.parse().map_err(|_| format!("Invalid duration: {}", s))?;
+        total += match c {
+            'd' => value * 86400,
+            'h' => value * 3600,
+            'm' => value * 60,
+            's' => value,
+            _ => return Err(format!("Invalid duration unit '{}' in {}", c, s)),
+        };
+        number.clear();
+    }
+
+    if !number.is_empty() || total == 0 {
+        return Err(format!("Invalid duration: {} (use e.g. 30m, 2h or 1h30m)", s));
+    }
+    Ok(Duration::from_secs(total))
+}
+
+pub fn gaming_active(config: &AutomationConfig) -> bool {
+    let processes = system::running_processes();
+    config.gaming_processes.iter().any(|name| system::process_running(&processes, name))
+}
+
+impl AutomationConfig {
+    pub fn has_rules(&self) -> bool {
+        self.on_ac.is_some() || self.on_battery.is_some() || self.on_low_battery.is_some() || self.while_gaming.is_some()
+    }
+
+    pub fn pause(&mut self, duration: Option<Duration>) {
+        self.paused = true;
+        self.paused_until = duration.map(|d| chrono::Local::now().timestamp() + d.as_secs() as i64);
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.paused_until = None;
+    }
+
+    /// Whether switching is suspended right now; a timed pause lapses on its own.
+    pub fn is_paused(&self) -> bool {
+        self.paused && self.paused_until.is_none_or(|until| chrono::Local::now().timestamp() < until)
+    }
+
+    /// End of the current timed pause, formatted for display.
+    pub fn paused_until_display(&self) -> Option<String> {
+        let until = chrono::DateTime::from_timestamp(self.paused_until?, 0)?;
+        Some(until.with_timezone(&chrono::Local).format("%H:%M").to_string())
+    }
+
+    /// The configured roles and their profiles, disabled ones included.
+    pub fn rules(&self) -> Vec<(Role, &str)> {
+        Role::ALL.into_iter().filter_map(|role| Some((role, self.profile(role)?))).collect()
+    }
+
+    pub fn profile(&self, role: Role) -> Option<&str> {
+        match role {
+            Role::OnAc => self.on_ac.as_deref(),
+            Role::OnBattery => self.on_battery.as_deref(),
+            Role::OnLowBattery => self.on_low_battery.as_deref(),
+            Role::WhileGaming => self.while_gaming.as_deref(),
+        }
+    }
+
+    pub fn is_enabled(&self, role: Role) -> bool {
+        !self.disabled.contains(&role)
+    }
+
+    pub fn set_enabled(&mut self, role: Role, enabled: bool) {
+        self.disabled.retain(|r| *r != role);
+        if !enabled {
+            self.disabled.push(role);
+        }
+    }
+
+    /// When `role` applies, in words.
+    pub fn condition(&self, role: Role) -> String {
+        match role {
+            Role::OnAc => "plugged in".to_string(),
+            Role::OnBattery => "on battery".to_string(),
+            Role::OnLowBattery => format!("on battery at or below {}%", self.low_battery_threshold),
+            Role::WhileGaming => format!("while {} runs", self.gaming_processes.join(" or ")),
+        }
+    }
+
+    /// Whether `role`'s condition holds, whatever its profile and whether it is enabled.
+    pub fn matches(&self, role: Role, power: PowerState, gaming: bool) -> bool {
+        match role {
+            Role::OnAc => power.on_ac,
+            Role::OnBattery => !power.on_ac,
+            Role::OnLowBattery => !power.on_ac && power.battery_percent.is_some_and(|p| p <= self.low_battery_threshold),
+            Role::WhileGaming => gaming,
+        }
+    }
+
+    /// The role that applies right now and its profile. Gaming wins over power source,
+    /// and low battery over plain battery; disabled roles are skipped.
+    pub fn select(&self, power: PowerState, gaming: bool) -> Option<(Role, &str)> {
+        Role::ALL
+            .into_iter()
+            .filter(|role| self.is_enabled(*role) && self.matches(*role, power, gaming))
+            .find_map(|role| Some((role, self.profile(role)?)))
+    }
+
+    /// Every role with its state, for `automation list` and the GUI's Automation tab.
+    pub fn list(&self, power: PowerState, gaming: bool) -> Vec<Rule> {
+        let triggers = last_triggered();
+        Role::ALL
+            .into_iter()
+            .map(|role| Rule {
+                role,
+                profile: self.profile(role).map(str::to_string),
+                enabled: self.is_enabled(role),
+                condition: self.condition(role),
+                matches: self.matches(role, power, gaming),
+                last_triggered: triggers.get(&role).copied(),
+            })
+            .collect()
+    }
+
+    /// Forgets roles pointing at a profile that no longer exists.
+    pub fn remove_profile(&mut self, name: &str) {
+        for role in [&mut self.on_ac, &mut self.on_battery, &mut self.on_low_battery, &mut self.while_gaming] {
+            if role.as_deref() == Some(name) {
+                *role = None;
+            }
+        }
+    }
+}
+
+/// A role as `automation list` shows it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub role: Role,
+    pub profile: Option<String>,
+    pub enabled: bool,
+    pub condition: String,
+    /// Whether the condition holds right now.
+    pub matches: bool,
+    /// Unix time the role last applied its profile.
+    pub last_triggered: Option<i64>,
+}
+
+impl Rule {
+    pub fn last_triggered_display(&self) -> String {
+        self.last_triggered
+            .and_then(|at| chrono::DateTime::from_timestamp(at, 0))
+            .map(|at| at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "never".to_string())
+    }
+}
+
+/// Kept in the config directory, so the CLI and GUI see what the daemon did.
+fn triggers_path() -> Option<PathBuf> {
+    AppConfig::config_dir().ok().map(|dir| dir.join(TRIGGERS_FILE))
+}
+
+/// When each role last applied its profile.
+pub fn last_triggered() -> BTreeMap<Role, i64> {
+    triggers_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Notes that `role` applied its profile just now.
+pub fn record_trigger(role: Role) {
+    let Some(path) = triggers_path() else { return };
+    let mut triggers = last_triggered();
+    triggers.insert(role, chrono::Local::now().timestamp());
+    if let Err(e) = fs::write(&path, serde_json::to_string_pretty(&triggers).unwrap_or_default()) {
+        log::debug!("Could not record the automation trigger in {}: {}", path.display(), e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled_roles_are_skipped_but_kept() {
+        let mut config = AutomationConfig {
+            on_ac: Some("Balanced".to_string()),
+            on_battery: Some("Silent".to_string()),
+            on_low_battery: Some("Super Battery".to_string()),
+            ..AutomationConfig::default()
+        };
+        let low = PowerState { on_ac: false, battery_percent: Some(15) };
+        assert_eq!(config.select(low, false), Some((Role::OnLowBattery, "Super Battery")));
+
+        config.set_enabled(Role::OnLowBattery, false);
+        assert_eq!(config.select(low, false), Some((Role::OnBattery, "Silent")));
+        assert_eq!(config.rules().len(), 3);
+        assert!(config.matches(Role::OnLowBattery, low, false));
+        assert_eq!("while_gaming".parse::<Role>(), Ok(Role::WhileGaming));
+
+        config.set_enabled(Role::OnLowBattery, true);
+        assert!(config.disabled.is_empty());
+    }
+
+    #[test]
+    fn durations_must_be_positive() {
+        assert_eq!(parse_duration("90"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(parse_duration("45s"), Ok(Duration::from_secs(45)));
+        for zero in ["0", "0m", "0h0s", "00"] {
+            assert!(parse_duration(zero).is_err(), "{} was accepted", zero);
+        }
+        assert!(parse_duration("5x").is_err());
+    }
+}
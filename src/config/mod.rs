@@ -1,8 +1,19 @@
-use crate::fan::FanCurve;
-use crate::scenario::{ScenarioSettings, ShiftMode, UserScenario};
+use crate::ambient::AmbientLightConfig;
+use crate::automation::AutomationConfig;
+use crate::ec::{Backend, PortTiming};
+use crate::fan::critical::CriticalTemp;
+use crate::fan::emergency::EmergencyCooling;
+use crate::fan::failure::FailureDetection;
+use crate::fan::{FanCurve, ManualFanSpeed, SpeedGuard};
+use crate::hooks::ProfileHooks;
+use crate::scenario::{DeferredControl, ScenarioSettings, ShiftMode, UserScenario};
+use crate::tuned::TunedConfig;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -17,11 +28,99 @@ pub enum ConfigError {
 
 pub type Result<T> = std::result::Result<T, ConfigError>;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Profile {
     pub name: String,
     pub scenario: UserScenario,
     pub settings: ScenarioSettings,
+    #[serde(default)]
+    pub alerts: AlertOverrides,
+    #[serde(default, skip_serializing_if = "ProfileHooks::is_empty")]
+    pub hooks: ProfileHooks,
+    /// Replaces the global `ambient_light` settings while this profile is active.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ambient_light: Option<AmbientLightConfig>,
+    /// Turns Cooler Boost on while the CPU or GPU runs hot under this profile; off if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emergency_cooling: Option<EmergencyCooling>,
+    /// Named sets of fan curves to switch between, e.g. for summer and winter.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub curves: BTreeMap<String, CurvePreset>,
+    /// The preset last copied into `settings`, if it hasn't been edited since.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_curves: Option<String>,
+    /// Named curve (from the config's `fan_curves`) the CPU fan follows; changes to
+    /// that curve carry over to the profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_curve: Option<String>,
+    /// Named curve the GPU fan follows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_curve: Option<String>,
+}
+
+/// A stored pair of fan curves; a missing curve leaves that fan's curve alone.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CurvePreset {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<FanCurve>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<FanCurve>,
+}
+
+impl Profile {
+    /// What is wrong with the profile's fan curves and curve presets, one line per
+    /// rejected curve; empty if every one of them can be applied.
+    pub fn curve_problems(&self, max_points: usize, guard: &SpeedGuard) -> Vec<String> {
+        let mut curves = vec![
+            ("CPU fan curve".to_string(), self.settings.cpu_fan_curve.as_ref()),
+            ("GPU fan curve".to_string(), self.settings.gpu_fan_curve.as_ref()),
+        ];
+        for (name, preset) in &self.curves {
+            curves.push((format!("preset '{}' CPU curve", name), preset.cpu.as_ref()));
+            curves.push((format!("preset '{}' GPU curve", name), preset.gpu.as_ref()));
+        }
+        curves
+            .into_iter()
+            .filter_map(|(what, curve)| Some(format!("{}: {}", what, curve?.validate_fitted(max_points, guard).err()?)))
+            .collect()
+    }
+
+    /// Stores the profile's current curves as preset `name`, replacing any with that name.
+    pub fn save_curves(&mut self, name: &str) {
+        let preset = CurvePreset { cpu: self.settings.cpu_fan_curve.clone(), gpu: self.settings.gpu_fan_curve.clone() };
+        self.curves.insert(name.to_string(), preset);
+        self.active_curves = Some(name.to_string());
+    }
+
+    /// Copies preset `name` into the profile's settings. Returns false if there is no such preset.
+    pub fn use_curves(&mut self, name: &str) -> bool {
+        let Some(preset) = self.curves.get(name) else {
+            return false;
+        };
+        if let Some(ref cpu) = preset.cpu {
+            self.settings.cpu_fan_curve = Some(cpu.clone());
+        }
+        if let Some(ref gpu) = preset.gpu {
+            self.settings.gpu_fan_curve = Some(gpu.clone());
+        }
+        if preset.cpu.is_some() {
+            self.cpu_curve = None;
+        }
+        if preset.gpu.is_some() {
+            self.gpu_curve = None;
+        }
+        self.settings.fan_mode = crate::fan::FanMode::Advanced;
+        self.settings.manual_fan_speed = None;
+        self.active_curves = Some(name.to_string());
+        true
+    }
+
+    pub fn remove_curves(&mut self, name: &str) -> bool {
+        if self.active_curves.as_deref() == Some(name) {
+            self.active_curves = None;
+        }
+        self.curves.remove(name).is_some()
+    }
 }
 
 impl Default for Profile {
@@ -30,6 +129,304 @@ impl Default for Profile {
             name: "Default".to_string(),
             scenario: UserScenario::Balanced,
             settings: ScenarioSettings::balanced(),
+            alerts: AlertOverrides::default(),
+            hooks: ProfileHooks::default(),
+            ambient_light: None,
+            emergency_cooling: None,
+            curves: BTreeMap::new(),
+            active_curves: None,
+            cpu_curve: None,
+            gpu_curve: None,
+        }
+    }
+}
+
+/// Per-profile replacements for the global alert settings; unset fields keep the global value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AlertOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub show_notifications: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temp_warning: Option<u8>,
+}
+
+/// Alert settings in effect for the active profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertSettings {
+    pub show_notifications: bool,
+    /// Warn when the CPU or GPU reaches this temperature (°C).
+    pub temp_warning: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TempLevel {
+    Normal,
+    Warm,
+    Hot,
+    Critical,
+}
+
+/// Colors of the temperature and fan speed bands, for readers who can't tell the
+/// standard green/yellow/red apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorPalette {
+    #[default]
+    Standard,
+    /// Blue to vermillion (Okabe-Ito), which red-green color blindness keeps apart.
+    Deuteranopia,
+    /// Saturated colors that also differ in brightness, for dark backgrounds.
+    HighContrast,
+}
+
+impl ColorPalette {
+    pub const ALL: [ColorPalette; 3] = [ColorPalette::Standard, ColorPalette::Deuteranopia, ColorPalette::HighContrast];
+
+    /// RGB color of a band.
+    pub fn rgb(self, level: TempLevel) -> [u8; 3] {
+        match (self, level) {
+            (ColorPalette::Standard, TempLevel::Normal) => [0, 255, 0],
+            (ColorPalette::Standard, TempLevel::Warm) => [255, 255, 0],
+            (ColorPalette::Standard, TempLevel::Hot) => [255, 165, 0],
+            (ColorPalette::Standard, TempLevel::Critical) => [255, 0, 0],
+            (ColorPalette::Deuteranopia, TempLevel::Normal) => [86, 180, 233],
+            (ColorPalette::Deuteranopia, TempLevel::Warm) => [240, 228, 66],
+            (ColorPalette::Deuteranopia, TempLevel::Hot) => [230, 159, 0],
+            (ColorPalette::Deuteranopia, TempLevel::Critical) => [213, 94, 0],
+            (ColorPalette::HighContrast, TempLevel::Normal) => [0, 255, 255],
+            (ColorPalette::HighContrast, TempLevel::Warm) => [255, 255, 0],
+            (ColorPalette::HighContrast, TempLevel::Hot) => [255, 0, 255],
+            (ColorPalette::HighContrast, TempLevel::Critical) => [255, 255, 255],
+        }
+    }
+}
+
+impl std::fmt::Display for ColorPalette {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorPalette::Standard => write!(f, "Standard"),
+            ColorPalette::Deuteranopia => write!(f, "Color-blind safe"),
+            ColorPalette::HighContrast => write!(f, "High contrast"),
+        }
+    }
+}
+
+/// How often the daemon and GUI re-read each kind of state, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollingIntervals {
+    /// CPU and GPU temperatures.
+    pub temperatures_ms: u64,
+    /// Fan RPM, fan mode, Cooler Boost and the scenario registers.
+    pub fans_ms: u64,
+    /// Feature bits such as the webcam switch and battery threshold, and the config file.
+    pub features_ms: u64,
+}
+
+impl Default for PollingIntervals {
+    fn default() -> Self {
+        Self {
+            temperatures_ms: 1000,
+            fans_ms: 2000,
+            features_ms: 10000,
+        }
+    }
+}
+
+/// Anything faster only keeps the EC busy.
+const MIN_POLL_INTERVAL_MS: u64 = 250;
+
+impl PollingIntervals {
+    fn duration(ms: u64) -> Duration {
+        Duration::from_millis(ms.max(MIN_POLL_INTERVAL_MS))
+    }
+
+    pub fn temperatures(&self) -> Duration {
+        Self::duration(self.temperatures_ms)
+    }
+
+    pub fn fans(&self) -> Duration {
+        Self::duration(self.fans_ms)
+    }
+
+    pub fn features(&self) -> Duration {
+        Self::duration(self.features_ms)
+    }
+
+    /// How long a polling loop may sleep without delaying any subsystem.
+    pub fn tick(&self) -> Duration {
+        self.temperatures().min(self.fans()).min(self.features())
+    }
+}
+
+/// Tracks when one subsystem was last polled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PollTimer {
+    last: Option<Instant>,
+}
+
+impl PollTimer {
+    /// True (and restarts the timer) once `interval` has passed since the last poll.
+    pub fn due(&mut self, interval: Duration) -> bool {
+        if self.last.is_some_and(|last| last.elapsed() < interval) {
+            return false;
+        }
+        self.last = Some(Instant::now());
+        true
+    }
+
+    /// Makes the next `due` call return true, e.g. after the user changed a setting.
+    pub fn reset(&mut self) {
+        self.last = None;
+    }
+}
+
+/// Fan curves followed by the daemon in software, for models whose firmware
+/// ignores the curve tables (the same loop as `fan run-curve`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoftwareFanControl {
+    pub enabled: bool,
+    /// Seconds between temperature readings.
+    pub interval_secs: u64,
+}
+
+impl Default for SoftwareFanControl {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 2,
+        }
+    }
+}
+
+impl SoftwareFanControl {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs.max(1))
+    }
+}
+
+/// What the daemon and the GUI do on startup when the hardware doesn't hold the
+/// active profile's settings, e.g. after another tool or a firmware reset changed them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StartupCheck {
+    /// Apply the active profile again.
+    Reapply,
+    /// Keep the hardware's settings, as the `Current (unsaved)` profile.
+    Adopt,
+    /// Ask in the GUI; the daemon only logs the difference.
+    #[default]
+    Prompt,
+    /// Don't compare.
+    Off,
+}
+
+/// What happens to the fans when the daemon or the GUI exits.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExitAction {
+    /// Leave the hardware as it is.
+    #[default]
+    Leave,
+    /// Put the fans back on automatic control and turn Cooler Boost off.
+    Auto,
+    /// Apply this profile's settings, without making it the active profile.
+    Profile(String),
+}
+
+impl std::fmt::Display for ExitAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitAction::Leave => write!(f, "Leave as is"),
+            ExitAction::Auto => write!(f, "Reset to Auto"),
+            ExitAction::Profile(name) => write!(f, "Apply {}", name),
+        }
+    }
+}
+
+/// Exit actions of the daemon and the GUI. They are set separately, as the GUI is
+/// usually closed while the daemon keeps running.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExitActions {
+    pub daemon: ExitAction,
+    pub gui: ExitAction,
+}
+
+impl ExitActions {
+    /// Leaves the hardware alone instead of applying a profile that no longer exists.
+    pub fn remove_profile(&mut self, name: &str) {
+        for action in [&mut self.daemon, &mut self.gui] {
+            if *action == ExitAction::Profile(name.to_string()) {
+                *action = ExitAction::Leave;
+            }
+        }
+    }
+}
+
+/// Profile holding settings adopted from the hardware; it goes away once another
+/// profile is activated.
+pub const CURRENT_PROFILE: &str = "Current (unsaved)";
+
+/// hwmon-style sensor files the daemon keeps up to date, for monitoring tools
+/// that only read those (see `sensors bridge`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HwmonBridge {
+    pub enabled: bool,
+    pub path: PathBuf,
+}
+
+impl Default for HwmonBridge {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            // tmpfs, so no reading outlives a reboot.
+            path: PathBuf::from("/run/msi-center/hwmon"),
+        }
+    }
+}
+
+/// Upper bounds (inclusive, °C) of the temperature color bands.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TempThresholds {
+    pub warm: u8,
+    pub hot: u8,
+    pub critical: u8,
+}
+
+impl Default for TempThresholds {
+    fn default() -> Self {
+        Self {
+            warm: 50,
+            hot: 70,
+            critical: 85,
+        }
+    }
+}
+
+impl TempThresholds {
+    /// Checks that each band starts above the one before it.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.warm >= self.hot || self.hot >= self.critical {
+            return Err(format!(
+                "warm ({}°C), hot ({}°C) and critical ({}°C) must each be higher than the one before",
+                self.warm, self.hot, self.critical
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn level(&self, temp: u8) -> TempLevel {
+        if temp <= self.warm {
+            TempLevel::Normal
+        } else if temp <= self.hot {
+            TempLevel::Warm
+        } else if temp <= self.critical {
+            TempLevel::Hot
+        } else {
+            TempLevel::Critical
         }
     }
 }
@@ -41,6 +438,91 @@ pub struct AppConfig {
     pub auto_start: bool,
     pub apply_on_boot: bool,
     pub show_notifications: bool,
+    #[serde(default)]
+    pub temp_thresholds: TempThresholds,
+    #[serde(default)]
+    pub color_palette: ColorPalette,
+    /// Temperature (°C) at which `notify-agent` warns; profiles may override it.
+    #[serde(default = "default_temp_warning")]
+    pub temp_warning: u8,
+    /// Named `status --template` formats; `{field}` placeholders are substituted.
+    #[serde(default = "default_status_templates")]
+    pub status_templates: BTreeMap<String, String>,
+    /// Also write debug logs to `msi-center.log` in the config directory.
+    #[serde(default)]
+    pub log_to_file: bool,
+    /// Controls left to other power-management tools (see `msi-center doctor`).
+    #[serde(default)]
+    pub deferred_controls: Vec<DeferredControl>,
+    #[serde(default)]
+    pub tuned: TunedConfig,
+    /// Minimum manual fan speed at high temperatures.
+    #[serde(default)]
+    pub speed_guard: SpeedGuard,
+    /// Cooler Boost is turned off again after this many minutes, as it is easily forgotten.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cooler_boost_timeout_minutes: Option<u32>,
+    /// When a fan reading 0 RPM counts as stalled.
+    #[serde(default)]
+    pub fan_failure: FailureDetection,
+    /// Temperatures at which the daemon overrides every profile to cool the machine down.
+    #[serde(default)]
+    pub critical_temp: CriticalTemp,
+    /// Profiles the daemon switches to on power source changes and while gaming.
+    #[serde(default)]
+    pub automation: AutomationConfig,
+    /// Highest fan duty (%) the user can't hear on this machine, from `fan calibrate-noise`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audible_threshold: Option<u8>,
+    #[serde(default)]
+    pub polling: PollingIntervals,
+    /// Handshake timeout and retries of the port EC backends (`/dev/port`, ioperm).
+    #[serde(default)]
+    pub ec_port: PortTiming,
+    /// EC backends to use, in order of preference; backends left out are never
+    /// tried. Empty for the default order: dev-port, ioperm, debugfs, msi-ec.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ec_backends: Vec<Backend>,
+    /// How long (ms) a register value read from the EC is reused; 0 reads every time.
+    #[serde(default = "default_ec_read_cache_ms")]
+    pub ec_read_cache_ms: u64,
+    /// Days of sensor history the daemon keeps for `history stats`; 0 records none.
+    #[serde(default = "default_history_days")]
+    pub history_days: u32,
+    /// Keyboard (and screen) brightness by ambient light, applied by the daemon.
+    #[serde(default)]
+    pub ambient_light: AmbientLightConfig,
+    #[serde(default)]
+    pub software_fan_control: SoftwareFanControl,
+    #[serde(default)]
+    pub hwmon_bridge: HwmonBridge,
+    #[serde(default)]
+    pub startup_check: StartupCheck,
+    #[serde(default)]
+    pub on_exit: ExitActions,
+    /// Custom fan curves saved by name, for `fan curve --preset` and profiles' `cpu_curve`/`gpu_curve`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fan_curves: BTreeMap<String, FanCurve>,
+}
+
+fn default_temp_warning() -> u8 {
+    90
+}
+
+fn default_ec_read_cache_ms() -> u64 {
+    250
+}
+
+fn default_history_days() -> u32 {
+    30
+}
+
+fn default_status_templates() -> BTreeMap<String, String> {
+    BTreeMap::from([
+        ("short".to_string(), "CPU {cpu_temp}°C GPU {gpu_temp}°C | {scenario}".to_string()),
+        ("fans".to_string(), "CPU {cpu_fan_rpm} RPM GPU {gpu_fan_rpm} RPM ({fan_mode})".to_string()),
+        ("mangohud".to_string(), "FAN {cpu_fan_rpm}/{gpu_fan_rpm} RPM | {shift_mode} | CB {cooler_boost}".to_string()),
+    ])
 }
 
 impl Default for AppConfig {
@@ -52,31 +534,101 @@ impl Default for AppConfig {
                     name: "Silent".to_string(),
                     scenario: UserScenario::Silent,
                     settings: ScenarioSettings::silent(),
+                    alerts: AlertOverrides {
+                        temp_warning: Some(85),
+                        ..Default::default()
+                    },
+                    hooks: ProfileHooks::default(),
+                    ambient_light: None,
+                    emergency_cooling: None,
+                    curves: BTreeMap::new(),
+                    active_curves: None,
+                    cpu_curve: None,
+                    gpu_curve: None,
                 },
                 Profile {
                     name: "Balanced".to_string(),
                     scenario: UserScenario::Balanced,
                     settings: ScenarioSettings::balanced(),
+                    alerts: AlertOverrides::default(),
+                    hooks: ProfileHooks::default(),
+                    ambient_light: None,
+                    emergency_cooling: None,
+                    curves: BTreeMap::new(),
+                    active_curves: None,
+                    cpu_curve: None,
+                    gpu_curve: None,
                 },
                 Profile {
                     name: "High Performance".to_string(),
                     scenario: UserScenario::HighPerformance,
                     settings: ScenarioSettings::high_performance(),
+                    alerts: AlertOverrides::default(),
+                    hooks: ProfileHooks::default(),
+                    ambient_light: None,
+                    emergency_cooling: None,
+                    curves: BTreeMap::new(),
+                    active_curves: None,
+                    cpu_curve: None,
+                    gpu_curve: None,
                 },
                 Profile {
                     name: "Turbo".to_string(),
                     scenario: UserScenario::Turbo,
                     settings: ScenarioSettings::turbo(),
+                    alerts: AlertOverrides {
+                        temp_warning: Some(95),
+                        ..Default::default()
+                    },
+                    hooks: ProfileHooks::default(),
+                    ambient_light: None,
+                    emergency_cooling: None,
+                    curves: BTreeMap::new(),
+                    active_curves: None,
+                    cpu_curve: None,
+                    gpu_curve: None,
                 },
                 Profile {
                     name: "Super Battery".to_string(),
                     scenario: UserScenario::SuperBattery,
                     settings: ScenarioSettings::super_battery(),
+                    alerts: AlertOverrides::default(),
+                    hooks: ProfileHooks::default(),
+                    ambient_light: None,
+                    emergency_cooling: None,
+                    curves: BTreeMap::new(),
+                    active_curves: None,
+                    cpu_curve: None,
+                    gpu_curve: None,
                 },
             ],
             auto_start: false,
             apply_on_boot: true,
             show_notifications: true,
+            temp_thresholds: TempThresholds::default(),
+            color_palette: ColorPalette::default(),
+            temp_warning: default_temp_warning(),
+            status_templates: default_status_templates(),
+            log_to_file: false,
+            deferred_controls: Vec::new(),
+            tuned: TunedConfig::default(),
+            speed_guard: SpeedGuard::default(),
+            cooler_boost_timeout_minutes: None,
+            fan_failure: FailureDetection::default(),
+            critical_temp: CriticalTemp::default(),
+            audible_threshold: None,
+            polling: PollingIntervals::default(),
+            ec_port: PortTiming::default(),
+            ec_backends: Vec::new(),
+            ec_read_cache_ms: default_ec_read_cache_ms(),
+            history_days: default_history_days(),
+            ambient_light: AmbientLightConfig::default(),
+            automation: AutomationConfig::default(),
+            software_fan_control: SoftwareFanControl::default(),
+            hwmon_bridge: HwmonBridge::default(),
+            startup_check: StartupCheck::default(),
+            on_exit: ExitActions::default(),
+            fan_curves: BTreeMap::new(),
         }
     }
 }
@@ -108,17 +660,54 @@ impl AppConfig {
         }
         
         let content = fs::read_to_string(&config_file)?;
-        let config: AppConfig = serde_json::from_str(&content)?;
+        let mut config: AppConfig = serde_json::from_str(&content)?;
+        config.resolve_named_curves();
+        if let Err(e) = config.temp_thresholds.validate() {
+            log::warn!("Ignoring temp_thresholds in {}: {}; using the defaults", config_file.display(), e);
+            config.temp_thresholds = TempThresholds::default();
+        }
         Ok(config)
     }
 
+    /// Writes the config atomically: the new content goes to a uniquely named temporary
+    /// file in the same directory which is fsynced and renamed over `config.json`, keeping
+    /// the previous version as `config.json.bak`. The unique name keeps the CLI, GUI and
+    /// daemon from clobbering each other's half-written file when they save at once.
     pub fn save(&self) -> Result<()> {
+        let config_dir = Self::config_dir()?;
         let config_file = Self::config_file()?;
         let content = serde_json::to_string_pretty(self)?;
-        fs::write(&config_file, content)?;
+
+        let mut temp_file = tempfile::Builder::new()
+            .prefix("config.json.")
+            .suffix(".tmp")
+            .tempfile_in(&config_dir)?;
+        temp_file.write_all(content.as_bytes())?;
+        temp_file.as_file().sync_all()?;
+
+        if config_file.exists() {
+            fs::copy(&config_file, config_dir.join("config.json.bak"))?;
+        }
+
+        temp_file.persist(&config_file).map_err(|e| e.error)?;
+        fs::File::open(&config_dir)?.sync_all()?;
         Ok(())
     }
 
+    /// Modification time of the config file, used to notice edits made by other processes.
+    pub fn modified_time() -> Option<SystemTime> {
+        fs::metadata(Self::config_file().ok()?).ok()?.modified().ok()
+    }
+
+    /// Looks up a status template, falling back to the built-in ones so templates added in
+    /// newer versions work with older config files.
+    pub fn status_template(&self, name: &str) -> Option<String> {
+        self.status_templates
+            .get(name)
+            .cloned()
+            .or_else(|| default_status_templates().remove(name))
+    }
+
     pub fn get_profile(&self, name: &str) -> Option<&Profile> {
         self.profiles.iter().find(|p| p.name == name)
     }
@@ -127,8 +716,140 @@ impl AppConfig {
         self.get_profile(&self.active_profile)
     }
 
+    /// Global alert settings with the active profile's overrides merged over them.
+    pub fn alert_settings(&self) -> AlertSettings {
+        let overrides = self.get_active_profile().map(|p| p.alerts.clone()).unwrap_or_default();
+        AlertSettings {
+            show_notifications: overrides.show_notifications.unwrap_or(self.show_notifications),
+            temp_warning: overrides.temp_warning.unwrap_or(self.temp_warning),
+        }
+    }
+
+    /// Ambient light settings in effect: the active profile's, or the global ones.
+    pub fn ambient_light(&self) -> &AmbientLightConfig {
+        self.get_active_profile()
+            .and_then(|p| p.ambient_light.as_ref())
+            .unwrap_or(&self.ambient_light)
+    }
+
+    /// Stores (or with `None` clears) manual fan speeds in the active profile so
+    /// `apply` restores them. Returns false if there is no active profile.
+    pub fn set_manual_fan_speed(&mut self, speed: Option<ManualFanSpeed>) -> bool {
+        let active = self.active_profile.clone();
+        let Some(profile) = self.profiles.iter_mut().find(|p| p.name == active) else {
+            return false;
+        };
+        if speed.is_some() {
+            profile.settings.fan_mode = crate::fan::FanMode::Advanced;
+        }
+        profile.settings.manual_fan_speed = speed;
+        true
+    }
+
+    /// CPU and GPU curves of the active profile, the defaults where it has none;
+    /// `None` if the profile pins manual speeds instead.
+    /// The active profile's fan floor (`min_fan_percent`), 0 if it has none.
+    pub fn min_fan_percent(&self) -> u8 {
+        self.get_active_profile().and_then(|p| p.settings.min_fan_percent).unwrap_or(0)
+    }
+
+    pub fn active_fan_curves(&self) -> Option<[FanCurve; 2]> {
+        let settings = self.get_active_profile().map(|p| &p.settings);
+        if settings.is_some_and(|s| s.manual_fan_speed.is_some()) {
+            return None;
+        }
+        let floor = self.min_fan_percent();
+        let curve = |curve: Option<&Option<FanCurve>>| curve.cloned().flatten().unwrap_or_default().floored(floor);
+        Some([curve(settings.map(|s| &s.cpu_fan_curve)), curve(settings.map(|s| &s.gpu_fan_curve))])
+    }
+
+    /// `Profile::curve_problems` of every profile and what is wrong with the named
+    /// curves, checked against this model's fan tables and the configured speed guard.
+    pub fn curve_problems(&self) -> Vec<String> {
+        let max_points = crate::ec::quirks::capabilities().curve_points as usize;
+        let named = self
+            .fan_curves
+            .iter()
+            .filter_map(|(name, curve)| Some(format!("Curve '{}': {}", name, curve.validate_fitted(max_points, &self.speed_guard).err()?)));
+        let missing = self.profiles.iter().flat_map(|profile| {
+            [&profile.cpu_curve, &profile.gpu_curve]
+                .into_iter()
+                .flatten()
+                .filter(|name| !self.fan_curves.contains_key(*name))
+                .map(move |name| format!("Profile '{}' refers to curve '{}', which doesn't exist", profile.name, name))
+        });
+        named
+            .chain(missing)
+            .chain(self.profiles.iter().flat_map(|profile| {
+                profile
+                    .curve_problems(max_points, &self.speed_guard)
+                    .into_iter()
+                    .map(move |problem| format!("Profile '{}', {}", profile.name, problem))
+            }))
+            .collect()
+    }
+
+    /// Copies the named curves into the settings of the profiles that refer to them.
+    /// References to curves that don't exist leave the profile's own curve in place.
+    pub fn resolve_named_curves(&mut self) {
+        for profile in &mut self.profiles {
+            let references = [(&profile.cpu_curve, &mut profile.settings.cpu_fan_curve), (&profile.gpu_curve, &mut profile.settings.gpu_fan_curve)];
+            for (name, curve) in references {
+                let Some(name) = name else { continue };
+                match self.fan_curves.get(name) {
+                    Some(named) => *curve = Some(named.clone()),
+                    None => log::warn!("Profile '{}' refers to unknown fan curve '{}'", profile.name, name),
+                }
+            }
+        }
+    }
+
+    /// Stores `curve` as `name`, replacing any with that name, and updates the profiles
+    /// that follow it.
+    pub fn save_named_curve(&mut self, name: &str, curve: FanCurve) {
+        self.fan_curves.insert(name.to_string(), curve);
+        self.resolve_named_curves();
+    }
+
+    /// Deletes a named curve. Profiles that followed it keep their copy of it.
+    pub fn remove_named_curve(&mut self, name: &str) -> bool {
+        for profile in &mut self.profiles {
+            for reference in [&mut profile.cpu_curve, &mut profile.gpu_curve] {
+                if reference.as_deref() == Some(name) {
+                    *reference = None;
+                }
+            }
+        }
+        self.fan_curves.remove(name).is_some()
+    }
+
+    /// Makes the fans (`cpu`, `gpu` or `both`) of profile `profile` follow named curve
+    /// `name`. Returns false if either doesn't exist.
+    pub fn follow_named_curve(&mut self, profile: &str, name: &str, cpu: bool, gpu: bool) -> bool {
+        if !self.fan_curves.contains_key(name) {
+            return false;
+        }
+        let Some(profile) = self.profiles.iter_mut().find(|p| p.name == profile) else {
+            return false;
+        };
+        if cpu {
+            profile.cpu_curve = Some(name.to_string());
+        }
+        if gpu {
+            profile.gpu_curve = Some(name.to_string());
+        }
+        profile.settings.fan_mode = crate::fan::FanMode::Advanced;
+        profile.settings.manual_fan_speed = None;
+        profile.active_curves = None;
+        self.resolve_named_curves();
+        true
+    }
+
     pub fn set_active_profile(&mut self, name: &str) -> bool {
         if self.profiles.iter().any(|p| p.name == name) {
+            if self.active_profile == CURRENT_PROFILE && name != CURRENT_PROFILE {
+                self.profiles.retain(|p| p.name != CURRENT_PROFILE);
+            }
             self.active_profile = name.to_string();
             true
         } else {
@@ -136,6 +857,14 @@ impl AppConfig {
         }
     }
 
+    /// Makes `settings`, as read from the hardware, the active profile under
+    /// [`CURRENT_PROFILE`], replacing an earlier one.
+    pub fn adopt_settings(&mut self, settings: ScenarioSettings) {
+        self.profiles.retain(|p| p.name != CURRENT_PROFILE);
+        self.profiles.push(Profile { name: CURRENT_PROFILE.to_string(), scenario: UserScenario::Custom, settings, ..Profile::default() });
+        self.active_profile = CURRENT_PROFILE.to_string();
+    }
+
     pub fn add_profile(&mut self, profile: Profile) {
         if !self.profiles.iter().any(|p| p.name == profile.name) {
             self.profiles.push(profile);
@@ -143,14 +872,15 @@ impl AppConfig {
     }
 
     pub fn remove_profile(&mut self, name: &str) -> bool {
-        if let Some(pos) = self.profiles.iter().position(|p| p.name == name) {
-            if self.profiles.len() > 1 {
-                self.profiles.remove(pos);
-                if self.active_profile == name {
-                    self.active_profile = self.profiles[0].name.clone();
-                }
-                return true;
+        if let Some(pos) = self.profiles.iter().position(|p| p.name == name)
+            && self.profiles.len() > 1 {
+            self.profiles.remove(pos);
+            self.automation.remove_profile(name);
+            self.on_exit.remove_profile(name);
+            if self.active_profile == name {
+                self.active_profile = self.profiles[0].name.clone();
             }
+            return true;
         }
         false
     }
@@ -161,16 +891,88 @@ impl AppConfig {
             fan_mode: crate::fan::FanMode::Advanced,
             cooler_boost: false,
             super_battery: false,
+            super_battery_features: BTreeMap::new(),
             cpu_fan_curve: Some(cpu_curve),
             gpu_fan_curve: Some(gpu_curve),
+            manual_fan_speed: None,
+            lid_logo: None,
+            indicator_led: Default::default(),
+            min_fan_percent: None,
         };
 
         let profile = Profile {
             name: name.to_string(),
             scenario: UserScenario::Custom,
             settings,
+            alerts: AlertOverrides::default(),
+            hooks: ProfileHooks::default(),
+            ambient_light: None,
+            emergency_cooling: None,
+            curves: BTreeMap::new(),
+            active_curves: None,
+            cpu_curve: None,
+            gpu_curve: None,
         };
 
         self.add_profile(profile);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_thresholds_must_rise() {
+        assert_eq!(TempThresholds::default().validate(), Ok(()));
+        assert!(TempThresholds { warm: 70, hot: 60, critical: 85 }.validate().is_err());
+        assert!(TempThresholds { warm: 50, hot: 85, critical: 85 }.validate().is_err());
+    }
+
+    #[test]
+    fn curve_presets_replace_only_the_curves_they_hold() {
+        let mut profile = Profile::default();
+        let balanced = profile.settings.cpu_fan_curve.clone();
+        profile.curves.insert("Summer".to_string(), CurvePreset { cpu: Some(FanCurve::performance()), gpu: None });
+
+        assert!(!profile.use_curves("Winter"));
+        assert!(profile.use_curves("Summer"));
+        assert_eq!(profile.settings.cpu_fan_curve, Some(FanCurve::performance()));
+        assert_eq!(profile.settings.fan_mode, crate::fan::FanMode::Advanced);
+        assert_eq!(profile.active_curves.as_deref(), Some("Summer"));
+
+        profile.settings.cpu_fan_curve = balanced.clone();
+        profile.save_curves("Winter");
+        assert_eq!(profile.curves["Winter"].cpu, balanced);
+        assert!(profile.remove_curves("Winter"));
+        assert_eq!(profile.active_curves, None);
+    }
+
+    #[test]
+    fn profiles_follow_named_curves() {
+        let mut config = AppConfig::default();
+        config.save_named_curve("gaming", FanCurve::performance());
+        assert!(!config.follow_named_curve("Silent", "office", true, true));
+        assert!(config.follow_named_curve("Silent", "gaming", true, false));
+        let silent = config.get_profile("Silent").unwrap();
+        assert_eq!(silent.settings.cpu_fan_curve, Some(FanCurve::performance()));
+        assert_ne!(silent.settings.gpu_fan_curve, Some(FanCurve::performance()));
+
+        // Saving over the curve carries over; deleting it leaves the last copy in place.
+        config.save_named_curve("gaming", FanCurve::silent());
+        assert_eq!(config.get_profile("Silent").unwrap().settings.cpu_fan_curve, Some(FanCurve::silent()));
+        assert!(config.remove_named_curve("gaming"));
+        let silent = config.get_profile("Silent").unwrap();
+        assert_eq!((silent.cpu_curve.as_ref(), silent.settings.cpu_fan_curve.clone()), (None, Some(FanCurve::silent())));
+    }
+
+    #[test]
+    fn exit_actions_forget_removed_profiles() {
+        let on_exit = serde_json::from_str(r#"{ "daemon": "auto", "gui": { "profile": "Silent" } }"#).unwrap();
+        let mut config = AppConfig { on_exit, ..AppConfig::default() };
+        assert_eq!(config.on_exit.gui, ExitAction::Profile("Silent".to_string()));
+
+        assert!(config.remove_profile("Silent"));
+        assert_eq!(config.on_exit, ExitActions { daemon: ExitAction::Auto, gui: ExitAction::Leave });
+    }
+}
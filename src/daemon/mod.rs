@@ -0,0 +1,1147 @@
+//! Background service exposing hardware state on the system D-Bus.
+//!
+//! The `com.msi_center_linux.Control1` interface at `/com/msi_center_linux/Control` is a
+//! stable API intended for third-party applets (KDE plasmoids, GNOME extensions). All
+//! readable values are properties that emit `org.freedesktop.DBus.Properties.PropertiesChanged`
+//! when the daemon observes a change, so clients never need to poll.
+
+use crate::ambient::AmbientLight;
+use crate::automation::{self, PowerState, Role};
+use crate::config::{AppConfig, ExitAction, HwmonBridge, PollTimer, Profile, StartupCheck};
+use crate::ec::EmbeddedController;
+use crate::fan::failure::{self, FailureWatch};
+use crate::fan::software::SoftwareCurve;
+use crate::fan::{self, FanController, FanInfo, FanMode};
+use crate::hooks;
+use crate::overrides::{Overrides, Setting};
+use crate::scenario::{DeferredControl, ScenarioError, ScenarioManager, ShiftMode, UserScenario};
+use crate::sensors;
+use crate::stats;
+use crate::system;
+use crate::travel;
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use zbus::fdo;
+use zbus::object_server::{Interface, SignalEmitter};
+use zbus::zvariant::{OwnedValue, Value};
+
+#[derive(Error, Debug)]
+pub enum DaemonError {
+    #[error("D-Bus error: {0}")]
+    DBusError(#[from] zbus::Error),
+    #[error("The daemon is not running")]
+    NotRunning,
+}
+
+pub type Result<T> = std::result::Result<T, DaemonError>;
+
+pub const BUS_NAME: &str = "com.msi_center_linux";
+pub const OBJECT_PATH: &str = "/com/msi_center_linux/Control";
+
+#[derive(Debug, Clone, Default, PartialEq)]
+struct State {
+    cpu_temp: u32,
+    gpu_temp: u32,
+    cpu_fan_rpm: u32,
+    gpu_fan_rpm: u32,
+    fan_mode: String,
+    cooler_boost: bool,
+    scenario: String,
+    shift_mode: String,
+    super_battery: bool,
+    active_profile: String,
+    stalled_fans: Vec<String>,
+    critical_temp: bool,
+    /// `None` where the model has no webcam switch.
+    webcam: Option<bool>,
+    /// `None` where the model has no battery charge limit.
+    charge_threshold: Option<u8>,
+    /// EC backend the last fan refresh went through, for `Health`.
+    backend: &'static str,
+}
+
+fn hardware_error(e: impl std::fmt::Display) {
+    log::warn!("Failed to read hardware state: {}", e);
+    record_error(&format!("Failed to read hardware state: {}", e));
+}
+
+/// A control's value, or `None` where the model lacks the control.
+fn supported<T>(read: std::result::Result<T, ScenarioError>) -> Option<T> {
+    match read {
+        Ok(value) => Some(value),
+        Err(ScenarioError::Unsupported(_)) => None,
+        Err(e) => {
+            hardware_error(e);
+            None
+        }
+    }
+}
+
+impl State {
+    fn read() -> Option<Self> {
+        let mut state = Self::default();
+        state.refresh_fans()?;
+        state.refresh_temperatures()?;
+        state.refresh_features();
+        Some(state)
+    }
+
+    fn refresh_temperatures(&mut self) -> Option<()> {
+        let ec = EmbeddedController::new().map_err(hardware_error).ok()?;
+        let (cpu_temp, gpu_temp) = FanController::new(ec).read_temperatures();
+        self.cpu_temp = cpu_temp as u32;
+        self.gpu_temp = gpu_temp as u32;
+        Some(())
+    }
+
+    /// Fan readings and the scenario registers, which change together when a
+    /// profile is applied.
+    fn refresh_fans(&mut self) -> Option<FanInfo> {
+        let ec = EmbeddedController::new().map_err(hardware_error);
+        self.backend = ec.as_ref().map_or("none", |ec| ec.backend_name());
+        let ec = ec.ok()?;
+        let mut fan_controller = FanController::new(ec);
+        let fan = fan_controller.get_fan_info().map_err(hardware_error).ok()?;
+        let scenario = ScenarioManager::new(&mut fan_controller)
+            .get_current_info()
+            .map_err(hardware_error)
+            .ok()?;
+
+        self.cpu_temp = fan.cpu_temp as u32;
+        self.gpu_temp = fan.gpu_temp as u32;
+        self.cpu_fan_rpm = fan.cpu_fan_rpm;
+        self.gpu_fan_rpm = fan.gpu_fan_rpm;
+        self.fan_mode = format!("{:?}", fan.fan_mode);
+        self.cooler_boost = fan.cooler_boost;
+        self.scenario = scenario.current_scenario.to_string();
+        self.shift_mode = scenario.shift_mode.to_string();
+        self.super_battery = scenario.super_battery;
+        Some(fan)
+    }
+
+    /// Slow-changing state: the active profile from the config file, and the
+    /// webcam switch and battery charge limit, which only change when asked to.
+    fn refresh_features(&mut self) {
+        self.active_profile = AppConfig::load().unwrap_or_default().active_profile;
+
+        let Ok(ec) = EmbeddedController::new().map_err(hardware_error) else {
+            return;
+        };
+        let mut fan_controller = FanController::new(ec);
+        let mut manager = ScenarioManager::new(&mut fan_controller);
+        self.webcam = supported(manager.webcam());
+        self.charge_threshold = supported(manager.charge_threshold());
+    }
+}
+
+/// Number of errors kept for `daemon status`.
+const RECENT_ERRORS: usize = 10;
+
+/// Operational state reported by the `Health` method.
+#[derive(Debug, Clone, Default)]
+struct Health {
+    started_at: String,
+    backend: String,
+    automation_role: Option<Role>,
+    last_applied_profile: Option<String>,
+    last_applied_at: Option<String>,
+    recent_errors: VecDeque<String>,
+}
+
+static HEALTH: Mutex<Option<Health>> = Mutex::new(None);
+static PENDING_OPERATIONS: AtomicU32 = AtomicU32::new(0);
+
+fn with_health<T>(f: impl FnOnce(&mut Health) -> T) -> T {
+    let mut health = HEALTH.lock().unwrap_or_else(PoisonError::into_inner);
+    f(health.get_or_insert_with(Health::default))
+}
+
+fn now() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn record_error(message: &str) {
+    with_health(|health| {
+        if health.recent_errors.len() == RECENT_ERRORS {
+            health.recent_errors.pop_front();
+        }
+        health.recent_errors.push_back(format!("{} {}", now(), message));
+    });
+}
+
+/// Counts a hardware-changing operation as pending for as long as it is alive.
+struct PendingOperation;
+
+impl PendingOperation {
+    fn start() -> Self {
+        PENDING_OPERATIONS.fetch_add(1, Ordering::Relaxed);
+        PendingOperation
+    }
+}
+
+impl Drop for PendingOperation {
+    fn drop(&mut self) {
+        PENDING_OPERATIONS.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Name of a shift mode as accepted by `SetShiftMode`.
+fn shift_mode_name(mode: ShiftMode) -> &'static str {
+    match mode {
+        ShiftMode::EcoSilent => "eco",
+        ShiftMode::Comfort => "comfort",
+        ShiftMode::Sport => "sport",
+        ShiftMode::Turbo => "turbo",
+    }
+}
+
+fn failed(e: impl std::fmt::Display) -> fdo::Error {
+    let message = e.to_string();
+    record_error(&message);
+    fdo::Error::Failed(message)
+}
+
+pub struct Control {
+    state: State,
+}
+
+#[zbus::interface(name = "com.msi_center_linux.Control1")]
+impl Control {
+    /// CPU temperature in °C.
+    #[zbus(property)]
+    fn cpu_temp(&self) -> u32 {
+        self.state.cpu_temp
+    }
+
+    /// GPU temperature in °C.
+    #[zbus(property)]
+    fn gpu_temp(&self) -> u32 {
+        self.state.gpu_temp
+    }
+
+    #[zbus(property)]
+    fn cpu_fan_rpm(&self) -> u32 {
+        self.state.cpu_fan_rpm
+    }
+
+    #[zbus(property)]
+    fn gpu_fan_rpm(&self) -> u32 {
+        self.state.gpu_fan_rpm
+    }
+
+    /// One of "Auto", "Silent", "Basic", "Advanced".
+    #[zbus(property)]
+    fn fan_mode(&self) -> String {
+        self.state.fan_mode.clone()
+    }
+
+    #[zbus(property)]
+    fn cooler_boost(&self) -> bool {
+        self.state.cooler_boost
+    }
+
+    #[zbus(property)]
+    fn set_cooler_boost(&mut self, enabled: bool) -> fdo::Result<()> {
+        let _pending = PendingOperation::start();
+        let mut fan_controller = FanController::new(EmbeddedController::new().map_err(failed)?);
+        fan_controller.set_cooler_boost(enabled).map_err(failed)?;
+        self.state.cooler_boost = enabled;
+        Ok(())
+    }
+
+    /// Display name of the detected scenario, e.g. "Balanced".
+    #[zbus(property)]
+    fn scenario(&self) -> String {
+        self.state.scenario.clone()
+    }
+
+    #[zbus(property)]
+    fn shift_mode(&self) -> String {
+        self.state.shift_mode.clone()
+    }
+
+    #[zbus(property)]
+    fn super_battery(&self) -> bool {
+        self.state.super_battery
+    }
+
+    #[zbus(property)]
+    fn active_profile(&self) -> String {
+        self.state.active_profile.clone()
+    }
+
+    /// Fans reading 0 RPM while their temperature is past `fan_failure.temp_threshold`.
+    #[zbus(property)]
+    fn stalled_fans(&self) -> Vec<String> {
+        self.state.stalled_fans.clone()
+    }
+
+    /// Whether the critical-temperature failsafe has the fans at 100% right now.
+    #[zbus(property)]
+    fn critical_temp(&self) -> bool {
+        self.state.critical_temp
+    }
+
+    /// Whether the webcam is switched on; false where the model has no webcam switch.
+    #[zbus(property)]
+    fn webcam(&self) -> bool {
+        self.state.webcam.unwrap_or(false)
+    }
+
+    /// Battery charge limit in percent; 0 where the model has none.
+    #[zbus(property)]
+    fn charge_threshold(&self) -> u8 {
+        self.state.charge_threshold.unwrap_or(0)
+    }
+
+    /// Scenario names accepted by `SetScenario`.
+    fn list_scenarios(&self) -> Vec<String> {
+        ScenarioManager::get_available_scenarios().iter().map(ToString::to_string).collect()
+    }
+
+    /// Accepts the same names as the CLI (silent, balanced, highperf, turbo, battery).
+    async fn set_scenario(
+        &mut self,
+        scenario: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let scenario: UserScenario = scenario.parse().map_err(fdo::Error::InvalidArgs)?;
+        let _pending = PendingOperation::start();
+        let config = AppConfig::load().unwrap_or_default();
+        // The threshold may have been calibrated since the daemon started.
+        fan::set_audible_threshold(config.audible_threshold);
+
+        let mut fan_controller = FanController::new(EmbeddedController::new().map_err(failed)?);
+        let mut manager = ScenarioManager::new(&mut fan_controller);
+        manager.set_deferred_controls(&config.deferred_controls);
+        manager.set_scenario(scenario).map_err(failed)?;
+
+        self.state.scenario = scenario.to_string();
+        self.scenario_changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// Shift modes supported by the firmware, as accepted by `SetShiftMode`.
+    fn list_shift_modes(&self) -> Vec<String> {
+        ShiftMode::available().into_iter().map(|mode| shift_mode_name(mode).to_string()).collect()
+    }
+
+    /// Accepts eco, comfort, sport or turbo; modes not in `ListShiftModes` are rejected.
+    async fn set_shift_mode(
+        &mut self,
+        mode: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let mode: ShiftMode = mode.parse().map_err(fdo::Error::InvalidArgs)?;
+        let _pending = PendingOperation::start();
+
+        let mut fan_controller = FanController::new(EmbeddedController::new().map_err(failed)?);
+        let mut manager = ScenarioManager::new(&mut fan_controller);
+        manager.set_shift_mode(mode).map_err(failed)?;
+
+        self.state.shift_mode = mode.to_string();
+        self.shift_mode_changed(&emitter).await?;
+        Ok(())
+    }
+
+    /// Flips Cooler Boost and returns the new state; meant for quick-settings toggles
+    /// and key remappers that can't keep track of the current value.
+    async fn toggle_cooler_boost(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<bool> {
+        let _pending = PendingOperation::start();
+        let mut fan_controller = FanController::new(EmbeddedController::new().map_err(failed)?);
+        let enabled = !fan_controller.get_fan_info().map_err(failed)?.cooler_boost;
+        fan_controller.set_cooler_boost(enabled).map_err(failed)?;
+
+        self.state.cooler_boost = enabled;
+        self.cooler_boost_changed(&emitter).await?;
+        Ok(enabled)
+    }
+
+    /// Switches to the next supported shift mode (wrapping around) and returns its name.
+    async fn cycle_shift_mode(
+        &mut self,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<String> {
+        let _pending = PendingOperation::start();
+        let mut fan_controller = FanController::new(EmbeddedController::new().map_err(failed)?);
+        let mut manager = ScenarioManager::new(&mut fan_controller);
+
+        let current = manager.get_current_info().map_err(failed)?.shift_mode;
+        let available = ShiftMode::available();
+        let next = available
+            .iter()
+            .position(|&mode| mode == current)
+            .map_or(available[0], |i| available[(i + 1) % available.len()]);
+        manager.set_shift_mode(next).map_err(failed)?;
+
+        self.state.shift_mode = next.to_string();
+        self.shift_mode_changed(&emitter).await?;
+        Ok(shift_mode_name(next).to_string())
+    }
+
+    fn list_profiles(&self) -> Vec<String> {
+        let config = AppConfig::load().unwrap_or_default();
+        config.profiles.into_iter().map(|p| p.name).collect()
+    }
+
+    /// Operational state for monitoring: `started_at`, `backend`, `automation_role`,
+    /// `last_applied_profile`, `last_applied_at` (s), `pending_operations` (u) and
+    /// `recent_errors` (as). Empty strings mean "none".
+    fn health(&self) -> HashMap<String, OwnedValue> {
+        let health = with_health(|health| health.clone());
+        let strings = [
+            ("started_at", health.started_at),
+            ("backend", health.backend),
+            ("automation_role", health.automation_role.map(|r| r.to_string()).unwrap_or_default()),
+            ("last_applied_profile", health.last_applied_profile.unwrap_or_default()),
+            ("last_applied_at", health.last_applied_at.unwrap_or_default()),
+        ];
+
+        let mut map: HashMap<String, OwnedValue> = strings
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), Value::from(value).try_to_owned().ok()?)))
+            .collect();
+        if let Ok(value) = Value::from(PENDING_OPERATIONS.load(Ordering::Relaxed)).try_to_owned() {
+            map.insert("pending_operations".to_string(), value);
+        }
+        let errors: Vec<String> = health.recent_errors.into_iter().collect();
+        if let Ok(value) = Value::from(errors).try_to_owned() {
+            map.insert("recent_errors".to_string(), value);
+        }
+        map
+    }
+
+    /// Makes the named profile active and applies its settings.
+    async fn apply_profile(
+        &mut self,
+        name: &str,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> fdo::Result<()> {
+        let profile = apply_profile(name).map_err(failed)?;
+        self.state.active_profile = profile.name;
+        self.active_profile_changed(&emitter).await?;
+        Ok(())
+    }
+}
+
+impl Control {
+    fn emit_changes(&self, old: &State, emitter: &SignalEmitter<'_>) -> zbus::Result<()> {
+        let new = &self.state;
+        zbus::block_on(async {
+            if old.cpu_temp != new.cpu_temp {
+                self.cpu_temp_changed(emitter).await?;
+            }
+            if old.gpu_temp != new.gpu_temp {
+                self.gpu_temp_changed(emitter).await?;
+            }
+            if old.cpu_fan_rpm != new.cpu_fan_rpm {
+                self.cpu_fan_rpm_changed(emitter).await?;
+            }
+            if old.gpu_fan_rpm != new.gpu_fan_rpm {
+                self.gpu_fan_rpm_changed(emitter).await?;
+            }
+            if old.fan_mode != new.fan_mode {
+                self.fan_mode_changed(emitter).await?;
+            }
+            if old.cooler_boost != new.cooler_boost {
+                self.cooler_boost_changed(emitter).await?;
+            }
+            if old.scenario != new.scenario {
+                self.scenario_changed(emitter).await?;
+            }
+            if old.shift_mode != new.shift_mode {
+                self.shift_mode_changed(emitter).await?;
+            }
+            if old.super_battery != new.super_battery {
+                self.super_battery_changed(emitter).await?;
+            }
+            if old.active_profile != new.active_profile {
+                self.active_profile_changed(emitter).await?;
+            }
+            if old.stalled_fans != new.stalled_fans {
+                self.stalled_fans_changed(emitter).await?;
+            }
+            if old.critical_temp != new.critical_temp {
+                self.critical_temp_changed(emitter).await?;
+            }
+            if old.webcam != new.webcam {
+                self.webcam_changed(emitter).await?;
+            }
+            if old.charge_threshold != new.charge_threshold {
+                self.charge_threshold_changed(emitter).await?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Applies a profile with its hooks and makes it the active one.
+fn apply_profile(name: &str) -> std::result::Result<Profile, Box<dyn std::error::Error>> {
+    let _pending = PendingOperation::start();
+    let mut config = AppConfig::load()?;
+    let Some(profile) = config.get_profile(name).cloned() else {
+        return Err(format!("Profile '{}' not found", name).into());
+    };
+
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    fan_controller.set_speed_guard(config.speed_guard);
+    let mut manager = ScenarioManager::new(&mut fan_controller);
+    manager.set_deferred_controls(&config.deferred_controls);
+    let previous = config.get_active_profile().cloned();
+    let mut settings = profile.settings.clone();
+    Overrides::load().apply_to(&mut settings);
+    hooks::with_profile_hooks(&profile, previous.as_ref(), || -> std::result::Result<(), Box<dyn std::error::Error>> {
+        Ok(manager.apply_settings(&settings)?)
+    })?;
+
+    config.set_active_profile(name);
+    config.save()?;
+    with_health(|health| {
+        health.last_applied_profile = Some(profile.name.clone());
+        health.last_applied_at = Some(now());
+    });
+    Ok(profile)
+}
+
+/// Compares the hardware with the active profile (and any overrides) once on
+/// startup, and handles a difference as `startup_check` says.
+fn check_startup_consistency() {
+    let mut config = AppConfig::load().unwrap_or_default();
+    // Travel mode departs from the profile on purpose.
+    if config.startup_check == StartupCheck::Off || travel::is_on() {
+        return;
+    }
+    let Some(profile) = config.get_active_profile().cloned() else {
+        return;
+    };
+
+    let mut settings = profile.settings.clone();
+    Overrides::load().apply_to(&mut settings);
+    let checked = EmbeddedController::new().map_err(|e| e.to_string()).and_then(|ec| {
+        let mut fan_controller = FanController::new(ec);
+        let mut manager = ScenarioManager::new(&mut fan_controller);
+        manager.set_deferred_controls(&config.deferred_controls);
+        let mismatches = manager.compare(&settings).map_err(|e| e.to_string())?;
+        let hardware = manager.hardware_settings(&profile.settings).map_err(|e| e.to_string())?;
+        Ok((mismatches, hardware))
+    });
+    let (mismatches, hardware) = match checked {
+        Ok(checked) => checked,
+        Err(e) => {
+            log::warn!("Could not compare the hardware with profile '{}': {}", profile.name, e);
+            return;
+        }
+    };
+    if mismatches.is_empty() {
+        log::debug!("Hardware matches profile '{}'", profile.name);
+        return;
+    }
+
+    let differences: Vec<String> = mismatches.iter().map(ToString::to_string).collect();
+    log::warn!("The hardware doesn't match profile '{}': {}", profile.name, differences.join("; "));
+    match config.startup_check {
+        StartupCheck::Reapply => match apply_profile(&profile.name) {
+            Ok(_) => log::info!("Applied profile '{}' again", profile.name),
+            Err(e) => {
+                log::warn!("Failed to apply profile '{}' again: {}", profile.name, e);
+                record_error(&format!("Failed to apply profile '{}' again: {}", profile.name, e));
+            }
+        },
+        StartupCheck::Adopt => {
+            config.adopt_settings(hardware);
+            match config.save() {
+                Ok(()) => log::info!("Kept the hardware's settings as profile '{}'", config.active_profile),
+                Err(e) => record_error(&format!("Failed to save the adopted settings: {}", e)),
+            }
+        }
+        StartupCheck::Prompt | StartupCheck::Off => {
+            log::info!("Leaving the hardware as it is; set startup_check to reapply or adopt to resolve this on startup")
+        }
+    }
+}
+
+/// Applies the profile of the automation role that currently matches, but only when
+/// the matching role changes, so manual switches stick until the next transition.
+fn run_automation(last: &mut Option<(Role, String)>) {
+    let config = AppConfig::load().unwrap_or_default();
+    if !config.automation.has_rules() {
+        *last = None;
+        with_health(|health| health.automation_role = None);
+        return;
+    }
+    if config.automation.is_paused() {
+        // Forget the last role so the current one is applied again on resume.
+        *last = None;
+        with_health(|health| health.automation_role = None);
+        return;
+    }
+
+    let gaming = automation::gaming_active(&config.automation);
+    let selected = config
+        .automation
+        .select(PowerState::read(), gaming)
+        .map(|(role, profile)| (role, profile.to_string()));
+    with_health(|health| health.automation_role = selected.as_ref().map(|(role, _)| *role));
+    if selected == *last {
+        return;
+    }
+    *last = selected.clone();
+
+    let Some((role, profile)) = selected else {
+        return;
+    };
+    match apply_profile(&profile) {
+        Ok(_) => {
+            log::info!("{}: applied profile {}", role, profile);
+            automation::record_trigger(role);
+        }
+        Err(e) => {
+            log::warn!("{}: failed to apply profile {}: {}", role, profile, e);
+            record_error(&format!("{}: failed to apply profile {}: {}", role, profile, e));
+        }
+    }
+}
+
+/// Keeps temporary overrides in force over whatever a profile or another tool wrote,
+/// and puts the active profile's settings back once one of them ends.
+fn enforce_overrides(last: &mut Vec<Setting>) {
+    let overrides = Overrides::load();
+    let current = overrides.settings();
+    let ended: Vec<Setting> = last.iter().filter(|setting| !current.contains(setting)).copied().collect();
+    *last = current;
+    if overrides.active().is_empty() && ended.is_empty() {
+        return;
+    }
+
+    let result = (|| -> std::result::Result<(), Box<dyn std::error::Error>> {
+        if !ended.is_empty()
+            && let Some(profile) = reapply_active_profile(&overrides)?
+        {
+            for setting in &ended {
+                log::info!("Override {} ended, restored profile {}", setting, profile);
+            }
+            return Ok(());
+        }
+        let config = AppConfig::load().unwrap_or_default();
+        let mut fan_controller = FanController::new(EmbeddedController::new()?);
+        fan_controller.set_speed_guard(config.speed_guard);
+        for o in overrides.active() {
+            if o.setting.enforce(&mut fan_controller)? {
+                log::info!("Reapplied override {}", o.setting);
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        log::warn!("Failed to enforce overrides: {}", e);
+        record_error(&format!("Failed to enforce overrides: {}", e));
+    }
+}
+
+/// Writes the active profile's settings with `overrides` on top, without its hooks.
+/// Returns the profile's name, or `None` if there is no active profile.
+fn reapply_active_profile(overrides: &Overrides) -> std::result::Result<Option<String>, Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    let Some(profile) = config.get_active_profile() else {
+        return Ok(None);
+    };
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    fan_controller.set_speed_guard(config.speed_guard);
+    let mut settings = profile.settings.clone();
+    overrides.apply_to(&mut settings);
+    let mut manager = ScenarioManager::new(&mut fan_controller);
+    manager.set_deferred_controls(&config.deferred_controls);
+    manager.apply_settings(&settings)?;
+    Ok(Some(profile.name.clone()))
+}
+
+/// GPU fan settings are skipped while the dGPU sleeps; write the active profile's
+/// once it wakes up.
+fn reapply_gpu_on_wake(was_active: &mut Option<bool>) {
+    let active = system::dgpu_active();
+    let woke = *was_active == Some(false) && active == Some(true);
+    *was_active = active;
+    if !woke {
+        return;
+    }
+
+    let config = AppConfig::load().unwrap_or_default();
+    let Some(profile) = config.get_active_profile() else {
+        return;
+    };
+    let result = (|| -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut fan_controller = FanController::new(EmbeddedController::new()?);
+        fan_controller.set_speed_guard(config.speed_guard);
+        Ok(ScenarioManager::new(&mut fan_controller).apply_gpu_settings(&profile.settings)?)
+    })();
+    match result {
+        Ok(()) => log::info!("dGPU woke up, reapplied GPU fan settings of {}", profile.name),
+        Err(e) => {
+            log::warn!("Failed to reapply GPU fan settings: {}", e);
+            record_error(&format!("Failed to reapply GPU fan settings: {}", e));
+        }
+    }
+}
+
+/// Checks for stalled fans, records them for `status`, and runs Cooler Boost while
+/// one is stalled if `fan_failure.cooler_boost` says so. Returns the stalled fans' names.
+fn watch_fans(watch: &mut FailureWatch, boosted: &mut bool, previous: &[String], info: &FanInfo) -> Vec<String> {
+    let detection = AppConfig::load().unwrap_or_default().fan_failure;
+    let stalled = watch.update(info, &detection, Instant::now());
+    for fan in stalled.iter().filter(|fan| !previous.contains(&fan.name)) {
+        log::error!("{}; it may have failed", fan);
+        record_error(&format!("{}; it may have failed", fan));
+    }
+    failure::record(&stalled);
+
+    let boost = detection.cooler_boost && !stalled.is_empty();
+    // Cooler Boost the user turned on is theirs to turn off.
+    if boost != *boosted && (*boosted || !info.cooler_boost) {
+        let result = EmbeddedController::new().map_err(fan::FanError::from).and_then(|ec| FanController::new(ec).set_cooler_boost(boost));
+        match result {
+            Ok(()) => {
+                if boost {
+                    log::warn!("Cooler Boost on to make up for the stalled fan");
+                } else {
+                    log::info!("No fan is stalled anymore, Cooler Boost off again");
+                }
+                *boosted = boost;
+            }
+            Err(e) => log::warn!("Failed to set Cooler Boost for the stalled fan: {}", e),
+        }
+    }
+    stalled.into_iter().map(|fan| fan.name).collect()
+}
+
+/// Adds the reading to the sensor history for `history stats`, and drops what is
+/// older than `history_days` once a day.
+fn record_history(recorder: &mut stats::Recorder, pruning: &mut PollTimer, info: &FanInfo) {
+    let config = AppConfig::load().unwrap_or_default();
+    if config.history_days == 0 {
+        return;
+    }
+    if pruning.due(Duration::from_secs(stats::DAY as u64))
+        && let Err(e) = stats::prune(config.history_days)
+    {
+        log::warn!("Failed to prune the sensor history: {}", e);
+    }
+    let temp_warning = config.alert_settings().temp_warning;
+    let entries = recorder.entries(info, &config.active_profile, temp_warning, Instant::now(), chrono::Local::now().timestamp());
+    if let Err(e) = stats::append(&entries) {
+        log::warn!("Failed to record sensor history: {}", e);
+    }
+}
+
+/// Turns Cooler Boost on while the active profile's `emergency_cooling` finds the
+/// CPU or GPU too hot, and off again once both have cooled down. Cooler Boost that
+/// was already on, or that someone turns off meanwhile, isn't ours; neither is
+/// Cooler Boost under an override. The boost for a stalled fan keeps it on.
+fn emergency_cooling(boosted: &mut bool, stalled_fan_boost: bool, info: &FanInfo) {
+    let config = AppConfig::load().unwrap_or_default();
+    let overridden = Overrides::load().settings().iter().any(|setting| matches!(setting, Setting::CoolerBoost(_)));
+    let rule = config.get_active_profile().and_then(|profile| profile.emergency_cooling).filter(|_| !overridden);
+    let Some(rule) = rule.filter(|_| !*boosted || info.cooler_boost) else {
+        *boosted = false;
+        return;
+    };
+    let boost = rule.wants_boost(info.cpu_temp, info.gpu_temp, *boosted);
+    if boost == *boosted || (boost && info.cooler_boost) {
+        return;
+    }
+    if !boost && stalled_fan_boost {
+        *boosted = false;
+        return;
+    }
+    let result = EmbeddedController::new().map_err(fan::FanError::from).and_then(|ec| FanController::new(ec).set_cooler_boost(boost));
+    match result {
+        Ok(()) => {
+            if boost {
+                log::warn!("CPU at {}°C, GPU at {}°C: Cooler Boost on until both are below {}°C", info.cpu_temp, info.gpu_temp, rule.off_below);
+            } else {
+                log::info!("Cooled down below {}°C, Cooler Boost off again", rule.off_below);
+            }
+            *boosted = boost;
+        }
+        Err(e) => {
+            log::warn!("Failed to set Cooler Boost for emergency cooling: {}", e);
+            record_error(&format!("Failed to set Cooler Boost for emergency cooling: {}", e));
+        }
+    }
+}
+
+/// The critical-temperature failsafe: once the CPU or GPU reaches `critical_temp`'s
+/// threshold, runs every fan at 100% and drops the shift mode to Eco, whatever the
+/// active profile or an override says, and writes them again if something turns
+/// the fans down meanwhile. Once both have recovered, the active profile is applied
+/// again and the failsafe re-arms. Returns whether it holds.
+fn critical_failsafe(tripped: bool, info: &FanInfo) -> bool {
+    let config = AppConfig::load().unwrap_or_default();
+    let critical = config.critical_temp;
+    if !critical.is_critical(info.cpu_temp, info.gpu_temp, tripped) {
+        if tripped {
+            match reapply_active_profile(&Overrides::load()) {
+                Ok(profile) => log::warn!(
+                    "Cooled down below {}°C, critical-temperature failsafe off, restored profile {}",
+                    critical.recover_below,
+                    profile.unwrap_or_default()
+                ),
+                Err(e) => {
+                    log::warn!("Failed to restore the active profile after the critical-temperature failsafe: {}", e);
+                    record_error(&format!("Failed to restore the active profile after the critical-temperature failsafe: {}", e));
+                }
+            }
+        }
+        return false;
+    }
+
+    if !tripped {
+        log::error!("CPU at {}°C, GPU at {}°C: critical temperature, fans to 100% and shift mode to Eco", info.cpu_temp, info.gpu_temp);
+        record_error(&format!("Critical temperature (CPU {}°C, GPU {}°C), fans forced to 100%", info.cpu_temp, info.gpu_temp));
+    } else if info.cooler_boost || info.fans.iter().all(|fan| fan.percent >= 100) {
+        return true;
+    }
+    let result = (|| -> std::result::Result<(), Box<dyn std::error::Error>> {
+        let mut fan_controller = FanController::new(EmbeddedController::new()?);
+        fan_controller.force_full_speed()?;
+        if config.deferred_controls.contains(&DeferredControl::ShiftMode) {
+            log::info!("Shift mode is deferred to another tool, leaving it as it is");
+        } else {
+            match ScenarioManager::new(&mut fan_controller).set_shift_mode(ShiftMode::EcoSilent) {
+                Err(ScenarioError::Unsupported(_)) => log::debug!("Shift mode is not supported here, only the fans are forced"),
+                result => result?,
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        log::error!("Critical-temperature failsafe failed: {}", e);
+        record_error(&format!("Critical-temperature failsafe failed: {}", e));
+    }
+    true
+}
+
+/// Turns Cooler Boost off once it has been on for `cooler_boost_timeout_minutes`,
+/// whoever turned it on. An override on it has its own duration, and Cooler Boost
+/// the daemon turned on itself (for a stalled fan or emergency cooling) lasts as
+/// long as its cause, so those are left alone.
+fn revert_cooler_boost(on_since: &mut Option<Instant>, daemon_boost: bool, info: &FanInfo) {
+    let timeout = AppConfig::load().unwrap_or_default().cooler_boost_timeout_minutes;
+    let overridden = Overrides::load().settings().contains(&Setting::CoolerBoost(true));
+    let Some(minutes) = timeout.filter(|_| info.cooler_boost && !daemon_boost && !overridden) else {
+        *on_since = None;
+        return;
+    };
+    let since = *on_since.get_or_insert_with(Instant::now);
+    if since.elapsed() < Duration::from_secs(minutes as u64 * 60) {
+        return;
+    }
+    let result = EmbeddedController::new().map_err(fan::FanError::from).and_then(|ec| FanController::new(ec).set_cooler_boost(false));
+    match result {
+        Ok(()) => {
+            log::info!("Cooler Boost was on for {} minutes, turned it off", minutes);
+            *on_since = None;
+        }
+        Err(e) => {
+            log::warn!("Failed to turn Cooler Boost off after {} minutes: {}", minutes, e);
+            record_error(&format!("Failed to turn Cooler Boost off after {} minutes: {}", minutes, e));
+        }
+    }
+}
+
+/// Puts the fans back on the firmware's automatic control.
+fn hand_back_fans() -> fan::Result<()> {
+    FanController::new(EmbeddedController::new()?).set_fan_mode(FanMode::Auto)
+}
+
+/// Leaves the fans as `on_exit.daemon` says on shutdown.
+fn run_exit_action() {
+    let config = AppConfig::load().unwrap_or_default();
+    let action = config.on_exit.daemon.clone();
+    let result = (|| -> std::result::Result<(), Box<dyn std::error::Error>> {
+        match &action {
+            ExitAction::Leave => {}
+            ExitAction::Auto => FanController::new(EmbeddedController::new()?).reset_to_auto()?,
+            ExitAction::Profile(name) => {
+                let Some(profile) = config.get_profile(name) else {
+                    return Err(format!("Profile '{}' not found", name).into());
+                };
+                let mut fan_controller = FanController::new(EmbeddedController::new()?);
+                fan_controller.set_speed_guard(config.speed_guard);
+                let mut manager = ScenarioManager::new(&mut fan_controller);
+                manager.set_deferred_controls(&config.deferred_controls);
+                manager.apply_settings(&profile.settings)?;
+            }
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) if action != ExitAction::Leave => log::info!("Exit action: {}", action),
+        Ok(()) => {}
+        Err(e) => log::warn!("Exit action '{}' failed: {}", action, e),
+    }
+}
+
+/// Follows the active profile's curves in software while `software_fan_control` is
+/// on, and hands the fans back to the firmware once it is turned off. Profiles with
+/// manual speeds are left alone, as applying them already wrote the duties.
+fn run_software_curve(follower: &mut Option<SoftwareCurve>) {
+    let config = AppConfig::load().unwrap_or_default();
+    let curves = config.software_fan_control.enabled.then(|| config.active_fan_curves()).flatten();
+    let Some(curves) = curves else {
+        if follower.take().is_some() && !config.software_fan_control.enabled {
+            match hand_back_fans() {
+                Ok(()) => log::info!("Software fan control stopped, fans are back on automatic control"),
+                Err(e) => {
+                    log::warn!("Failed to hand the fans back to the firmware: {}", e);
+                    record_error(&format!("Failed to hand the fans back to the firmware: {}", e));
+                }
+            }
+        }
+        return;
+    };
+
+    let follower = follower.get_or_insert_with(|| {
+        log::info!("Following the fan curves in software");
+        SoftwareCurve::new(curves.clone())
+    });
+    follower.set_curves(curves);
+    let result = EmbeddedController::new().map_err(fan::FanError::from).and_then(|ec| {
+        let mut fan_controller = FanController::new(ec);
+        fan_controller.set_speed_guard(config.speed_guard);
+        follower.step(&mut fan_controller)
+    });
+    match result {
+        Ok(step) if step.changed => log::debug!(
+            "Software curve: CPU {}°C GPU {}°C, fans at {:?}%",
+            step.cpu_temp,
+            step.gpu_temp,
+            step.speeds
+        ),
+        Ok(_) => {}
+        Err(e) => {
+            log::warn!("Failed to follow the fan curves: {}", e);
+            record_error(&format!("Failed to follow the fan curves: {}", e));
+        }
+    }
+}
+
+/// Mirrors the fan readings into hwmon-style files while `hwmon_bridge` is on, and
+/// removes them once it is turned off or pointed elsewhere.
+fn update_hwmon_bridge(bridge: &HwmonBridge, written: &mut Option<PathBuf>, info: Option<&FanInfo>) {
+    if let Some(dir) = written.take_if(|dir| !bridge.enabled || *dir != bridge.path)
+        && let Err(e) = sensors::remove_hwmon(&dir)
+    {
+        log::warn!("Failed to remove the hwmon bridge at {}: {}", dir.display(), e);
+    }
+    let Some(info) = info.filter(|_| bridge.enabled) else {
+        return;
+    };
+    match sensors::write_hwmon(&bridge.path, info) {
+        Ok(()) => *written = Some(bridge.path.clone()),
+        Err(e) => {
+            log::warn!("Failed to update the hwmon bridge at {}: {}", bridge.path.display(), e);
+            record_error(&format!("Failed to update the hwmon bridge: {}", e));
+        }
+    }
+}
+
+/// Introspection XML of the control interface, generated from the interface definition.
+pub fn introspection_xml() -> String {
+    let control = Control { state: State::default() };
+    let mut xml = String::new();
+    xml.push_str("<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\"\n");
+    xml.push_str(" \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">\n");
+    xml.push_str(&format!("<node name=\"{}\">\n", OBJECT_PATH));
+    control.introspect_to_writer(&mut xml, 2);
+    xml.push_str("</node>\n");
+    xml
+}
+
+static SHUTDOWN: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn request_shutdown(_signal: libc::c_int) {
+    SHUTDOWN.store(true, Ordering::SeqCst);
+}
+
+/// Runs the daemon: owns the bus name and refreshes the exported state at the
+/// configured polling intervals. `fans_interval` overrides the configured fan rate.
+pub fn run(fans_interval: Option<Duration>) -> Result<()> {
+    fan::install_panic_hook();
+    // Before the restored profile writes anything.
+    crate::ec::snapshot::record_baseline_if_missing();
+    let handler = request_shutdown as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGTERM, handler);
+        libc::signal(libc::SIGINT, handler);
+    }
+
+    for conflict in system::detect_conflicts() {
+        log::warn!(
+            "{} is active and manages {}; run 'msi-center doctor' for advice",
+            conflict.name,
+            conflict.manages
+        );
+    }
+
+    let state = State::read().unwrap_or_default();
+    with_health(|health| health.backend = state.backend.to_string());
+    let control = Control { state };
+    let connection = zbus::blocking::connection::Builder::system()?
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, control)?
+        .build()?;
+    log::info!("Daemon running as {} on the system bus", BUS_NAME);
+
+    let iface_ref = connection.object_server().interface::<_, Control>(OBJECT_PATH)?;
+
+    check_startup_consistency();
+
+    let mut automation_role = None;
+    let mut override_settings = Vec::new();
+    let mut ambient_light = AmbientLight::default();
+    let mut dgpu_active = system::dgpu_active();
+    with_health(|health| health.started_at = now());
+
+    let polling = |config: &AppConfig| {
+        let mut polling = config.polling;
+        if let Some(interval) = fans_interval {
+            polling.fans_ms = interval.as_millis() as u64;
+        }
+        polling
+    };
+    let config = AppConfig::load().unwrap_or_default();
+    let mut intervals = polling(&config);
+    let mut software_fan_control = config.software_fan_control;
+    let mut software_curve = None;
+    let mut hwmon_bridge = config.hwmon_bridge;
+    let mut hwmon_written: Option<PathBuf> = None;
+    let mut failure_watch = FailureWatch::default();
+    let mut failure_boost = false;
+    let mut emergency_boost = false;
+    let mut cooler_boost_since = None;
+    let mut history = stats::Recorder::default();
+    let mut history_pruning = PollTimer::default();
+    let (mut temperatures, mut fans, mut features) = (PollTimer::default(), PollTimer::default(), PollTimer::default());
+    let mut software = PollTimer::default();
+    // `State::read` above just refreshed everything.
+    temperatures.due(intervals.temperatures());
+    fans.due(intervals.fans());
+    features.due(intervals.features());
+
+    loop {
+        if software_fan_control.enabled {
+            std::thread::sleep(intervals.tick().min(software_fan_control.interval()));
+        } else {
+            std::thread::sleep(intervals.tick());
+        }
+
+        if SHUTDOWN.load(Ordering::SeqCst) {
+            log::info!("Daemon shutting down");
+            if software_curve.is_some()
+                && let Err(e) = hand_back_fans()
+            {
+                log::warn!("Failed to hand the fans back to the firmware: {}", e);
+            }
+            run_exit_action();
+            fan::restore_safe_state();
+            failure::record(&[]);
+            if let Some(dir) = hwmon_written
+                && let Err(e) = sensors::remove_hwmon(&dir)
+            {
+                log::warn!("Failed to remove the hwmon bridge at {}: {}", dir.display(), e);
+            }
+            return Ok(());
+        }
+
+        // Each group is refreshed at its own rate from `AppConfig::polling`; a fan
+        // refresh reads the temperatures too.
+        let mut state = iface_ref.get().state.clone();
+        let fans_due = fans.due(intervals.fans());
+        let temperatures_due = temperatures.due(intervals.temperatures());
+        if fans_due {
+            run_automation(&mut automation_role);
+            enforce_overrides(&mut override_settings);
+            reapply_gpu_on_wake(&mut dgpu_active);
+            ambient_light.update(AppConfig::load().unwrap_or_default().ambient_light());
+            let info = state.refresh_fans();
+            with_health(|health| health.backend = state.backend.to_string());
+            if let Some(ref info) = info {
+                state.critical_temp = critical_failsafe(state.critical_temp, info);
+                state.stalled_fans = watch_fans(&mut failure_watch, &mut failure_boost, &state.stalled_fans, info);
+                emergency_cooling(&mut emergency_boost, failure_boost, info);
+                revert_cooler_boost(&mut cooler_boost_since, failure_boost || emergency_boost || state.critical_temp, info);
+                record_history(&mut history, &mut history_pruning, info);
+            }
+            update_hwmon_bridge(&hwmon_bridge, &mut hwmon_written, info.as_ref());
+        } else if temperatures_due {
+            state.refresh_temperatures();
+        }
+        // The failsafe owns the fans while it holds.
+        if !state.critical_temp && (software_fan_control.enabled || software_curve.is_some()) && software.due(software_fan_control.interval()) {
+            run_software_curve(&mut software_curve);
+        }
+        if features.due(intervals.features()) {
+            state.refresh_features();
+            let config = AppConfig::load().unwrap_or_default();
+            intervals = polling(&config);
+            software_fan_control = config.software_fan_control;
+            hwmon_bridge = config.hwmon_bridge;
+        }
+
+        let mut iface = iface_ref.get_mut();
+        if iface.state == state {
+            continue;
+        }
+        let old = std::mem::replace(&mut iface.state, state);
+        if let Err(e) = iface.emit_changes(&old, iface_ref.signal_emitter()) {
+            log::warn!("Failed to emit property changes: {}", e);
+        }
+    }
+}
+
+/// Health report of the running daemon, as returned by its `Health` method.
+#[derive(Debug, Clone, Default)]
+pub struct HealthReport {
+    pub started_at: String,
+    pub backend: String,
+    pub automation_role: String,
+    pub last_applied_profile: String,
+    pub last_applied_at: String,
+    pub pending_operations: u32,
+    pub recent_errors: Vec<String>,
+}
+
+/// Asks the running daemon for its health; `NotRunning` if nobody owns the bus name.
+pub fn query_health() -> Result<HealthReport> {
+    let connection = zbus::blocking::Connection::system()?;
+    let reply = match connection.call_method(Some(BUS_NAME), OBJECT_PATH, Some("com.msi_center_linux.Control1"), "Health", &()) {
+        Ok(reply) => reply,
+        Err(zbus::Error::MethodError(name, _, _)) if name.as_str() == "org.freedesktop.DBus.Error.ServiceUnknown" => {
+            return Err(DaemonError::NotRunning);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let map: HashMap<String, OwnedValue> = reply.body().deserialize()?;
+
+    let string = |key: &str| map.get(key).and_then(|v| String::try_from(v.clone()).ok()).unwrap_or_default();
+    Ok(HealthReport {
+        started_at: string("started_at"),
+        backend: string("backend"),
+        automation_role: string("automation_role"),
+        last_applied_profile: string("last_applied_profile"),
+        last_applied_at: string("last_applied_at"),
+        pending_operations: map.get("pending_operations").and_then(|v| u32::try_from(v).ok()).unwrap_or(0),
+        recent_errors: map
+            .get("recent_errors")
+            .and_then(|v| Vec::<String>::try_from(v.clone()).ok())
+            .unwrap_or_default(),
+    })
+}
@@ -0,0 +1,236 @@
+//! Synthetic hardware for `msi-center-gui --demo`: temperatures follow a simulated load
+//! and the fans respond according to the active mode and curves, without touching the EC.
+
+use crate::fan::{FanCurve, FanInfo, FanMode, FanReading};
+use crate::scenario::{IndicatorSettings, ScenarioInfo, ScenarioSettings, ShiftMode, UserScenario};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+const AMBIENT_TEMP: f32 = 35.0;
+/// Time constants (seconds) of the simulated heatsink and fan spin-up.
+const THERMAL_TIME_CONSTANT: f32 = 8.0;
+const FAN_TIME_CONSTANT: f32 = 2.0;
+const MAX_RPM: f32 = 5500.0;
+/// Highest duty the EC allows in Silent fan mode.
+const SILENT_MAX_PERCENT: u8 = 60;
+
+pub struct DemoHardware {
+    started: Instant,
+    last_step: Instant,
+    cpu_temp: f32,
+    gpu_temp: f32,
+    cpu_percent: f32,
+    gpu_percent: f32,
+    fan_mode: FanMode,
+    cooler_boost: bool,
+    scenario: UserScenario,
+    shift_mode: ShiftMode,
+    super_battery: bool,
+    /// Lid logo brightness in percent; the simulated model has the LED.
+    lid_logo: u8,
+    /// The simulated model maps both indicator LED settings.
+    indicator_led: IndicatorSettings,
+    cpu_curve: FanCurve,
+    gpu_curve: FanCurve,
+    manual_speed: Option<(u8, u8)>,
+}
+
+impl DemoHardware {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        let mut demo = Self {
+            started: now,
+            last_step: now,
+            cpu_temp: 45.0,
+            gpu_temp: 42.0,
+            cpu_percent: 30.0,
+            gpu_percent: 30.0,
+            fan_mode: FanMode::Auto,
+            cooler_boost: false,
+            scenario: UserScenario::Balanced,
+            shift_mode: ShiftMode::Comfort,
+            super_battery: false,
+            lid_logo: 100,
+            indicator_led: IndicatorSettings { off_on_battery: Some(false), blink_below: Some(10) },
+            cpu_curve: FanCurve::default(),
+            gpu_curve: FanCurve::default(),
+            manual_speed: None,
+        };
+        demo.apply_settings(&ScenarioSettings::balanced());
+        demo
+    }
+
+    /// Simulated CPU/GPU load (0.0-1.0): slow waves with periodic bursts, like a
+    /// mix of browsing and short compile or game sessions.
+    fn load(&self) -> (f32, f32) {
+        let t = self.started.elapsed().as_secs_f32();
+        let base = 0.35 + 0.25 * (t / 45.0).sin();
+        let burst = if (t / 30.0).sin() > 0.6 { 0.45 } else { 0.0 };
+        let cpu = (base + burst + 0.05 * (t * 1.3).sin()).clamp(0.0, 1.0);
+        let gpu = (0.2 + 0.6 * ((t / 70.0).sin().max(0.0)) + 0.05 * (t * 0.7).cos()).clamp(0.0, 1.0);
+        (cpu, gpu)
+    }
+
+    fn power_factor(&self) -> f32 {
+        let shift = match self.shift_mode {
+            ShiftMode::EcoSilent => 0.6,
+            ShiftMode::Comfort => 0.8,
+            ShiftMode::Sport => 1.0,
+            ShiftMode::Turbo => 1.2,
+        };
+        if self.super_battery { shift * 0.5 } else { shift }
+    }
+
+    fn target_percent(&self, curve: &FanCurve, temp: f32, manual: Option<u8>) -> f32 {
+        if self.cooler_boost {
+            return 100.0;
+        }
+        let speed = match (self.fan_mode, manual) {
+            (FanMode::Advanced, Some(speed)) => speed,
+            (FanMode::Silent, _) => curve.get_speed_for_temp(temp as u8).min(SILENT_MAX_PERCENT),
+            _ => curve.get_speed_for_temp(temp as u8),
+        };
+        speed as f32
+    }
+
+    fn step(&mut self) {
+        let now = Instant::now();
+        let dt = now.duration_since(self.last_step).as_secs_f32();
+        self.last_step = now;
+
+        let (cpu_load, gpu_load) = self.load();
+        let power = self.power_factor();
+
+        let cpu_target = AMBIENT_TEMP + cpu_load * power * 65.0 - self.cpu_percent / 100.0 * 25.0;
+        let gpu_target = AMBIENT_TEMP + gpu_load * power * 55.0 - self.gpu_percent / 100.0 * 22.0;
+        let thermal = 1.0 - (-dt / THERMAL_TIME_CONSTANT).exp();
+        self.cpu_temp += (cpu_target - self.cpu_temp) * thermal;
+        self.gpu_temp += (gpu_target - self.gpu_temp) * thermal;
+
+        let cpu_fan_target = self.target_percent(&self.cpu_curve, self.cpu_temp, self.manual_speed.map(|(cpu, _)| cpu));
+        let gpu_fan_target = self.target_percent(&self.gpu_curve, self.gpu_temp, self.manual_speed.map(|(_, gpu)| gpu));
+        let spin = 1.0 - (-dt / FAN_TIME_CONSTANT).exp();
+        self.cpu_percent += (cpu_fan_target - self.cpu_percent) * spin;
+        self.gpu_percent += (gpu_fan_target - self.gpu_percent) * spin;
+    }
+
+    /// Advances the simulation to now and returns the readings.
+    pub fn fan_info(&mut self) -> FanInfo {
+        self.step();
+        // Targets are only known where a real EC's fan table would hold them.
+        let known = self.cooler_boost || self.fan_mode == FanMode::Advanced;
+        let cpu_target = self.target_percent(&self.cpu_curve, self.cpu_temp, self.manual_speed.map(|(cpu, _)| cpu));
+        let gpu_target = self.target_percent(&self.gpu_curve, self.gpu_temp, self.manual_speed.map(|(_, gpu)| gpu));
+        // The simulated model has the usual two fans.
+        let fans: Vec<FanReading> = [("CPU", self.cpu_percent, cpu_target), ("GPU", self.gpu_percent, gpu_target)]
+            .into_iter()
+            .map(|(name, percent, target)| FanReading {
+                name,
+                rpm: (percent / 100.0 * MAX_RPM) as u32,
+                percent: percent.round() as u8,
+                target_percent: known.then_some(target.round() as u8),
+            })
+            .collect();
+        FanInfo {
+            cpu_fan_rpm: fans[0].rpm,
+            gpu_fan_rpm: fans[1].rpm,
+            cpu_fan_percent: fans[0].percent,
+            gpu_fan_percent: fans[1].percent,
+            cpu_temp: self.cpu_temp.round() as u8,
+            gpu_temp: self.gpu_temp.round() as u8,
+            fan_mode: self.fan_mode,
+            cooler_boost: self.cooler_boost,
+            fans,
+            gpu: None,
+        }
+    }
+
+    pub fn scenario_info(&self) -> ScenarioInfo {
+        ScenarioInfo {
+            current_scenario: self.scenario,
+            shift_mode: self.shift_mode,
+            super_battery: self.super_battery,
+            // The simulated model maps no super battery bits.
+            super_battery_features: BTreeMap::new(),
+            lid_logo: Some(self.lid_logo),
+            indicator_led: Some(self.indicator_led),
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &ScenarioSettings) {
+        self.shift_mode = settings.shift_mode;
+        self.super_battery = settings.super_battery;
+        self.fan_mode = settings.fan_mode;
+        self.cooler_boost = settings.cooler_boost;
+        self.manual_speed = settings.manual_fan_speed.map(|speed| (speed.cpu, speed.gpu));
+        if let Some(percent) = settings.lid_logo {
+            self.lid_logo = percent.min(100);
+        }
+        self.set_indicator_led(&settings.indicator_led);
+        if let Some(ref curve) = settings.cpu_fan_curve {
+            self.cpu_curve = curve.clone();
+        }
+        if let Some(ref curve) = settings.gpu_fan_curve {
+            self.gpu_curve = curve.clone();
+        }
+    }
+
+    pub fn set_scenario(&mut self, scenario: UserScenario) {
+        if let Some(settings) = ScenarioSettings::for_scenario(scenario) {
+            self.apply_settings(&settings);
+            self.scenario = scenario;
+        }
+    }
+
+    pub fn set_shift_mode(&mut self, mode: ShiftMode) {
+        self.shift_mode = mode;
+    }
+
+    pub fn set_lid_logo(&mut self, percent: u8) {
+        self.lid_logo = percent.min(100);
+    }
+
+    pub fn set_indicator_led(&mut self, settings: &IndicatorSettings) {
+        if let Some(off) = settings.off_on_battery {
+            self.indicator_led.off_on_battery = Some(off);
+        }
+        if let Some(percent) = settings.blink_below {
+            self.indicator_led.blink_below = Some(percent.min(100));
+        }
+    }
+
+    pub fn set_fan_mode(&mut self, mode: FanMode) {
+        self.fan_mode = mode;
+    }
+
+    pub fn set_cooler_boost(&mut self, enabled: bool) {
+        self.cooler_boost = enabled;
+    }
+
+    pub fn set_manual_fan_speed(&mut self, cpu_percent: u8, gpu_percent: u8) {
+        self.fan_mode = FanMode::Advanced;
+        self.manual_speed = Some((cpu_percent.min(100), gpu_percent.min(100)));
+    }
+
+    pub fn set_cpu_fan_curve(&mut self, curve: FanCurve) {
+        self.cpu_curve = curve;
+        self.manual_speed = None;
+    }
+
+    pub fn set_gpu_fan_curve(&mut self, curve: FanCurve) {
+        self.gpu_curve = curve;
+        self.manual_speed = None;
+    }
+
+    pub fn reset_to_auto(&mut self) {
+        self.fan_mode = FanMode::Auto;
+        self.cooler_boost = false;
+        self.manual_speed = None;
+    }
+}
+
+impl Default for DemoHardware {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,149 @@
+//! The report behind `msi-center doctor --json`: what the text checks look at,
+//! gathered once in a form users can attach to an issue and scripts can parse.
+
+use crate::config::AppConfig;
+use crate::ec::quirks::{self, Capabilities, ModelInfo};
+use crate::ec::{self, msi_ec, Backend, EmbeddedController, Support};
+use crate::scenario::DeferredControl;
+use crate::system::{self, Conflict};
+use nix::unistd::{access, AccessFlags};
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+/// Bumped when a field is renamed or removed; added fields keep the version.
+const REPORT_VERSION: u32 = 1;
+/// Kernel modules the EC backends and the ACPI fallback use.
+const MODULES: [&str; 3] = ["msi_ec", "ec_sys", "acpi_call"];
+/// Files the EC backends open.
+const EC_PATHS: [&str; 4] = ["/dev/port", "/sys/kernel/debug/ec/ec0/io", msi_ec::MSI_EC_PATH, "/run/msi-center.lock"];
+
+#[derive(Debug, Serialize)]
+pub struct ModuleState {
+    pub name: &'static str,
+    pub loaded: bool,
+    /// `modprobe` can find it for the running kernel.
+    pub installed: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PathAccess {
+    pub path: String,
+    pub exists: bool,
+    pub readable: bool,
+    pub writable: bool,
+}
+
+impl PathAccess {
+    fn check(path: &Path) -> Self {
+        Self {
+            path: path.display().to_string(),
+            exists: path.exists(),
+            readable: access(path, AccessFlags::R_OK).is_ok(),
+            writable: access(path, AccessFlags::W_OK).is_ok(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EcAccess {
+    /// Backends tried, in order.
+    pub allowed: Vec<Backend>,
+    /// The backend in use, or `None` with the reason in `error`.
+    pub backend: Option<String>,
+    pub error: Option<String>,
+    /// The EC answered a read of the CPU temperature register.
+    pub responds: bool,
+    pub port_watchdog_tripped: bool,
+    /// Controls that work through the backend in use.
+    pub support: Option<Support>,
+}
+
+impl EcAccess {
+    fn check() -> Self {
+        let allowed = ec::backend_order();
+        match EmbeddedController::new() {
+            Ok(mut ec) => Self {
+                allowed,
+                backend: Some(ec.backend_name().to_string()),
+                error: None,
+                responds: ec.read_byte(ec.registers().cpu_temp).is_ok(),
+                port_watchdog_tripped: EmbeddedController::port_watchdog_tripped(),
+                support: Some(ec.support()),
+            },
+            Err(e) => Self {
+                allowed,
+                backend: None,
+                error: Some(e.to_string()),
+                responds: false,
+                port_watchdog_tripped: EmbeddedController::port_watchdog_tripped(),
+                support: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DoctorReport {
+    pub report_version: u32,
+    pub tool_version: &'static str,
+    pub generated_at: String,
+    /// `uname -r`.
+    pub kernel: Option<String>,
+    pub root: bool,
+    pub vendor: Option<String>,
+    pub model: ModelInfo,
+    /// Quirk file the register map came from, if one matched.
+    pub quirk_source: Option<String>,
+    pub capabilities: Capabilities,
+    pub lockdown: Option<String>,
+    pub secure_boot: Option<bool>,
+    pub ec: EcAccess,
+    pub modules: Vec<ModuleState>,
+    /// ec_sys's `write_support` parameter, when the module is loaded.
+    pub ec_sys_write_support: Option<bool>,
+    pub paths: Vec<PathAccess>,
+    pub conflicts: Vec<Conflict>,
+    pub deferred_controls: Vec<DeferredControl>,
+    /// Profile fan curves that would be rejected when applied, and why.
+    pub invalid_curves: Vec<String>,
+}
+
+fn read_trimmed(path: &str) -> Option<String> {
+    let value = fs::read_to_string(path).ok()?;
+    Some(value.trim().to_string()).filter(|value| !value.is_empty())
+}
+
+impl DoctorReport {
+    pub fn collect(config: &AppConfig) -> Self {
+        let modules = MODULES
+            .iter()
+            .map(|&name| ModuleState { name, loaded: system::module_loaded(name), installed: system::module_available(name) })
+            .collect();
+        let mut paths: Vec<PathAccess> = EC_PATHS.iter().map(|path| PathAccess::check(Path::new(path))).collect();
+        if let Ok(dir) = AppConfig::config_dir() {
+            paths.push(PathAccess::check(&dir));
+        }
+
+        Self {
+            report_version: REPORT_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            generated_at: chrono::Local::now().to_rfc3339(),
+            kernel: read_trimmed("/proc/sys/kernel/osrelease"),
+            root: nix::unistd::geteuid().is_root(),
+            vendor: read_trimmed("/sys/class/dmi/id/sys_vendor"),
+            model: quirks::model_info(),
+            quirk_source: quirks::detected().map(|quirk| quirk.source.clone()),
+            capabilities: quirks::capabilities(),
+            lockdown: system::kernel_lockdown(),
+            secure_boot: system::secure_boot(),
+            ec: EcAccess::check(),
+            modules,
+            ec_sys_write_support: system::ec_sys_write_support(),
+            paths,
+            conflicts: system::detect_conflicts(),
+            deferred_controls: config.deferred_controls.clone(),
+            invalid_curves: config.curve_problems(),
+        }
+    }
+}
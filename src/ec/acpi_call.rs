@@ -0,0 +1,52 @@
+//! Calls ACPI methods through the acpi_call module (`/proc/acpi/call`), for models
+//! whose firmware ignores raw EC writes to some controls but honors its own
+//! methods. Which method sets which control is model-specific and comes from the
+//! `[acpi_methods]` table of a user's quirk file; no bundled quirk maps any.
+
+use super::quirks::{AcpiMethods, RegisterMap};
+use std::fs;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AcpiCallError {
+    #[error("acpi_call module is not loaded")]
+    NotLoaded,
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0} failed: {1}")]
+    Failed(String, String),
+}
+
+pub type Result<T> = std::result::Result<T, AcpiCallError>;
+
+const ACPI_CALL_PATH: &str = "/proc/acpi/call";
+
+pub fn available() -> bool {
+    std::path::Path::new(ACPI_CALL_PATH).exists()
+}
+
+/// Invokes `method` with a single integer argument and returns what it evaluated to.
+pub fn call(method: &str, argument: u8) -> Result<String> {
+    if !available() {
+        return Err(AcpiCallError::NotLoaded);
+    }
+    let expression = format!("{} {:#04x}", method, argument);
+    fs::write(ACPI_CALL_PATH, &expression)?;
+    // The result of the last call is read back from the same file.
+    let result = fs::read_to_string(ACPI_CALL_PATH)?.trim_end_matches('\0').trim().to_string();
+    if result.starts_with("Error") {
+        return Err(AcpiCallError::Failed(expression, result));
+    }
+    log::debug!("ACPI call {} returned {}", expression, result);
+    Ok(result)
+}
+
+/// The method a quirk maps to the control at `address`, if any.
+pub fn method_for(methods: &AcpiMethods, address: u8, registers: &RegisterMap) -> Option<String> {
+    let mapped = [
+        (registers.shift_mode, &methods.shift_mode),
+        (registers.cooler_boost, &methods.cooler_boost),
+        (registers.fan_mode, &methods.fan_mode),
+    ];
+    mapped.into_iter().find(|(register, _)| *register == address).and_then(|(_, method)| method.clone())
+}
@@ -0,0 +1,251 @@
+//! Imports EC diffs captured under Windows (e.g. with RWEverything while clicking
+//! through MSI Center), turning writes to known registers into profile settings.
+//!
+//! A capture is a text file of `[Section]` headers, one per action, each followed by
+//! the registers it changed: `D2: C1 -> C4`, `0xD2 = 0xC4` or `D2 C4`. Numbers are
+//! hex; `#`, `;` and `//` start comments.
+
+use super::quirks::{Capabilities, RegisterMap};
+use crate::fan::{self, FanCurve, FanCurvePoint, FanMode};
+use crate::scenario::{ScenarioSettings, ShiftMode};
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CaptureError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Line {0}: {1}")]
+    ParseError(usize, String),
+    #[error("Capture contains no register changes")]
+    Empty,
+}
+
+pub type Result<T> = std::result::Result<T, CaptureError>;
+
+/// Name used for changes listed before the first section header.
+const UNNAMED_SECTION: &str = "Imported";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterWrite {
+    pub address: u8,
+    pub old: Option<u8>,
+    pub value: u8,
+}
+
+impl std::fmt::Display for RegisterWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.old {
+            Some(old) => write!(f, "{:#04x}: {:#04x} -> {:#04x}", self.address, old, self.value),
+            None => write!(f, "{:#04x} = {:#04x}", self.address, self.value),
+        }
+    }
+}
+
+/// Setting a write to a known register stands for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recognized {
+    ShiftMode(ShiftMode),
+    FanMode(FanMode),
+    CoolerBoost(bool),
+    SuperBattery(bool),
+    CpuFanCurve(FanCurve),
+    GpuFanCurve(FanCurve),
+}
+
+impl std::fmt::Display for Recognized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let on_off = |on: &bool| if *on { "ON" } else { "OFF" };
+        let points = |curve: &FanCurve| {
+            curve.points.iter().map(|p| format!("{}°C:{}%", p.temp, p.speed)).collect::<Vec<_>>().join(" ")
+        };
+        match self {
+            Recognized::ShiftMode(mode) => write!(f, "shift mode {}", mode),
+            Recognized::FanMode(mode) => write!(f, "fan mode {:?}", mode),
+            Recognized::CoolerBoost(on) => write!(f, "Cooler Boost {}", on_off(on)),
+            Recognized::SuperBattery(on) => write!(f, "super battery {}", on_off(on)),
+            Recognized::CpuFanCurve(curve) => write!(f, "CPU fan curve {}", points(curve)),
+            Recognized::GpuFanCurve(curve) => write!(f, "GPU fan curve {}", points(curve)),
+        }
+    }
+}
+
+/// One captured action, split into what could be recognized and what could not.
+#[derive(Debug, Clone)]
+pub struct CapturedAction {
+    pub name: String,
+    pub writes: Vec<RegisterWrite>,
+    pub recognized: Vec<Recognized>,
+    pub unknown: Vec<RegisterWrite>,
+}
+
+impl CapturedAction {
+    /// `base` with the recognized settings applied.
+    pub fn settings(&self, base: ScenarioSettings) -> ScenarioSettings {
+        let mut settings = base;
+        for recognized in &self.recognized {
+            match recognized {
+                Recognized::ShiftMode(mode) => settings.shift_mode = *mode,
+                Recognized::FanMode(mode) => settings.fan_mode = *mode,
+                Recognized::CoolerBoost(on) => settings.cooler_boost = *on,
+                Recognized::SuperBattery(on) => settings.super_battery = *on,
+                Recognized::CpuFanCurve(curve) => settings.cpu_fan_curve = Some(curve.clone()),
+                Recognized::GpuFanCurve(curve) => settings.gpu_fan_curve = Some(curve.clone()),
+            }
+        }
+        settings
+    }
+}
+
+fn parse_hex(token: &str) -> Option<u8> {
+    let digits = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+    u8::from_str_radix(digits, 16).ok()
+}
+
+fn parse_line(line: &str) -> std::result::Result<RegisterWrite, String> {
+    let tokens: Vec<&str> = line
+        .split(|c: char| c.is_whitespace() || c == ':' || c == '=' || c == ',')
+        .filter(|t| !t.is_empty() && *t != "->")
+        .collect();
+    let values: Option<Vec<u8>> = tokens.iter().map(|t| parse_hex(t)).collect();
+    match values.as_deref() {
+        Some([address, value]) => Ok(RegisterWrite { address: *address, old: None, value: *value }),
+        Some([address, old, value]) => Ok(RegisterWrite { address: *address, old: Some(*old), value: *value }),
+        _ => Err(format!("expected 'ADDRESS: OLD -> NEW' or 'ADDRESS = VALUE', got '{}'", line)),
+    }
+}
+
+/// Parses a capture into its sections, in file order.
+pub fn parse(content: &str) -> Result<Vec<(String, Vec<RegisterWrite>)>> {
+    let mut sections: Vec<(String, Vec<RegisterWrite>)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = ["#", ";", "//"].iter().fold(line, |line, marker| line.split(marker).next().unwrap_or(""));
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((name.trim().to_string(), Vec::new()));
+            continue;
+        }
+
+        let write = parse_line(line).map_err(|e| CaptureError::ParseError(index + 1, e))?;
+        if sections.is_empty() {
+            sections.push((UNNAMED_SECTION.to_string(), Vec::new()));
+        }
+        if let Some((_, writes)) = sections.last_mut() {
+            writes.push(write);
+        }
+    }
+
+    sections.retain(|(_, writes)| !writes.is_empty());
+    if sections.is_empty() {
+        return Err(CaptureError::Empty);
+    }
+    Ok(sections)
+}
+
+/// Rebuilds a fan curve from writes to its EC table, laid out as `capabilities` says.
+fn curve_from_writes(base: u8, writes: &[RegisterWrite], capabilities: &Capabilities, used: &mut Vec<u8>) -> Option<FanCurve> {
+    let value_at = |address: u8| writes.iter().rev().find(|w| w.address == address).map(|w| w.value);
+    let mut points = Vec::new();
+    for i in 0..capabilities.curve_points {
+        let (temp_addr, speed_addr) = capabilities.table_entry(base, i);
+        if let (Some(temp), Some(speed)) = (value_at(temp_addr), value_at(speed_addr)) {
+            used.extend([temp_addr, speed_addr]);
+            points.push(FanCurvePoint { temp, speed: fan::duty_percent(speed) });
+        }
+    }
+    (points.len() >= 2).then_some(FanCurve { points, ..FanCurve::default() })
+}
+
+/// Matches the writes of one action against the register map.
+pub fn recognize(name: &str, writes: Vec<RegisterWrite>, registers: &RegisterMap, capabilities: &Capabilities) -> CapturedAction {
+    let mut recognized = Vec::new();
+    let mut used = Vec::new();
+
+    if let Some(curve) = curve_from_writes(registers.fan1_base, &writes, capabilities, &mut used) {
+        recognized.push(Recognized::CpuFanCurve(curve));
+    }
+    if let Some(curve) = curve_from_writes(registers.fan2_base, &writes, capabilities, &mut used) {
+        recognized.push(Recognized::GpuFanCurve(curve));
+    }
+
+    for write in &writes {
+        if used.contains(&write.address) {
+            continue;
+        }
+        let value = write.value;
+        let setting = if write.address == registers.shift_mode {
+            ShiftMode::ALL.into_iter().find(|mode| *mode as u8 == value).map(Recognized::ShiftMode)
+        } else if write.address == registers.fan_mode {
+            (value & 0x0F <= FanMode::Advanced as u8).then(|| Recognized::FanMode(FanMode::from(value & 0x0F)))
+        } else if write.address == registers.cooler_boost {
+            Some(Recognized::CoolerBoost(value & 0x80 != 0))
+        } else if write.address == registers.super_battery {
+            Some(Recognized::SuperBattery(value != 0))
+        } else {
+            None
+        };
+        if let Some(setting) = setting {
+            // A later write to the same register wins.
+            recognized.retain(|r| std::mem::discriminant(r) != std::mem::discriminant(&setting));
+            recognized.push(setting);
+            used.push(write.address);
+        }
+    }
+
+    let unknown = writes.iter().copied().filter(|w| !used.contains(&w.address)).collect();
+    CapturedAction { name: name.to_string(), writes, recognized, unknown }
+}
+
+pub fn load(path: &Path, registers: &RegisterMap, capabilities: &Capabilities) -> Result<Vec<CapturedAction>> {
+    let content = fs::read_to_string(path)?;
+    Ok(parse(&content)?
+        .into_iter()
+        .map(|(name, writes)| recognize(&name, writes, registers, capabilities))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_sections_map_to_settings() {
+        let capture = "\
+# clicked through MSI Center on Windows
+[Extreme Performance]
+D2: C1 -> C4   ; shift mode
+0x98 = 0x82
+E8 01
+
+[Quiet]
+72 28
+73 00
+74 3C
+75 80
+";
+        let registers = RegisterMap::default();
+        let actions: Vec<CapturedAction> = parse(capture)
+            .unwrap()
+            .into_iter()
+            .map(|(name, writes)| recognize(&name, writes, &registers, &Capabilities::default()))
+            .collect();
+
+        assert_eq!(actions.len(), 2);
+        assert_eq!(actions[0].name, "Extreme Performance");
+        assert_eq!(actions[0].recognized, vec![Recognized::ShiftMode(ShiftMode::Turbo), Recognized::CoolerBoost(true)]);
+        assert_eq!(actions[0].unknown, vec![RegisterWrite { address: 0xE8, old: None, value: 0x01 }]);
+
+        let settings = actions[1].settings(ScenarioSettings::balanced());
+        let curve = settings.cpu_fan_curve.expect("CPU curve");
+        assert_eq!(curve.points.len(), 2);
+        assert_eq!((curve.points[1].temp, curve.points[1].speed), (60, 50));
+        assert!(actions[1].unknown.is_empty());
+
+        assert!(matches!(parse("D2 C4 C1 00"), Err(CaptureError::ParseError(1, _))));
+    }
+}
@@ -0,0 +1,90 @@
+use crate::config::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+const FAILURES_FILE: &str = "ec-write-failures.json";
+/// Failures kept on disk; older ones are dropped.
+const MAX_FAILURES: usize = 20;
+/// Failures older than this no longer count as recent.
+const RECENT_WINDOW: chrono::Duration = chrono::Duration::hours(1);
+
+/// An EC write that returned an error, or whose register read back a different value.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteFailure {
+    pub at: String,
+    pub address: u8,
+    pub value: u8,
+    pub backend: String,
+    pub reason: String,
+}
+
+impl WriteFailure {
+    fn age(&self) -> Option<chrono::Duration> {
+        let at = chrono::DateTime::parse_from_rfc3339(&self.at).ok()?;
+        Some(chrono::Local::now().signed_duration_since(at))
+    }
+
+    /// Local time of the failure, for display.
+    pub fn time(&self) -> String {
+        chrono::DateTime::parse_from_rfc3339(&self.at)
+            .map(|at| at.with_timezone(&chrono::Local).format("%H:%M:%S").to_string())
+            .unwrap_or_else(|_| self.at.clone())
+    }
+}
+
+impl std::fmt::Display for WriteFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} write {:#04x} = {:#04x} via {}: {}", self.time(), self.address, self.value, self.backend, self.reason)
+    }
+}
+
+/// Kept in the config directory so `status` and the GUI see failures from other
+/// processes, such as the daemon.
+fn failures_path() -> Option<PathBuf> {
+    AppConfig::config_dir().ok().map(|dir| dir.join(FAILURES_FILE))
+}
+
+fn load() -> Vec<WriteFailure> {
+    failures_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn record(address: u8, value: u8, backend: &str, reason: String) {
+    let Some(path) = failures_path() else { return };
+    let mut failures = load();
+    failures.push(WriteFailure {
+        at: chrono::Local::now().to_rfc3339(),
+        address,
+        value,
+        backend: backend.to_string(),
+        reason,
+    });
+    let excess = failures.len().saturating_sub(MAX_FAILURES);
+    failures.drain(..excess);
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, serde_json::to_string_pretty(&failures).unwrap_or_default()));
+    if let Err(e) = result {
+        log::debug!("Could not record EC write failure in {}: {}", path.display(), e);
+    }
+}
+
+/// Failures within the last hour, oldest first.
+pub fn recent() -> Vec<WriteFailure> {
+    load().into_iter().filter(|f| f.age().is_some_and(|age| age < RECENT_WINDOW)).collect()
+}
+
+/// Forgets all recorded failures, e.g. once the user has seen them.
+pub fn clear() {
+    if let Some(path) = failures_path()
+        && path.exists()
+        && let Err(e) = fs::remove_file(&path)
+    {
+        log::debug!("Could not remove {}: {}", path.display(), e);
+    }
+}
@@ -0,0 +1,99 @@
+//! Direct x86 port I/O through `ioperm()`, for kernels built without `/dev/port`
+//! (`CONFIG_DEVPORT=n`). Needs root (CAP_SYS_RAWIO); kernel lockdown refuses it
+//! just like `/dev/port`.
+//!
+//! All unsafe code for raw port access lives here. Only the EC's own ports can be
+//! reached, and only once the kernel has granted them to the calling thread.
+
+use std::cell::Cell;
+use std::io;
+
+/// The EC's ACPI interface: data at 0x62, command/status at 0x66.
+const FIRST_PORT: u16 = 0x62;
+const PORT_COUNT: u16 = 5;
+
+thread_local! {
+    /// `ioperm()` grants access to the calling thread only, so each thread that
+    /// talks to the EC asks once.
+    static GRANTED: Cell<bool> = const { Cell::new(false) };
+}
+
+#[cfg(all(target_arch = "x86_64", any(target_env = "gnu", target_env = "musl")))]
+mod arch {
+    use std::io;
+
+    pub fn grant(first: u16, count: u16) -> io::Result<()> {
+        // SAFETY: ioperm only changes the calling thread's I/O permission bitmap.
+        if unsafe { libc::ioperm(first as libc::c_ulong, count as libc::c_ulong, 1) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// # Safety
+    /// The calling thread must have been granted access to `port`.
+    pub unsafe fn inb(port: u16) -> u8 {
+        let value: u8;
+        // SAFETY: upheld by the caller; `in` touches no memory.
+        unsafe {
+            std::arch::asm!("in al, dx", out("al") value, in("dx") port, options(nomem, nostack, preserves_flags));
+        }
+        value
+    }
+
+    /// # Safety
+    /// The calling thread must have been granted access to `port`.
+    pub unsafe fn outb(port: u16, value: u8) {
+        // SAFETY: upheld by the caller; `out` touches no memory.
+        unsafe {
+            std::arch::asm!("out dx, al", in("dx") port, in("al") value, options(nomem, nostack, preserves_flags));
+        }
+    }
+}
+
+#[cfg(not(all(target_arch = "x86_64", any(target_env = "gnu", target_env = "musl"))))]
+mod arch {
+    use std::io;
+
+    pub fn grant(_first: u16, _count: u16) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "port I/O needs an x86_64 Linux target"))
+    }
+
+    pub unsafe fn inb(_port: u16) -> u8 {
+        unreachable!("port access is never granted on this target")
+    }
+
+    pub unsafe fn outb(_port: u16, _value: u8) {
+        unreachable!("port access is never granted on this target")
+    }
+}
+
+/// Asks the kernel for the EC ports on the calling thread, unless it has them already.
+pub fn request_access() -> io::Result<()> {
+    if GRANTED.get() {
+        return Ok(());
+    }
+    arch::grant(FIRST_PORT, PORT_COUNT)?;
+    GRANTED.set(true);
+    Ok(())
+}
+
+fn checked(port: u16) -> io::Result<()> {
+    if !(FIRST_PORT..FIRST_PORT + PORT_COUNT).contains(&port) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("port {:#x} is not an EC port", port)));
+    }
+    request_access()
+}
+
+pub fn inb(port: u16) -> io::Result<u8> {
+    checked(port)?;
+    // SAFETY: `checked` made sure this thread was granted `port`.
+    Ok(unsafe { arch::inb(port) })
+}
+
+pub fn outb(port: u16, value: u8) -> io::Result<()> {
+    checked(port)?;
+    // SAFETY: `checked` made sure this thread was granted `port`.
+    unsafe { arch::outb(port, value) };
+    Ok(())
+}
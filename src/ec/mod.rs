@@ -1,5 +1,26 @@
+pub mod acpi_call;
+pub mod capture;
+pub mod failures;
+mod ioport;
+pub mod msi_ec;
+pub mod probe;
+pub mod quirks;
+pub mod snapshot;
+pub mod trace;
+
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::os::fd::AsRawFd;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, Instant};
+use nix::errno::Errno;
+use nix::fcntl::{flock, FlockArg};
+use serde::{Deserialize, Serialize};
+use quirks::{Capabilities, RegisterMap};
+use snapshot::EcSnapshot;
+use trace::{MockEc, TraceOp};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,6 +35,14 @@ pub enum EcError {
     InvalidAddress(u16),
     #[error("EC read/write failed")]
     IoFailed,
+    #[error("EC did not respond in time")]
+    Timeout,
+    #[error("Another msi-center instance is holding the EC lock ({0})")]
+    Busy(&'static str),
+    #[error("msi-ec driver does not accept {0} = {1}")]
+    UnsupportedValue(String, String),
+    #[error("Refusing to write EC register {0:#04x}: it is not on this model's list of safe registers (override with msi-center --force <command>, or expert mode in the GUI)")]
+    UnsafeWrite(u8),
 }
 
 pub type Result<T> = std::result::Result<T, EcError>;
@@ -25,8 +54,12 @@ const EC_SC_WRITE_CMD: u8 = 0x81;
 const EC_SC_IBF: u8 = 0x02;
 const EC_SC_OBF: u8 = 0x01;
 
-pub const MSI_ADDRESS_CPU_FAN_SPEED: u8 = 0xC8;
-pub const MSI_ADDRESS_GPU_FAN_SPEED: u8 = 0xCA;
+/// Duty the EC runs each fan at (0-150), just below its fan table, as msi-ec reads it.
+pub const MSI_ADDRESS_CPU_FAN_SPEED: u8 = 0x71;
+pub const MSI_ADDRESS_GPU_FAN_SPEED: u8 = 0x89;
+/// High byte of each fan's 16-bit tachometer period; the low byte follows.
+pub const MSI_ADDRESS_CPU_FAN_TACH: u8 = 0xC8;
+pub const MSI_ADDRESS_GPU_FAN_TACH: u8 = 0xCA;
 pub const MSI_ADDRESS_CPU_TEMP: u8 = 0x68;
 pub const MSI_ADDRESS_GPU_TEMP: u8 = 0x80;
 pub const MSI_ADDRESS_FAN_MODE: u8 = 0xD4;
@@ -35,28 +68,553 @@ pub const MSI_ADDRESS_SHIFT_MODE: u8 = 0xD2;
 pub const MSI_ADDRESS_SUPER_BATTERY: u8 = 0xEB;
 pub const MSI_ADDRESS_FAN1_BASE: u8 = 0x72;
 pub const MSI_ADDRESS_FAN2_BASE: u8 = 0x8A;
+pub const MSI_ADDRESS_WEBCAM: u8 = 0x2E;
+pub const MSI_ADDRESS_WEBCAM_BLOCK: u8 = 0x2F;
+pub const MSI_ADDRESS_FN_WIN_SWAP: u8 = 0xBF;
+pub const MSI_ADDRESS_BATTERY_CHARGE: u8 = 0xEF;
+pub const MSI_ADDRESS_KBD_BACKLIGHT: u8 = 0xF3;
+
+type BackendProbe = fn() -> Result<EmbeddedController>;
+
+/// EC writes queued to be applied together with [`EmbeddedController::apply_batch`].
+#[derive(Debug, Clone, Default)]
+pub struct WriteBatch {
+    writes: Vec<(u8, u8)>,
+}
+
+impl WriteBatch {
+    pub fn write(&mut self, address: u8, value: u8) -> &mut Self {
+        self.writes.push((address, value));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+}
+
+/// A queued write that did not take effect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedWrite {
+    pub address: u8,
+    pub value: u8,
+    /// What the register held afterwards, if the write went through but didn't stick.
+    pub actual: Option<u8>,
+    pub reason: String,
+}
+
+impl std::fmt::Display for RejectedWrite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.actual {
+            Some(actual) => write!(f, "{:#04x} = {:#04x} (reads {:#04x})", self.address, self.value, actual),
+            None => write!(f, "{:#04x} = {:#04x} ({})", self.address, self.value, self.reason),
+        }
+    }
+}
+
+/// Outcome of [`EmbeddedController::apply_batch`].
+#[derive(Debug, Clone, Default)]
+pub struct BatchReport {
+    /// Registers that read back the value written.
+    pub verified: usize,
+    /// Registers this backend cannot read back (e.g. unmapped ones on the msi-ec driver).
+    pub unverified: usize,
+    pub rejected: Vec<RejectedWrite>,
+}
+
+impl BatchReport {
+    pub fn is_ok(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// What can be controlled on this machine through the active backend: the model's
+/// capabilities, narrowed to the registers the backend can actually write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Support {
+    pub fan_mode: bool,
+    /// Fan curves and manual speeds, which both program the fan tables.
+    pub fan_curves: bool,
+    pub cooler_boost: bool,
+    pub shift_mode: bool,
+    pub super_battery: bool,
+    pub second_fan: bool,
+    /// Not listed by `missing`, since most models have only two fans.
+    pub third_fan: bool,
+    /// Fan RPM from the tachometer registers.
+    pub fan_rpm: bool,
+    pub battery_threshold: bool,
+    pub keyboard_backlight: bool,
+    pub webcam: bool,
+    pub lid_logo: bool,
+    /// Not listed by `missing` either; few firmwares let the indicator be configured.
+    pub indicator_led: bool,
+}
+
+impl Support {
+    /// Everything, for when nothing is known to be missing (e.g. demo mode).
+    pub const ALL: Support = Support {
+        fan_mode: true,
+        fan_curves: true,
+        cooler_boost: true,
+        shift_mode: true,
+        super_battery: true,
+        second_fan: true,
+        third_fan: true,
+        fan_rpm: true,
+        battery_threshold: true,
+        keyboard_backlight: true,
+        webcam: true,
+        lid_logo: true,
+        indicator_led: true,
+    };
+
+    /// Names of the unsupported controls, for `status` and `doctor`.
+    pub fn missing(&self) -> Vec<&'static str> {
+        let features = [
+            (self.fan_mode, "fan mode"),
+            (self.fan_curves, "fan curves and manual speed"),
+            (self.cooler_boost, "Cooler Boost"),
+            (self.shift_mode, "shift mode"),
+            (self.super_battery, "super battery"),
+            (self.second_fan, "second fan"),
+            (self.fan_rpm, "fan RPM"),
+            (self.battery_threshold, "battery charge limit"),
+            (self.keyboard_backlight, "keyboard backlight"),
+            (self.webcam, "webcam switch"),
+            (self.lid_logo, "lid logo"),
+        ];
+        features.into_iter().filter(|(supported, _)| !supported).map(|(_, name)| name).collect()
+    }
+}
+
+/// Handshake timing of the port backends (`ec_port` in the config).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PortTiming {
+    /// How long to wait for the EC to accept a byte or produce one.
+    pub timeout_ms: u64,
+    /// Delay between status register polls.
+    pub poll_interval_us: u64,
+    /// Extra attempts for a transaction that timed out.
+    pub retries: u32,
+    /// Delay before the first retry; doubled for each further one.
+    pub retry_delay_ms: u64,
+}
+
+impl Default for PortTiming {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 100,
+            poll_interval_us: 10,
+            retries: 2,
+            retry_delay_ms: 5,
+        }
+    }
+}
+
+impl PortTiming {
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.timeout_ms.max(1))
+    }
+
+    fn poll_interval(&self) -> Duration {
+        Duration::from_micros(self.poll_interval_us)
+    }
+
+    fn retry_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_delay_ms)
+    }
+}
+
+static PORT_TIMING: Mutex<Option<PortTiming>> = Mutex::new(None);
+
+/// A way of reaching the EC, for `ec_backends` in the config and `--backend`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backend {
+    /// Raw port I/O through `/dev/port`.
+    DevPort,
+    /// Raw port I/O with `ioperm`, for kernels without `/dev/port`.
+    Ioperm,
+    /// The `ec_sys` debugfs interface.
+    Debugfs,
+    /// The msi-ec kernel driver, which only exposes the registers it knows.
+    MsiEc,
+}
+
+impl Backend {
+    /// Every backend, in the order they are tried by default.
+    pub const ALL: [Backend; 4] = [Backend::DevPort, Backend::Ioperm, Backend::Debugfs, Backend::MsiEc];
+
+    /// The name [`EmbeddedController::backend_name`] reports for it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Backend::DevPort => "/dev/port",
+            Backend::Ioperm => "ioperm",
+            Backend::Debugfs => "debugfs ec_sys",
+            Backend::MsiEc => "msi-ec driver",
+        }
+    }
+
+    /// Whether it drives the EC's I/O ports directly.
+    pub fn is_port(self) -> bool {
+        matches!(self, Backend::DevPort | Backend::Ioperm)
+    }
+
+    fn probe(self) -> BackendProbe {
+        match self {
+            Backend::DevPort => EmbeddedController::try_direct_port_access,
+            Backend::Ioperm => EmbeddedController::try_ioperm_access,
+            Backend::Debugfs => EmbeddedController::try_acpi_access,
+            Backend::MsiEc => EmbeddedController::try_msi_ec_driver,
+        }
+    }
+}
+
+impl std::fmt::Display for Backend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let id = match self {
+            Backend::DevPort => "dev-port",
+            Backend::Ioperm => "ioperm",
+            Backend::Debugfs => "debugfs",
+            Backend::MsiEc => "msi-ec",
+        };
+        write!(f, "{}", id)
+    }
+}
+
+impl std::str::FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "dev-port" | "/dev/port" | "port" => Ok(Backend::DevPort),
+            "ioperm" => Ok(Backend::Ioperm),
+            "debugfs" | "ec_sys" | "ec-sys" => Ok(Backend::Debugfs),
+            "msi-ec" | "msi_ec" => Ok(Backend::MsiEc),
+            _ => Err(format!("unknown EC backend '{}' (expected dev-port, ioperm, debugfs or msi-ec)", s)),
+        }
+    }
+}
+
+/// Backends to try, in order; `None` for [`Backend::ALL`].
+static BACKEND_ORDER: Mutex<Option<Vec<Backend>>> = Mutex::new(None);
+
+/// Restricts every EC instance of this process to `order`, tried first to last.
+/// An empty list restores the default order.
+pub fn set_backend_order(order: &[Backend]) {
+    let order = (!order.is_empty()).then(|| order.to_vec());
+    *BACKEND_ORDER.lock().unwrap_or_else(PoisonError::into_inner) = order;
+}
+
+pub fn backend_order() -> Vec<Backend> {
+    BACKEND_ORDER.lock().unwrap_or_else(PoisonError::into_inner).clone().unwrap_or_else(default_backend_order)
+}
+
+/// Every backend, or under kernel lockdown only the msi-ec driver: lockdown
+/// refuses port I/O and debugfs, and trying them would only add noise.
+fn default_backend_order() -> Vec<Backend> {
+    match crate::system::kernel_lockdown() {
+        Some(mode) => {
+            log::debug!("Kernel lockdown ({}) is active, using the msi-ec driver only", mode);
+            vec![Backend::MsiEc]
+        }
+        None => Backend::ALL.to_vec(),
+    }
+}
+
+/// Held for every EC access in this process, so a batch of writes is never
+/// interleaved with another thread's reads or writes.
+static EC_LOCK: Mutex<()> = Mutex::new(());
+
+/// Locked alongside [`EC_LOCK`] so that other instances (GUI, CLI, daemon) don't
+/// interleave their port handshakes with ours.
+const LOCK_FILE_PATH: &str = "/run/msi-center.lock";
+/// How long to wait for another instance before giving up on the access.
+const LOCK_FILE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The lock file, opened once per process. `None` when it can't be opened (e.g.
+/// without root), in which case only this process's own accesses are serialized.
+fn lock_file() -> Option<&'static File> {
+    static LOCK_FILE: OnceLock<Option<File>> = OnceLock::new();
+    LOCK_FILE
+        .get_or_init(|| {
+            let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(LOCK_FILE_PATH);
+            file.or_else(|_| File::open(LOCK_FILE_PATH))
+                .inspect_err(|e| log::debug!("No cross-process EC lock, cannot open {}: {}", LOCK_FILE_PATH, e))
+                .ok()
+        })
+        .as_ref()
+}
+
+thread_local! {
+    /// Set while this thread holds [`EC_LOCK`].
+    static HOLDS_EC_LOCK: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+/// Both EC locks; the file lock is released on drop, before the mutex. Neither
+/// is held by the lock a panic hook takes on top of its own thread's.
+struct EcLock {
+    file: Option<&'static File>,
+    guard: Option<MutexGuard<'static, ()>>,
+}
+
+impl Drop for EcLock {
+    fn drop(&mut self) {
+        if let Some(file) = self.file {
+            let _ = flock(file.as_raw_fd(), FlockArg::Unlock);
+        }
+        if self.guard.take().is_some() {
+            HOLDS_EC_LOCK.with(|holds| holds.set(false));
+        }
+    }
+}
+
+/// Waits for `file`'s exclusive lock, giving up after [`LOCK_FILE_TIMEOUT`] so a
+/// hung instance can't stall this one forever. Giving up fails the access rather
+/// than interleaving with the other instance's handshake.
+fn lock_across_processes(file: &File) -> Result<()> {
+    let started = Instant::now();
+    loop {
+        match flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock) {
+            Ok(()) => return Ok(()),
+            Err(Errno::EWOULDBLOCK | Errno::EINTR) if started.elapsed() < LOCK_FILE_TIMEOUT => std::thread::sleep(Duration::from_millis(1)),
+            Err(Errno::EWOULDBLOCK | Errno::EINTR) => {
+                log::warn!("Another msi-center instance has held {} for {:?}, not accessing the EC", LOCK_FILE_PATH, LOCK_FILE_TIMEOUT);
+                return Err(EcError::Busy(LOCK_FILE_PATH));
+            }
+            Err(e) => {
+                log::warn!("Could not lock {}: {}", LOCK_FILE_PATH, e);
+                return Err(EcError::OpenError(e.into()));
+            }
+        }
+    }
+}
+
+fn lock_ec() -> Result<EcLock> {
+    // A panic in the middle of an access runs the panic hook, which restores the
+    // fans, before unwinding releases the lock. The mutex isn't reentrant, so the
+    // hook goes ahead under the lock its thread already holds.
+    if std::thread::panicking() && HOLDS_EC_LOCK.with(|holds| holds.get()) {
+        return Ok(EcLock { file: None, guard: None });
+    }
+    let guard = EC_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    HOLDS_EC_LOCK.with(|holds| holds.set(true));
+    let mut lock = EcLock { file: None, guard: Some(guard) };
+    // Dropping `lock` on failure releases the mutex again.
+    if let Some(file) = lock_file() {
+        lock_across_processes(file)?;
+        lock.file = Some(file);
+    }
+    Ok(lock)
+}
+
+/// Set when the user asked to write registers outside the safe list.
+static UNSAFE_WRITES_ALLOWED: AtomicBool = AtomicBool::new(false);
+
+/// Lets every EC instance of this process write any register (`--force`, or the
+/// GUI's expert mode). Off by default: a stray write can leave the fans stuck
+/// until the next reboot.
+pub fn allow_unsafe_writes(allowed: bool) {
+    UNSAFE_WRITES_ALLOWED.store(allowed, Ordering::Relaxed);
+}
+
+pub fn unsafe_writes_allowed() -> bool {
+    UNSAFE_WRITES_ALLOWED.load(Ordering::Relaxed)
+}
+
+/// Sets the handshake timing for every EC instance of this process.
+pub fn set_port_timing(timing: PortTiming) {
+    *PORT_TIMING.lock().unwrap_or_else(PoisonError::into_inner) = Some(timing);
+}
+
+fn port_timing() -> PortTiming {
+    PORT_TIMING.lock().unwrap_or_else(PoisonError::into_inner).unwrap_or_default()
+}
+
+/// Byte order of 16-bit values spread over two consecutive EC registers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordOrder {
+    /// High byte at the lower address, as MSI firmware stores fan tachometers.
+    BigEndian,
+    LittleEndian,
+}
+
+/// Consecutive port timeouts after which the port backends are abandoned for the
+/// rest of the process, so later calls don't each wait out the full timeout.
+const PORT_TIMEOUT_LIMIT: u32 = 3;
+
+static PORT_TIMEOUTS: AtomicU32 = AtomicU32::new(0);
+static PORT_BACKEND_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// How the port backends reach the EC's I/O ports.
+enum PortAccess {
+    /// Reads and writes at the port's offset in `/dev/port`.
+    DevPort(File),
+    /// `in`/`out` instructions, for kernels without `/dev/port`.
+    Ioperm,
+}
 
 pub struct EmbeddedController {
-    port_file: Option<File>,
+    port: Option<PortAccess>,
     use_acpi: bool,
     acpi_path: Option<String>,
+    mock: Option<Arc<Mutex<MockEc>>>,
+    registers: RegisterMap,
+    capabilities: Capabilities,
+    cache: ReadCache,
+}
+
+/// How long register values are reused by default (`ec_read_cache_ms` in the config).
+const DEFAULT_READ_CACHE_MS: u64 = 250;
+
+static READ_CACHE_MS: AtomicU64 = AtomicU64::new(DEFAULT_READ_CACHE_MS);
+
+/// Sets how long EC instances opened from now on reuse a register's value before
+/// reading it again; zero turns the cache off.
+pub fn set_read_cache_ttl(ttl: Duration) {
+    READ_CACHE_MS.store(ttl.as_millis() as u64, Ordering::Relaxed);
+}
+
+/// Register values read recently, so the several readers of one refresh (status,
+/// fan info, scenario info) don't each go to the EC. Writes drop the register.
+#[derive(Debug, Default)]
+struct ReadCache {
+    /// `None` when disabled.
+    ttl: Option<Duration>,
+    values: HashMap<u8, (u8, Instant)>,
+}
+
+impl ReadCache {
+    fn new() -> Self {
+        let ttl = Some(Duration::from_millis(READ_CACHE_MS.load(Ordering::Relaxed))).filter(|ttl| !ttl.is_zero());
+        Self { ttl, values: HashMap::new() }
+    }
+
+    fn get(&self, address: u8) -> Option<u8> {
+        let ttl = self.ttl?;
+        // Recorded traces need every read to reach the EC to be replayable.
+        if trace::is_recording() {
+            return None;
+        }
+        self.values.get(&address).filter(|(_, read_at)| read_at.elapsed() < ttl).map(|(value, _)| *value)
+    }
+
+    fn insert(&mut self, address: u8, value: u8) {
+        if self.ttl.is_some() {
+            self.values.insert(address, (value, Instant::now()));
+        }
+    }
+
+    fn remove(&mut self, address: u8) {
+        self.values.remove(&address);
+    }
 }
 
 impl EmbeddedController {
+    /// Opens the first backend of [`backend_order`] that works. Under kernel
+    /// lockdown that is the msi-ec driver unless the user chose otherwise.
     pub fn new() -> Result<Self> {
-        if let Ok(ec) = Self::try_direct_port_access() {
-            return Ok(ec);
+        let order = backend_order();
+        for backend in &order {
+            if backend.is_port() && PORT_BACKEND_DISABLED.load(Ordering::Relaxed) {
+                log::debug!("EC backend {} disabled after repeated timeouts", backend.name());
+                continue;
+            }
+            match backend.probe()() {
+                Ok(ec) => {
+                    log::debug!("Using EC backend: {}", ec.backend_name());
+                    return Ok(ec);
+                }
+                Err(e) => log::debug!("EC backend {} unavailable: {}", backend.name(), e),
+            }
         }
 
-        if let Ok(ec) = Self::try_acpi_access() {
-            return Ok(ec);
+        let tried: Vec<String> = order.iter().map(ToString::to_string).collect();
+        log::warn!("No EC backend available (tried {})", tried.join(", "));
+        if let Some(warning) = crate::system::lockdown_warning() {
+            log::warn!("{}", warning);
+        }
+        Err(EcError::NotSupported)
+    }
+
+    /// An EC backed by `mock` instead of hardware, for replaying recorded traces.
+    /// Instances sharing one mock see each other's writes.
+    pub fn with_mock(mock: Arc<Mutex<MockEc>>) -> Self {
+        Self {
+            port: None,
+            use_acpi: false,
+            acpi_path: None,
+            mock: Some(mock),
+            registers: RegisterMap::default(),
+            capabilities: Capabilities::default(),
+            // Replays expect every read to reach the mock.
+            cache: ReadCache::default(),
         }
+    }
+
+    /// Addresses of this machine's registers, from its quirk file if it has one.
+    pub fn registers(&self) -> RegisterMap {
+        self.registers
+    }
 
-        if let Ok(ec) = Self::try_msi_ec_driver() {
-            return Ok(ec);
+    pub fn set_registers(&mut self, registers: RegisterMap) {
+        self.registers = registers;
+    }
+
+    /// Fan count and optional controls of this machine.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.capabilities = capabilities;
+    }
+
+    /// Which controls work on this machine through this backend. Raw backends reach
+    /// every register of the model's map; the msi-ec driver only those it exposes.
+    pub fn support(&self) -> Support {
+        let caps = self.capabilities;
+        let registers = self.registers;
+        let raw = self.backend_name() != "msi-ec driver";
+        let writable = |address: u8| raw || msi_ec::attribute_for(address, &registers).is_some_and(|attribute| attribute.exists());
+        Support {
+            fan_mode: writable(registers.fan_mode),
+            fan_curves: writable(registers.fan1_base),
+            cooler_boost: caps.cooler_boost && writable(registers.cooler_boost),
+            shift_mode: caps.shift_mode && writable(registers.shift_mode),
+            super_battery: caps.super_battery && writable(registers.super_battery),
+            second_fan: caps.fans > 1,
+            third_fan: caps.fans > 2,
+            fan_rpm: self.word_order().is_some(),
+            battery_threshold: writable(registers.battery_charge),
+            keyboard_backlight: writable(registers.kbd_backlight),
+            webcam: writable(registers.webcam),
+            lid_logo: caps.lid_logo.is_some_and(|logo| writable(logo.register)),
+            indicator_led: caps.indicator_led.is_some_and(|led| writable(led.register)),
         }
+    }
 
-        Err(EcError::NotSupported)
+    pub fn is_mock(&self) -> bool {
+        self.mock.is_some()
+    }
+
+    pub fn backend_name(&self) -> &'static str {
+        if self.mock.is_some() {
+            return "mock";
+        }
+        match (&self.port, &self.acpi_path) {
+            (Some(PortAccess::DevPort(_)), _) => "/dev/port",
+            (Some(PortAccess::Ioperm), _) => "ioperm",
+            (None, Some(path)) if path.contains("msi-ec") => "msi-ec driver",
+            (None, Some(_)) => "debugfs ec_sys",
+            (None, None) => "none",
+        }
     }
 
     fn try_direct_port_access() -> Result<Self> {
@@ -73,9 +631,34 @@ impl EmbeddedController {
             })?;
 
         Ok(Self {
-            port_file: Some(file),
+            port: Some(PortAccess::DevPort(file)),
             use_acpi: false,
             acpi_path: None,
+            mock: None,
+            registers: quirks::registers(),
+            capabilities: quirks::capabilities(),
+            cache: ReadCache::new(),
+        })
+    }
+
+    /// Port I/O without `/dev/port`, e.g. on kernels built with `CONFIG_DEVPORT=n`.
+    fn try_ioperm_access() -> Result<Self> {
+        ioport::request_access().map_err(|e| {
+            if e.kind() == std::io::ErrorKind::PermissionDenied {
+                EcError::PermissionDenied
+            } else {
+                EcError::OpenError(e)
+            }
+        })?;
+
+        Ok(Self {
+            port: Some(PortAccess::Ioperm),
+            use_acpi: false,
+            acpi_path: None,
+            mock: None,
+            registers: quirks::registers(),
+            capabilities: quirks::capabilities(),
+            cache: ReadCache::new(),
         })
     }
 
@@ -83,102 +666,467 @@ impl EmbeddedController {
         let acpi_path = "/sys/kernel/debug/ec/ec0/io";
         if std::path::Path::new(acpi_path).exists() {
             return Ok(Self {
-                port_file: None,
+                port: None,
                 use_acpi: true,
                 acpi_path: Some(acpi_path.to_string()),
+                mock: None,
+                registers: quirks::registers(),
+                capabilities: quirks::capabilities(),
+                cache: ReadCache::new(),
             });
         }
         Err(EcError::NotSupported)
     }
 
     fn try_msi_ec_driver() -> Result<Self> {
-        let msi_ec_path = "/sys/devices/platform/msi-ec";
-        if std::path::Path::new(msi_ec_path).exists() {
+        if std::path::Path::new(msi_ec::MSI_EC_PATH).exists() {
             return Ok(Self {
-                port_file: None,
+                port: None,
                 use_acpi: true,
-                acpi_path: Some(msi_ec_path.to_string()),
+                acpi_path: Some(msi_ec::MSI_EC_PATH.to_string()),
+                mock: None,
+                registers: quirks::registers(),
+                capabilities: quirks::capabilities(),
+                cache: ReadCache::new(),
             });
         }
         Err(EcError::NotSupported)
     }
 
-    fn wait_ec_ibf_clear(&mut self) -> Result<()> {
-        if let Some(ref mut file) = self.port_file {
-            for _ in 0..10000 {
-                file.seek(SeekFrom::Start(EC_SC as u64))?;
-                let mut buf = [0u8; 1];
-                file.read_exact(&mut buf)?;
-                if (buf[0] & EC_SC_IBF) == 0 {
-                    return Ok(());
-                }
-                std::thread::sleep(std::time::Duration::from_micros(10));
+    /// Polls the status register until `ready` holds for it, or the handshake timeout passes.
+    fn wait_ec_status(&mut self, ready: impl Fn(u8) -> bool) -> Result<()> {
+        let timing = port_timing();
+        let started = Instant::now();
+        loop {
+            if ready(self.read_port(EC_SC)?) {
+                return Ok(());
             }
+            if started.elapsed() >= timing.timeout() {
+                return Err(EcError::Timeout);
+            }
+            std::thread::sleep(timing.poll_interval());
         }
-        Err(EcError::IoFailed)
+    }
+
+    fn wait_ec_ibf_clear(&mut self) -> Result<()> {
+        self.wait_ec_status(|status| status & EC_SC_IBF == 0)
     }
 
     fn wait_ec_obf_set(&mut self) -> Result<()> {
-        if let Some(ref mut file) = self.port_file {
-            for _ in 0..10000 {
-                file.seek(SeekFrom::Start(EC_SC as u64))?;
-                let mut buf = [0u8; 1];
-                file.read_exact(&mut buf)?;
-                if (buf[0] & EC_SC_OBF) != 0 {
-                    return Ok(());
+        self.wait_ec_status(|status| status & EC_SC_OBF != 0)
+    }
+
+    /// Runs a port transaction, retrying timed-out attempts with a growing delay.
+    fn with_port_retries<T>(&mut self, mut transaction: impl FnMut(&mut Self) -> Result<T>) -> Result<T> {
+        let timing = port_timing();
+        let mut delay = timing.retry_delay();
+        let mut attempt = 0;
+        loop {
+            match transaction(self) {
+                Err(EcError::Timeout) if attempt < timing.retries => {
+                    attempt += 1;
+                    log::debug!("EC handshake timed out, retrying ({}/{}) in {:?}", attempt, timing.retries, delay);
+                    std::thread::sleep(delay);
+                    delay *= 2;
+                    // A stale byte in the output buffer would be mistaken for the next reply.
+                    let _ = self.read_port(EC_DATA);
                 }
-                std::thread::sleep(std::time::Duration::from_micros(10));
+                result => return result,
             }
         }
-        Err(EcError::IoFailed)
     }
 
     fn write_port(&mut self, port: u16, value: u8) -> Result<()> {
-        if let Some(ref mut file) = self.port_file {
-            file.seek(SeekFrom::Start(port as u64))?;
-            file.write_all(&[value])?;
-            Ok(())
-        } else {
-            Err(EcError::IoFailed)
+        match self.port {
+            Some(PortAccess::DevPort(ref mut file)) => {
+                file.seek(SeekFrom::Start(port as u64))?;
+                file.write_all(&[value])?;
+                Ok(())
+            }
+            Some(PortAccess::Ioperm) => Ok(ioport::outb(port, value)?),
+            None => Err(EcError::IoFailed),
         }
     }
 
     fn read_port(&mut self, port: u16) -> Result<u8> {
-        if let Some(ref mut file) = self.port_file {
-            file.seek(SeekFrom::Start(port as u64))?;
-            let mut buf = [0u8; 1];
-            file.read_exact(&mut buf)?;
-            Ok(buf[0])
-        } else {
-            Err(EcError::IoFailed)
+        match self.port {
+            Some(PortAccess::DevPort(ref mut file)) => {
+                file.seek(SeekFrom::Start(port as u64))?;
+                let mut buf = [0u8; 1];
+                file.read_exact(&mut buf)?;
+                Ok(buf[0])
+            }
+            Some(PortAccess::Ioperm) => Ok(ioport::inb(port)?),
+            None => Err(EcError::IoFailed),
         }
     }
 
     pub fn read_byte(&mut self, address: u8) -> Result<u8> {
+        if let Some(value) = self.cache.get(address) {
+            return Ok(value);
+        }
+        let _lock = lock_ec()?;
+        self.read_byte_logged(address)
+    }
+
+    /// Changes how long this instance reuses register values; `None` reads every
+    /// time, e.g. for watching registers change.
+    pub fn set_read_cache(&mut self, ttl: Option<Duration>) {
+        self.cache = ReadCache { ttl: ttl.filter(|ttl| !ttl.is_zero()), values: HashMap::new() };
+    }
+
+    /// Drops the cached value of a register written without going through this instance.
+    pub fn forget_cached(&mut self, address: u8) {
+        self.cache.remove(address);
+    }
+
+    /// Reads the EC itself, bypassing the cache, and caches the result.
+    fn read_byte_logged(&mut self, address: u8) -> Result<u8> {
+        let result = self.read_byte_inner(address);
+        match result {
+            Ok(value) => {
+                log::trace!("EC read {:#04x} = {:#04x} via {}", address, value, self.backend_name());
+                trace::record(TraceOp::Read, address, value);
+                self.cache.insert(address, value);
+            }
+            Err(ref e) => log::debug!("EC read {:#04x} via {} failed: {}", address, self.backend_name(), e),
+        }
+        result
+    }
+
+    fn read_byte_inner(&mut self, address: u8) -> Result<u8> {
+        if let Some(ref mock) = self.mock {
+            return mock.lock().unwrap_or_else(PoisonError::into_inner).read(address).ok_or(EcError::NotSupported);
+        }
         if self.use_acpi {
             return self.read_byte_acpi(address);
         }
 
-        self.wait_ec_ibf_clear()?;
-        self.write_port(EC_SC, EC_SC_READ_CMD)?;
-        self.wait_ec_ibf_clear()?;
-        self.write_port(EC_DATA, address)?;
-        self.wait_ec_obf_set()?;
-        self.read_port(EC_DATA)
+        let result = self.read_byte_port(address);
+        if self.check_port_watchdog(&result) {
+            return self.read_byte_acpi(address);
+        }
+        result
+    }
+
+    fn read_byte_port(&mut self, address: u8) -> Result<u8> {
+        self.with_port_retries(|ec| {
+            ec.wait_ec_ibf_clear()?;
+            ec.write_port(EC_SC, EC_SC_READ_CMD)?;
+            ec.wait_ec_ibf_clear()?;
+            ec.write_port(EC_DATA, address)?;
+            ec.wait_ec_obf_set()?;
+            ec.read_port(EC_DATA)
+        })
     }
 
     pub fn write_byte(&mut self, address: u8, value: u8) -> Result<()> {
+        let _lock = lock_ec()?;
+        self.write_byte_logged(address, value)
+    }
+
+    /// Refuses writes outside this model's safe registers unless the user overrode
+    /// the check. The mock stands in for no real EC, so replays write freely.
+    pub fn check_write(&self, address: u8, value: u8) -> Result<()> {
+        if self.is_mock() || unsafe_writes_allowed() {
+            return Ok(());
+        }
+        if self.registers.is_safe_write(address, &self.capabilities) || quirks::extra_writable().contains(&address) {
+            return Ok(());
+        }
+        log::warn!(
+            "Refused EC write {:#04x} = {:#04x}: not a known-safe register on this model, pass --force before the command (or enable expert mode in the GUI) to write it anyway",
+            address,
+            value
+        );
+        Err(EcError::UnsafeWrite(address))
+    }
+
+    fn write_byte_logged(&mut self, address: u8, value: u8) -> Result<()> {
+        self.check_write(address, value)?;
+        // The EC may not keep what was written, so the next read goes to it.
+        self.cache.remove(address);
+        let result = self.write_byte_inner(address, value);
+        match result {
+            Ok(()) => {
+                log::debug!("EC write {:#04x} = {:#04x} via {}", address, value, self.backend_name());
+                trace::record(TraceOp::Write, address, value);
+                self.verify_write(address, value);
+            }
+            Err(ref e) => {
+                log::warn!("EC write {:#04x} = {:#04x} via {} failed: {}", address, value, self.backend_name(), e);
+                if !self.is_mock() {
+                    failures::record(address, value, self.backend_name(), e.to_string());
+                }
+            }
+        }
+        result
+    }
+
+    /// Applies the queued writes in order under one lock, then reads every register
+    /// back. Writes that fail are not retried and don't stop the rest of the batch;
+    /// if the lock can't be taken, every write is rejected.
+    pub fn apply_batch(&mut self, batch: &WriteBatch) -> BatchReport {
+        let mut report = BatchReport::default();
+        let _lock = match lock_ec() {
+            Ok(lock) => lock,
+            Err(e) => {
+                let reason = e.to_string();
+                report.rejected = batch.writes.iter().map(|&(address, value)| RejectedWrite { address, value, actual: None, reason: reason.clone() }).collect();
+                return report;
+            }
+        };
+        let mut written = Vec::new();
+        for &(address, value) in &batch.writes {
+            match self.write_byte_logged(address, value) {
+                Ok(()) => written.push((address, value)),
+                Err(e) => report.rejected.push(RejectedWrite { address, value, actual: None, reason: e.to_string() }),
+            }
+        }
+
+        for (i, &(address, value)) in written.iter().enumerate() {
+            // Only the last write to a register has to stick.
+            if written[i + 1..].iter().any(|(later, _)| *later == address) {
+                continue;
+            }
+            // Written through msi-ec, which may store other bits than the ones asked for.
+            if let Some(attribute) = self.driver_attribute(address) {
+                match attribute.holds(value) {
+                    Ok(true) => report.verified += 1,
+                    Ok(false) => {
+                        report.rejected.push(RejectedWrite { address, value, actual: None, reason: "msi-ec reports another value".to_string() })
+                    }
+                    Err(_) => report.unverified += 1,
+                }
+                continue;
+            }
+            match self.read_byte_logged(address) {
+                Ok(actual) if actual == value => report.verified += 1,
+                Ok(actual) => {
+                    if !self.is_mock() {
+                        failures::record(address, value, self.backend_name(), format!("read back {:#04x} in a batch", actual));
+                    }
+                    report.rejected.push(RejectedWrite { address, value, actual: Some(actual), reason: "did not stick".to_string() });
+                }
+                Err(e) => {
+                    log::debug!("Could not read back {:#04x} via {}: {}", address, self.backend_name(), e);
+                    report.unverified += 1;
+                }
+            }
+        }
+        report
+    }
+
+    /// Whether a backup holds `address`: the registers [`Self::check_write`] allows on
+    /// this model without an override. Sensors and unmapped registers are left out.
+    pub fn is_backed_up(&self, address: u8) -> bool {
+        self.registers.is_safe_write(address, &self.capabilities) || quirks::extra_writable().contains(&address)
+    }
+
+    /// Saves this model's writable registers for [`Self::restore`].
+    pub fn backup(&mut self) -> EcSnapshot {
+        let registers = self.registers;
+        let capabilities = self.capabilities;
+        let extra = quirks::extra_writable();
+        EcSnapshot::capture_only(self, |address| registers.is_safe_write(address, &capabilities) || extra.contains(&address))
+    }
+
+    /// Writes back the registers of `backup` (or of any snapshot) that this model
+    /// allows writing and that differ from what the EC holds now, as one batch.
+    /// Fan tables sit below the fan mode register, so a restored curve is in place
+    /// before Advanced mode switches to it.
+    pub fn restore(&mut self, backup: &EcSnapshot) -> BatchReport {
+        let mut batch = WriteBatch::default();
+        for address in 0..=u8::MAX {
+            let Some(value) = backup.get(address) else {
+                continue;
+            };
+            if self.is_backed_up(address) && self.read_byte(address).ok() != Some(value) {
+                batch.write(address, value);
+            }
+        }
+        if batch.is_empty() {
+            return BatchReport::default();
+        }
+        log::info!("Restoring {} EC registers from the backup taken {}", batch.len(), backup.taken_at);
+        self.apply_batch(&batch)
+    }
+
+    /// How this backend lays out words, or `None` if it cannot access raw
+    /// register pairs. Port and debugfs access see the EC's own layout; the
+    /// msi-ec driver only exposes named attributes.
+    pub fn word_order(&self) -> Option<WordOrder> {
+        match self.backend_name() {
+            "/dev/port" | "ioperm" | "debugfs ec_sys" | "mock" => Some(WordOrder::BigEndian),
+            _ => None,
+        }
+    }
+
+    pub fn read_word(&mut self, address: u8) -> Result<u16> {
+        let order = self.word_order().ok_or(EcError::NotSupported)?;
+        let next = address.checked_add(1).ok_or(EcError::InvalidAddress(address as u16 + 1))?;
+        let bytes = match (self.cache.get(address), self.cache.get(next)) {
+            (Some(first), Some(second)) => [first, second],
+            _ => {
+                let _lock = lock_ec()?;
+                [self.read_byte_logged(address)?, self.read_byte_logged(next)?]
+            }
+        };
+        Ok(match order {
+            WordOrder::BigEndian => u16::from_be_bytes(bytes),
+            WordOrder::LittleEndian => u16::from_le_bytes(bytes),
+        })
+    }
+
+    pub fn write_word(&mut self, address: u8, value: u16) -> Result<()> {
+        let order = self.word_order().ok_or(EcError::NotSupported)?;
+        let next = address.checked_add(1).ok_or(EcError::InvalidAddress(address as u16 + 1))?;
+        let [first, second] = match order {
+            WordOrder::BigEndian => value.to_be_bytes(),
+            WordOrder::LittleEndian => value.to_le_bytes(),
+        };
+        let _lock = lock_ec()?;
+        self.write_byte_logged(address, first)?;
+        self.write_byte_logged(next, second)
+    }
+
+    /// Reads a control register back after writing it, since the EC may silently
+    /// ignore a write it does not accept. Mismatches are recorded, not returned.
+    pub fn verify_write(&mut self, address: u8, value: u8) {
+        let registers = self.registers;
+        let controls = [registers.fan_mode, registers.cooler_boost, registers.shift_mode, registers.super_battery];
+        if self.is_mock() || !controls.contains(&address) {
+            return;
+        }
+        // The msi-ec driver exposes these as named attributes rather than raw bytes.
+        if self.backend_name() == "msi-ec driver" || self.driver_attribute(address).is_some() {
+            return;
+        }
+        if let Ok(actual) = self.read_byte_inner(address)
+            && actual != value
+        {
+            log::warn!("EC register {:#04x} reads {:#04x} after writing {:#04x}", address, actual, value);
+            let reason = match self.write_via_acpi_method(address, value) {
+                Some(Ok(())) => return,
+                Some(Err(e)) => format!("read back {:#04x}; ACPI fallback: {}", actual, e),
+                None => format!("read back {:#04x}", actual),
+            };
+            failures::record(address, value, self.backend_name(), reason);
+        }
+    }
+
+    /// Retries a write the firmware ignored through the ACPI method the model's
+    /// quirk maps to the register. `None` if there is no such method.
+    fn write_via_acpi_method(&mut self, address: u8, value: u8) -> Option<std::result::Result<(), String>> {
+        let method = acpi_call::method_for(&quirks::acpi_methods(), address, &self.registers)?;
+        let result = acpi_call::call(&method, value).map_err(|e| e.to_string()).and_then(|_| {
+            match self.read_byte_inner(address) {
+                Ok(actual) if actual == value => Ok(()),
+                Ok(actual) => Err(format!("{} left the register at {:#04x}", method, actual)),
+                Err(e) => Err(e.to_string()),
+            }
+        });
+        match result {
+            Ok(()) => log::info!("EC register {:#04x} set to {:#04x} through ACPI method {}", address, value, method),
+            Err(ref e) => log::warn!("ACPI fallback for register {:#04x} failed: {}", address, e),
+        }
+        Some(result)
+    }
+
+    fn write_byte_inner(&mut self, address: u8, value: u8) -> Result<()> {
+        if let Some(ref mock) = self.mock {
+            mock.lock().unwrap_or_else(PoisonError::into_inner).write(address, value);
+            return Ok(());
+        }
+        if let Some(attribute) = self.driver_attribute(address) {
+            match attribute.write(value) {
+                Ok(()) => return Ok(()),
+                Err(e) => log::warn!(
+                    "msi-ec did not take {:#04x} = {:#04x} ({}), writing the register directly; the driver may report the old value",
+                    address,
+                    value,
+                    e
+                ),
+            }
+        }
         if self.use_acpi {
             return self.write_byte_acpi(address, value);
         }
 
-        self.wait_ec_ibf_clear()?;
-        self.write_port(EC_SC, EC_SC_WRITE_CMD)?;
-        self.wait_ec_ibf_clear()?;
-        self.write_port(EC_DATA, address)?;
-        self.wait_ec_ibf_clear()?;
-        self.write_port(EC_DATA, value)?;
-        Ok(())
+        let result = self.write_byte_port(address, value);
+        if self.check_port_watchdog(&result) {
+            return self.write_byte_acpi(address, value);
+        }
+        result
+    }
+
+    /// The msi-ec attribute a setting is written through while a raw backend is in use.
+    /// The driver keeps its own idea of the settings it manages, which a raw write
+    /// behind its back would leave stale, so those go through the driver instead.
+    fn driver_attribute(&self, address: u8) -> Option<msi_ec::Attribute> {
+        if !self.shares_ec_with_driver() {
+            return None;
+        }
+        msi_ec::attribute_for(address, &self.registers).filter(|attribute| attribute.is_setting() && attribute.exists())
+    }
+
+    /// Whether msi-ec is loaded while this instance reaches the EC through a raw backend.
+    pub fn shares_ec_with_driver(&self) -> bool {
+        matches!(self.backend_name(), "/dev/port" | "ioperm" | "debugfs ec_sys") && msi_ec::driver_loaded()
+    }
+
+    /// Counts consecutive port timeouts and, once the limit is hit, switches this
+    /// instance (and all later ones) to another backend. Returns true if it switched,
+    /// in which case the caller retries the operation on the new backend.
+    fn check_port_watchdog<T>(&mut self, result: &Result<T>) -> bool {
+        match result {
+            Err(EcError::Timeout) => {}
+            _ => {
+                PORT_TIMEOUTS.store(0, Ordering::Relaxed);
+                return false;
+            }
+        }
+
+        let timeouts = PORT_TIMEOUTS.fetch_add(1, Ordering::Relaxed) + 1;
+        if timeouts < PORT_TIMEOUT_LIMIT {
+            return false;
+        }
+
+        PORT_BACKEND_DISABLED.store(true, Ordering::Relaxed);
+        let port_backend = self.backend_name();
+        let fallbacks = backend_order().into_iter().filter(|backend| !backend.is_port());
+        match fallbacks.into_iter().find_map(|backend| backend.probe()().ok()) {
+            Some(ec) => {
+                *self = ec;
+                log::warn!(
+                    "EC stopped responding on {} ({} timeouts in a row), falling back to {}",
+                    port_backend,
+                    timeouts,
+                    self.backend_name()
+                );
+                true
+            }
+            None => {
+                log::warn!("EC stopped responding on {} ({} timeouts in a row) and no fallback backend is available", port_backend, timeouts);
+                false
+            }
+        }
+    }
+
+    /// Whether the port backends were abandoned after repeated timeouts in this process.
+    pub fn port_watchdog_tripped() -> bool {
+        PORT_BACKEND_DISABLED.load(Ordering::Relaxed)
+    }
+
+    fn write_byte_port(&mut self, address: u8, value: u8) -> Result<()> {
+        self.with_port_retries(|ec| {
+            ec.wait_ec_ibf_clear()?;
+            ec.write_port(EC_SC, EC_SC_WRITE_CMD)?;
+            ec.wait_ec_ibf_clear()?;
+            ec.write_port(EC_DATA, address)?;
+            ec.wait_ec_ibf_clear()?;
+            ec.write_port(EC_DATA, value)
+        })
     }
 
     fn read_byte_acpi(&self, address: u8) -> Result<u8> {
@@ -209,33 +1157,11 @@ impl EmbeddedController {
     }
 
     fn read_msi_ec_driver(&self, address: u8) -> Result<u8> {
-        let sysfs_map = self.get_sysfs_mapping(address);
-        if let Some(path) = sysfs_map {
-            let content = std::fs::read_to_string(path)?;
-            let value: u8 = content.trim().parse().unwrap_or(0);
-            return Ok(value);
-        }
-        Err(EcError::NotSupported)
+        msi_ec::attribute_for(address, &self.registers).ok_or(EcError::NotSupported)?.read()
     }
 
     fn write_msi_ec_driver(&self, address: u8, value: u8) -> Result<()> {
-        let sysfs_map = self.get_sysfs_mapping(address);
-        if let Some(path) = sysfs_map {
-            std::fs::write(path, format!("{}", value))?;
-            return Ok(());
-        }
-        Err(EcError::NotSupported)
-    }
-
-    fn get_sysfs_mapping(&self, address: u8) -> Option<String> {
-        let base = "/sys/devices/platform/msi-ec";
-        match address {
-            MSI_ADDRESS_SHIFT_MODE => Some(format!("{}/shift_mode", base)),
-            MSI_ADDRESS_SUPER_BATTERY => Some(format!("{}/super_battery", base)),
-            MSI_ADDRESS_COOLER_BOOST => Some(format!("{}/cooler_boost", base)),
-            MSI_ADDRESS_FAN_MODE => Some(format!("{}/fan_mode", base)),
-            _ => None,
-        }
+        msi_ec::attribute_for(address, &self.registers).ok_or(EcError::NotSupported)?.write(value)
     }
 
     pub fn is_msi_laptop(&mut self) -> bool {
@@ -250,9 +1176,94 @@ impl EmbeddedController {
 impl Default for EmbeddedController {
     fn default() -> Self {
         Self::new().unwrap_or(Self {
-            port_file: None,
+            port: None,
             use_acpi: false,
             acpi_path: None,
+            mock: None,
+            registers: quirks::registers(),
+            capabilities: quirks::capabilities(),
+            cache: ReadCache::new(),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::trace::{TraceOp, mock_ec, mock_ec_with};
+
+    #[test]
+    fn words_span_two_registers_high_byte_first() {
+        let mut registers = vec![Some(0u8); 256];
+        registers[0xC8] = Some(0x01);
+        registers[0xC9] = Some(0x2C);
+        let (mock, mut ec) = mock_ec_with(registers);
+
+        assert_eq!(ec.read_word(0xC8).unwrap(), 0x012C);
+        ec.write_word(0xCA, 0x0200).unwrap();
+        assert_eq!(ec.read_word(0xCA).unwrap(), 0x0200);
+        assert_eq!(mock.lock().unwrap().read(0xCB), Some(0x00));
+    }
+
+    #[test]
+    fn panicking_while_holding_the_lock_does_not_deadlock() {
+        /// Writes to the EC while the thread unwinds, as the panic hook does.
+        struct RestoreOnUnwind(super::EmbeddedController, std::sync::mpsc::Sender<()>);
+        impl Drop for RestoreOnUnwind {
+            fn drop(&mut self) {
+                self.0.write_byte(0xD4, 0x0D).unwrap();
+                self.1.send(()).unwrap();
+            }
+        }
+
+        let (mock, ec) = mock_ec(0);
+        let (done, restored) = std::sync::mpsc::channel();
+        let panicking = std::thread::spawn(move || {
+            let _held = super::lock_ec().unwrap();
+            let _restore = RestoreOnUnwind(ec, done);
+            panic!("fan write failed mid-batch");
+        });
+        assert!(restored.recv_timeout(std::time::Duration::from_secs(5)).is_ok(), "the EC write during unwinding deadlocked");
+        assert!(panicking.join().is_err());
+        assert_eq!(mock.lock().unwrap().read(0xD4), Some(0x0D));
+        // The lock is free again afterwards.
+        drop(super::lock_ec().unwrap());
+    }
+
+    #[test]
+    fn repeated_reads_are_served_from_the_cache_until_written() {
+        let (mock, mut ec) = mock_ec(0x0D);
+        ec.set_read_cache(Some(std::time::Duration::from_secs(60)));
+        let reads = || mock.lock().unwrap().log().iter().filter(|o| o.op == TraceOp::Read).count();
+
+        assert_eq!(ec.read_byte(0xD4).unwrap(), 0x0D);
+        assert_eq!(ec.read_byte(0xD4).unwrap(), 0x0D);
+        assert_eq!(reads(), 1);
+
+        ec.write_byte(0xD4, 0x01).unwrap();
+        assert_eq!(ec.read_byte(0xD4).unwrap(), 0x01);
+        assert_eq!(reads(), 2);
+
+        ec.set_read_cache(None);
+        ec.read_byte(0xD4).unwrap();
+        assert_eq!(reads(), 3);
+    }
+
+    #[test]
+    fn restore_writes_back_only_changed_writable_registers() {
+        let (mock, mut ec) = mock_ec(0x0D);
+        let backup = ec.backup();
+        assert_eq!(backup.get(0xD4), Some(0x0D));
+        assert_eq!(backup.get(0x68), None, "sensors are not backed up");
+
+        ec.write_byte(0xD4, 0x01).unwrap();
+        ec.write_byte(0x72, 0x40).unwrap();
+        ec.write_byte(0x68, 0x50).unwrap();
+        let report = ec.restore(&backup);
+        assert!(report.is_ok());
+        assert_eq!(report.verified, 2);
+
+        let writes: Vec<(u8, u8)> = mock.lock().unwrap().writes().iter().skip(3).map(|o| (o.address, o.value)).collect();
+        assert_eq!(writes, [(0x72, 0x0D), (0xD4, 0x0D)]);
+        assert_eq!(ec.restore(&backup).verified, 0);
+    }
+}
@@ -0,0 +1,242 @@
+//! Register access through the msi-ec kernel driver, which exposes named sysfs
+//! attributes with string values instead of raw EC bytes. Each mapped register is
+//! translated both ways, so callers keep working with the bytes the EC itself
+//! would hold.
+
+use super::quirks::RegisterMap;
+use super::{EcError, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MSI_EC_PATH: &str = "/sys/devices/platform/msi-ec";
+const KBD_BACKLIGHT_PATHS: &[&str] = &[
+    "/sys/class/leds/msiacpi::kbd_backlight",
+    "/sys/class/leds/msi::kbd_backlight",
+];
+const BATTERY_NAMES: &[&str] = &["BAT0", "BAT1", "BAT2"];
+
+/// Shift mode bytes and the names msi-ec uses for them.
+const SHIFT_MODES: [(u8, &str); 4] = [(0xC2, "eco"), (0xC1, "comfort"), (0xC0, "sport"), (0xC4, "turbo")];
+/// Fan mode bytes (low nibble of the register) and their msi-ec names.
+const FAN_MODES: [(u8, &str); 4] = [(0, "auto"), (1, "silent"), (2, "basic"), (3, "advanced")];
+
+/// Bit 7 of the charge limit and keyboard backlight registers, set whenever the
+/// value in the low bits is in effect.
+pub const VALUE_SET: u8 = 0x80;
+/// Highest keyboard backlight level the EC accepts.
+pub const MAX_KBD_LEVEL: u8 = 3;
+/// Bit of the webcam register that switches the camera on.
+pub const WEBCAM_ON: u8 = 0x02;
+
+/// How a register's byte is written as an attribute value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    /// Decimal, unchanged (temperatures, fan speeds).
+    Number,
+    /// "on" when any of the bits in the mask is set.
+    OnOff(u8),
+    /// Side of the Win key the Fn key sits on: "right" when the bits are set.
+    FnKeySide(u8),
+    ShiftMode,
+    FanMode,
+    /// Charge stop threshold in percent, stored as `0x80 | percent`.
+    ChargeLimit,
+    /// Backlight level 0–3, stored as `0x80 | level`.
+    KbdLevel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    path: PathBuf,
+    format: Format,
+}
+
+/// The attribute that holds the register at `address`, if the driver exposes it.
+/// Attributes that live outside the msi-ec directory (keyboard backlight, charge
+/// threshold) are only mapped when their device exists.
+pub fn attribute_for(address: u8, registers: &RegisterMap) -> Option<Attribute> {
+    let base = Path::new(MSI_EC_PATH);
+    let mapped = [
+        (registers.shift_mode, "shift_mode", Format::ShiftMode),
+        (registers.super_battery, "super_battery", Format::OnOff(0x01)),
+        (registers.cooler_boost, "cooler_boost", Format::OnOff(0x80)),
+        (registers.fan_mode, "fan_mode", Format::FanMode),
+        (registers.webcam, "webcam", Format::OnOff(WEBCAM_ON)),
+        (registers.webcam_block, "webcam_block", Format::OnOff(0x02)),
+        (registers.fn_win_swap, "fn_key", Format::FnKeySide(0x10)),
+        (registers.cpu_temp, "cpu/realtime_temperature", Format::Number),
+        (registers.gpu_temp, "gpu/realtime_temperature", Format::Number),
+        (registers.cpu_fan_speed, "cpu/realtime_fan_speed", Format::Number),
+        (registers.gpu_fan_speed, "gpu/realtime_fan_speed", Format::Number),
+    ];
+    if let Some((_, name, format)) = mapped.into_iter().find(|(register, _, _)| *register == address) {
+        return Some(Attribute { path: base.join(name), format });
+    }
+
+    if address == registers.kbd_backlight {
+        let device = KBD_BACKLIGHT_PATHS.iter().map(Path::new).find(|p| p.exists())?;
+        return Some(Attribute { path: device.join("brightness"), format: Format::KbdLevel });
+    }
+    if address == registers.battery_charge {
+        // msi-ec adds the threshold to the battery rather than to its own directory.
+        let path = BATTERY_NAMES
+            .iter()
+            .map(|bat| Path::new("/sys/class/power_supply").join(bat).join("charge_control_end_threshold"))
+            .find(|p| p.exists())?;
+        return Some(Attribute { path, format: Format::ChargeLimit });
+    }
+    None
+}
+
+/// Whether the driver is loaded, whichever backend this process reaches the EC through.
+pub fn driver_loaded() -> bool {
+    Path::new(MSI_EC_PATH).exists()
+}
+
+impl Attribute {
+    /// Whether this kernel's msi-ec exposes the attribute; older versions lack some.
+    pub fn exists(&self) -> bool {
+        self.path.exists()
+    }
+
+    /// Whether the attribute holds a setting the driver manages, rather than a sensor reading.
+    pub fn is_setting(&self) -> bool {
+        self.format != Format::Number
+    }
+
+    /// Whether the driver reports the setting as writing `value` would leave it.
+    pub fn holds(&self, value: u8) -> Result<bool> {
+        let content = fs::read_to_string(&self.path)?;
+        Ok(content.trim() == self.encode(value)?)
+    }
+
+    pub fn read(&self) -> Result<u8> {
+        let content = fs::read_to_string(&self.path)?;
+        self.decode(content.trim())
+    }
+
+    pub fn write(&self, value: u8) -> Result<()> {
+        let text = self.encode(value)?;
+        fs::write(&self.path, &text)?;
+        log::debug!("msi-ec {} = {}", self.path.display(), text);
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        self.path.strip_prefix(MSI_EC_PATH).unwrap_or(&self.path).display().to_string()
+    }
+
+    fn unexpected(&self, text: &str) -> EcError {
+        EcError::UnsupportedValue(self.name(), text.to_string())
+    }
+
+    fn decode(&self, text: &str) -> Result<u8> {
+        let number = || text.parse::<u8>().map_err(|_| self.unexpected(text));
+        match self.format {
+            Format::Number => number(),
+            Format::OnOff(mask) => match text {
+                "on" => Ok(mask),
+                "off" => Ok(0),
+                _ => Err(self.unexpected(text)),
+            },
+            Format::FnKeySide(mask) => match text {
+                "right" => Ok(mask),
+                "left" => Ok(0),
+                _ => Err(self.unexpected(text)),
+            },
+            Format::ShiftMode => byte_for(&SHIFT_MODES, text).ok_or_else(|| self.unexpected(text)),
+            Format::FanMode => byte_for(&FAN_MODES, text).ok_or_else(|| self.unexpected(text)),
+            Format::ChargeLimit => Ok(VALUE_SET | number()?.min(100)),
+            Format::KbdLevel => Ok(VALUE_SET | number()?.min(MAX_KBD_LEVEL)),
+        }
+    }
+
+    fn encode(&self, value: u8) -> Result<String> {
+        let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+        let text = match self.format {
+            Format::Number => value.to_string(),
+            Format::OnOff(mask) => on_off(value & mask != 0),
+            Format::FnKeySide(mask) => if value & mask != 0 { "right" } else { "left" }.to_string(),
+            Format::ShiftMode => self.listed(&SHIFT_MODES, value, available_shift_modes())?,
+            Format::FanMode => self.listed(&FAN_MODES, value & 0x0F, available_fan_modes())?,
+            // Without bit 7 the EC charges to full.
+            Format::ChargeLimit if value & VALUE_SET == 0 => "100".to_string(),
+            Format::ChargeLimit => (value & !VALUE_SET).min(100).to_string(),
+            Format::KbdLevel => (value & !VALUE_SET).min(MAX_KBD_LEVEL).to_string(),
+        };
+        Ok(text)
+    }
+
+    /// The driver's name for `value`, refused if the driver doesn't list it for this model.
+    fn listed(&self, names: &[(u8, &'static str)], value: u8, available: Option<Vec<String>>) -> Result<String> {
+        let name = names
+            .iter()
+            .find(|(byte, _)| *byte == value)
+            .map(|(_, name)| name.to_string())
+            .ok_or_else(|| self.unexpected(&format!("{:#04x}", value)))?;
+        if available.is_some_and(|modes| !modes.contains(&name)) {
+            return Err(self.unexpected(&name));
+        }
+        Ok(name)
+    }
+}
+
+fn byte_for(names: &[(u8, &str)], text: &str) -> Option<u8> {
+    names.iter().find(|(_, name)| *name == text).map(|(byte, _)| *byte)
+}
+
+fn available(list: &str) -> Option<Vec<String>> {
+    let content = fs::read_to_string(Path::new(MSI_EC_PATH).join(list)).ok()?;
+    Some(content.split_whitespace().map(str::to_string).collect())
+}
+
+/// Shift mode names the driver offers on this model, or `None` without msi-ec.
+pub fn available_shift_modes() -> Option<Vec<String>> {
+    available("available_shift_modes")
+}
+
+/// Fan mode names the driver offers on this model, or `None` without msi-ec.
+pub fn available_fan_modes() -> Option<Vec<String>> {
+    available("available_fan_modes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(format: Format) -> Attribute {
+        Attribute { path: PathBuf::from("/nonexistent"), format }
+    }
+
+    #[test]
+    fn bytes_round_trip_through_driver_values() {
+        let cooler_boost = attribute(Format::OnOff(0x80));
+        assert_eq!(cooler_boost.encode(0x83).unwrap(), "on");
+        assert_eq!(cooler_boost.decode("off").unwrap(), 0);
+
+        let shift_mode = attribute(Format::ShiftMode);
+        assert_eq!(shift_mode.decode("turbo").unwrap(), 0xC4);
+        assert!(shift_mode.decode("unspecified").is_err());
+
+        assert_eq!(attribute(Format::FanMode).encode(0x0D).unwrap_err().to_string(), "msi-ec driver does not accept /nonexistent = 0x0d");
+        assert_eq!(attribute(Format::FnKeySide(0x10)).encode(0x10).unwrap(), "right");
+
+        let charge = attribute(Format::ChargeLimit);
+        assert_eq!(charge.decode("80").unwrap(), 0xD0);
+        assert_eq!(charge.encode(0xBC).unwrap(), "60");
+        assert_eq!(charge.encode(0x00).unwrap(), "100");
+        assert_eq!(attribute(Format::KbdLevel).encode(0x87).unwrap(), "3");
+    }
+
+    #[test]
+    fn settings_are_checked_against_the_driver_value() {
+        assert!(!attribute(Format::Number).is_setting());
+        let path = std::env::temp_dir().join(format!("msi-ec-cooler-boost-{}", std::process::id()));
+        fs::write(&path, "on\n").unwrap();
+        let cooler_boost = Attribute { path: path.clone(), format: Format::OnOff(0x80) };
+        assert!(cooler_boost.is_setting());
+        assert!(cooler_boost.holds(0x81).unwrap());
+        assert!(!cooler_boost.holds(0x01).unwrap());
+        fs::remove_file(path).unwrap();
+    }
+}
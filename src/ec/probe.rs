@@ -0,0 +1,223 @@
+//! Collects what a model database entry needs for `msi-center models probe`: DMI
+//! strings, the EC firmware version, a register dump and a check of which known
+//! register layout fits this machine. Only reads; nothing is written to the EC.
+
+use super::quirks::{self, RegisterMap};
+use super::snapshot::EcSnapshot;
+use super::{msi_ec, EmbeddedController};
+use crate::scenario::ShiftMode;
+use std::fs;
+
+const DMI_PATH: &str = "/sys/class/dmi/id";
+/// DMI fields that identify the model. Serial numbers and UUIDs are left out.
+const DMI_FIELDS: [&str; 7] = ["sys_vendor", "product_name", "product_version", "board_vendor", "board_name", "bios_version", "bios_date"];
+/// Registers holding the firmware version and build date, which are public and kept.
+const FIRMWARE_REGISTERS: std::ops::Range<usize> = 0xA0..0xC0;
+/// Letter-and-digit runs at least this long elsewhere in the EC could be serial
+/// numbers. Shorter ones are usually just neighbouring values that happen to be
+/// printable, like a row of temperatures.
+const MIN_TEXT_RUN: usize = 6;
+
+/// How well a known layout's registers match what the EC holds.
+#[derive(Debug, Clone)]
+pub struct LayoutMatch {
+    /// Template id, or `default` for the built-in (WMI2) map.
+    pub id: String,
+    pub name: String,
+    pub plausible: usize,
+    pub checked: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ModelProbe {
+    pub dmi: Vec<(&'static str, String)>,
+    pub firmware: Option<String>,
+    pub backend: String,
+    /// The dump with possibly identifying text removed.
+    pub registers: Vec<Option<u8>>,
+    pub redacted: usize,
+    /// Best match first.
+    pub layouts: Vec<LayoutMatch>,
+    pub shift_modes: Option<Vec<String>>,
+    pub fan_modes: Option<Vec<String>>,
+}
+
+impl ModelProbe {
+    pub fn run(ec: &mut EmbeddedController) -> Self {
+        let dmi = DMI_FIELDS
+            .iter()
+            .filter_map(|field| {
+                let value = fs::read_to_string(format!("{}/{}", DMI_PATH, field)).ok()?;
+                Some((*field, value.trim().to_string())).filter(|(_, value)| !value.is_empty())
+            })
+            .collect();
+        let snapshot = EcSnapshot::capture(ec);
+
+        let mut candidates = vec![("default".to_string(), "Default (WMI2) layout".to_string(), RegisterMap::default(), 2)];
+        for entry in quirks::entries().into_iter().filter(|entry| entry.is_template) {
+            candidates.push((entry.id, entry.quirk.name, entry.quirk.registers, entry.quirk.capabilities.fans));
+        }
+        let mut layouts: Vec<LayoutMatch> = candidates
+            .into_iter()
+            .map(|(id, name, map, fans)| {
+                let (plausible, checked) = score_layout(&map, fans, &snapshot.registers);
+                LayoutMatch { id, name, plausible, checked }
+            })
+            .collect();
+        layouts.sort_by(|a, b| (b.plausible * a.checked).cmp(&(a.plausible * b.checked)));
+
+        let (registers, redacted) = sanitize(&snapshot.registers);
+        Self {
+            dmi,
+            firmware: quirks::firmware_version(),
+            backend: snapshot.backend,
+            registers,
+            redacted,
+            layouts,
+            shift_modes: msi_ec::available_shift_modes(),
+            fan_modes: msi_ec::available_fan_modes(),
+        }
+    }
+
+    fn dmi(&self, field: &str) -> Option<&str> {
+        self.dmi.iter().find(|(name, _)| *name == field).map(|(_, value)| value.as_str())
+    }
+
+    /// The layout to inherit from, if one fits better than the default map.
+    pub fn suggested_template(&self) -> Option<&LayoutMatch> {
+        self.layouts.first().filter(|layout| layout.id != "default" && layout.plausible > 0)
+    }
+
+    /// A quirk file for this machine, with everything gathered in its comments.
+    pub fn to_quirk_file(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("# Model database entry generated by `msi-center models probe` on {}.\n", chrono::Local::now().format("%Y-%m-%d")));
+        out.push_str("# Check the values below, then test it from ~/.config/msi-center-linux/quirks/ and\n");
+        out.push_str("# submit it. Nothing in it was written to the EC.\n#\n");
+        for (field, value) in &self.dmi {
+            out.push_str(&format!("# {:<16} {}\n", field, value));
+        }
+        out.push_str(&format!("# {:<16} {}\n", "ec_firmware", self.firmware.as_deref().unwrap_or("unknown")));
+        out.push_str(&format!("# {:<16} {}\n", "ec_backend", self.backend));
+        if let Some(ref modes) = self.shift_modes {
+            out.push_str(&format!("# {:<16} {}\n", "msi-ec shift", modes.join(" ")));
+        }
+        if let Some(ref modes) = self.fan_modes {
+            out.push_str(&format!("# {:<16} {}\n", "msi-ec fan", modes.join(" ")));
+        }
+
+        out.push_str("#\n# Known layouts, by registers holding plausible values:\n");
+        for layout in &self.layouts {
+            out.push_str(&format!("#   {:<16} {}/{}  {}\n", layout.id, layout.plausible, layout.checked, layout.name));
+        }
+
+        out.push_str("#\n# EC registers");
+        if self.redacted > 0 {
+            out.push_str(&format!(" ({} bytes of text that could identify this machine shown as --)", self.redacted));
+        }
+        out.push_str(":\n");
+        for line in hex_dump(&self.registers) {
+            out.push_str(&format!("#   {}\n", line));
+        }
+        out.push('\n');
+
+        let product = self.dmi("product_name").unwrap_or("Unknown MSI model");
+        out.push_str(&format!("name = {}\n", quoted(product)));
+        match self.suggested_template() {
+            Some(layout) => out.push_str(&format!("inherits = {}\n", quoted(&layout.id))),
+            None => out.push_str("# No known template fits better than the default layout; if a control\n# misbehaves, find its address with `msi-center ec watch --annotate`.\n"),
+        }
+        out.push_str(&format!("products = [{}]\n", quoted(product)));
+        if let Some(prefix) = self.firmware.as_deref().and_then(|fw| fw.split('.').next()) {
+            out.push_str(&format!("firmware = [{}]\n", quoted(&format!("{}*", prefix))));
+        }
+        out.push_str("support = \"partial\"         # \"full\" once every control has been tried\n");
+        out.push_str("\n# [registers]               # only what differs from the template\n");
+        out
+    }
+}
+
+/// Counts the registers of `map` whose current values make sense: temperatures in
+/// range, a known shift mode, and fan tables with rising temperatures.
+fn score_layout(map: &RegisterMap, fans: u8, registers: &[Option<u8>]) -> (usize, usize) {
+    let value = |address: u8| registers.get(address as usize).copied().flatten();
+    let fan_table = |base: u8| {
+        let temps: Option<Vec<u8>> = (0..6u8).map(|i| value(base.wrapping_add(i * 2))).collect();
+        temps.is_some_and(|temps| temps.windows(2).all(|pair| pair[0] <= pair[1]) && temps.iter().all(|t| (20..=110).contains(t)))
+    };
+    let mut checks = vec![
+        value(map.cpu_temp).is_some_and(|t| (15..=105).contains(&t)),
+        value(map.gpu_temp).is_some_and(|t| t <= 105),
+        value(map.shift_mode).is_some_and(|mode| ShiftMode::ALL.iter().any(|known| *known as u8 == mode)),
+        fan_table(map.fan1_base),
+    ];
+    if fans > 1 {
+        checks.push(fan_table(map.fan2_base));
+    }
+    if fans > 2
+        && let Some(fan) = map.fan(2)
+    {
+        checks.push(fan_table(fan.table));
+    }
+    (checks.iter().filter(|ok| **ok).count(), checks.len())
+}
+
+/// Removes text outside the firmware version that could hold serial numbers.
+/// Returns the cleaned dump and how many bytes were removed.
+fn sanitize(registers: &[Option<u8>]) -> (Vec<Option<u8>>, usize) {
+    let mut cleaned = registers.to_vec();
+    let mut redacted = 0;
+    let mut start = 0;
+    while start < cleaned.len() {
+        let length = cleaned[start..].iter().take_while(|byte| byte.is_some_and(|b| b.is_ascii_alphanumeric())).count();
+        let run = start..start + length;
+        let text: Vec<u8> = cleaned[run.clone()].iter().flatten().copied().collect();
+        let looks_like_serial = text.iter().any(u8::is_ascii_digit) && text.iter().any(u8::is_ascii_alphabetic);
+        if length >= MIN_TEXT_RUN && looks_like_serial && !FIRMWARE_REGISTERS.contains(&run.start) {
+            cleaned[run].iter_mut().for_each(|byte| *byte = None);
+            redacted += length;
+        }
+        start += length.max(1);
+    }
+    (cleaned, redacted)
+}
+
+fn hex_dump(registers: &[Option<u8>]) -> Vec<String> {
+    let header: Vec<String> = (0..16).map(|column| format!("{:02X}", column)).collect();
+    let mut lines = vec![format!("    {}", header.join(" "))];
+    for (row, chunk) in registers.chunks(16).enumerate() {
+        let cells: Vec<String> = chunk.iter().map(|byte| byte.map_or("--".to_string(), |b| format!("{:02X}", b))).collect();
+        lines.push(format!("{:02X}  {}", row * 16, cells.join(" ")));
+    }
+    lines
+}
+
+fn quoted(text: &str) -> String {
+    toml::Value::String(text.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serials_are_removed_but_the_firmware_version_is_kept() {
+        let mut registers = vec![Some(0u8); 256];
+        for (i, byte) in b"14C1EMS1.012".iter().enumerate() {
+            registers[0xA0 + i] = Some(*byte);
+        }
+        for (i, byte) in b"SN12345".iter().enumerate() {
+            registers[0x10 + i] = Some(*byte);
+        }
+        // Fan table temperatures of 50-55°C read as "234567".
+        for i in 0..6 {
+            registers[0x72 + i] = Some(50 + i as u8);
+        }
+
+        let (cleaned, redacted) = sanitize(&registers);
+        assert_eq!(redacted, 7);
+        assert!(cleaned[0x10..0x17].iter().all(Option::is_none));
+        assert_eq!(cleaned[0xA0], Some(b'1'));
+        assert_eq!(cleaned[0x72], Some(50));
+    }
+}
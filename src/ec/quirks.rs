@@ -0,0 +1,1060 @@
+use super::{
+    MSI_ADDRESS_COOLER_BOOST, MSI_ADDRESS_CPU_FAN_SPEED, MSI_ADDRESS_CPU_FAN_TACH,
+    MSI_ADDRESS_CPU_TEMP, MSI_ADDRESS_FAN1_BASE, MSI_ADDRESS_FAN2_BASE, MSI_ADDRESS_FAN_MODE,
+    MSI_ADDRESS_GPU_FAN_SPEED, MSI_ADDRESS_GPU_FAN_TACH, MSI_ADDRESS_GPU_TEMP,
+    MSI_ADDRESS_SHIFT_MODE, MSI_ADDRESS_SUPER_BATTERY, MSI_ADDRESS_WEBCAM, MSI_ADDRESS_WEBCAM_BLOCK,
+    MSI_ADDRESS_FN_WIN_SWAP, MSI_ADDRESS_BATTERY_CHARGE, MSI_ADDRESS_KBD_BACKLIGHT,
+};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum QuirkError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid quirk file: {0}")]
+    TomlError(#[from] toml::de::Error),
+}
+
+pub type Result<T> = std::result::Result<T, QuirkError>;
+
+/// MSI firmware keeps its version string here (e.g. `16R3EMS1.104`).
+const EC_FIRMWARE_ADDRESS: u64 = 0xA0;
+const EC_FIRMWARE_LENGTH: usize = 12;
+
+/// System-wide quirk files, checked before the bundled ones.
+const SYSTEM_QUIRKS_DIR: &str = "/etc/msi-center-linux/quirks";
+
+const BUNDLED: &[(&str, &str)] = &[
+    ("wmi1-family.toml", include_str!("../../quirks/wmi1-family.toml")),
+    ("msi-wmi1.toml", include_str!("../../quirks/msi-wmi1.toml")),
+];
+
+/// EC addresses the rest of the code talks to. The defaults are the layout of
+/// current (WMI2) MSI firmware; quirk files override them per model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegisterMap {
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub cpu_fan_speed: u8,
+    pub gpu_fan_speed: u8,
+    /// First of the two registers holding the fan tachometer period.
+    pub cpu_fan_tach: u8,
+    pub gpu_fan_tach: u8,
+    pub fan_mode: u8,
+    pub cooler_boost: u8,
+    pub shift_mode: u8,
+    pub super_battery: u8,
+    pub fan1_base: u8,
+    pub fan2_base: u8,
+    /// Fan table of a third (system) fan, on models that have one (GE78, Titan).
+    /// Its other registers are only read once the table is mapped.
+    pub fan3_base: Option<u8>,
+    pub fan3_speed: Option<u8>,
+    pub fan3_tach: Option<u8>,
+    pub webcam: u8,
+    pub webcam_block: u8,
+    /// Set when the Fn and Win keys are swapped.
+    pub fn_win_swap: u8,
+    /// Charge stop threshold: `0x80 | percent` while a limit is set.
+    pub battery_charge: u8,
+    /// Keyboard backlight level: `0x80 | level`.
+    pub kbd_backlight: u8,
+}
+
+impl Default for RegisterMap {
+    fn default() -> Self {
+        Self {
+            cpu_temp: MSI_ADDRESS_CPU_TEMP,
+            gpu_temp: MSI_ADDRESS_GPU_TEMP,
+            cpu_fan_speed: MSI_ADDRESS_CPU_FAN_SPEED,
+            gpu_fan_speed: MSI_ADDRESS_GPU_FAN_SPEED,
+            cpu_fan_tach: MSI_ADDRESS_CPU_FAN_TACH,
+            gpu_fan_tach: MSI_ADDRESS_GPU_FAN_TACH,
+            fan_mode: MSI_ADDRESS_FAN_MODE,
+            cooler_boost: MSI_ADDRESS_COOLER_BOOST,
+            shift_mode: MSI_ADDRESS_SHIFT_MODE,
+            super_battery: MSI_ADDRESS_SUPER_BATTERY,
+            fan1_base: MSI_ADDRESS_FAN1_BASE,
+            fan2_base: MSI_ADDRESS_FAN2_BASE,
+            fan3_base: None,
+            fan3_speed: None,
+            fan3_tach: None,
+            webcam: MSI_ADDRESS_WEBCAM,
+            webcam_block: MSI_ADDRESS_WEBCAM_BLOCK,
+            fn_win_swap: MSI_ADDRESS_FN_WIN_SWAP,
+            battery_charge: MSI_ADDRESS_BATTERY_CHARGE,
+            kbd_backlight: MSI_ADDRESS_KBD_BACKLIGHT,
+        }
+    }
+}
+
+/// Names of the fans by index, as the CLI and GUI label them.
+pub const FAN_NAMES: [&str; 3] = ["CPU", "GPU", "System"];
+/// Fans of the default layout; a mapped third fan table adds one.
+const DEFAULT_FANS: u8 = 2;
+
+/// The registers of one fan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FanRegisters {
+    pub speed: Option<u8>,
+    /// First of the two registers holding the tachometer period.
+    pub tach: Option<u8>,
+    /// First of the fan table's (temperature, speed) pairs, or its first speed
+    /// on models that keep the temperatures apart (`temp_table_offset`).
+    pub table: u8,
+}
+
+/// Points in each fan table, unless the model's quirk says otherwise.
+pub const CURVE_POINTS: u8 = 6;
+/// Most points a fan table holds on any model.
+pub const MAX_CURVE_POINTS: u8 = 7;
+
+impl RegisterMap {
+    /// Registers of fan `index` (0 CPU, 1 GPU, 2 system), if the map has them.
+    pub fn fan(&self, index: usize) -> Option<FanRegisters> {
+        match index {
+            0 => Some(FanRegisters {
+                speed: Some(self.cpu_fan_speed),
+                tach: Some(self.cpu_fan_tach),
+                table: self.fan1_base,
+            }),
+            1 => Some(FanRegisters {
+                speed: Some(self.gpu_fan_speed),
+                tach: Some(self.gpu_fan_tach),
+                table: self.fan2_base,
+            }),
+            2 => self.fan3_base.map(|table| FanRegisters { speed: self.fan3_speed, tach: self.fan3_tach, table }),
+            _ => None,
+        }
+    }
+
+    /// Fans the map has registers for.
+    pub fn fan_count(&self) -> u8 {
+        DEFAULT_FANS + self.fan3_base.is_some() as u8
+    }
+
+    /// Whether the register at `address` is one this tool writes on purpose: a
+    /// control, a fan table entry, or the lid logo, indicator LED and fan tuning
+    /// registers on models that have them.
+    /// Sensors and everything unmapped are read-only.
+    pub fn is_safe_write(&self, address: u8, capabilities: &Capabilities) -> bool {
+        let controls = [
+            self.fan_mode,
+            self.cooler_boost,
+            self.shift_mode,
+            self.super_battery,
+            self.webcam,
+            self.webcam_block,
+            self.fn_win_swap,
+            self.battery_charge,
+            self.kbd_backlight,
+        ];
+        controls.contains(&address)
+            || (0..capabilities.fans as usize).filter_map(|index| self.fan(index)).any(|fan| capabilities.table_point(fan.table, address).is_some())
+            || capabilities.lid_logo.is_some_and(|logo| logo.register == address)
+            || capabilities.indicator_led.is_some_and(|led| led.contains(address))
+            || capabilities.fan_tuning.contains(address, capabilities.fans, capabilities.curve_points)
+    }
+
+    /// What the register at `address` holds, for annotating raw EC output.
+    pub fn describe(&self, address: u8, capabilities: &Capabilities) -> Option<String> {
+        let named = [
+            (self.cpu_temp, "CPU temperature"),
+            (self.gpu_temp, "GPU temperature"),
+            (self.cpu_fan_speed, "CPU fan speed"),
+            (self.gpu_fan_speed, "GPU fan speed"),
+            (self.cpu_fan_tach, "CPU fan tachometer (high byte)"),
+            (self.gpu_fan_tach, "GPU fan tachometer (high byte)"),
+            (self.fan_mode, "fan mode"),
+            (self.cooler_boost, "Cooler Boost"),
+            (self.shift_mode, "shift mode"),
+            (self.super_battery, "super battery"),
+            (self.webcam, "webcam"),
+            (self.webcam_block, "webcam block"),
+            (self.fn_win_swap, "Fn/Win key swap"),
+            (self.battery_charge, "battery charge limit"),
+            (self.kbd_backlight, "keyboard backlight"),
+        ];
+        let mut names: Vec<String> =
+            named.iter().filter(|(register, _)| *register == address).map(|(_, name)| name.to_string()).collect();
+        if self.fan3_base.is_some() {
+            let third = [(self.fan3_speed, "speed"), (self.fan3_tach, "tachometer (high byte)")];
+            names.extend(third.iter().filter(|(register, _)| *register == Some(address)).map(|(_, name)| format!("System fan {}", name)));
+        }
+        for (index, fan) in FAN_NAMES.iter().enumerate() {
+            let Some(registers) = self.fan(index) else {
+                continue;
+            };
+            if registers.tach.and_then(|tach| tach.checked_add(1)) == Some(address) {
+                names.push(format!("{} fan tachometer (low byte)", fan));
+            }
+            if let Some((point, is_temp)) = capabilities.table_point(registers.table, address) {
+                let field = if is_temp { "temperature" } else { "speed" };
+                return Some(format!("{} fan curve point {} {}", fan, point + 1, field));
+            }
+        }
+        (!names.is_empty()).then(|| names.join(", "))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SupportLevel {
+    #[default]
+    Full,
+    Partial,
+    Unsupported,
+}
+
+impl std::fmt::Display for SupportLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SupportLevel::Full => write!(f, "fully supported"),
+            SupportLevel::Partial => write!(f, "partially supported"),
+            SupportLevel::Unsupported => write!(f, "not supported"),
+        }
+    }
+}
+
+/// Controls a quirk file can mark as missing on its model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Feature {
+    CoolerBoost,
+    ShiftMode,
+    SuperBattery,
+}
+
+/// Bits of the super battery register that switch single behaviors, on models
+/// where it does more than one thing (`[super_battery_bits]` in a quirk file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuperBatteryBits {
+    pub gpu_clamp: Option<u8>,
+    pub panel_dimming: Option<u8>,
+}
+
+/// The lid logo LED, on models whose EC drives it (`[lid_logo]` in a quirk file).
+/// The register holds the brightness level, 0 being off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LidLogo {
+    pub register: u8,
+    /// Highest brightness level; 1 for an LED that is only on or off.
+    #[serde(default = "default_lid_logo_levels")]
+    pub max_level: u8,
+}
+
+fn default_lid_logo_levels() -> u8 {
+    1
+}
+
+impl LidLogo {
+    /// Register value for a brightness in percent; any non-zero brightness lights the LED.
+    pub fn level(&self, percent: u8) -> u8 {
+        let max_level = self.max_level.max(1) as u16;
+        let level = (percent.min(100) as u16 * max_level + 50) / 100;
+        if percent > 0 { level.max(1) as u8 } else { 0 }
+    }
+
+    pub fn percent(&self, level: u8) -> u8 {
+        let max_level = self.max_level.max(1);
+        (level.min(max_level) as u16 * 100 / max_level as u16) as u8
+    }
+}
+
+/// The power/battery indicator LED, on models whose EC lets its behavior be
+/// configured (`[indicator_led]` in a quirk file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndicatorLed {
+    /// Register holding the LED's behavior bits.
+    pub register: u8,
+    /// Bit of `register` that keeps the LED dark while on battery.
+    pub off_on_battery: Option<u8>,
+    /// Register holding the battery level (percent) below which the LED blinks, 0 never.
+    pub blink_threshold: Option<u8>,
+}
+
+impl IndicatorLed {
+    fn contains(&self, address: u8) -> bool {
+        (self.off_on_battery.is_some() && self.register == address) || self.blink_threshold == Some(address)
+    }
+}
+
+/// Registers some firmwares program alongside the fan tables in Advanced mode, as
+/// MSI Center does (`[fan_tuning]` in a quirk file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FanTuning {
+    /// First of the registers holding a signed duty offset for each CPU curve point.
+    pub cpu_offsets: Option<u8>,
+    pub gpu_offsets: Option<u8>,
+    /// Lowest duty the firmware runs the CPU fan at while following its curve.
+    pub cpu_min_duty: Option<u8>,
+    pub gpu_min_duty: Option<u8>,
+}
+
+impl FanTuning {
+    fn inherit(&mut self, parent: &Self) {
+        self.cpu_offsets = self.cpu_offsets.or(parent.cpu_offsets);
+        self.gpu_offsets = self.gpu_offsets.or(parent.gpu_offsets);
+        self.cpu_min_duty = self.cpu_min_duty.or(parent.cpu_min_duty);
+        self.gpu_min_duty = self.gpu_min_duty.or(parent.gpu_min_duty);
+    }
+
+    /// The offset and minimum duty registers of fan `index`; the system fan has none.
+    pub fn for_fan(&self, index: usize) -> (Option<u8>, Option<u8>) {
+        match index {
+            0 => (self.cpu_offsets, self.cpu_min_duty),
+            1 => (self.gpu_offsets, self.gpu_min_duty),
+            _ => (None, None),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether `address` is one of these registers, with an offset for each of `points`.
+    fn contains(&self, address: u8, fans: u8, points: u8) -> bool {
+        let in_offsets = |base: Option<u8>| base.is_some_and(|base| address >= base && address - base < points);
+        in_offsets(self.cpu_offsets)
+            || self.cpu_min_duty == Some(address)
+            || (fans > 1 && (in_offsets(self.gpu_offsets) || self.gpu_min_duty == Some(address)))
+    }
+}
+
+/// MSI Center's tachometer constant: the EC reports each fan's period as a 16-bit
+/// word, and RPM is this divided by it.
+pub const DEFAULT_TACH_CONSTANT: u32 = 478_000;
+/// Top speed assumed for a fan when RPM has to be estimated from its duty.
+pub const DEFAULT_MAX_RPM: u32 = 6_000;
+
+/// How fan readings turn into RPM on models that differ from MSI Center's defaults
+/// (`[fan_rpm]` in a quirk file).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FanRpm {
+    /// Divided by the tachometer word to give RPM.
+    pub constant: Option<u32>,
+    /// Top speed of the fans, for estimating RPM from the duty byte on backends
+    /// that can't read the tachometer (msi-ec).
+    pub max_rpm: Option<u32>,
+}
+
+impl FanRpm {
+    fn inherit(&mut self, parent: &Self) {
+        self.constant = self.constant.or(parent.constant);
+        self.max_rpm = self.max_rpm.or(parent.max_rpm);
+    }
+
+    /// RPM for a tachometer period; 0 when the fan is stopped.
+    pub fn rpm_at_period(&self, period: u16) -> u32 {
+        match period {
+            0 => 0,
+            period => self.constant.unwrap_or(DEFAULT_TACH_CONSTANT) / period as u32,
+        }
+    }
+
+    /// Estimated RPM at a duty in percent.
+    pub fn rpm_at_percent(&self, percent: u8) -> u32 {
+        self.max_rpm.unwrap_or(DEFAULT_MAX_RPM) * percent.min(100) as u32 / 100
+    }
+}
+
+/// ACPI methods (full paths, e.g. from the model's DSDT) that set a control when
+/// the firmware ignores raw writes to its register. Each is called with the
+/// register value as its only argument (`[acpi_methods]` in a quirk file).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AcpiMethods {
+    pub shift_mode: Option<String>,
+    pub cooler_boost: Option<String>,
+    pub fan_mode: Option<String>,
+}
+
+/// What the model has besides register addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub fans: u8,
+    /// Fan table points the firmware follows; curves may not have more.
+    pub curve_points: u8,
+    /// Distance from a fan table's first speed to its first temperature, on models
+    /// that keep the temperatures in a table of their own. Without it the table
+    /// holds (temperature, speed) pairs.
+    pub temp_table_offset: Option<i8>,
+    pub cooler_boost: bool,
+    pub shift_mode: bool,
+    pub super_battery: bool,
+    pub super_battery_bits: SuperBatteryBits,
+    pub lid_logo: Option<LidLogo>,
+    pub indicator_led: Option<IndicatorLed>,
+    pub fan_tuning: FanTuning,
+    pub fan_rpm: FanRpm,
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self {
+            fans: 2,
+            curve_points: CURVE_POINTS,
+            temp_table_offset: None,
+            cooler_boost: true,
+            shift_mode: true,
+            super_battery: true,
+            super_battery_bits: SuperBatteryBits::default(),
+            lid_logo: None,
+            indicator_led: None,
+            fan_tuning: FanTuning::default(),
+            fan_rpm: FanRpm::default(),
+        }
+    }
+}
+
+impl Capabilities {
+    /// Addresses of the temperature and speed of point `point` (from 0) in the fan
+    /// table at `base`.
+    pub fn table_entry(&self, base: u8, point: u8) -> (u8, u8) {
+        match self.temp_table_offset {
+            Some(offset) => (base.wrapping_add_signed(offset).wrapping_add(point), base.wrapping_add(point)),
+            None => (base.wrapping_add(point * 2), base.wrapping_add(point * 2 + 1)),
+        }
+    }
+
+    /// The point of the fan table at `base` that `address` belongs to, and whether
+    /// it holds the point's temperature rather than its speed.
+    pub fn table_point(&self, base: u8, address: u8) -> Option<(u8, bool)> {
+        (0..self.curve_points).find_map(|point| match self.table_entry(base, point) {
+            (temp, _) if temp == address => Some((point, true)),
+            (_, speed) if speed == address => Some((point, false)),
+            _ => None,
+        })
+    }
+}
+
+/// `[registers]` table of a quirk file; unset addresses keep the default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegisterOverrides {
+    cpu_temp: Option<u8>,
+    gpu_temp: Option<u8>,
+    cpu_fan_speed: Option<u8>,
+    gpu_fan_speed: Option<u8>,
+    cpu_fan_tach: Option<u8>,
+    gpu_fan_tach: Option<u8>,
+    fan_mode: Option<u8>,
+    cooler_boost: Option<u8>,
+    shift_mode: Option<u8>,
+    super_battery: Option<u8>,
+    fan1_base: Option<u8>,
+    fan2_base: Option<u8>,
+    fan3_base: Option<u8>,
+    fan3_speed: Option<u8>,
+    fan3_tach: Option<u8>,
+    webcam: Option<u8>,
+    webcam_block: Option<u8>,
+    fn_win_swap: Option<u8>,
+    battery_charge: Option<u8>,
+    kbd_backlight: Option<u8>,
+}
+
+impl RegisterOverrides {
+    /// Fills the addresses this file leaves unset from `parent`.
+    fn inherit(&mut self, parent: &Self) {
+        let fields = [
+            (&mut self.cpu_temp, parent.cpu_temp),
+            (&mut self.gpu_temp, parent.gpu_temp),
+            (&mut self.cpu_fan_speed, parent.cpu_fan_speed),
+            (&mut self.gpu_fan_speed, parent.gpu_fan_speed),
+            (&mut self.cpu_fan_tach, parent.cpu_fan_tach),
+            (&mut self.gpu_fan_tach, parent.gpu_fan_tach),
+            (&mut self.fan_mode, parent.fan_mode),
+            (&mut self.cooler_boost, parent.cooler_boost),
+            (&mut self.shift_mode, parent.shift_mode),
+            (&mut self.super_battery, parent.super_battery),
+            (&mut self.fan1_base, parent.fan1_base),
+            (&mut self.fan2_base, parent.fan2_base),
+            (&mut self.fan3_base, parent.fan3_base),
+            (&mut self.fan3_speed, parent.fan3_speed),
+            (&mut self.fan3_tach, parent.fan3_tach),
+            (&mut self.webcam, parent.webcam),
+            (&mut self.webcam_block, parent.webcam_block),
+            (&mut self.fn_win_swap, parent.fn_win_swap),
+            (&mut self.battery_charge, parent.battery_charge),
+            (&mut self.kbd_backlight, parent.kbd_backlight),
+        ];
+        for (field, inherited) in fields {
+            *field = field.or(inherited);
+        }
+    }
+
+    /// Addresses this file sets, by key, for `models show`.
+    fn entries(&self) -> Vec<(&'static str, u8)> {
+        let fields = [
+            ("cpu_temp", self.cpu_temp),
+            ("gpu_temp", self.gpu_temp),
+            ("cpu_fan_speed", self.cpu_fan_speed),
+            ("gpu_fan_speed", self.gpu_fan_speed),
+            ("cpu_fan_tach", self.cpu_fan_tach),
+            ("gpu_fan_tach", self.gpu_fan_tach),
+            ("fan_mode", self.fan_mode),
+            ("cooler_boost", self.cooler_boost),
+            ("shift_mode", self.shift_mode),
+            ("super_battery", self.super_battery),
+            ("fan1_base", self.fan1_base),
+            ("fan2_base", self.fan2_base),
+            ("fan3_base", self.fan3_base),
+            ("fan3_speed", self.fan3_speed),
+            ("fan3_tach", self.fan3_tach),
+            ("webcam", self.webcam),
+            ("webcam_block", self.webcam_block),
+            ("fn_win_swap", self.fn_win_swap),
+            ("battery_charge", self.battery_charge),
+            ("kbd_backlight", self.kbd_backlight),
+        ];
+        fields.into_iter().filter_map(|(key, value)| Some((key, value?))).collect()
+    }
+
+    fn apply(&self, map: &mut RegisterMap) {
+        let fields = [
+            (self.cpu_temp, &mut map.cpu_temp),
+            (self.gpu_temp, &mut map.gpu_temp),
+            (self.cpu_fan_speed, &mut map.cpu_fan_speed),
+            (self.gpu_fan_speed, &mut map.gpu_fan_speed),
+            (self.cpu_fan_tach, &mut map.cpu_fan_tach),
+            (self.gpu_fan_tach, &mut map.gpu_fan_tach),
+            (self.fan_mode, &mut map.fan_mode),
+            (self.cooler_boost, &mut map.cooler_boost),
+            (self.shift_mode, &mut map.shift_mode),
+            (self.super_battery, &mut map.super_battery),
+            (self.fan1_base, &mut map.fan1_base),
+            (self.fan2_base, &mut map.fan2_base),
+            (self.webcam, &mut map.webcam),
+            (self.webcam_block, &mut map.webcam_block),
+            (self.fn_win_swap, &mut map.fn_win_swap),
+            (self.battery_charge, &mut map.battery_charge),
+            (self.kbd_backlight, &mut map.kbd_backlight),
+        ];
+        for (value, field) in fields {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        let third_fan = [
+            (self.fan3_base, &mut map.fan3_base),
+            (self.fan3_speed, &mut map.fan3_speed),
+            (self.fan3_tach, &mut map.fan3_tach),
+        ];
+        for (value, field) in third_fan {
+            if value.is_some() {
+                *field = value;
+            }
+        }
+    }
+}
+
+/// Longest `inherits` chain followed, which also stops cycles.
+const MAX_INHERITANCE_DEPTH: usize = 8;
+
+/// A quirk file. Files without `products` and `firmware` never match a machine on
+/// their own; they are templates (e.g. a model family) that others name in
+/// `inherits` and only override what differs.
+#[derive(Debug, Clone, Deserialize)]
+struct QuirkFile {
+    name: String,
+    /// File name (without `.toml`) of the template this entry builds on.
+    #[serde(default)]
+    inherits: Option<String>,
+    /// DMI product names; a trailing `*` matches any suffix. Case-insensitive.
+    #[serde(default)]
+    products: Vec<String>,
+    /// EC firmware versions, matched like `products` and preferred over them.
+    #[serde(default)]
+    firmware: Vec<String>,
+    #[serde(default)]
+    support: Option<SupportLevel>,
+    #[serde(default)]
+    fans: Option<u8>,
+    /// Points of each fan table, if not six: fewer when the firmware ignores the
+    /// hottest ones, seven on models with longer tables.
+    #[serde(default)]
+    curve_points: Option<u8>,
+    #[serde(default)]
+    temp_table_offset: Option<i8>,
+    #[serde(default)]
+    unsupported: Option<Vec<Feature>>,
+    #[serde(default)]
+    registers: RegisterOverrides,
+    #[serde(default)]
+    super_battery_bits: SuperBatteryBits,
+    #[serde(default)]
+    lid_logo: Option<LidLogo>,
+    #[serde(default)]
+    indicator_led: Option<IndicatorLed>,
+    #[serde(default)]
+    fan_tuning: FanTuning,
+    #[serde(default)]
+    fan_rpm: FanRpm,
+    #[serde(default)]
+    acpi_methods: AcpiMethods,
+    /// Further registers that are safe to write on this model, beyond the mapped controls.
+    #[serde(default)]
+    writable: Option<Vec<u8>>,
+}
+
+impl QuirkFile {
+    fn is_template(&self) -> bool {
+        self.products.is_empty() && self.firmware.is_empty()
+    }
+
+    /// Takes every setting this file leaves unset from `parent`. Which machines
+    /// match is never inherited.
+    fn inherit(&mut self, parent: &QuirkFile) {
+        self.support = self.support.or(parent.support);
+        self.fans = self.fans.or(parent.fans);
+        self.curve_points = self.curve_points.or(parent.curve_points);
+        self.temp_table_offset = self.temp_table_offset.or(parent.temp_table_offset);
+        if self.unsupported.is_none() {
+            self.unsupported = parent.unsupported.clone();
+        }
+        self.registers.inherit(&parent.registers);
+        self.super_battery_bits.gpu_clamp = self.super_battery_bits.gpu_clamp.or(parent.super_battery_bits.gpu_clamp);
+        self.super_battery_bits.panel_dimming = self.super_battery_bits.panel_dimming.or(parent.super_battery_bits.panel_dimming);
+        self.lid_logo = self.lid_logo.or(parent.lid_logo);
+        self.indicator_led = self.indicator_led.or(parent.indicator_led);
+        self.fan_tuning.inherit(&parent.fan_tuning);
+        self.fan_rpm.inherit(&parent.fan_rpm);
+        if self.writable.is_none() {
+            self.writable = parent.writable.clone();
+        }
+        let methods = &mut self.acpi_methods;
+        for (method, inherited) in [
+            (&mut methods.shift_mode, &parent.acpi_methods.shift_mode),
+            (&mut methods.cooler_boost, &parent.acpi_methods.cooler_boost),
+            (&mut methods.fan_mode, &parent.acpi_methods.fan_mode),
+        ] {
+            if method.is_none() {
+                *method = inherited.clone();
+            }
+        }
+    }
+}
+
+fn matches_any(patterns: &[String], value: &str) -> bool {
+    let value = value.trim().to_lowercase();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.trim().to_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => value.starts_with(prefix),
+            None => value == pattern,
+        }
+    })
+}
+
+/// The quirk selected for a model.
+#[derive(Debug, Clone)]
+pub struct Quirk {
+    pub name: String,
+    /// File the quirk came from, or `bundled:<file>`.
+    pub source: String,
+    pub registers: RegisterMap,
+    pub capabilities: Capabilities,
+    pub support: SupportLevel,
+    pub acpi_methods: AcpiMethods,
+    /// Registers the quirk file adds to the safe write list.
+    pub writable: Vec<u8>,
+}
+
+impl Quirk {
+    fn from_file(source: String, file: QuirkFile) -> Self {
+        let mut registers = RegisterMap::default();
+        file.registers.apply(&mut registers);
+        let unsupported = file.unsupported.unwrap_or_default();
+        // A fan needs its table mapped; `fans` can only leave some out.
+        let mapped_fans = registers.fan_count();
+        if file.fans.is_some_and(|fans| fans > mapped_fans) {
+            log::warn!("Quirk {} lists {} fans but maps registers for {}", file.name, file.fans.unwrap_or_default(), mapped_fans);
+        }
+        if file.curve_points.is_some_and(|points| points > MAX_CURVE_POINTS) {
+            log::warn!("Quirk {} lists {} curve points but fan tables hold at most {}", file.name, file.curve_points.unwrap_or_default(), MAX_CURVE_POINTS);
+        }
+        let capabilities = Capabilities {
+            fans: file.fans.unwrap_or(mapped_fans).clamp(1, mapped_fans),
+            curve_points: file.curve_points.unwrap_or(CURVE_POINTS).clamp(2, MAX_CURVE_POINTS),
+            temp_table_offset: file.temp_table_offset,
+            cooler_boost: !unsupported.contains(&Feature::CoolerBoost),
+            shift_mode: !unsupported.contains(&Feature::ShiftMode),
+            super_battery: !unsupported.contains(&Feature::SuperBattery),
+            super_battery_bits: file.super_battery_bits,
+            lid_logo: file.lid_logo,
+            indicator_led: file.indicator_led,
+            fan_tuning: file.fan_tuning,
+            fan_rpm: file.fan_rpm,
+        };
+        Self {
+            name: file.name,
+            source,
+            registers,
+            capabilities,
+            support: file.support.unwrap_or_default(),
+            acpi_methods: file.acpi_methods,
+            writable: file.writable.unwrap_or_default(),
+        }
+    }
+}
+
+/// The detected machine, as shown by `status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelInfo {
+    pub product: Option<String>,
+    pub firmware: Option<String>,
+    /// Name of the matching quirk, if any.
+    pub quirk: Option<String>,
+    pub support: SupportLevel,
+}
+
+pub fn product_name() -> Option<String> {
+    let name = fs::read_to_string("/sys/class/dmi/id/product_name").ok()?;
+    Some(name.trim().to_string()).filter(|n| !n.is_empty())
+}
+
+fn is_msi_vendor() -> bool {
+    fs::read_to_string("/sys/class/dmi/id/sys_vendor")
+        .map(|vendor| {
+            let vendor = vendor.to_lowercase();
+            vendor.contains("micro-star") || vendor.contains("msi")
+        })
+        .unwrap_or(false)
+}
+
+/// EC firmware version from the msi-ec driver, or read from debugfs. Not read
+/// through `EmbeddedController`, whose construction depends on the detected model.
+pub fn firmware_version() -> Option<String> {
+    if let Ok(version) = fs::read_to_string("/sys/devices/platform/msi-ec/fw_version") {
+        return Some(version.trim().to_string()).filter(|v| !v.is_empty());
+    }
+
+    let mut file = fs::File::open("/sys/kernel/debug/ec/ec0/io").ok()?;
+    let mut buf = [0u8; EC_FIRMWARE_LENGTH];
+    file.seek(SeekFrom::Start(EC_FIRMWARE_ADDRESS)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    let version: String = buf.iter().take_while(|&&b| b != 0).map(|&b| b as char).collect();
+    Some(version.trim().to_string()).filter(|v| v.len() >= 4 && v.chars().all(|c| c.is_ascii_graphic()))
+}
+
+/// Directories searched for quirk files, most specific first.
+pub fn user_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(SYSTEM_QUIRKS_DIR)];
+    if let Some(config) = dirs::config_dir() {
+        dirs.insert(0, config.join("msi-center-linux").join("quirks"));
+    }
+    dirs
+}
+
+fn load_file(path: &Path) -> Result<QuirkFile> {
+    Ok(toml::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// A model database entry with its templates applied, as listed by `models list`.
+#[derive(Debug, Clone)]
+pub struct ModelEntry {
+    /// File name without `.toml`; what `inherits` refers to.
+    pub id: String,
+    pub source: String,
+    /// Templates this entry builds on, nearest first.
+    pub inherits: Vec<String>,
+    pub products: Vec<String>,
+    pub firmware: Vec<String>,
+    pub is_template: bool,
+    /// Register addresses the entry (or its templates) sets, by quirk file key.
+    pub register_overrides: Vec<(&'static str, u8)>,
+    pub quirk: Quirk,
+}
+
+fn file_id(source: &str) -> String {
+    let name = source.strip_prefix("bundled:").unwrap_or(source);
+    Path::new(name).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+/// Applies each file's `inherits` chain. The first file with an id wins, so a user's
+/// copy of a bundled template replaces it. Entries whose template is missing or
+/// that inherit in a cycle are skipped.
+fn resolve(files: Vec<(String, QuirkFile)>) -> Vec<ModelEntry> {
+    let mut templates: Vec<(String, &QuirkFile)> = Vec::new();
+    for (source, file) in &files {
+        let id = file_id(source);
+        if !templates.iter().any(|(existing, _)| *existing == id) {
+            templates.push((id, file));
+        }
+    }
+    let find = |id: &str| templates.iter().find(|(existing, _)| existing == id).map(|(_, file)| *file);
+
+    let mut entries = Vec::new();
+    'files: for (source, file) in &files {
+        let mut resolved = file.clone();
+        let mut chain = Vec::new();
+        let mut next = file.inherits.clone();
+        while let Some(parent_id) = next {
+            if chain.len() >= MAX_INHERITANCE_DEPTH || chain.contains(&parent_id) || parent_id == file_id(source) {
+                log::warn!("Ignoring quirk file {}: inherits from {} in a cycle", source, parent_id);
+                continue 'files;
+            }
+            let Some(parent) = find(&parent_id) else {
+                log::warn!("Ignoring quirk file {}: template {} not found", source, parent_id);
+                continue 'files;
+            };
+            resolved.inherit(parent);
+            next = parent.inherits.clone();
+            chain.push(parent_id);
+        }
+        entries.push(ModelEntry {
+            id: file_id(source),
+            source: source.clone(),
+            inherits: chain,
+            products: resolved.products.clone(),
+            firmware: resolved.firmware.clone(),
+            is_template: resolved.is_template(),
+            register_overrides: resolved.registers.entries(),
+            quirk: Quirk::from_file(source.clone(), resolved),
+        });
+    }
+    entries
+}
+
+/// The model database: every quirk file with its templates applied.
+pub fn entries() -> Vec<ModelEntry> {
+    resolve(load_all())
+}
+
+/// All quirk files in lookup order: the user's config directory, then
+/// `/etc/msi-center-linux/quirks`, then the bundled ones. Broken files are skipped.
+fn load_all() -> Vec<(String, QuirkFile)> {
+    let mut quirks = Vec::new();
+
+    for dir in user_dirs() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        paths.sort();
+        for path in paths {
+            match load_file(&path) {
+                Ok(quirk) => quirks.push((path.display().to_string(), quirk)),
+                Err(e) => log::warn!("Ignoring quirk file {}: {}", path.display(), e),
+            }
+        }
+    }
+
+    for (file, content) in BUNDLED {
+        match toml::from_str(content) {
+            Ok(quirk) => quirks.push((format!("bundled:{}", file), quirk)),
+            Err(e) => log::error!("Bundled quirk file {} is invalid: {}", file, e),
+        }
+    }
+
+    quirks
+}
+
+/// Finds the quirk for a machine. A firmware match wins over a product name match,
+/// since one product name can ship with different EC firmware.
+pub fn lookup(product: Option<&str>, firmware: Option<&str>) -> Option<Quirk> {
+    lookup_entry(product, firmware).map(|entry| entry.quirk)
+}
+
+pub fn lookup_entry(product: Option<&str>, firmware: Option<&str>) -> Option<ModelEntry> {
+    let entries = entries();
+    let by_firmware = firmware.and_then(|fw| entries.iter().position(|e| matches_any(&e.firmware, fw)));
+    let by_product = product.and_then(|p| entries.iter().position(|e| matches_any(&e.products, p)));
+    let index = by_firmware.or(by_product)?;
+    entries.into_iter().nth(index)
+}
+
+pub fn for_product(product: &str) -> Option<Quirk> {
+    lookup(Some(product), None)
+}
+
+struct Detection {
+    product: Option<String>,
+    firmware: Option<String>,
+    quirk: Option<Quirk>,
+}
+
+fn detection() -> &'static Detection {
+    static DETECTED: OnceLock<Detection> = OnceLock::new();
+    DETECTED.get_or_init(|| {
+        let product = product_name();
+        let firmware = firmware_version();
+        let quirk = lookup(product.as_deref(), firmware.as_deref());
+        let model = product.as_deref().unwrap_or("unknown model");
+        match quirk {
+            Some(ref quirk) => log::info!("Using EC register map '{}' from {} for {}", quirk.name, quirk.source, model),
+            None => log::debug!("No quirk file for {}, using the default EC register map", model),
+        }
+        Detection { product, firmware, quirk }
+    })
+}
+
+/// The quirk for this machine, looked up once per process.
+pub fn detected() -> Option<&'static Quirk> {
+    detection().quirk.as_ref()
+}
+
+pub fn model_info() -> ModelInfo {
+    let detection = detection();
+    let support = match detection.quirk {
+        Some(ref quirk) => quirk.support,
+        // Not described anywhere; the default layout fits most current MSI firmware.
+        None if is_msi_vendor() => SupportLevel::Partial,
+        None => SupportLevel::Unsupported,
+    };
+    ModelInfo {
+        product: detection.product.clone(),
+        firmware: detection.firmware.clone(),
+        quirk: detection.quirk.as_ref().map(|q| q.name.clone()),
+        support,
+    }
+}
+
+/// Register map for this machine.
+pub fn registers() -> RegisterMap {
+    detected().map(|quirk| quirk.registers).unwrap_or_default()
+}
+
+pub fn capabilities() -> Capabilities {
+    detected().map(|quirk| quirk.capabilities).unwrap_or_default()
+}
+
+/// Registers outside the register map that this machine's quirk file allows writing.
+pub fn extra_writable() -> Vec<u8> {
+    detected().map(|quirk| quirk.writable.clone()).unwrap_or_default()
+}
+
+/// ACPI fallback methods for this machine; none unless its quirk file maps them.
+pub fn acpi_methods() -> AcpiMethods {
+    detected().map(|quirk| quirk.acpi_methods.clone()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_quirks_override_registers() {
+        let quirk = for_product("GF63 Thin 9SC-066XES").expect("bundled WMI1 quirk");
+        assert_eq!(quirk.registers.fan_mode, 0xF4);
+        assert_eq!(quirk.registers.shift_mode, 0xF2);
+        assert_eq!(quirk.registers.cooler_boost, MSI_ADDRESS_COOLER_BOOST);
+        assert!(for_product("reference").is_none());
+
+        let by_firmware = lookup(Some("Unknown Model"), Some("14C1EMS1.012")).expect("firmware match");
+        assert_eq!(by_firmware.registers.shift_mode, 0xF2);
+    }
+
+    #[test]
+    fn acpi_methods_follow_remapped_registers() {
+        let file: QuirkFile = toml::from_str(
+            r#"
+name = "test"
+[registers]
+shift_mode = 0xF2
+[acpi_methods]
+shift_mode = "\\_SB.AMW0.SHMD"
+"#,
+        )
+        .unwrap();
+        let quirk = Quirk::from_file("test".to_string(), file);
+        let method = |address| crate::ec::acpi_call::method_for(&quirk.acpi_methods, address, &quirk.registers);
+        assert_eq!(method(0xF2).as_deref(), Some("\\_SB.AMW0.SHMD"));
+        assert_eq!(method(MSI_ADDRESS_SHIFT_MODE), None);
+        assert_eq!(method(MSI_ADDRESS_COOLER_BOOST), None);
+    }
+
+    #[test]
+    fn lid_logo_levels_scale_with_brightness() {
+        let file: QuirkFile = toml::from_str("name = \"test\"\n[lid_logo]\nregister = 0xE5\nmax_level = 4\n").unwrap();
+        let logo = Quirk::from_file("test".to_string(), file).capabilities.lid_logo.expect("lid logo");
+        assert_eq!(logo.register, 0xE5);
+        assert_eq!([0, 1, 50, 100].map(|percent| logo.level(percent)), [0, 1, 2, 4]);
+        assert_eq!(logo.percent(3), 75);
+
+        let on_off = LidLogo { register: 0xE5, max_level: default_lid_logo_levels() };
+        assert_eq!(on_off.level(30), 1);
+        assert_eq!(on_off.percent(1), 100);
+    }
+
+    #[test]
+    fn only_controls_and_fan_tables_are_safe_to_write() {
+        let registers = RegisterMap::default();
+        let mut capabilities = Capabilities::default();
+        let safe = |address, capabilities: &Capabilities| registers.is_safe_write(address, capabilities);
+        assert!(safe(MSI_ADDRESS_FAN_MODE, &capabilities));
+        assert!(safe(MSI_ADDRESS_FAN1_BASE + 11, &capabilities));
+        assert!(!safe(MSI_ADDRESS_FAN1_BASE + 12, &capabilities));
+        assert!(!safe(MSI_ADDRESS_CPU_TEMP, &capabilities));
+        assert!(!safe(0xE5, &capabilities));
+
+        capabilities.fans = 1;
+        capabilities.lid_logo = Some(LidLogo { register: 0xE5, max_level: 1 });
+        assert!(!safe(MSI_ADDRESS_FAN2_BASE, &capabilities));
+        assert!(safe(0xE5, &capabilities));
+    }
+
+    #[test]
+    fn seven_point_tables_can_keep_temperatures_apart() {
+        let quirk = Quirk::from_file("test".to_string(), toml::from_str("name = \"Split\"\ncurve_points = 7\ntemp_table_offset = -8\n").unwrap());
+        let (registers, capabilities) = (&quirk.registers, &quirk.capabilities);
+        let base = registers.fan1_base;
+        assert_eq!(capabilities.table_entry(base, 0), (base - 8, base));
+        assert_eq!(capabilities.table_entry(base, 6), (base - 2, base + 6));
+        assert!(registers.is_safe_write(base - 8, capabilities) && registers.is_safe_write(base + 6, capabilities));
+        assert!(!registers.is_safe_write(base + 7, capabilities));
+        assert_eq!(registers.describe(base - 7, capabilities).as_deref(), Some("CPU fan curve point 2 temperature"));
+        assert_eq!(Capabilities::default().table_point(base, base + 3), Some((1, false)));
+    }
+
+    #[test]
+    fn a_mapped_third_fan_table_adds_a_fan() {
+        let quirk = |content: &str| Quirk::from_file("test".to_string(), toml::from_str(content).unwrap());
+        let titan = quirk("name = \"Titan\"\n[registers]\nfan3_base = 0x5A\nfan3_tach = 0xCC\n");
+        assert_eq!(titan.capabilities.fans, 3);
+        let system = titan.registers.fan(2).expect("third fan");
+        assert_eq!((system.table, system.tach, system.speed), (0x5A, Some(0xCC), None));
+        assert!(titan.registers.is_safe_write(0x5A + 11, &titan.capabilities));
+        assert_eq!(titan.registers.describe(0x5B, &titan.capabilities).as_deref(), Some("System fan curve point 1 speed"));
+        assert_eq!(titan.registers.describe(0xCD, &titan.capabilities).as_deref(), Some("System fan tachometer (low byte)"));
+
+        // Claiming a third fan without its registers doesn't invent one.
+        let unmapped = quirk("name = \"Unmapped\"\nfans = 3\n");
+        assert_eq!(unmapped.capabilities.fans, DEFAULT_FANS);
+        assert!(unmapped.registers.fan(2).is_none());
+    }
+
+    #[test]
+    fn entries_override_only_what_differs_from_their_template() {
+        let file = |source: &str, content: &str| (source.to_string(), toml::from_str::<QuirkFile>(content).unwrap());
+        let entries = resolve(vec![
+            file("/quirks/gs66.toml", "name = \"GS66\"\ninherits = \"family\"\nproducts = [\"GS66*\"]\nfans = 1\n[registers]\nfan_mode = 0xD4\n"),
+            file("/quirks/loop.toml", "name = \"Loop\"\ninherits = \"loop\"\nproducts = [\"Loop\"]\n"),
+            file("bundled:family.toml", "name = \"Family\"\nsupport = \"partial\"\nunsupported = [\"shift_mode\"]\n[registers]\nfan_mode = 0xF4\nshift_mode = 0xF2\n"),
+        ]);
+        assert_eq!(entries.len(), 2, "the self-inheriting entry is skipped");
+
+        let gs66 = &entries[0];
+        assert_eq!(gs66.inherits, ["family"]);
+        assert_eq!((gs66.quirk.registers.fan_mode, gs66.quirk.registers.shift_mode), (0xD4, 0xF2));
+        assert_eq!(gs66.quirk.support, SupportLevel::Partial);
+        assert_eq!(gs66.quirk.capabilities.fans, 1);
+        assert!(!gs66.quirk.capabilities.shift_mode);
+        assert!(entries[1].is_template && entries[1].quirk.capabilities.fans == DEFAULT_FANS);
+    }
+}
@@ -0,0 +1,166 @@
+use super::EmbeddedController;
+use crate::config::{AppConfig, ConfigError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid snapshot file: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("Snapshot not found: {0}")]
+    NotFound(String),
+}
+
+pub type Result<T> = std::result::Result<T, SnapshotError>;
+
+const BASELINE_NAME: &str = "baseline";
+/// Prefix of the timestamped snapshots `msi-center ec backup` writes.
+const BACKUP_PREFIX: &str = "backup-";
+
+/// Contents of the whole EC register space at one point in time. Registers the
+/// backend cannot read (e.g. unmapped ones on the msi-ec driver) are `None`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcSnapshot {
+    pub taken_at: String,
+    pub backend: String,
+    /// Name of the quirk that matched the machine, so a backup isn't restored on another model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub registers: Vec<Option<u8>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegisterChange {
+    pub address: u8,
+    pub old: Option<u8>,
+    pub new: Option<u8>,
+}
+
+impl EcSnapshot {
+    pub fn capture(ec: &mut EmbeddedController) -> Self {
+        Self::capture_only(ec, |_| true)
+    }
+
+    /// Like [`Self::capture`], but registers `wanted` rejects are left out as `None`.
+    pub fn capture_only(ec: &mut EmbeddedController, wanted: impl Fn(u8) -> bool) -> Self {
+        let registers = (0..=u8::MAX).map(|address| if wanted(address) { ec.read_byte(address).ok() } else { None }).collect();
+        Self {
+            taken_at: chrono::Local::now().to_rfc3339(),
+            backend: ec.backend_name().to_string(),
+            model: super::quirks::detected().map(|quirk| quirk.name.clone()),
+            registers,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, address: u8) -> Option<u8> {
+        self.registers.get(address as usize).copied().flatten()
+    }
+
+    /// Registers whose value differs from `self` (the older snapshot) in `newer`.
+    pub fn diff(&self, newer: &EcSnapshot) -> Vec<RegisterChange> {
+        (0..=u8::MAX)
+            .map(|address| RegisterChange {
+                address,
+                old: self.get(address),
+                new: newer.get(address),
+            })
+            .filter(|change| change.old != change.new)
+            .collect()
+    }
+}
+
+pub fn snapshots_dir() -> Result<PathBuf> {
+    Ok(AppConfig::config_dir()?.join("ec-snapshots"))
+}
+
+pub fn baseline_path() -> Result<PathBuf> {
+    Ok(snapshots_dir()?.join(format!("{}.json", BASELINE_NAME)))
+}
+
+/// A new timestamped backup in the snapshots directory.
+pub fn backup_path() -> Result<PathBuf> {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    Ok(snapshots_dir()?.join(format!("{}{}.json", BACKUP_PREFIX, stamp)))
+}
+
+/// The most recent backup, if any was made.
+pub fn latest_backup() -> Result<PathBuf> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(snapshots_dir()?)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.file_name().is_some_and(|name| name.to_string_lossy().starts_with(BACKUP_PREFIX)))
+        .collect();
+    // The timestamps sort in date order.
+    backups.sort();
+    backups.pop().ok_or_else(|| SnapshotError::NotFound("no backups yet; create one with `msi-center ec backup`".to_string()))
+}
+
+/// Resolves a snapshot given either as a file path or as the name of a snapshot
+/// stored in the snapshots directory.
+pub fn resolve(name_or_path: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(name_or_path);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let named = snapshots_dir()?.join(format!("{}.json", name_or_path));
+    if named.exists() {
+        return Ok(named);
+    }
+
+    Err(SnapshotError::NotFound(name_or_path.to_string()))
+}
+
+/// Where to save a new snapshot: `name_or_path` itself if it looks like a path,
+/// otherwise `<name>.json` in the snapshots directory.
+pub fn target_path(name_or_path: &str) -> Result<PathBuf> {
+    let path = PathBuf::from(name_or_path);
+    if path.components().count() > 1 || path.extension().is_some() {
+        return Ok(path);
+    }
+    Ok(snapshots_dir()?.join(format!("{}.json", name_or_path)))
+}
+
+/// Captures the baseline at install and daemon start, before the daemon writes
+/// to the EC, so later dumps can be compared against the firmware's own state.
+pub fn record_baseline_if_missing() {
+    let Ok(path) = baseline_path() else {
+        return;
+    };
+    if path.exists() {
+        return;
+    }
+    let Ok(mut ec) = EmbeddedController::new() else {
+        return;
+    };
+
+    let snapshot = EcSnapshot::capture(&mut ec);
+    if snapshot.registers.iter().all(Option::is_none) {
+        return;
+    }
+    match snapshot.save(&path) {
+        Ok(()) => log::info!("Recorded EC baseline at {}", path.display()),
+        Err(e) => log::warn!("Failed to record EC baseline: {}", e),
+    }
+}
@@ -0,0 +1,227 @@
+use super::snapshot::EcSnapshot;
+use super::EmbeddedController;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::sync::{Mutex, PoisonError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TraceError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid trace file: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("No EC trace is being recorded")]
+    NotRecording,
+}
+
+pub type Result<T> = std::result::Result<T, TraceError>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceOp {
+    Read,
+    Write,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EcOperation {
+    pub op: TraceOp,
+    pub address: u8,
+    pub value: u8,
+}
+
+impl std::fmt::Display for EcOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self.op {
+            TraceOp::Read => "read",
+            TraceOp::Write => "write",
+        };
+        write!(f, "{} {:#04x} = {:#04x}", op, self.address, self.value)
+    }
+}
+
+/// Every EC access made by one CLI command on a real machine, together with the
+/// register contents before it ran. Replaying it against [`MockEc`] shows whether
+/// the current code still drives that firmware the same way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EcTrace {
+    pub model: String,
+    pub backend: String,
+    pub recorded_at: String,
+    /// CLI arguments of the recorded command, without the program name.
+    pub command: Vec<String>,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    /// Registers before the command ran; may be empty if every read is in `operations`.
+    #[serde(default)]
+    pub initial: Vec<Option<u8>>,
+    pub operations: Vec<EcOperation>,
+}
+
+impl EcTrace {
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn writes(&self) -> Vec<EcOperation> {
+        self.operations.iter().copied().filter(|o| o.op == TraceOp::Write).collect()
+    }
+}
+
+struct Recording {
+    initial: EcSnapshot,
+    operations: Vec<EcOperation>,
+}
+
+static RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+/// Starts logging every EC access of this process, after capturing the initial registers.
+pub fn start_recording() {
+    let initial = match EmbeddedController::new() {
+        Ok(mut ec) => EcSnapshot::capture(&mut ec),
+        Err(_) => EcSnapshot {
+            taken_at: chrono::Local::now().to_rfc3339(),
+            backend: "none".to_string(),
+            model: None,
+            registers: Vec::new(),
+        },
+    };
+    let mut recording = RECORDING.lock().unwrap_or_else(PoisonError::into_inner);
+    *recording = Some(Recording { initial, operations: Vec::new() });
+}
+
+pub fn is_recording() -> bool {
+    RECORDING.lock().unwrap_or_else(PoisonError::into_inner).is_some()
+}
+
+/// Adds an access to the recording, if one is in progress. Called by every path
+/// that touches the EC, including the direct debugfs access in the fan module.
+pub fn record(op: TraceOp, address: u8, value: u8) {
+    let mut recording = RECORDING.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(recording) = recording.as_mut() {
+        recording.operations.push(EcOperation { op, address, value });
+    }
+}
+
+/// Stops recording and writes the trace for `command` to `path`.
+pub fn finish_recording(path: &Path, command: Vec<String>) -> Result<EcTrace> {
+    let recording = RECORDING.lock().unwrap_or_else(PoisonError::into_inner).take();
+    let recording = recording.ok_or(TraceError::NotRecording)?;
+
+    let model = fs::read_to_string("/sys/class/dmi/id/product_name")
+        .map(|s| s.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    let trace = EcTrace {
+        model,
+        backend: recording.initial.backend,
+        recorded_at: recording.initial.taken_at,
+        command,
+        notes: String::new(),
+        initial: recording.initial.registers,
+        operations: recording.operations,
+    };
+    trace.save(path)?;
+    Ok(trace)
+}
+
+/// In-memory EC backend. Reads return what the recorded firmware answered, in
+/// order, and fall back to the register contents once those run out; writes
+/// update the registers. Every access is logged.
+#[derive(Debug, Default)]
+pub struct MockEc {
+    registers: Vec<Option<u8>>,
+    responses: HashMap<u8, VecDeque<u8>>,
+    log: Vec<EcOperation>,
+}
+
+impl MockEc {
+    pub fn new(registers: Vec<Option<u8>>) -> Self {
+        Self { registers, ..Default::default() }
+    }
+
+    pub fn from_trace(trace: &EcTrace) -> Self {
+        let mut mock = Self::new(trace.initial.clone());
+        for op in trace.operations.iter().filter(|o| o.op == TraceOp::Read) {
+            mock.responses.entry(op.address).or_default().push_back(op.value);
+        }
+        mock
+    }
+
+    pub fn read(&mut self, address: u8) -> Option<u8> {
+        let value = self
+            .responses
+            .get_mut(&address)
+            .and_then(VecDeque::pop_front)
+            .or_else(|| self.registers.get(address as usize).copied().flatten())?;
+        self.log.push(EcOperation { op: TraceOp::Read, address, value });
+        Some(value)
+    }
+
+    pub fn write(&mut self, address: u8, value: u8) {
+        if self.registers.len() <= address as usize {
+            self.registers.resize(address as usize + 1, None);
+        }
+        self.registers[address as usize] = Some(value);
+        self.log.push(EcOperation { op: TraceOp::Write, address, value });
+    }
+
+    /// Makes the next read of `address` answer `value`, as firmware that overrides a write would.
+    pub fn respond(&mut self, address: u8, value: u8) {
+        self.responses.entry(address).or_default().push_back(value);
+    }
+
+    pub fn log(&self) -> &[EcOperation] {
+        &self.log
+    }
+
+    pub fn writes(&self) -> Vec<EcOperation> {
+        self.log.iter().copied().filter(|o| o.op == TraceOp::Write).collect()
+    }
+}
+
+/// A controller on a mock EC holding `registers`, and the mock to inspect, for tests.
+#[cfg(test)]
+pub fn mock_ec_with(registers: Vec<Option<u8>>) -> (std::sync::Arc<Mutex<MockEc>>, EmbeddedController) {
+    let mock = std::sync::Arc::new(Mutex::new(MockEc::new(registers)));
+    let ec = EmbeddedController::with_mock(std::sync::Arc::clone(&mock));
+    (mock, ec)
+}
+
+/// [`mock_ec_with`] every register holding `fill`.
+#[cfg(test)]
+pub fn mock_ec(fill: u8) -> (std::sync::Arc<Mutex<MockEc>>, EmbeddedController) {
+    mock_ec_with(vec![Some(fill); 256])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mock_serves_recorded_reads_before_registers() {
+        let trace = EcTrace {
+            model: "test".to_string(),
+            backend: "mock".to_string(),
+            recorded_at: String::new(),
+            command: Vec::new(),
+            notes: String::new(),
+            initial: vec![Some(0x11); 256],
+            operations: vec![EcOperation { op: TraceOp::Read, address: 0x68, value: 72 }],
+        };
+        let mut mock = MockEc::from_trace(&trace);
+
+        assert_eq!(mock.read(0x68), Some(72));
+        assert_eq!(mock.read(0x68), Some(0x11));
+        mock.write(0x68, 0x20);
+        assert_eq!(mock.read(0x68), Some(0x20));
+        assert_eq!(mock.writes(), vec![EcOperation { op: TraceOp::Write, address: 0x68, value: 0x20 }]);
+    }
+}
@@ -0,0 +1,96 @@
+//! Text chart of a fan curve for `fan curve show`: temperature across, duty up,
+//! with the curve's points and the operating point marked.
+
+use super::{FanCurve, OperatingPoint};
+
+/// Degrees per column and percent per row.
+const DEGREES_PER_COLUMN: u8 = 2;
+const PERCENT_PER_ROW: u8 = 10;
+/// The temperature axis covers at least this range (°C), wider if the curve does.
+const MIN_TEMP: u8 = 20;
+const MAX_TEMP: u8 = 100;
+
+pub const LINE: char = '•';
+pub const POINT: char = '●';
+pub const OPERATING_POINT: char = '◆';
+
+/// The chart's lines, top row first; the caller colors `OPERATING_POINT`.
+pub fn render(curve: &FanCurve, operating_point: Option<OperatingPoint>) -> Vec<String> {
+    let first = curve.points.first().map_or(MIN_TEMP, |p| p.temp);
+    let last = curve.points.last().map_or(MAX_TEMP, |p| p.temp);
+    let low = first.min(operating_point.map_or(MIN_TEMP, |op| op.temp)).min(MIN_TEMP) / 10 * 10;
+    let high = last.max(operating_point.map_or(MAX_TEMP, |op| op.temp)).max(MAX_TEMP).div_ceil(10).saturating_mul(10);
+    let columns = ((high - low) / DEGREES_PER_COLUMN) as usize + 1;
+    let rows = (100 / PERCENT_PER_ROW) as usize + 1;
+    let temp_of = |column: usize| low as usize + column * DEGREES_PER_COLUMN as usize;
+    let column_of = |temp: u8| (temp.clamp(low, high) - low).div_ceil(DEGREES_PER_COLUMN) as usize;
+    let row_of = |duty: u8| (duty.min(100) as f32 / PERCENT_PER_ROW as f32).round() as usize;
+
+    // grid[row][column], row 0 at 0%.
+    let mut grid = vec![vec![' '; columns]; rows];
+    let mut previous: Option<usize> = None;
+    for column in 0..columns {
+        let row = row_of(curve.get_speed_for_temp(temp_of(column) as u8));
+        // Steep segments jump rows; fill the gap so the line stays connected.
+        let (from, to) = match previous {
+            Some(previous) if previous + 1 < row => (previous + 1, row),
+            Some(previous) if previous > row + 1 => (row, previous - 1),
+            _ => (row, row),
+        };
+        for cells in &mut grid[from..=to] {
+            cells[column] = LINE;
+        }
+        previous = Some(row);
+    }
+    for point in &curve.points {
+        grid[row_of(point.speed)][column_of(point.temp)] = POINT;
+    }
+    if let Some(op) = operating_point {
+        grid[row_of(op.duty)][column_of(op.temp)] = OPERATING_POINT;
+    }
+
+    let mut lines: Vec<String> = grid
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(row, cells)| format!("{:>4}% ┤{}", row * PERCENT_PER_ROW as usize, cells.iter().collect::<String>().trim_end()))
+        .collect();
+    lines.push(format!("      └{}", "─".repeat(columns)));
+    // A label under every 10 °C that has room for it.
+    let mut labels = vec![' '; columns + 4];
+    for column in (0..columns).filter(|column| temp_of(*column) % 10 == 0) {
+        let label = format!("{}°", temp_of(column));
+        if labels[column..column + label.chars().count()].iter().all(|c| *c == ' ') {
+            for (offset, c) in label.chars().enumerate() {
+                labels[column + offset] = c;
+            }
+        }
+    }
+    lines.push(format!("       {}", labels.iter().collect::<String>().trim_end()));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fan::FanCurvePoint;
+
+    #[test]
+    fn chart_marks_points_and_operating_point() {
+        let curve = FanCurve {
+            points: vec![FanCurvePoint { temp: 40, speed: 0 }, FanCurvePoint { temp: 60, speed: 50 }, FanCurvePoint { temp: 80, speed: 100 }],
+            ..FanCurve::default()
+        };
+        let lines = render(&curve, curve.operating_point(70));
+        // 11 duty rows, the axis and its labels.
+        assert_eq!(lines.len(), 13);
+        let column = |line: &str, marker: char| line.chars().position(|c| c == marker);
+        // Rows from the top: 100% is line 0, 50% line 5; columns start after "NNN% ┤".
+        assert_eq!(column(&lines[0], POINT), Some(7 + 30));
+        assert_eq!(column(&lines[5], POINT), Some(7 + 20));
+        assert_eq!(column(&lines[10], POINT), Some(7 + 10));
+        // 70°C commands 75%, drawn in the 80% row.
+        assert_eq!(column(&lines[2], OPERATING_POINT), Some(7 + 25));
+        assert!(lines[12].starts_with("       20°"));
+    }
+}
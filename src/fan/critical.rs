@@ -0,0 +1,47 @@
+//! Critical-temperature failsafe: once the CPU or GPU reaches `threshold`, the daemon
+//! runs every fan at 100% and drops the shift mode to Eco, whatever the active profile
+//! says, until both have cooled below `recover_below`.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CriticalTemp {
+    pub enabled: bool,
+    /// Temperature (°C) of the CPU or GPU at which the failsafe takes over.
+    pub threshold: u8,
+    /// Both have to be below this (°C) before the active profile is applied again.
+    pub recover_below: u8,
+}
+
+impl Default for CriticalTemp {
+    fn default() -> Self {
+        Self { enabled: true, threshold: 95, recover_below: 85 }
+    }
+}
+
+impl CriticalTemp {
+    /// Whether the failsafe should hold at these temperatures, given whether it
+    /// holds now. It only lets go, and re-arms, once both have recovered.
+    pub fn is_critical(&self, cpu_temp: u8, gpu_temp: u8, tripped: bool) -> bool {
+        let hottest = cpu_temp.max(gpu_temp);
+        if tripped { hottest >= self.recover_below.min(self.threshold) } else { self.enabled && hottest >= self.threshold }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failsafe_holds_until_both_chips_recover() {
+        let critical = CriticalTemp::default();
+        assert!(!critical.is_critical(94, 80, false));
+        assert!(critical.is_critical(70, 95, false));
+        assert!(critical.is_critical(86, 60, true));
+        assert!(!critical.is_critical(84, 84, true));
+        assert!(!CriticalTemp { enabled: false, ..critical }.is_critical(100, 100, false));
+        // A recovery point above the threshold is read as the threshold.
+        assert!(CriticalTemp { threshold: 80, recover_below: 90, ..critical }.is_critical(85, 60, true));
+    }
+}
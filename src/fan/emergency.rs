@@ -0,0 +1,58 @@
+//! Emergency cooling: Cooler Boost turned on while the CPU or GPU is past a
+//! temperature, and off again once both have cooled below a lower one.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmergencyCooling {
+    /// Temperature (°C) of the CPU or GPU above which Cooler Boost is turned on.
+    pub on_above: u8,
+    /// Both have to be below this (°C) before it is turned off again.
+    pub off_below: u8,
+}
+
+impl Default for EmergencyCooling {
+    fn default() -> Self {
+        Self { on_above: 90, off_below: 75 }
+    }
+}
+
+impl EmergencyCooling {
+    /// Whether Cooler Boost should be on at these temperatures, given whether the
+    /// rule has it on now. Between the two bounds it stays as it is.
+    pub fn wants_boost(&self, cpu_temp: u8, gpu_temp: u8, boosting: bool) -> bool {
+        let hottest = cpu_temp.max(gpu_temp);
+        if boosting { hottest >= self.off_below } else { hottest > self.on_above }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.off_below > self.on_above {
+            return Err(format!("off_below ({}°C) must not be above on_above ({}°C)", self.off_below, self.on_above));
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for EmergencyCooling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "on above {}°C, off below {}°C", self.on_above, self.off_below)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boost_follows_the_hotter_chip_with_hysteresis() {
+        let rule = EmergencyCooling::default();
+        assert!(!rule.wants_boost(90, 60, false));
+        assert!(rule.wants_boost(70, 91, false));
+        // Stays on until both are below the lower bound.
+        assert!(rule.wants_boost(80, 60, true));
+        assert!(rule.wants_boost(60, 75, true));
+        assert!(!rule.wants_boost(74, 70, true));
+        assert!(EmergencyCooling { on_above: 70, off_below: 80 }.validate().is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Spots fans that stopped turning: a fan reading 0 RPM while its temperature is
+//! past a threshold, for longer than a spinning-down or fan-stop fan would.
+
+use super::FanInfo;
+use crate::config::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+const STALLED_FILE: &str = "stalled-fans.json";
+/// A record the daemon hasn't refreshed for this long is from a daemon that stopped.
+const RECORD_LIFETIME: chrono::Duration = chrono::Duration::minutes(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FailureDetection {
+    pub enabled: bool,
+    /// Temperature (°C) of the fan's sensor from which 0 RPM counts as stalled.
+    pub temp_threshold: u8,
+    /// How long a fan has to be stalled before it is reported.
+    pub seconds: u64,
+    /// Turns Cooler Boost on while a fan is stalled, so the others make up for it.
+    pub cooler_boost: bool,
+}
+
+impl Default for FailureDetection {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            temp_threshold: 65,
+            seconds: 30,
+            cooler_boost: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StalledFan {
+    pub name: String,
+    /// Temperature of the fan's sensor at the last reading.
+    pub temp: u8,
+    /// How long the fan has read 0 RPM.
+    pub seconds: u64,
+}
+
+impl std::fmt::Display for StalledFan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} fan reads 0 RPM at {}°C for {} s", self.name, self.temp, self.seconds)
+    }
+}
+
+/// Tracks since when each fan has been stalled, across readings.
+#[derive(Debug, Default)]
+pub struct FailureWatch {
+    since: Vec<Option<Instant>>,
+}
+
+impl FailureWatch {
+    /// Fans that have been stalled for at least `detection.seconds` as of `info`, in fan order.
+    pub fn update(&mut self, info: &FanInfo, detection: &FailureDetection, now: Instant) -> Vec<StalledFan> {
+        if !detection.enabled {
+            self.since.clear();
+            return Vec::new();
+        }
+        self.since.resize(info.fans.len(), None);
+        let mut stalled = Vec::new();
+        for (index, fan) in info.fans.iter().enumerate() {
+            // The CPU fan follows the CPU, the others the GPU, as in the firmware.
+            let temp = if index == 0 { info.cpu_temp } else { info.gpu_temp };
+            if fan.rpm > 0 || temp < detection.temp_threshold {
+                self.since[index] = None;
+                continue;
+            }
+            let since = *self.since[index].get_or_insert(now);
+            let elapsed = now.saturating_duration_since(since);
+            if elapsed >= Duration::from_secs(detection.seconds) {
+                stalled.push(StalledFan { name: fan.name.to_string(), temp, seconds: elapsed.as_secs() });
+            }
+        }
+        stalled
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Record {
+    at: String,
+    fans: Vec<StalledFan>,
+}
+
+/// Kept in the config directory so `status` sees what the daemon detected.
+fn record_path() -> Option<PathBuf> {
+    AppConfig::config_dir().ok().map(|dir| dir.join(STALLED_FILE))
+}
+
+/// Stores the daemon's latest findings; an empty list removes the record.
+pub fn record(stalled: &[StalledFan]) {
+    let Some(path) = record_path() else { return };
+    if stalled.is_empty() {
+        if path.exists()
+            && let Err(e) = fs::remove_file(&path)
+        {
+            log::debug!("Could not remove {}: {}", path.display(), e);
+        }
+        return;
+    }
+    let record = Record { at: chrono::Local::now().to_rfc3339(), fans: stalled.to_vec() };
+    if let Err(e) = fs::write(&path, serde_json::to_string_pretty(&record).unwrap_or_default()) {
+        log::debug!("Could not record stalled fans in {}: {}", path.display(), e);
+    }
+}
+
+/// Stalled fans the daemon reported recently.
+pub fn recorded() -> Vec<StalledFan> {
+    let Some(record) = record_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<Record>(&content).ok())
+    else {
+        return Vec::new();
+    };
+    let fresh = chrono::DateTime::parse_from_rfc3339(&record.at)
+        .is_ok_and(|at| chrono::Local::now().signed_duration_since(at) < RECORD_LIFETIME);
+    if fresh { record.fans } else { Vec::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fan::{FanMode, FanReading};
+
+    fn info(cpu_rpm: u32, cpu_temp: u8, gpu_rpm: u32, gpu_temp: u8) -> FanInfo {
+        FanInfo {
+            cpu_fan_rpm: cpu_rpm,
+            gpu_fan_rpm: gpu_rpm,
+            cpu_fan_percent: 50,
+            gpu_fan_percent: 50,
+            cpu_temp,
+            gpu_temp,
+            fan_mode: FanMode::Auto,
+            cooler_boost: false,
+            fans: vec![
+                FanReading { name: "CPU", rpm: cpu_rpm, percent: 50, target_percent: None },
+                FanReading { name: "GPU", rpm: gpu_rpm, percent: 50, target_percent: None },
+            ],
+            gpu: None,
+        }
+    }
+
+    #[test]
+    fn fans_count_as_stalled_only_when_hot_for_long_enough() {
+        let detection = FailureDetection::default();
+        let mut watch = FailureWatch::default();
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+
+        // A stopped fan at idle temperatures is fan-stop, not a failure.
+        assert!(watch.update(&info(0, 45, 0, 40), &detection, at(0)).is_empty());
+        assert!(watch.update(&info(0, 45, 0, 40), &detection, at(60)).is_empty());
+
+        assert!(watch.update(&info(3000, 80, 0, 70), &detection, at(61)).is_empty());
+        assert!(watch.update(&info(3000, 80, 0, 70), &detection, at(80)).is_empty());
+        let stalled = watch.update(&info(3000, 80, 0, 72), &detection, at(95));
+        assert_eq!(stalled, vec![StalledFan { name: "GPU".to_string(), temp: 72, seconds: 34 }]);
+
+        // Spinning again starts the count over.
+        assert!(watch.update(&info(3000, 80, 2500, 72), &detection, at(96)).is_empty());
+        assert!(watch.update(&info(3000, 80, 0, 72), &detection, at(100)).is_empty());
+    }
+}
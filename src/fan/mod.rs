@@ -1,11 +1,24 @@
-use crate::ec::{
-    EcError, EmbeddedController, MSI_ADDRESS_COOLER_BOOST, MSI_ADDRESS_CPU_FAN_SPEED,
-    MSI_ADDRESS_CPU_TEMP, MSI_ADDRESS_FAN1_BASE, MSI_ADDRESS_FAN2_BASE, MSI_ADDRESS_FAN_MODE,
-    MSI_ADDRESS_GPU_FAN_SPEED, MSI_ADDRESS_GPU_TEMP,
-};
+pub mod chart;
+pub mod critical;
+pub mod emergency;
+pub mod failure;
+pub mod selftest;
+pub mod simulate;
+pub mod software;
+pub mod source;
+
+use crate::ec::msi_ec;
+use crate::ec::quirks::{FAN_NAMES, MAX_CURVE_POINTS};
+use crate::ec::trace::{self, TraceOp};
+use crate::ec::{EcError, EmbeddedController, WriteBatch};
+use crate::gpu::{self, GpuTelemetry};
+use crate::system;
+use source::TempSource;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -18,10 +31,72 @@ pub enum FanError {
     FanNotFound(String),
     #[error("Hwmon interface error: {0}")]
     HwmonError(String),
+    #[error("Refusing to set the {fan} fan to {speed}% at {temp}°C (minimum {min}% above {threshold}°C)")]
+    UnsafeSpeed { fan: &'static str, speed: u8, temp: u8, min: u8, threshold: u8 },
+    #[error("Refusing to stop the {fan} fan at {temp}°C (0% is only allowed below {limit}°C)")]
+    FanStop { fan: &'static str, temp: u8, limit: u8 },
+    #[error("{0} is not supported on this model or EC backend")]
+    Unsupported(&'static str),
+    #[error("{what} did not take effect: {registers}")]
+    NotApplied { what: &'static str, registers: String },
+    #[error("Invalid fan curve: {0}")]
+    InvalidCurve(#[from] CurveError),
 }
 
 pub type Result<T> = std::result::Result<T, FanError>;
 
+/// Why `FanCurve::validate` rejected a curve, phrased so the user knows what to change.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CurveError {
+    #[error("a curve needs at least 2 points, this one has {0}")]
+    TooFewPoints(usize),
+    #[error("this model's fan table holds {max} points, the curve has {count}; remove {}", count - max)]
+    TooManyPoints { count: usize, max: usize },
+    #[error("points {first} and {second} are both at {temp}°C; remove one of them")]
+    DuplicateTemperature { first: usize, second: usize, temp: u8 },
+    #[error("point {index} ({temp}°C) is cooler than point {previous} ({previous_temp}°C); list the points from coolest to hottest")]
+    DecreasingTemperature { index: usize, temp: u8, previous: usize, previous_temp: u8 },
+    #[error("point {index} has a speed of {speed}%; speeds go from 0 to 100")]
+    SpeedOutOfRange { index: usize, speed: u8 },
+    #[error("the fan runs at {speed}% at {temp}°C, but needs at least {min}% from {threshold}°C on; raise the hotter points (or change speed_guard in the config)")]
+    UnsafeSpeed { temp: u8, speed: u8, min: u8, threshold: u8 },
+    #[error("the fan stops (0%) at {temp}°C, but may only stop below {limit}°C; raise the points around it (or change speed_guard in the config)")]
+    FanStop { temp: u8, limit: u8 },
+    #[error("the fan table holds {points} points and the hottest point ({temp}°C) leaves no room to pad the curve up to them; lower it or add points")]
+    NoRoomToPad { temp: u8, points: usize },
+}
+
+/// Set while this process has pinned the fans below the speed guard's floor, so a
+/// crash or daemon shutdown can hand them back to the firmware.
+static PINNED_LOW: AtomicBool = AtomicBool::new(false);
+
+/// Set once the user has been told that ec_sys is read-only, to warn only once.
+static EC_SYS_READ_ONLY_WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Puts the fans back in Auto mode if this process left them pinned low.
+pub fn restore_safe_state() {
+    if !PINNED_LOW.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    let result = EmbeddedController::new()
+        .map_err(FanError::from)
+        .and_then(|ec| FanController::new(ec).set_fan_mode(FanMode::Auto));
+    match result {
+        Ok(()) => log::warn!("Fans were pinned low, restored automatic fan control"),
+        Err(e) => log::error!("Fans are pinned low and restoring automatic control failed: {}", e),
+    }
+}
+
+/// Restores automatic fan control before the default panic output, so a crash
+/// never leaves the fans stuck at a low fixed speed.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_safe_state();
+        previous(info);
+    }));
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FanMode {
     Auto = 0,
@@ -30,6 +105,34 @@ pub enum FanMode {
     Advanced = 3,
 }
 
+impl FanMode {
+    pub const ALL: [FanMode; 4] = [FanMode::Auto, FanMode::Silent, FanMode::Basic, FanMode::Advanced];
+
+    /// The msi-ec driver's name for this mode.
+    pub fn name(self) -> &'static str {
+        match self {
+            FanMode::Auto => "auto",
+            FanMode::Silent => "silent",
+            FanMode::Basic => "basic",
+            FanMode::Advanced => "advanced",
+        }
+    }
+
+    /// Fan modes the firmware supports, as listed by msi-ec. Falls back to all modes
+    /// when the driver isn't loaded.
+    pub fn available() -> Vec<FanMode> {
+        let Some(names) = msi_ec::available_fan_modes() else {
+            return Self::ALL.to_vec();
+        };
+        let modes: Vec<FanMode> = Self::ALL.into_iter().filter(|mode| names.iter().any(|name| name == mode.name())).collect();
+        if modes.is_empty() {
+            log::debug!("Unrecognized available_fan_modes: {:?}", names);
+            return Self::ALL.to_vec();
+        }
+        modes
+    }
+}
+
 impl From<u8> for FanMode {
     fn from(value: u8) -> Self {
         match value {
@@ -42,15 +145,187 @@ impl From<u8> for FanMode {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FanCurvePoint {
     pub temp: u8,
     pub speed: u8,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Refuses manual speeds below `min_speed` while the fan's sensor is at or above
+/// `temp_threshold`, so a typo like 0% can't cook a machine under load, and a
+/// stopped fan from `zero_rpm_below` on, where the VRMs still want airflow.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SpeedGuard {
+    pub enabled: bool,
+    pub temp_threshold: u8,
+    pub min_speed: u8,
+    /// Temperature (°C) from which the fan may no longer run at 0%.
+    #[serde(default = "default_zero_rpm_below")]
+    pub zero_rpm_below: u8,
+}
+
+fn default_zero_rpm_below() -> u8 {
+    50
+}
+
+impl Default for SpeedGuard {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            temp_threshold: 85,
+            min_speed: 50,
+            zero_rpm_below: default_zero_rpm_below(),
+        }
+    }
+}
+
+impl SpeedGuard {
+    /// Lowest duty the guard lets the fan run at with its sensor at `temp`.
+    pub fn floor(&self, temp: u8) -> u8 {
+        match self.enabled {
+            true if temp >= self.temp_threshold => self.min_speed.max(1),
+            true if temp >= self.zero_rpm_below => 1,
+            _ => 0,
+        }
+    }
+
+    pub fn check(&self, fan: &'static str, speed: u8, temp: u8) -> Result<()> {
+        if self.enabled && temp >= self.temp_threshold && speed < self.min_speed {
+            return Err(FanError::UnsafeSpeed {
+                fan,
+                speed,
+                temp,
+                min: self.min_speed,
+                threshold: self.temp_threshold,
+            });
+        }
+        if self.enabled && speed == 0 && temp >= self.zero_rpm_below {
+            return Err(FanError::FanStop { fan, temp, limit: self.zero_rpm_below });
+        }
+        Ok(())
+    }
+}
+
+/// Fixed duty for the fans, stored in a profile when the user sets manual speeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManualFanSpeed {
+    pub cpu: u8,
+    pub gpu: u8,
+    /// Third fan, on models that have one; follows the faster of the other two if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<u8>,
+}
+
+impl ManualFanSpeed {
+    /// Duty of each fan, by index.
+    pub fn speeds(&self) -> [u8; 3] {
+        [self.cpu, self.gpu, self.system.unwrap_or(self.cpu.max(self.gpu))]
+    }
+
+    /// These speeds with none below `min_percent`.
+    pub fn floored(&self, min_percent: u8) -> Self {
+        Self { cpu: self.cpu.max(min_percent), gpu: self.gpu.max(min_percent), system: self.system.map(|speed| speed.max(min_percent)) }
+    }
+}
+
+/// How long a fan table read back for the target duty is used before it is read again.
+const TABLE_READ_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Temperature (°C) from which quieted curves keep their full duty.
+pub const QUIET_TEMP_LIMIT: u8 = 70;
+
+/// `AUDIBLE_THRESHOLD` when no threshold was calibrated.
+const NO_AUDIBLE_THRESHOLD: u8 = u8::MAX;
+
+static AUDIBLE_THRESHOLD: AtomicU8 = AtomicU8::new(NO_AUDIBLE_THRESHOLD);
+
+/// Sets the duty (%) the user can't hear, from `fan calibrate-noise`, that the
+/// silent preset stays under in this process; `None` leaves the preset as built.
+pub fn set_audible_threshold(threshold: Option<u8>) {
+    AUDIBLE_THRESHOLD.store(threshold.map_or(NO_AUDIBLE_THRESHOLD, |t| t.min(100)), Ordering::Relaxed);
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct FanCurve {
     pub points: Vec<FanCurvePoint>,
+    #[serde(default, skip_serializing_if = "CurveTuning::is_empty")]
+    pub tuning: CurveTuning,
+    #[serde(default, skip_serializing_if = "CurveDamping::is_empty")]
+    pub damping: CurveDamping,
+    /// Sensor the software control loop looks the curve up against. The firmware
+    /// always follows its own temperature registers.
+    #[serde(default, skip_serializing_if = "TempSource::is_fan")]
+    pub source: TempSource,
+}
+
+/// Advanced options some firmwares apply on top of the curve's points (mapped
+/// under `[fan_tuning]` in the model's quirk file). On models that have the
+/// registers, unset options are written as zero, like MSI Center does.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CurveTuning {
+    /// Duty (%) the fan never drops below while following the curve.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_duty: Option<u8>,
+    /// Duty (%) added to each point's speed, -50 to 50; missing points get none.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub offsets: Vec<i8>,
+}
+
+/// How the software control loop moves between duties, so fans don't audibly hunt
+/// while the temperature hovers around a curve point. The firmware ignores it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CurveDamping {
+    /// Degrees (°C) the temperature must fall before the duty is lowered again.
+    #[serde(default)]
+    pub hysteresis: u8,
+    /// Largest duty change per second (%), up or down; 0 for none.
+    #[serde(default)]
+    pub max_ramp: u8,
+}
+
+impl CurveDamping {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Largest duty offset the firmware is given for a curve point.
+pub const MAX_DUTY_OFFSET: i8 = 50;
+
+impl CurveTuning {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// These options as a table of `points` holds them once written and read back.
+    pub fn as_written(&self, points: usize) -> Self {
+        let mut offsets: Vec<i8> = self.offsets.iter().take(points).map(|offset| (*offset).clamp(-MAX_DUTY_OFFSET, MAX_DUTY_OFFSET)).collect();
+        while offsets.last() == Some(&0) {
+            offsets.pop();
+        }
+        Self { min_duty: self.min_duty.map(|duty| duty.min(100)).filter(|&duty| duty > 0), offsets }
+    }
+}
+
+/// EC value for a duty in percent, as the fan tables store it.
+fn duty_byte(percent: u8) -> u8 {
+    ((percent.min(100) as u16 * 255) / 100) as u8
+}
+
+/// Percent for a fan table speed byte; undoes `duty_byte`.
+pub(crate) fn duty_percent(byte: u8) -> u8 {
+    ((byte as u16 * 100 + 127) / 255) as u8
+}
+
+/// EC value for a duty offset in percent: signed, in the same units as the table's speeds.
+fn offset_byte(offset: i8) -> u8 {
+    (offset.clamp(-MAX_DUTY_OFFSET, MAX_DUTY_OFFSET) as i16 * 255 / 100) as i8 as u8
+}
+
+/// Percent for a duty offset byte; undoes `offset_byte`.
+fn offset_percent(byte: u8) -> i8 {
+    let value = byte as i8 as i16 * 100;
+    ((value + 127 * value.signum()) / 255) as i8
 }
 
 impl Default for FanCurve {
@@ -64,21 +339,37 @@ impl Default for FanCurve {
                 FanCurvePoint { temp: 80, speed: 90 },
                 FanCurvePoint { temp: 90, speed: 100 },
             ],
+            tuning: CurveTuning::default(),
+            damping: CurveDamping::default(),
+            source: TempSource::Fan,
         }
     }
 }
 
 impl FanCurve {
+    /// The silent preset, quieted to the audible threshold where one is set (see
+    /// [`set_audible_threshold`]).
     pub fn silent() -> Self {
+        let curve = Self::silent_unquieted();
+        match AUDIBLE_THRESHOLD.load(Ordering::Relaxed) {
+            NO_AUDIBLE_THRESHOLD => curve,
+            threshold => curve.quieted(threshold),
+        }
+    }
+
+    fn silent_unquieted() -> Self {
         Self {
             points: vec![
-                FanCurvePoint { temp: 50, speed: 0 },
+                FanCurvePoint { temp: 45, speed: 0 },
                 FanCurvePoint { temp: 60, speed: 20 },
                 FanCurvePoint { temp: 70, speed: 40 },
                 FanCurvePoint { temp: 80, speed: 60 },
                 FanCurvePoint { temp: 90, speed: 80 },
                 FanCurvePoint { temp: 95, speed: 100 },
             ],
+            tuning: CurveTuning::default(),
+            damping: CurveDamping::default(),
+            source: TempSource::Fan,
         }
     }
 
@@ -92,9 +383,156 @@ impl FanCurve {
                 FanCurvePoint { temp: 75, speed: 100 },
                 FanCurvePoint { temp: 85, speed: 100 },
             ],
+            tuning: CurveTuning::default(),
+            damping: CurveDamping::default(),
+            source: TempSource::Fan,
+        }
+    }
+
+    /// Caps the duty of points below `QUIET_TEMP_LIMIT` at `audible_threshold`, so the
+    /// curve stays inaudible for as long as it safely can. Hotter points are untouched.
+    pub fn quieted(&self, audible_threshold: u8) -> Self {
+        let points = self
+            .points
+            .iter()
+            .map(|p| FanCurvePoint {
+                temp: p.temp,
+                speed: if p.temp < QUIET_TEMP_LIMIT { p.speed.min(audible_threshold) } else { p.speed },
+            })
+            .collect();
+        Self { points, tuning: self.tuning.clone(), damping: self.damping, source: self.source.clone() }
+    }
+
+    /// The curve with every point raised to at least `min_percent`, a profile's fan floor.
+    pub fn floored(&self, min_percent: u8) -> Self {
+        let points = self.points.iter().map(|p| FanCurvePoint { temp: p.temp, speed: p.speed.max(min_percent) }).collect();
+        Self { points, tuning: self.tuning.clone(), damping: self.damping, source: self.source.clone() }
+    }
+
+    /// Whether `table`, read back from a fan table, holds what writing this curve
+    /// raised to `floor` puts there; its tuning only counts where the model has
+    /// the registers (`tuned`).
+    pub fn held_by(&self, table: &FanCurve, floor: u8, tuned: bool) -> bool {
+        self.floored(floor).fitted(table.points.len()).points == table.points
+            && (!tuned || self.tuning.as_written(table.points.len()) == table.tuning)
+    }
+
+    /// The curve with exactly `count` points, for a fan table of that size. A short
+    /// curve is padded with copies of its hottest point a degree apart, so the table
+    /// keeps rising and the duty past the curve stays the same. A long one loses the
+    /// points whose removal changes it least; the coolest and hottest stay.
+    pub fn fitted(&self, count: usize) -> Self {
+        let mut points = self.points.clone();
+        while points.len() > count.max(2) {
+            let deviation = |i: usize| {
+                let (before, point, after) = (&points[i - 1], &points[i], &points[i + 1]);
+                let span = after.temp.saturating_sub(before.temp).max(1) as f32;
+                let interpolated = before.speed as f32 + point.temp.saturating_sub(before.temp) as f32 / span * (after.speed as f32 - before.speed as f32);
+                (point.speed as f32 - interpolated).abs()
+            };
+            let Some(least) = (1..points.len() - 1).min_by(|a, b| deviation(*a).total_cmp(&deviation(*b))) else {
+                break;
+            };
+            points.remove(least);
+        }
+        while let Some(last) = points.last()
+            && points.len() < count
+        {
+            let padding = FanCurvePoint { temp: last.temp.saturating_add(1), speed: last.speed };
+            points.push(padding);
+        }
+        Self { points, tuning: self.tuning.clone(), damping: self.damping, source: self.source.clone() }
+    }
+
+    /// Checks that the points rise in temperature, fit the model's fan table
+    /// (`max_points`), keep the fan turning from the guard's `zero_rpm_below` on
+    /// and at the guard's minimum from its threshold on.
+    /// Points are numbered from 1 in the errors, as the CLI and GUI show them.
+    pub fn validate(&self, max_points: usize, guard: &SpeedGuard) -> std::result::Result<(), CurveError> {
+        let count = self.points.len();
+        if count < 2 {
+            return Err(CurveError::TooFewPoints(count));
+        }
+        if count > max_points {
+            return Err(CurveError::TooManyPoints { count, max: max_points });
+        }
+        for (i, point) in self.points.iter().enumerate() {
+            if point.speed > 100 {
+                return Err(CurveError::SpeedOutOfRange { index: i + 1, speed: point.speed });
+            }
+            let Some(previous) = i.checked_sub(1).map(|j| &self.points[j]) else {
+                continue;
+            };
+            if point.temp == previous.temp {
+                return Err(CurveError::DuplicateTemperature { first: i, second: i + 1, temp: point.temp });
+            }
+            if point.temp < previous.temp {
+                return Err(CurveError::DecreasingTemperature { index: i + 1, temp: point.temp, previous: i, previous_temp: previous.temp });
+            }
+        }
+
+        if guard.enabled {
+            // The curve's duty at the threshold and at every hotter point; it is linear in between.
+            let hot = std::iter::once(guard.temp_threshold).chain(self.points.iter().map(|p| p.temp).filter(|temp| *temp > guard.temp_threshold));
+            for temp in hot {
+                let speed = self.get_speed_for_temp(temp);
+                if speed < guard.min_speed {
+                    return Err(CurveError::UnsafeSpeed { temp, speed, min: guard.min_speed, threshold: guard.temp_threshold });
+                }
+            }
+            // And that it doesn't stop anywhere from `zero_rpm_below` on.
+            let mut warm = std::iter::once(guard.zero_rpm_below).chain(self.points.iter().map(|p| p.temp).filter(|temp| *temp > guard.zero_rpm_below));
+            if let Some(temp) = warm.find(|temp| self.get_speed_for_temp(*temp) == 0) {
+                return Err(CurveError::FanStop { temp, limit: guard.zero_rpm_below });
+            }
+        }
+        Ok(())
+    }
+
+    /// The points of this fan table without those that pad `written`, raised to
+    /// `floor`, out to it. Padding is only known where the table holds exactly what
+    /// writing that curve puts there; otherwise every point is the table's own.
+    pub fn unpadded(&self, written: Option<&FanCurve>, floor: u8) -> Vec<FanCurvePoint> {
+        match written.map(|curve| curve.floored(floor)) {
+            Some(curve) if curve.points.len() < self.points.len() && curve.fitted(self.points.len()).points == self.points => curve.points,
+            _ => self.points.clone(),
         }
     }
 
+    /// `validate` for a curve that is fitted to the fan table when written, as
+    /// profile curves are: it may have as many points as the longest tables hold,
+    /// and has to pass again once fitted, padded or thinned, into this model's
+    /// `max_points`.
+    pub fn validate_fitted(&self, max_points: usize, guard: &SpeedGuard) -> std::result::Result<(), CurveError> {
+        self.validate(MAX_CURVE_POINTS as usize, guard)?;
+        let count = self.points.len();
+        if let Some(last) = self.points.last()
+            && count < max_points
+            && last.temp as usize + (max_points - count) > u8::MAX as usize
+        {
+            return Err(CurveError::NoRoomToPad { temp: last.temp, points: max_points });
+        }
+        if count != max_points { self.fitted(max_points).validate(max_points, guard) } else { Ok(()) }
+    }
+
+    /// The duty the firmware runs the fan at for `temp`: the curve with each
+    /// point's offset added, never below its minimum duty.
+    pub fn tuned_speed_for_temp(&self, temp: u8) -> u8 {
+        let mut offset = self.clone();
+        for (point, delta) in offset.points.iter_mut().zip(&self.tuning.offsets) {
+            point.speed = (point.speed as i16 + (*delta).clamp(-MAX_DUTY_OFFSET, MAX_DUTY_OFFSET) as i16).clamp(0, 100) as u8;
+        }
+        offset.get_speed_for_temp(temp).max(self.tuning.min_duty.unwrap_or(0).min(100))
+    }
+
+    pub fn operating_point(&self, temp: u8) -> Option<OperatingPoint> {
+        if self.points.is_empty() {
+            return None;
+        }
+        let segment = self.points.iter().rposition(|p| p.temp <= temp).unwrap_or(0);
+        Some(OperatingPoint { temp, segment, duty: self.get_speed_for_temp(temp) })
+    }
+
     pub fn get_speed_for_temp(&self, temp: u8) -> u8 {
         if self.points.is_empty() {
             return 50;
@@ -126,7 +564,41 @@ impl FanCurve {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Where a curve stands at a temperature: the segment starting at `points[segment]`
+/// (the first or last point outside the curve's range) and the duty it commands.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct OperatingPoint {
+    pub temp: u8,
+    pub segment: usize,
+    pub duty: u8,
+}
+
+/// Percent the EC's duty may stray from the target before it counts as overriding it.
+pub const TARGET_TOLERANCE: u8 = 10;
+
+/// One fan's speed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FanReading {
+    pub name: &'static str,
+    /// Measured by the tachometer, or estimated from `percent` without one.
+    pub rpm: u32,
+    /// Duty the EC commands, from its duty registers.
+    pub percent: u8,
+    /// Duty the EC was asked for: its fan table's speed at the fan's temperature in
+    /// Advanced mode, 100 under Cooler Boost. Unknown where the firmware follows its
+    /// own curve or the table can't be read.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_percent: Option<u8>,
+}
+
+impl FanReading {
+    /// Whether the EC runs the fan at another duty than it was asked for.
+    pub fn off_target(&self) -> bool {
+        self.target_percent.is_some_and(|target| target.abs_diff(self.percent) > TARGET_TOLERANCE)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct FanInfo {
     pub cpu_fan_rpm: u32,
     pub gpu_fan_rpm: u32,
@@ -136,60 +608,106 @@ pub struct FanInfo {
     pub gpu_temp: u8,
     pub fan_mode: FanMode,
     pub cooler_boost: bool,
+    /// Every fan of the model in order, including a third one the fields above leave out.
+    pub fans: Vec<FanReading>,
+    /// What the dGPU's driver reports, where a provider reaches it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<GpuTelemetry>,
 }
 
 pub struct FanController {
     ec: EmbeddedController,
-    cpu_curve: FanCurve,
-    gpu_curve: FanCurve,
+    /// Last curve set for each fan, by index.
+    curves: Vec<Option<FanCurve>>,
+    /// Each fan's table as last read back for its target duty, and when.
+    tables: Vec<Option<(Instant, FanCurve)>>,
     coretemp_path: Option<String>,
+    /// hwmon device of the msi-ec driver, which reports fan RPM as `fan<n>_input`.
+    msi_ec_hwmon_path: Option<String>,
+    speed_guard: SpeedGuard,
 }
 
 impl FanController {
     pub fn new(ec: EmbeddedController) -> Self {
-        let coretemp_path = Self::find_coretemp_path();
+        let coretemp_path = Self::find_hwmon(&["coretemp"]);
+        let msi_ec_hwmon_path = Self::find_hwmon(&["msi_ec", "msi-ec"]);
         Self {
             ec,
-            cpu_curve: FanCurve::default(),
-            gpu_curve: FanCurve::default(),
+            curves: vec![None; FAN_NAMES.len()],
+            tables: vec![None; FAN_NAMES.len()],
             coretemp_path,
+            msi_ec_hwmon_path,
+            speed_guard: SpeedGuard::default(),
         }
     }
 
-    fn find_coretemp_path() -> Option<String> {
+    /// The EC handle this controller writes through, for other controls sharing it.
+    pub fn ec_mut(&mut self) -> &mut EmbeddedController {
+        &mut self.ec
+    }
+
+    /// Replaces the default speed guard, e.g. with the configured one or a disabled one for `--force`.
+    pub fn set_speed_guard(&mut self, guard: SpeedGuard) {
+        self.speed_guard = guard;
+    }
+
+    /// Fans this model has, from its register map.
+    pub fn fan_count(&self) -> usize {
+        self.ec.capabilities().fans as usize
+    }
+
+    /// Whether the model maps fan tuning registers for fan `index`.
+    pub fn has_tuning(&self, index: usize) -> bool {
+        self.ec.capabilities().fan_tuning.for_fan(index) != (None, None)
+    }
+
+    /// The first hwmon device whose `name` is one of `names`.
+    fn find_hwmon(names: &[&str]) -> Option<String> {
         let hwmon_base = "/sys/class/hwmon";
         if let Ok(entries) = fs::read_dir(hwmon_base) {
             for entry in entries.flatten() {
                 let path = entry.path();
                 let name_file = path.join("name");
-                if let Ok(name) = fs::read_to_string(&name_file) {
-                    if name.trim() == "coretemp" {
-                        return Some(path.to_string_lossy().to_string());
-                    }
+                if let Ok(name) = fs::read_to_string(&name_file)
+                    && names.contains(&name.trim()) {
+                    log::debug!("Found {} hwmon at {}", name.trim(), path.display());
+                    return Some(path.to_string_lossy().to_string());
                 }
             }
         }
         None
     }
 
+    /// Fan RPM as the msi-ec driver reports it; fan 1 is the CPU fan.
+    fn read_fan_rpm_from_hwmon(&self, fan_num: usize) -> Option<u32> {
+        if self.ec.is_mock() {
+            return None;
+        }
+        let path = self.msi_ec_hwmon_path.as_ref()?;
+        let content = fs::read_to_string(format!("{}/fan{}_input", path, fan_num)).ok()?;
+        content.trim().parse().ok()
+    }
+
     fn read_cpu_temp_from_hwmon(&self) -> Option<u8> {
+        // A replayed trace carries its own temperatures.
+        if self.ec.is_mock() {
+            return None;
+        }
         if let Some(ref path) = self.coretemp_path {
             let temp_path = format!("{}/temp1_input", path);
-            if let Ok(content) = fs::read_to_string(&temp_path) {
-                if let Ok(millidegrees) = content.trim().parse::<i32>() {
-                    return Some((millidegrees / 1000) as u8);
-                }
+            if let Ok(content) = fs::read_to_string(&temp_path)
+                && let Ok(millidegrees) = content.trim().parse::<i32>() {
+                return Some((millidegrees / 1000) as u8);
             }
         }
         
         for i in 0..3 {
             let tz_path = format!("/sys/class/thermal/thermal_zone{}/temp", i);
-            if let Ok(content) = fs::read_to_string(&tz_path) {
-                if let Ok(millidegrees) = content.trim().parse::<i32>() {
-                    let temp = (millidegrees / 1000) as u8;
-                    if temp > 20 && temp < 110 {
-                        return Some(temp);
-                    }
+            if let Ok(content) = fs::read_to_string(&tz_path)
+                && let Ok(millidegrees) = content.trim().parse::<i32>() {
+                let temp = (millidegrees / 1000) as u8;
+                if temp > 20 && temp < 110 {
+                    return Some(temp);
                 }
             }
         }
@@ -197,6 +715,9 @@ impl FanController {
     }
 
     fn read_gpu_temp_from_hwmon(&self) -> Option<u8> {
+        if self.ec.is_mock() {
+            return None;
+        }
         let hwmon_base = "/sys/class/hwmon";
         if let Ok(entries) = fs::read_dir(hwmon_base) {
             for entry in entries.flatten() {
@@ -206,10 +727,9 @@ impl FanController {
                     let name = name.trim().to_lowercase();
                     if name.contains("nvidia") || name.contains("amdgpu") || name.contains("nouveau") {
                         let temp_path = path.join("temp1_input");
-                        if let Ok(content) = fs::read_to_string(&temp_path) {
-                            if let Ok(millidegrees) = content.trim().parse::<i32>() {
-                                return Some((millidegrees / 1000) as u8);
-                            }
+                        if let Ok(content) = fs::read_to_string(&temp_path)
+                            && let Ok(millidegrees) = content.trim().parse::<i32>() {
+                            return Some((millidegrees / 1000) as u8);
                         }
                     }
                 }
@@ -218,14 +738,17 @@ impl FanController {
         None
     }
 
-    fn read_ec_byte(&self, address: u8) -> Option<u8> {
+    fn read_ec_byte(&mut self, address: u8) -> Option<u8> {
+        if self.ec.is_mock() {
+            return self.ec.read_byte(address).ok();
+        }
         let ec_path = "/sys/kernel/debug/ec/ec0/io";
         if let Ok(mut file) = fs::File::open(ec_path) {
             let mut buf = [0u8; 1];
-            if file.seek(SeekFrom::Start(address as u64)).is_ok() {
-                if file.read_exact(&mut buf).is_ok() {
-                    return Some(buf[0]);
-                }
+            if file.seek(SeekFrom::Start(address as u64)).is_ok()
+                && file.read_exact(&mut buf).is_ok() {
+                trace::record(TraceOp::Read, address, buf[0]);
+                return Some(buf[0]);
             }
         }
         None
@@ -233,62 +756,137 @@ impl FanController {
 
     fn write_ec_byte(&mut self, address: u8, value: u8) -> Result<()> {
         use std::io::Write;
+        self.ec.check_write(address, value)?;
         let ec_path = "/sys/kernel/debug/ec/ec0/io";
-        if let Ok(mut file) = fs::OpenOptions::new().write(true).open(ec_path) {
-            if file.seek(SeekFrom::Start(address as u64)).is_ok() {
-                if file.write_all(&[value]).is_ok() {
-                    return Ok(());
-                }
-            }
+        if !self.ec.is_mock()
+            && let Ok(mut file) = fs::OpenOptions::new().write(true).open(ec_path)
+            && file.seek(SeekFrom::Start(address as u64)).is_ok()
+            && file.write_all(&[value]).is_ok() {
+            log::debug!("EC write {:#04x} = {:#04x} via debugfs", address, value);
+            trace::record(TraceOp::Write, address, value);
+            self.ec.forget_cached(address);
+            self.ec.verify_write(address, value);
+            return Ok(());
+        }
+        if !self.ec.is_mock() && system::ec_sys_read_only() && !EC_SYS_READ_ONLY_WARNED.swap(true, Ordering::Relaxed) {
+            log::warn!(
+                "ec_sys is loaded without write_support, so EC writes go through {} instead; run 'sudo msi-center ec enable-writes' to reload it with write support",
+                self.ec.backend_name()
+            );
         }
+        log::debug!("debugfs EC write to {:#04x} unavailable, falling back to {}", address, self.ec.backend_name());
         self.ec.write_byte(address, value)?;
         Ok(())
     }
 
-    fn read_fan_rpm_from_ec(&self, fan_num: u8) -> (u32, u8) {
-        let address = if fan_num == 1 { 0xC8 } else { 0xCA };
-        
-        if let Some(raw) = self.read_ec_byte(address) {
-            if raw > 0 {
-                let rpm = (raw as u32) * 100;
-                let percent = ((raw as f32 / 150.0) * 100.0).clamp(0.0, 100.0) as u8;
-                return (rpm, percent);
-            }
-        }
-        
-        let realtime_addr = if fan_num == 1 { 0xC9 } else { 0xCB };
-        if let Some(raw) = self.read_ec_byte(realtime_addr) {
-            if raw > 0 {
-                let rpm = (raw as u32) * 100;
-                let percent = ((raw as f32 / 150.0) * 100.0).clamp(0.0, 100.0) as u8;
-                return (rpm, percent);
-            }
-        }
-        
-        (0, 0)
+    /// RPM and duty of fan `index` (0 CPU, 1 GPU, 2 system).
+    fn read_fan_rpm_from_ec(&mut self, index: usize) -> (u32, u8) {
+        let Some(registers) = self.ec.registers().fan(index) else {
+            return (0, 0);
+        };
+        let fan_num = index + 1;
+        let fan_rpm = self.ec.capabilities().fan_rpm;
+        let tach_rpm = match self.read_fan_rpm_from_hwmon(fan_num) {
+            Some(rpm) => Some(rpm),
+            None => registers.tach.and_then(|tach_addr| match self.ec.read_word(tach_addr) {
+                Ok(period) => Some(fan_rpm.rpm_at_period(period)),
+                Err(e) => {
+                    log::debug!("Fan {} tachometer unavailable via {}: {}", fan_num, self.ec.backend_name(), e);
+                    None
+                }
+            }),
+        };
+
+        let raw = registers.speed.and_then(|address| self.read_ec_byte(address));
+
+        let percent = raw.map(|raw| ((raw as f32 / 150.0) * 100.0).clamp(0.0, 100.0) as u8).unwrap_or(0);
+        // Without hwmon or a tachometer (older msi-ec), the duty is the only hint.
+        let rpm = tach_rpm.unwrap_or_else(|| fan_rpm.rpm_at_percent(percent));
+        (rpm, percent)
     }
 
-    pub fn get_fan_info(&mut self) -> Result<FanInfo> {
+    /// CPU and GPU temperatures only, for callers that poll them more often than the fans.
+    pub fn read_temperatures(&mut self) -> (u8, u8) {
+        let registers = self.ec.registers();
         let cpu_temp = self.read_cpu_temp_from_hwmon()
-            .or_else(|| self.read_ec_byte(MSI_ADDRESS_CPU_TEMP))
-            .or_else(|| self.ec.read_byte(MSI_ADDRESS_CPU_TEMP).ok())
+            .or_else(|| {
+                log::debug!("CPU temperature not available from hwmon, falling back to EC");
+                self.read_ec_byte(registers.cpu_temp)
+            })
+            .or_else(|| self.ec.read_byte(registers.cpu_temp).ok())
             .unwrap_or(0);
 
-        let gpu_temp = self.read_gpu_temp_from_hwmon()
-            .or_else(|| self.read_ec_byte(MSI_ADDRESS_GPU_TEMP))
-            .or_else(|| self.ec.read_byte(MSI_ADDRESS_GPU_TEMP).ok())
+        // The dGPU's own driver first: the first hwmon GPU found may be a Ryzen APU's.
+        let gpu_temp = self.gpu_telemetry().map(|gpu| gpu.temp)
+            .or_else(|| self.read_gpu_temp_from_hwmon())
+            .or_else(|| {
+                log::debug!("GPU temperature not available from hwmon, falling back to EC");
+                self.read_ec_byte(registers.gpu_temp)
+            })
+            .or_else(|| self.ec.read_byte(registers.gpu_temp).ok())
             .unwrap_or(0);
 
-        let (cpu_fan_rpm, cpu_fan_percent) = self.read_fan_rpm_from_ec(1);
-        let (gpu_fan_rpm, gpu_fan_percent) = self.read_fan_rpm_from_ec(2);
+        (cpu_temp, gpu_temp)
+    }
 
-        let fan_mode_raw = self.read_ec_byte(MSI_ADDRESS_FAN_MODE)
-            .or_else(|| self.ec.read_byte(MSI_ADDRESS_FAN_MODE).ok())
+    /// Temperature a curve with `source` is looked up at for fan `index`, given the
+    /// CPU and GPU temperatures just read. A sensor that can't be read falls back to
+    /// the fan's own temperature, as does every source during a replay. The GPU fans
+    /// follow the hotspot where the GPU reports one, as the edge reads 10-20°C lower.
+    pub fn source_temp(&self, source: &TempSource, index: usize, cpu_temp: u8, gpu_temp: u8) -> u8 {
+        let own = if index == 0 { cpu_temp } else { gpu_temp };
+        if self.ec.is_mock() || (source.is_fan() && index == 0) {
+            return own;
+        }
+        if source.is_fan() {
+            return self.gpu_telemetry().and_then(|gpu| gpu.hotspot).unwrap_or(own);
+        }
+        source.read().unwrap_or_else(|| {
+            log::warn!("Temperature source {} is unavailable, using the {} fan's own temperature", source, FAN_NAMES[index]);
+            own
+        })
+    }
+
+    /// The dGPU driver's readings; a replayed trace carries its own.
+    fn gpu_telemetry(&self) -> Option<GpuTelemetry> {
+        if self.ec.is_mock() {
+            return None;
+        }
+        gpu::telemetry()
+    }
+
+    pub fn get_fan_info(&mut self) -> Result<FanInfo> {
+        let registers = self.ec.registers();
+        let (cpu_temp, gpu_temp) = self.read_temperatures();
+
+        let fan_mode_raw = self.read_ec_byte(registers.fan_mode)
+            .or_else(|| self.ec.read_byte(registers.fan_mode).ok())
             .unwrap_or(0);
+        let fan_mode = FanMode::from(fan_mode_raw & 0x0F);
 
-        let cooler_boost_raw = self.read_ec_byte(MSI_ADDRESS_COOLER_BOOST)
-            .or_else(|| self.ec.read_byte(MSI_ADDRESS_COOLER_BOOST).ok())
+        let cooler_boost_raw = self.read_ec_byte(registers.cooler_boost)
+            .or_else(|| self.ec.read_byte(registers.cooler_boost).ok())
             .unwrap_or(0);
+        let cooler_boost = (cooler_boost_raw & 0x80) != 0;
+
+        let fans: Vec<FanReading> = (0..self.fan_count())
+            .map(|index| {
+                let (rpm, percent) = self.read_fan_rpm_from_ec(index);
+                // The system fan has no sensor of its own; it cools whichever chip is hotter.
+                let temp = [cpu_temp, gpu_temp].get(index).copied().unwrap_or(cpu_temp.max(gpu_temp));
+                let target_percent = if cooler_boost {
+                    Some(100)
+                } else if fan_mode == FanMode::Advanced {
+                    self.table(index).map(|table| table.tuned_speed_for_temp(temp))
+                } else {
+                    None
+                };
+                FanReading { name: FAN_NAMES[index], rpm, percent, target_percent }
+            })
+            .collect();
+        let reading = |index: usize| fans.get(index).map_or((0, 0), |fan| (fan.rpm, fan.percent));
+        let (cpu_fan_rpm, cpu_fan_percent) = reading(0);
+        let (gpu_fan_rpm, gpu_fan_percent) = reading(1);
 
         Ok(FanInfo {
             cpu_fan_rpm,
@@ -297,88 +895,287 @@ impl FanController {
             gpu_fan_percent,
             cpu_temp,
             gpu_temp,
-            fan_mode: FanMode::from(fan_mode_raw & 0x0F),
-            cooler_boost: (cooler_boost_raw & 0x80) != 0,
+            fan_mode,
+            cooler_boost,
+            fans,
+            gpu: self.gpu_telemetry(),
         })
     }
 
-    fn calculate_rpm(&self, raw_value: u8) -> u32 {
-        if raw_value == 0 {
-            return 0;
-        }
-        (raw_value as u32) * 100
-    }
-
     pub fn set_fan_mode(&mut self, mode: FanMode) -> Result<()> {
+        if !self.ec.support().fan_mode {
+            return Err(FanError::Unsupported("Fan mode"));
+        }
         let mode_value = mode as u8;
-        self.write_ec_byte(MSI_ADDRESS_FAN_MODE, mode_value)?;
+        self.write_ec_byte(self.ec.registers().fan_mode, mode_value)?;
+        if mode != FanMode::Advanced && !self.ec.is_mock() {
+            PINNED_LOW.store(false, Ordering::SeqCst);
+        }
         Ok(())
     }
 
     pub fn set_cooler_boost(&mut self, enabled: bool) -> Result<()> {
-        let current = self.read_ec_byte(MSI_ADDRESS_COOLER_BOOST).unwrap_or(0);
+        if !self.ec.support().cooler_boost {
+            return if enabled { Err(FanError::Unsupported("Cooler Boost")) } else { Ok(()) };
+        }
+        let address = self.ec.registers().cooler_boost;
+        let current = self.read_ec_byte(address).unwrap_or(0);
         let new_value = if enabled {
             current | 0x80
         } else {
             current & 0x7F
         };
-        self.write_ec_byte(MSI_ADDRESS_COOLER_BOOST, new_value)?;
+        self.write_ec_byte(address, new_value)?;
         Ok(())
     }
 
     pub fn set_cpu_fan_curve(&mut self, curve: FanCurve) -> Result<()> {
-        self.apply_fan_curve(MSI_ADDRESS_FAN1_BASE, &curve)?;
-        self.cpu_curve = curve;
+        self.apply_fan_curve(0, &curve, "CPU fan curve")?;
+        self.curves[0] = Some(curve);
         Ok(())
     }
 
+    /// Sets the curve of fan `index` (0 CPU, 1 GPU, 2 system).
+    pub fn set_fan_curve(&mut self, index: usize, curve: FanCurve) -> Result<()> {
+        match index {
+            0 => self.set_cpu_fan_curve(curve),
+            1 => self.set_gpu_fan_curve(curve),
+            _ if index < self.fan_count() => {
+                self.apply_fan_curve(index, &curve, "System fan curve")?;
+                self.curves[index] = Some(curve);
+                Ok(())
+            }
+            _ => Err(FanError::FanNotFound(format!("fan {} (this model has {})", index + 1, self.fan_count()))),
+        }
+    }
+
+    /// The GPU fan table is only written while the dGPU is powered; on hybrid
+    /// machines it sleeps most of the time, and the daemon reapplies the
+    /// profile's GPU settings when it wakes.
+    pub fn gpu_fan_writable(&self) -> bool {
+        if self.ec.capabilities().fans < 2 {
+            return false;
+        }
+        if !self.ec.is_mock() && system::dgpu_active() == Some(false) {
+            log::debug!("dGPU is suspended, not writing the GPU fan table");
+            return false;
+        }
+        true
+    }
+
     pub fn set_gpu_fan_curve(&mut self, curve: FanCurve) -> Result<()> {
-        self.apply_fan_curve(MSI_ADDRESS_FAN2_BASE, &curve)?;
-        self.gpu_curve = curve;
+        if self.gpu_fan_writable() {
+            self.apply_fan_curve(1, &curve, "GPU fan curve")?;
+        }
+        self.curves[1] = Some(curve);
         Ok(())
     }
 
-    fn apply_fan_curve(&mut self, base_address: u8, curve: &FanCurve) -> Result<()> {
-        let num_points = curve.points.len().min(6);
-        
-        for (i, point) in curve.points.iter().take(num_points).enumerate() {
-            let temp_addr = base_address + (i as u8 * 2);
-            let speed_addr = temp_addr + 1;
-            
-            self.write_ec_byte(temp_addr, point.temp)?;
-            let speed_value = ((point.speed as u16 * 255) / 100) as u8;
-            self.write_ec_byte(speed_addr, speed_value)?;
+    /// Writes the fan table of fan `index` and the model's fan tuning registers for it.
+    fn apply_fan_curve(&mut self, index: usize, curve: &FanCurve, what: &'static str) -> Result<()> {
+        if !self.ec.support().fan_curves {
+            return Err(FanError::Unsupported("Fan curves"));
         }
+        let Some(registers) = self.ec.registers().fan(index) else {
+            return Err(FanError::FanNotFound(FAN_NAMES[index].to_string()));
+        };
+        let capabilities = self.ec.capabilities();
+        let points = capabilities.curve_points;
+        if curve.points.len() > points as usize {
+            log::info!("{}: the fan table holds {} points, fitting the curve's {} into it", what, points, curve.points.len());
+        }
+        curve.validate_fitted(points as usize, &self.speed_guard)?;
+        // The firmware reads every point of the table, so all of them are written.
+        let curve = curve.fitted(points as usize);
+        let mut batch = WriteBatch::default();
 
-        Ok(())
+        for (i, point) in curve.points.iter().enumerate() {
+            let (temp_addr, speed_addr) = capabilities.table_entry(registers.table, i as u8);
+            batch.write(temp_addr, point.temp);
+            batch.write(speed_addr, duty_byte(point.speed));
+        }
+
+        let (offsets, min_duty) = capabilities.fan_tuning.for_fan(index);
+        if let Some(base) = offsets {
+            for i in 0..points {
+                batch.write(base + i, offset_byte(curve.tuning.offsets.get(i as usize).copied().unwrap_or(0)));
+            }
+        }
+        if let Some(address) = min_duty {
+            batch.write(address, duty_byte(curve.tuning.min_duty.unwrap_or(0)));
+        }
+        if offsets.is_none() && min_duty.is_none() && !curve.tuning.is_empty() {
+            log::debug!("{}: this model has no fan tuning registers, ignoring minimum duty and offsets", what);
+        }
+        if !curve.source.is_fan() {
+            log::info!("{}: the firmware follows its own temperature, not {}; software fan control uses it", what, curve.source);
+        }
+
+        self.apply_batch(&batch, what)
     }
 
+    /// Fan `index`'s table, read back at most every [`TABLE_READ_INTERVAL`] so
+    /// polling the fans doesn't read every table register each time. Another
+    /// process may write the table, so it isn't kept for longer.
+    fn table(&mut self, index: usize) -> Option<FanCurve> {
+        if let Some(Some((read_at, table))) = self.tables.get(index)
+            && read_at.elapsed() < TABLE_READ_INTERVAL
+        {
+            return Some(table.clone());
+        }
+        let table = self.read_fan_curve(index).ok()?;
+        self.tables[index] = Some((Instant::now(), table.clone()));
+        Some(table)
+    }
+
+    /// Writes the fan table as one verified batch, failing with the registers that didn't stick.
+    fn apply_batch(&mut self, batch: &WriteBatch, what: &'static str) -> Result<()> {
+        // Our own writes show at once.
+        self.tables.fill(None);
+        let report = self.ec.apply_batch(batch);
+        if report.unverified > 0 {
+            log::debug!("{}: {} of {} writes could not be read back", what, report.unverified, batch.len());
+        }
+        if report.is_ok() {
+            return Ok(());
+        }
+        let registers: Vec<String> = report.rejected.iter().map(ToString::to_string).collect();
+        log::warn!("{}: {} of {} writes did not take effect", what, report.rejected.len(), batch.len());
+        Err(FanError::NotApplied { what, registers: registers.join(", ") })
+    }
+
+    /// Pins the CPU and GPU fans at fixed speeds; a third fan runs at the faster of the two.
     pub fn set_manual_fan_speed(&mut self, cpu_percent: u8, gpu_percent: u8) -> Result<()> {
-        if cpu_percent > 100 || gpu_percent > 100 {
-            return Err(FanError::InvalidSpeed(cpu_percent.max(gpu_percent)));
+        self.set_manual_fan_speeds(&ManualFanSpeed { cpu: cpu_percent, gpu: gpu_percent, system: None }.speeds())
+    }
+
+    /// Pins each fan at a fixed speed, by index; speeds for fans the model lacks are ignored.
+    pub fn set_manual_fan_speeds(&mut self, speeds: &[u8]) -> Result<()> {
+        if let Some(&speed) = speeds.iter().find(|speed| **speed > 100) {
+            return Err(FanError::InvalidSpeed(speed));
         }
 
-        self.set_fan_mode(FanMode::Advanced)?;
+        if !self.ec.support().fan_curves {
+            return Err(FanError::Unsupported("Manual fan speed"));
+        }
 
-        let cpu_value = ((cpu_percent as u16 * 255) / 100) as u8;
-        let gpu_value = ((gpu_percent as u16 * 255) / 100) as u8;
+        let count = self.fan_count().min(speeds.len());
+        if self.speed_guard.enabled {
+            let info = self.get_fan_info()?;
+            for (index, &speed) in speeds.iter().enumerate().take(count.max(2)) {
+                // The system fan cools the whole chassis, so it answers to the hotter sensor.
+                let temp = match index {
+                    0 => info.cpu_temp,
+                    1 => info.gpu_temp,
+                    _ => info.cpu_temp.max(info.gpu_temp),
+                };
+                self.speed_guard.check(FAN_NAMES[index], speed, temp)?;
+            }
+        }
+
+        self.set_fan_mode(FanMode::Advanced)?;
 
-        for i in 0..6u8 {
-            self.write_ec_byte(MSI_ADDRESS_FAN1_BASE + (i * 2), 0)?;
-            self.write_ec_byte(MSI_ADDRESS_FAN1_BASE + (i * 2) + 1, cpu_value)?;
-            self.write_ec_byte(MSI_ADDRESS_FAN2_BASE + (i * 2), 0)?;
-            self.write_ec_byte(MSI_ADDRESS_FAN2_BASE + (i * 2) + 1, gpu_value)?;
+        let registers = self.ec.registers();
+        let capabilities = self.ec.capabilities();
+        let second_fan = self.gpu_fan_writable();
+        let mut batch = WriteBatch::default();
+        // Every point the firmware reads, or a point left out keeps its old duty.
+        for i in 0..capabilities.curve_points {
+            for (index, &speed) in speeds.iter().enumerate().take(count) {
+                if index == 1 && !second_fan {
+                    continue;
+                }
+                let Some(fan) = registers.fan(index) else {
+                    continue;
+                };
+                let (temp_addr, speed_addr) = capabilities.table_entry(fan.table, i);
+                batch.write(temp_addr, 0).write(speed_addr, duty_byte(speed));
+            }
+        }
+        // A curve's minimum duty or offsets would otherwise still apply on top of the pinned speed.
+        for index in 0..count {
+            if (index == 1 && !second_fan) || registers.fan(index).is_none() {
+                continue;
+            }
+            let (offsets, min_duty) = capabilities.fan_tuning.for_fan(index);
+            if let Some(base) = offsets {
+                for i in 0..capabilities.curve_points {
+                    batch.write(base + i, 0);
+                }
+            }
+            if let Some(address) = min_duty {
+                batch.write(address, 0);
+            }
         }
+        self.apply_batch(&batch, "Manual fan speed")?;
 
+        if !self.ec.is_mock() {
+            // Measured against the configured floor, even with the guard turned off,
+            // so a crash still hands fans pinned below it back to the firmware.
+            let slowest = speeds.iter().take(count).min().copied().unwrap_or(0);
+            PINNED_LOW.store(slowest < self.speed_guard.min_speed, Ordering::SeqCst);
+        }
         Ok(())
     }
 
-    pub fn get_cpu_curve(&self) -> &FanCurve {
-        &self.cpu_curve
+    pub fn get_cpu_curve(&mut self) -> Result<FanCurve> {
+        self.get_curve(0)
     }
 
-    pub fn get_gpu_curve(&self) -> &FanCurve {
-        &self.gpu_curve
+    pub fn get_gpu_curve(&mut self) -> Result<FanCurve> {
+        self.get_curve(1)
+    }
+
+    /// The curve fan `index` follows: the last one set, or else the one its table
+    /// holds, read from the EC each time.
+    pub fn get_curve(&mut self, index: usize) -> Result<FanCurve> {
+        if let Some(Some(curve)) = self.curves.get(index).filter(|_| index < self.fan_count()) {
+            return Ok(curve.clone());
+        }
+        self.read_fan_curve(index)
+    }
+
+    /// Reads every point of fan `index`'s table from the EC, with the model's fan
+    /// tuning registers for it. Zero tuning reads back as unset, as it is written.
+    /// Backends that can't reach the tables (msi-ec) fail.
+    pub fn read_fan_curve(&mut self, index: usize) -> Result<FanCurve> {
+        if !self.ec.support().fan_curves {
+            return Err(FanError::Unsupported("Reading fan curves"));
+        }
+        let Some(registers) = self.ec.registers().fan(index).filter(|_| index < self.fan_count()) else {
+            return Err(FanError::FanNotFound(FAN_NAMES.get(index).map_or_else(|| format!("fan {}", index + 1), |name| name.to_string())));
+        };
+        let capabilities = self.ec.capabilities();
+        let mut points = Vec::new();
+        for point in 0..capabilities.curve_points {
+            let (temp_addr, speed_addr) = capabilities.table_entry(registers.table, point);
+            points.push(FanCurvePoint { temp: self.ec.read_byte(temp_addr)?, speed: duty_percent(self.ec.read_byte(speed_addr)?) });
+        }
+
+        let mut tuning = CurveTuning::default();
+        let (offsets, min_duty) = capabilities.fan_tuning.for_fan(index);
+        if let Some(base) = offsets {
+            for i in 0..capabilities.curve_points {
+                tuning.offsets.push(offset_percent(self.ec.read_byte(base + i)?));
+            }
+            while tuning.offsets.last() == Some(&0) {
+                tuning.offsets.pop();
+            }
+        }
+        if let Some(address) = min_duty {
+            tuning.min_duty = Some(duty_percent(self.ec.read_byte(address)?)).filter(|&duty| duty > 0);
+        }
+        Ok(FanCurve { points, tuning, ..FanCurve::default() })
+    }
+
+    /// Runs every fan at 100%: through the fan tables where they can be written,
+    /// otherwise with Cooler Boost. The speed guard has nothing to refuse here.
+    pub fn force_full_speed(&mut self) -> Result<()> {
+        if self.ec.support().fan_curves {
+            self.set_manual_fan_speeds(&[100; 3])
+        } else {
+            self.set_cooler_boost(true)
+        }
     }
 
     pub fn reset_to_auto(&mut self) -> Result<()> {
@@ -387,3 +1184,191 @@ impl FanController {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::quirks::{Capabilities, FanRpm, FanTuning};
+    use crate::ec::trace::{mock_ec, mock_ec_with};
+
+    fn curve(points: &[(u8, u8)]) -> FanCurve {
+        FanCurve { points: points.iter().map(|&(temp, speed)| FanCurvePoint { temp, speed }).collect(), ..FanCurve::default() }
+    }
+
+    #[test]
+    fn validation_names_the_offending_points() {
+        let guard = SpeedGuard::default();
+        for preset in [FanCurve::default(), FanCurve::silent(), FanCurve::performance(), FanCurve::silent().quieted(10)] {
+            assert_eq!(preset.validate(6, &guard), Ok(()));
+        }
+        assert_eq!(curve(&[(50, 30)]).validate(6, &guard), Err(CurveError::TooFewPoints(1)));
+        assert_eq!(FanCurve::default().validate(5, &guard), Err(CurveError::TooManyPoints { count: 6, max: 5 }));
+        assert_eq!(
+            curve(&[(40, 0), (60, 50), (60, 70)]).validate(6, &guard),
+            Err(CurveError::DuplicateTemperature { first: 2, second: 3, temp: 60 })
+        );
+        assert_eq!(
+            curve(&[(40, 0), (70, 50), (60, 70)]).validate(6, &guard),
+            Err(CurveError::DecreasingTemperature { index: 3, temp: 60, previous: 2, previous_temp: 70 })
+        );
+        // Dropping to 0% at 90°C, or never getting past 40%, both fail the guard.
+        assert!(matches!(curve(&[(40, 0), (80, 80), (90, 0)]).validate(6, &guard), Err(CurveError::UnsafeSpeed { temp: 85, speed: 40, .. })));
+        assert!(matches!(curve(&[(40, 0), (70, 40)]).validate(6, &guard), Err(CurveError::UnsafeSpeed { temp: 85, speed: 40, .. })));
+        assert_eq!(curve(&[(40, 0), (70, 40)]).validate(6, &SpeedGuard { enabled: false, ..guard }), Ok(()));
+        // Stopping the fan at 50°C or warmer fails too, unless a profile's floor lifts it.
+        let fan_stop = curve(&[(50, 0), (60, 20), (85, 60)]);
+        assert_eq!(fan_stop.validate(6, &guard), Err(CurveError::FanStop { temp: 50, limit: 50 }));
+        assert_eq!(fan_stop.floored(10).validate(6, &guard), Ok(()));
+        assert!(matches!(guard.check("CPU", 0, 55), Err(FanError::FanStop { temp: 55, limit: 50, .. })));
+        assert!(guard.check("CPU", 0, 45).is_ok());
+    }
+
+    #[test]
+    fn curves_are_fitted_to_the_fan_table() {
+        let short = curve(&[(40, 0), (60, 50), (85, 100)]);
+        assert_eq!(short.fitted(5), curve(&[(40, 0), (60, 50), (85, 100), (86, 100), (87, 100)]));
+        assert_eq!(short.fitted(5).unpadded(Some(&short), 0), short.points);
+        // A table that only looks padded keeps its points.
+        let table = curve(&[(40, 0), (60, 50), (85, 100), (86, 100), (87, 100)]);
+        assert_eq!(table.unpadded(None, 0), table.points);
+        assert_eq!(table.unpadded(Some(&table), 0), table.points);
+        assert!((0..=100).all(|percent| duty_percent(duty_byte(percent)) == percent));
+        // The point that sits on the line between its neighbours goes first.
+        let long = curve(&[(40, 0), (50, 20), (60, 40), (70, 70), (80, 90), (90, 100), (95, 100)]);
+        assert_eq!(long.fitted(6), curve(&[(40, 0), (60, 40), (70, 70), (80, 90), (90, 100), (95, 100)]));
+        assert_eq!(long.fitted(2).points.len(), 2);
+        assert_eq!(long.fitted(7), long);
+        // Profile curves are checked as they will be written, and padding can't make up for a missing point.
+        let guard = SpeedGuard::default();
+        assert_eq!(long.validate_fitted(6, &guard), Ok(()));
+        assert_eq!(curve(&[(50, 60)]).validate_fitted(6, &guard), Err(CurveError::TooFewPoints(1)));
+        assert_eq!(curve(&[(50, 60), (252, 100)]).validate_fitted(6, &guard), Err(CurveError::NoRoomToPad { temp: 252, points: 6 }));
+        assert_eq!(curve(&[(50, 60), (251, 100)]).validate_fitted(6, &guard), Ok(()));
+    }
+
+    #[test]
+    fn fans_count_as_off_target_beyond_the_tolerance() {
+        let fan = |percent, target_percent| FanReading { name: "CPU", rpm: 3000, percent, target_percent };
+        assert!(!fan(45, Some(50)).off_target());
+        assert!(fan(30, Some(50)).off_target());
+        assert!(!fan(30, None).off_target());
+    }
+
+    #[test]
+    fn target_duty_applies_offsets_and_minimum_duty() {
+        let curve = FanCurve { tuning: CurveTuning { min_duty: Some(25), offsets: vec![0, 10, -60] }, ..FanCurve::default() };
+        assert_eq!(curve.tuned_speed_for_temp(40), 25);
+        assert_eq!(curve.tuned_speed_for_temp(50), 40);
+        assert_eq!(curve.tuned_speed_for_temp(60), 25);
+        assert_eq!(curve.tuned_speed_for_temp(90), 100);
+    }
+
+    #[test]
+    fn cooler_boost_keeps_other_bits() {
+        let (mock, ec) = mock_ec(0x02);
+        let mut fans = FanController::new(ec);
+
+        fans.set_cooler_boost(true).unwrap();
+        fans.set_cooler_boost(false).unwrap();
+
+        let writes: Vec<u8> = mock.lock().unwrap().writes().iter().map(|o| o.value).collect();
+        assert_eq!(writes, vec![0x82, 0x02]);
+    }
+
+    #[test]
+    fn fan_rpm_comes_from_tachometer_word() {
+        let mut registers = vec![Some(0u8); 256];
+        registers[0xC8] = Some(0x01);
+        registers[0xC9] = Some(0x2C);
+        registers[0xCA] = Some(0x02);
+        // The duty registers hold something else entirely.
+        registers[0x71] = Some(75);
+        registers[0x89] = Some(150);
+        let (mock, ec) = mock_ec_with(registers);
+
+        let info = FanController::new(ec).get_fan_info().unwrap();
+        assert_eq!((info.cpu_fan_rpm, info.cpu_fan_percent), (478_000 / 0x012C, 50));
+        assert_eq!((info.gpu_fan_rpm, info.gpu_fan_percent), (478_000 / 0x0200, 100));
+
+        let mut ec = EmbeddedController::with_mock(mock);
+        ec.set_capabilities(Capabilities { fan_rpm: FanRpm { constant: Some(600_000), max_rpm: None }, ..Capabilities::default() });
+        assert_eq!(FanController::new(ec).get_fan_info().unwrap().cpu_fan_rpm, 2000);
+        assert_eq!(FanRpm { constant: None, max_rpm: Some(5000) }.rpm_at_percent(50), 2500);
+    }
+
+    #[test]
+    fn curve_writes_that_do_not_stick_are_reported() {
+        let (mock, ec) = mock_ec(0);
+        // The firmware keeps the second point's speed at its own value.
+        mock.lock().unwrap().respond(0x75, 0x33);
+        let mut fans = FanController::new(ec);
+
+        let error = fans.set_cpu_fan_curve(FanCurve::default()).unwrap_err().to_string();
+        assert_eq!(error, "CPU fan curve did not take effect: 0x75 = 0x4c (reads 0x33)");
+        assert_eq!(mock.lock().unwrap().writes().len(), 12);
+    }
+
+    #[test]
+    fn unsupported_controls_are_refused_without_writing() {
+        let (mock, mut ec) = mock_ec(0);
+        ec.set_capabilities(Capabilities { cooler_boost: false, fans: 1, ..Capabilities::default() });
+        let support = ec.support();
+        assert_eq!(support.missing(), ["Cooler Boost", "second fan", "lid logo"]);
+
+        let mut fans = FanController::new(ec);
+        assert_eq!(fans.set_cooler_boost(true).unwrap_err().to_string(), "Cooler Boost is not supported on this model or EC backend");
+        fans.set_cooler_boost(false).unwrap();
+        assert!(mock.lock().unwrap().writes().is_empty());
+    }
+
+    #[test]
+    fn fan_tuning_registers_are_written_with_the_curve() {
+        let (mock, mut ec) = mock_ec(0);
+        let fan_tuning = FanTuning { cpu_offsets: Some(0xE0), cpu_min_duty: Some(0xE6), ..FanTuning::default() };
+        ec.set_capabilities(Capabilities { fan_tuning, ..Capabilities::default() });
+
+        let curve = FanCurve { tuning: CurveTuning { min_duty: Some(20), offsets: vec![0, 10, -10] }, ..FanCurve::default() };
+        let mut controller = FanController::new(ec);
+        controller.set_cpu_fan_curve(curve.clone()).unwrap();
+
+        {
+            let mock = mock.lock().unwrap();
+            let written = |address: usize| mock.writes().iter().rev().find(|o| o.address as usize == address).map(|o| o.value);
+            assert_eq!((0xE0..0xE6).map(written).collect::<Vec<_>>(), [Some(0), Some(25), Some(0xE7), Some(0), Some(0), Some(0)]);
+            assert_eq!(written(0xE6), Some(51));
+        }
+        assert_eq!(controller.read_fan_curve(0).unwrap(), curve);
+    }
+
+    #[test]
+    fn manual_speeds_pin_every_point_of_a_split_table() {
+        let (mock, mut ec) = mock_ec(0);
+        let capabilities = Capabilities { curve_points: 7, temp_table_offset: Some(-8), ..Capabilities::default() };
+        ec.set_capabilities(capabilities);
+        let table = ec.registers().fan(0).unwrap().table;
+        FanController::new(ec).set_manual_fan_speed(60, 60).unwrap();
+
+        let mock = mock.lock().unwrap();
+        let written = |address: u8| mock.writes().iter().rev().find(|o| o.address == address).map(|o| o.value);
+        for point in 0..7 {
+            let (temp, speed) = capabilities.table_entry(table, point);
+            assert_eq!((written(temp), written(speed)), (Some(0), Some(duty_byte(60))), "point {}", point);
+        }
+        assert_eq!(written(table + 7), None);
+    }
+
+    #[test]
+    fn manual_speeds_clear_the_curve_tuning() {
+        let (mock, mut ec) = mock_ec(0);
+        let fan_tuning = FanTuning { cpu_offsets: Some(0xE0), cpu_min_duty: Some(0xE6), ..FanTuning::default() };
+        ec.set_capabilities(Capabilities { fan_tuning, ..Capabilities::default() });
+        let mut controller = FanController::new(ec);
+        let curve = FanCurve { tuning: CurveTuning { min_duty: Some(40), offsets: vec![10] }, ..FanCurve::default() };
+        controller.set_cpu_fan_curve(curve).unwrap();
+
+        controller.set_manual_fan_speed(60, 60).unwrap();
+        let mock = mock.lock().unwrap();
+        let written = |address: u8| mock.writes().iter().rev().find(|o| o.address == address).map(|o| o.value);
+        assert_eq!((written(0xE0), written(0xE6)), (Some(0), Some(0)));
+    }
+}
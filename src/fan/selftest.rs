@@ -0,0 +1,135 @@
+//! `fan test`: steps each fan on its own through a series of duties, records the
+//! RPM it settles at, and tells whether it follows manual control on this model.
+//! The quickest way to check that a model's fan table and RPM addresses are right.
+
+use super::{FanController, Result};
+use crate::ec::quirks::FAN_NAMES;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+pub const DUTIES: [u8; 5] = [20, 40, 60, 80, 100];
+/// Duty of the fans not under test, so the machine stays cooled meanwhile.
+const OTHER_FANS_DUTY: u8 = 50;
+/// Successive readings this close (RPM) count as settled.
+const SETTLE_TOLERANCE: u32 = 100;
+const READ_INTERVAL: Duration = Duration::from_secs(1);
+/// RPM a fan has to gain from the lowest to the highest duty to count as following it.
+const MIN_RESPONSE: u32 = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Sample {
+    pub duty: u8,
+    pub rpm: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Verdict {
+    Responds,
+    /// The RPM barely changed between the lowest and highest duty.
+    NoResponse,
+    /// The RPM rose overall but fell between steps.
+    Erratic,
+    /// Every reading was 0 RPM.
+    NoReading,
+}
+
+impl Verdict {
+    /// Judges a fan by the RPM it settled at for each duty, lowest duty first.
+    pub fn of(samples: &[Sample]) -> Self {
+        let rpms: Vec<u32> = samples.iter().map(|sample| sample.rpm).collect();
+        let (Some(&min), Some(&max)) = (rpms.iter().min(), rpms.iter().max()) else {
+            return Verdict::NoReading;
+        };
+        if max == 0 {
+            return Verdict::NoReading;
+        }
+        let gained = rpms.last().copied().unwrap_or(0).saturating_sub(rpms[0]);
+        if max - min < MIN_RESPONSE || gained < MIN_RESPONSE {
+            return Verdict::NoResponse;
+        }
+        if rpms.windows(2).any(|pair| pair[1] + SETTLE_TOLERANCE < pair[0]) {
+            return Verdict::Erratic;
+        }
+        Verdict::Responds
+    }
+}
+
+impl std::fmt::Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Responds => write!(f, "follows manual control"),
+            Verdict::NoResponse => write!(f, "does not follow manual control; the fan table address may be wrong, or the firmware ignores it"),
+            Verdict::Erratic => write!(f, "RPM does not rise steadily with duty; the RPM address may belong to another fan"),
+            Verdict::NoReading => write!(f, "reads 0 RPM throughout; the RPM address may be wrong, or the fan is not turning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FanResponse {
+    pub fan: &'static str,
+    pub samples: Vec<Sample>,
+    pub verdict: Verdict,
+}
+
+/// Runs the test on every fan the model has, calling `progress` after each step.
+/// Gives up waiting for a fan to settle after `settle`; stops early once `stop` is
+/// set. The caller hands the fans back to the firmware afterwards.
+pub fn run(controller: &mut FanController, settle: Duration, stop: &AtomicBool, mut progress: impl FnMut(&'static str, Sample)) -> Result<Vec<FanResponse>> {
+    let mut responses = Vec::new();
+    for index in 0..controller.fan_count().min(FAN_NAMES.len()) {
+        if index == 1 && !controller.gpu_fan_writable() {
+            continue;
+        }
+        let mut samples = Vec::new();
+        for duty in DUTIES {
+            if stop.load(Ordering::SeqCst) {
+                return Ok(responses);
+            }
+            let mut speeds = [OTHER_FANS_DUTY; 3];
+            speeds[index] = duty;
+            controller.set_manual_fan_speeds(&speeds)?;
+            let sample = Sample { duty, rpm: settled_rpm(controller, index, settle, stop)? };
+            progress(FAN_NAMES[index], sample);
+            samples.push(sample);
+        }
+        responses.push(FanResponse { fan: FAN_NAMES[index], verdict: Verdict::of(&samples), samples });
+    }
+    Ok(responses)
+}
+
+/// Reads fan `index` until two successive readings agree, or `settle` has passed.
+fn settled_rpm(controller: &mut FanController, index: usize, settle: Duration, stop: &AtomicBool) -> Result<u32> {
+    let started = Instant::now();
+    let mut last = None;
+    loop {
+        std::thread::sleep(READ_INTERVAL);
+        let rpm = controller.get_fan_info()?.fans.get(index).map_or(0, |fan| fan.rpm);
+        let settled = last.is_some_and(|last: u32| last.abs_diff(rpm) <= SETTLE_TOLERANCE);
+        if settled || started.elapsed() >= settle || stop.load(Ordering::SeqCst) {
+            return Ok(rpm);
+        }
+        last = Some(rpm);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn samples(rpms: [u32; 5]) -> Vec<Sample> {
+        DUTIES.into_iter().zip(rpms).map(|(duty, rpm)| Sample { duty, rpm }).collect()
+    }
+
+    #[test]
+    fn verdict_follows_the_rpm_response() {
+        assert_eq!(Verdict::of(&samples([1800, 2600, 3400, 4100, 4800])), Verdict::Responds);
+        // Jitter within the settle tolerance is fine.
+        assert_eq!(Verdict::of(&samples([1800, 2600, 2550, 4100, 4800])), Verdict::Responds);
+        assert_eq!(Verdict::of(&samples([2900, 3000, 2950, 3000, 3050])), Verdict::NoResponse);
+        assert_eq!(Verdict::of(&samples([1800, 4200, 2400, 4100, 4800])), Verdict::Erratic);
+        assert_eq!(Verdict::of(&samples([0; 5])), Verdict::NoReading);
+    }
+}
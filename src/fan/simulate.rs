@@ -0,0 +1,249 @@
+use super::FanCurve;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SimulateError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid curve file: {0}")]
+    CurveError(#[from] serde_json::Error),
+    #[error("Invalid trace line {0}: {1}")]
+    TraceError(usize, String),
+    #[error("Trace contains no samples")]
+    EmptyTrace,
+}
+
+pub type Result<T> = std::result::Result<T, SimulateError>;
+
+/// Assumed noise of a laptop fan at full speed, and the room's noise floor (dBA).
+const FULL_SPEED_DBA: f32 = 45.0;
+const AMBIENT_DBA: f32 = 20.0;
+/// Duty changes at least this large (percent) are counted as audible steps.
+const AUDIBLE_STEP: u8 = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TraceSample {
+    pub time: f32,
+    pub temp: f32,
+}
+
+/// Reads a temperature trace. Lines are either `time_seconds,temp` or just `temp`
+/// (one sample per second); a header line and `#` comments are skipped.
+pub fn load_trace(path: &Path) -> Result<Vec<TraceSample>> {
+    let content = fs::read_to_string(path)?;
+    let mut samples = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let parsed = match fields.as_slice() {
+            [temp] => temp.parse().map(|temp| TraceSample { time: samples.len() as f32, temp }),
+            [time, temp, ..] => time.parse().and_then(|time| Ok(TraceSample { time, temp: temp.parse()? })),
+            [] => continue,
+        };
+
+        match parsed {
+            Ok(sample) => samples.push(sample),
+            // Tolerate a header row.
+            Err(_) if samples.is_empty() && index == 0 => continue,
+            Err(e) => return Err(SimulateError::TraceError(index + 1, e.to_string())),
+        }
+    }
+
+    if samples.is_empty() {
+        return Err(SimulateError::EmptyTrace);
+    }
+    Ok(samples)
+}
+
+pub fn load_curve(path: &Path) -> Result<FanCurve> {
+    Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+}
+
+/// Temperatures from `from` to `to` (°C, both included), as given to `--range 40-95`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempRange {
+    pub from: u8,
+    pub to: u8,
+}
+
+impl TempRange {
+    /// The temperatures `step` degrees apart, always ending with `to`.
+    pub fn temps(&self, step: u8) -> Vec<u8> {
+        let mut temps: Vec<u8> = (self.from..=self.to).step_by(step.max(1) as usize).collect();
+        if temps.last() != Some(&self.to) {
+            temps.push(self.to);
+        }
+        temps
+    }
+}
+
+impl std::str::FromStr for TempRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("invalid range '{}' (expected e.g. 40-95)", s);
+        let (from, to) = s.split_once('-').ok_or_else(invalid)?;
+        let (from, to): (u8, u8) = (from.trim().parse().map_err(|_| invalid())?, to.trim().parse().map_err(|_| invalid())?);
+        if from > to {
+            return Err(format!("range '{}' runs backwards", s));
+        }
+        Ok(Self { from, to })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimulationSettings {
+    /// Time constant (seconds) of the exponential smoothing applied to the temperature.
+    pub smoothing: f32,
+    /// Degrees the temperature must fall before the duty is lowered again.
+    pub hysteresis: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimulatedSample {
+    pub time: f32,
+    pub temp: f32,
+    pub smoothed_temp: f32,
+    pub duty: u8,
+    pub noise_dba: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SimulationSummary {
+    pub duration: f32,
+    pub average_duty: f32,
+    pub max_duty: u8,
+    pub average_dba: f32,
+    pub peak_dba: f32,
+    pub audible_steps: usize,
+    /// Seconds spent in each noise band: <25, 25-35, 35-42 and >42 dBA.
+    pub noise_bands: [f32; 4],
+}
+
+/// Rough fan noise estimate from the fan affinity laws (sound power grows with
+/// 50·log10 of speed), combined with the ambient floor.
+pub fn estimate_noise(duty: u8) -> f32 {
+    if duty == 0 {
+        return AMBIENT_DBA;
+    }
+    let fan = FULL_SPEED_DBA + 50.0 * (duty as f32 / 100.0).log10();
+    10.0 * (10f32.powf(fan / 10.0) + 10f32.powf(AMBIENT_DBA / 10.0)).log10()
+}
+
+/// Replays `trace` through `curve`. The curve's own damping applies as in software
+/// control, with the larger of its hysteresis and the one in `settings`.
+pub fn simulate(curve: &FanCurve, trace: &[TraceSample], settings: SimulationSettings) -> Vec<SimulatedSample> {
+    let mut result = Vec::with_capacity(trace.len());
+    let mut smoothed = trace.first().map(|s| s.temp).unwrap_or_default();
+    let mut held = smoothed;
+    let mut last_time = trace.first().map(|s| s.time).unwrap_or_default();
+    let hysteresis = settings.hysteresis.max(curve.damping.hysteresis as f32);
+    let mut ramped: Option<f32> = None;
+
+    for sample in trace {
+        let dt = (sample.time - last_time).max(0.0);
+        last_time = sample.time;
+
+        if settings.smoothing > 0.0 {
+            smoothed += (sample.temp - smoothed) * (1.0 - (-dt / settings.smoothing).exp());
+        } else {
+            smoothed = sample.temp;
+        }
+
+        // Follow rising temperatures immediately, falling ones only past the hysteresis band.
+        if smoothed > held || smoothed < held - hysteresis {
+            held = smoothed;
+        }
+
+        let target = curve.get_speed_for_temp(held.round().clamp(0.0, 255.0) as u8) as f32;
+        let duty = match ramped {
+            Some(duty) if curve.damping.max_ramp > 0 => {
+                let step = curve.damping.max_ramp as f32 * dt;
+                duty + (target - duty).clamp(-step, step)
+            }
+            _ => target,
+        };
+        ramped = Some(duty);
+        let duty = duty.round() as u8;
+        result.push(SimulatedSample {
+            time: sample.time,
+            temp: sample.temp,
+            smoothed_temp: smoothed,
+            duty,
+            noise_dba: estimate_noise(duty),
+        });
+    }
+
+    result
+}
+
+pub fn summarize(samples: &[SimulatedSample]) -> SimulationSummary {
+    let mut summary = SimulationSummary {
+        duration: 0.0,
+        average_duty: 0.0,
+        max_duty: 0,
+        average_dba: AMBIENT_DBA,
+        peak_dba: AMBIENT_DBA,
+        audible_steps: 0,
+        noise_bands: [0.0; 4],
+    };
+    if samples.is_empty() {
+        return summary;
+    }
+
+    let mut weighted_duty = 0.0;
+    let mut weighted_power = 0.0;
+    let mut total = 0.0;
+
+    for (i, sample) in samples.iter().enumerate() {
+        // Each sample holds until the next one; the last one counts as one second.
+        let span = samples.get(i + 1).map(|next| (next.time - sample.time).max(0.0)).unwrap_or(1.0);
+        total += span;
+        weighted_duty += sample.duty as f32 * span;
+        weighted_power += 10f32.powf(sample.noise_dba / 10.0) * span;
+
+        summary.max_duty = summary.max_duty.max(sample.duty);
+        summary.peak_dba = summary.peak_dba.max(sample.noise_dba);
+
+        let band = match sample.noise_dba {
+            n if n < 25.0 => 0,
+            n if n < 35.0 => 1,
+            n if n < 42.0 => 2,
+            _ => 3,
+        };
+        summary.noise_bands[band] += span;
+
+        if i > 0 && sample.duty.abs_diff(samples[i - 1].duty) >= AUDIBLE_STEP {
+            summary.audible_steps += 1;
+        }
+    }
+
+    summary.duration = total;
+    if total > 0.0 {
+        summary.average_duty = weighted_duty / total;
+        // Energy average, as a sound level meter would report it.
+        summary.average_dba = 10.0 * (weighted_power / total).log10();
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_covers_both_ends() {
+        let range: TempRange = "40-95".parse().unwrap();
+        assert_eq!(range.temps(10), vec![40, 50, 60, 70, 80, 90, 95]);
+        assert_eq!("70 - 70".parse::<TempRange>().unwrap().temps(5), vec![70]);
+        assert!("95-40".parse::<TempRange>().is_err());
+        assert!("40".parse::<TempRange>().is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Fan curves followed in userspace, for models whose firmware ignores the curve
+//! tables: the temperatures are read on a timer and each fan is given its curve's
+//! duty as a manual speed.
+
+use super::{FanController, FanCurve, ManualFanSpeed, Result, SpeedGuard};
+use std::time::Instant;
+
+/// One step of the loop: the temperatures the curves were looked up at and the
+/// duty each fan was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CurveStep {
+    pub cpu_temp: u8,
+    pub gpu_temp: u8,
+    pub speeds: [u8; 3],
+    /// False when the duties were already in place and nothing was written.
+    pub changed: bool,
+}
+
+/// Where one fan stands between steps.
+#[derive(Debug, Clone, Copy, Default)]
+struct FanState {
+    /// Temperature the duty was looked up at, which only falls past the hysteresis.
+    held_temp: Option<u8>,
+    /// Duty given so far; fractional so that slow ramps still make progress.
+    duty: Option<f32>,
+}
+
+impl FanState {
+    fn advance(&mut self, curve: &FanCurve, temp: u8, elapsed: f32, floor: u8) -> u8 {
+        let held = match self.held_temp {
+            Some(held) if temp < held && held - temp <= curve.damping.hysteresis => held,
+            _ => temp,
+        };
+        self.held_temp = Some(held);
+
+        let target = curve.get_speed_for_temp(held) as f32;
+        let duty = match self.duty {
+            Some(duty) if curve.damping.max_ramp > 0 => {
+                let step = curve.damping.max_ramp as f32 * elapsed;
+                duty + (target - duty).clamp(-step, step)
+            }
+            _ => target,
+        };
+        // The guard's floor is never ramped towards.
+        let duty = duty.max(floor as f32);
+        self.duty = Some(duty);
+        duty.round() as u8
+    }
+}
+
+/// CPU and GPU curves being followed, and the duties last written.
+#[derive(Debug, Clone)]
+pub struct SoftwareCurve {
+    curves: [FanCurve; 2],
+    fans: [FanState; 2],
+    written: Option<[u8; 3]>,
+    last_step: Option<Instant>,
+}
+
+impl SoftwareCurve {
+    pub fn new(curves: [FanCurve; 2]) -> Self {
+        Self { curves, fans: Default::default(), written: None, last_step: None }
+    }
+
+    /// Switches to other curves, e.g. after a profile change; the next step writes.
+    pub fn set_curves(&mut self, curves: [FanCurve; 2]) {
+        if self.curves != curves {
+            self.curves = curves;
+            self.written = None;
+            self.fans = Default::default();
+        }
+    }
+
+    /// Duty of each fan at these temperatures, `elapsed` seconds after the previous
+    /// step, following each curve's damping and never below the speed guard's floor.
+    /// A third fan runs at the faster of the other two, as with manual speeds.
+    pub fn duties(&mut self, cpu_temp: u8, gpu_temp: u8, elapsed: f32, guard: &SpeedGuard) -> [u8; 3] {
+        let cpu = self.fans[0].advance(&self.curves[0], cpu_temp, elapsed, guard.floor(cpu_temp));
+        let gpu = self.fans[1].advance(&self.curves[1], gpu_temp, elapsed, guard.floor(gpu_temp));
+        ManualFanSpeed { cpu, gpu, system: None }.speeds()
+    }
+
+    /// Reads each curve's temperature source and writes the duties if they differ
+    /// from the last ones.
+    pub fn step(&mut self, controller: &mut FanController) -> Result<CurveStep> {
+        let (cpu, gpu) = controller.read_temperatures();
+        let cpu_temp = controller.source_temp(&self.curves[0].source, 0, cpu, gpu);
+        let gpu_temp = controller.source_temp(&self.curves[1].source, 1, cpu, gpu);
+        let elapsed = self.last_step.replace(Instant::now()).map_or(0.0, |last| last.elapsed().as_secs_f32());
+        let speeds = self.duties(cpu_temp, gpu_temp, elapsed, &controller.speed_guard);
+        let changed = self.written != Some(speeds);
+        if changed {
+            controller.set_manual_fan_speeds(&speeds)?;
+            self.written = Some(speeds);
+        }
+        Ok(CurveStep { cpu_temp, gpu_temp, speeds, changed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fan::CurveDamping;
+
+    #[test]
+    fn duties_follow_each_curve_with_damping_and_respect_the_guard() {
+        let mut software = SoftwareCurve::new([FanCurve::silent(), FanCurve::performance()]);
+        let guard = SpeedGuard::default();
+        assert_eq!(software.duties(40, 40, 0.0, &guard), [0, 40, 40]);
+        assert_eq!(software.duties(65, 50, 2.0, &guard), [30, 60, 60]);
+        // The silent curve gives 70% at 85°C, which a 90% floor lifts; the cool GPU is left alone.
+        let strict = SpeedGuard { min_speed: 90, ..guard };
+        assert_eq!(software.duties(85, 30, 2.0, &strict), [90, 30, 90]);
+
+        let mut damped = FanCurve::silent();
+        damped.damping = CurveDamping { hysteresis: 3, max_ramp: 5 };
+        let mut software = SoftwareCurve::new([damped.clone(), damped]);
+        assert_eq!(software.duties(70, 70, 0.0, &guard)[0], 40);
+        // Hovering just below the point keeps the duty; a bigger drop ramps down 5%/s.
+        assert_eq!(software.duties(68, 68, 2.0, &guard)[0], 40);
+        assert_eq!(software.duties(62, 62, 2.0, &guard)[0], 30);
+        assert_eq!(software.duties(62, 62, 2.0, &guard)[0], 24);
+        assert_eq!(software.duties(80, 80, 1.0, &guard)[0], 29);
+    }
+}
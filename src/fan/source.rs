@@ -0,0 +1,167 @@
+//! The sensor a fan curve is looked up against. The firmware follows its own
+//! temperature registers; curves followed in software can use any hwmon sensor,
+//! which matters on models whose EC temperature registers read garbage.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TempSource {
+    /// The fan's own temperature: the CPU's for the CPU fan, the GPU's for the others
+    /// (its hotspot, if the GPU reports one).
+    #[default]
+    Fan,
+    /// CPU package (coretemp `Package id`, k10temp `Tdie`/`Tctl`).
+    CpuPackage,
+    /// Hottest CPU core (coretemp `Core N`) or core complex (k10temp `TccdN`).
+    CpuHottestCore,
+    /// GPU edge temperature (amdgpu `edge`, nouveau).
+    GpuEdge,
+    /// GPU hotspot (amdgpu `junction`).
+    GpuHotspot,
+    /// Hottest NVMe drive (`Composite`).
+    Nvme,
+    /// Any `temp*_input` file, in millidegrees.
+    Hwmon(PathBuf),
+}
+
+impl TempSource {
+    pub fn is_fan(&self) -> bool {
+        *self == TempSource::Fan
+    }
+
+    /// Reads the source now; `None` for `Fan`, which the caller reads itself, and
+    /// when the sensor doesn't exist on this machine.
+    pub fn read(&self) -> Option<u8> {
+        self.read_in(Path::new(HWMON_ROOT))
+    }
+
+    fn read_in(&self, root: &Path) -> Option<u8> {
+        let millidegrees = match self {
+            TempSource::Fan => return None,
+            TempSource::CpuPackage => {
+                let cpu = devices(root, &["coretemp", "k10temp", "zenpower"]);
+                ["Package id", "Tdie", "Tctl"].into_iter().find_map(|label| hottest(&cpu, |l| l.starts_with(label)))
+            }
+            TempSource::CpuHottestCore => {
+                let cpu = devices(root, &["coretemp", "k10temp", "zenpower"]);
+                hottest(&cpu, |l| l.starts_with("Core ") || l.starts_with("Tccd"))
+            }
+            TempSource::GpuEdge => {
+                let gpu = devices(root, &["amdgpu", "nouveau", "nvidia"]);
+                hottest(&gpu, |l| l == "edge").or_else(|| hottest(&gpu, |_| true))
+            }
+            TempSource::GpuHotspot => hottest(&devices(root, &["amdgpu"]), |l| l == "junction"),
+            TempSource::Nvme => hottest(&devices(root, &["nvme"]), |l| l == "Composite" || l.is_empty()),
+            TempSource::Hwmon(path) => read_millidegrees(path),
+        }?;
+        Some((millidegrees / 1000).clamp(0, u8::MAX as i32) as u8)
+    }
+}
+
+impl std::fmt::Display for TempSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TempSource::Fan => write!(f, "fan"),
+            TempSource::CpuPackage => write!(f, "cpu-package"),
+            TempSource::CpuHottestCore => write!(f, "cpu-hottest-core"),
+            TempSource::GpuEdge => write!(f, "gpu-edge"),
+            TempSource::GpuHotspot => write!(f, "gpu-hotspot"),
+            TempSource::Nvme => write!(f, "nvme"),
+            TempSource::Hwmon(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+impl std::str::FromStr for TempSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "fan" | "default" => Ok(TempSource::Fan),
+            "cpu-package" | "cpu" => Ok(TempSource::CpuPackage),
+            "cpu-hottest-core" | "cpu-core" => Ok(TempSource::CpuHottestCore),
+            "gpu-edge" | "gpu" => Ok(TempSource::GpuEdge),
+            "gpu-hotspot" | "gpu-junction" => Ok(TempSource::GpuHotspot),
+            "nvme" => Ok(TempSource::Nvme),
+            _ if s.starts_with('/') => Ok(TempSource::Hwmon(PathBuf::from(s))),
+            _ => Err(format!(
+                "unknown temperature source '{}' (expected fan, cpu-package, cpu-hottest-core, gpu-edge, gpu-hotspot, nvme or a temp*_input path)",
+                s
+            )),
+        }
+    }
+}
+
+/// hwmon devices under `root` whose `name` is one of `names`.
+fn devices(root: &Path, names: &[&str]) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut devices: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| fs::read_to_string(path.join("name")).is_ok_and(|name| names.contains(&name.trim())))
+        .collect();
+    devices.sort();
+    devices
+}
+
+/// Highest `temp*_input` of `devices` whose label (empty if it has none) matches.
+fn hottest(devices: &[PathBuf], label: impl Fn(&str) -> bool) -> Option<i32> {
+    devices
+        .iter()
+        .filter_map(|device| fs::read_dir(device).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("temp") && n.ends_with("_input")))
+        .filter(|input| {
+            let label_file = input.to_string_lossy().replace("_input", "_label");
+            label(fs::read_to_string(label_file).unwrap_or_default().trim())
+        })
+        .filter_map(|input| read_millidegrees(&input))
+        .max()
+}
+
+fn read_millidegrees(path: &Path) -> Option<i32> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sensor(root: &Path, device: &str, name: &str, temps: &[(&str, i32)]) {
+        let dir = root.join(device);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("name"), format!("{}\n", name)).unwrap();
+        for (i, (label, millidegrees)) in temps.iter().enumerate() {
+            fs::write(dir.join(format!("temp{}_input", i + 1)), format!("{}\n", millidegrees)).unwrap();
+            if !label.is_empty() {
+                fs::write(dir.join(format!("temp{}_label", i + 1)), format!("{}\n", label)).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn sources_pick_their_sensor_from_hwmon() {
+        let root = std::env::temp_dir().join(format!("msi-center-hwmon-{}", std::process::id()));
+        sensor(&root, "hwmon1", "coretemp", &[("Package id 0", 71000), ("Core 0", 68000), ("Core 4", 79000)]);
+        sensor(&root, "hwmon2", "amdgpu", &[("edge", 55000), ("junction", 64000)]);
+        sensor(&root, "hwmon3", "nvme", &[("Composite", 48000), ("Sensor 1", 52000)]);
+
+        assert_eq!(TempSource::CpuPackage.read_in(&root), Some(71));
+        assert_eq!(TempSource::CpuHottestCore.read_in(&root), Some(79));
+        assert_eq!(TempSource::GpuEdge.read_in(&root), Some(55));
+        assert_eq!(TempSource::GpuHotspot.read_in(&root), Some(64));
+        assert_eq!(TempSource::Nvme.read_in(&root), Some(48));
+        assert_eq!(TempSource::Hwmon(root.join("hwmon3/temp2_input")).read_in(&root), Some(52));
+        assert_eq!(TempSource::Fan.read_in(&root), None);
+        assert_eq!("/sys/x/temp1_input".parse(), Ok(TempSource::Hwmon(PathBuf::from("/sys/x/temp1_input"))));
+
+        fs::remove_dir_all(root).unwrap();
+    }
+}
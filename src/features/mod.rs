@@ -0,0 +1,62 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+
+const MSI_EC_PATH: &str = "/sys/devices/platform/msi-ec";
+const KBD_BACKLIGHT_PATHS: &[&str] = &[
+    "/sys/class/leds/msiacpi::kbd_backlight",
+    "/sys/class/leds/msi::kbd_backlight",
+];
+const BATTERY_NAMES: &[&str] = &["BAT0", "BAT1", "BAT2"];
+
+/// State of the non-thermal toggles, as far as the running kernel exposes them.
+/// Missing attributes are `None` rather than an error, since support varies by
+/// msi-ec version and model.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FeatureState {
+    pub webcam: Option<bool>,
+    pub webcam_block: Option<bool>,
+    pub fn_lock: Option<bool>,
+    /// Whether the Fn and Win keys are swapped.
+    pub win_swap: Option<bool>,
+    pub win_key: Option<bool>,
+    /// Charge stop threshold in percent.
+    pub charge_threshold: Option<u8>,
+    pub keyboard_backlight: Option<u32>,
+    pub keyboard_backlight_max: Option<u32>,
+}
+
+fn read_attr(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+fn read_on_off(name: &str) -> Option<bool> {
+    match read_attr(&Path::new(MSI_EC_PATH).join(name))?.as_str() {
+        "on" | "1" => Some(true),
+        "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+impl FeatureState {
+    pub fn read() -> Self {
+        let backlight = KBD_BACKLIGHT_PATHS.iter().map(Path::new).find(|p| p.exists());
+        let charge_threshold = BATTERY_NAMES.iter().find_map(|bat| {
+            read_attr(&Path::new("/sys/class/power_supply").join(bat).join("charge_control_end_threshold"))?
+                .parse()
+                .ok()
+        });
+
+        Self {
+            webcam: read_on_off("webcam"),
+            webcam_block: read_on_off("webcam_block"),
+            fn_lock: read_on_off("fn_lock"),
+            // msi-ec reports which side of the space bar the Fn key is on.
+            win_swap: read_attr(&Path::new(MSI_EC_PATH).join("fn_key")).map(|side| side == "right"),
+            win_key: read_on_off("win_key"),
+            charge_threshold,
+            keyboard_backlight: backlight.and_then(|p| read_attr(&p.join("brightness"))?.parse().ok()),
+            keyboard_backlight_max: backlight.and_then(|p| read_attr(&p.join("max_brightness"))?.parse().ok()),
+        }
+    }
+}
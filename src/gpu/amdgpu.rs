@@ -0,0 +1,98 @@
+//! Radeon GPUs through amdgpu's hwmon device, which labels its temperatures
+//! `edge`, `junction` (hotspot) and `mem`, and reports the fan when it drives one.
+
+use super::GpuTelemetry;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const HWMON_ROOT: &str = "/sys/class/hwmon";
+
+pub fn telemetry() -> Option<GpuTelemetry> {
+    telemetry_in(Path::new(HWMON_ROOT))
+}
+
+fn telemetry_in(root: &Path) -> Option<GpuTelemetry> {
+    let device = discrete(root)?;
+    let temps = temperatures(&device);
+    let temp = |label: &str| temps.iter().find(|(l, _)| l == label).map(|&(_, temp)| temp);
+    let read = |file: &str| fs::read_to_string(device.join(file)).ok()?.trim().parse::<u32>().ok();
+
+    let fan_rpm = read("fan1_input").map(|rpm| rpm.min(u16::MAX as u32) as u16);
+    Some(GpuTelemetry {
+        temp: temp("edge").or_else(|| temps.first().map(|&(_, temp)| temp))?,
+        hotspot: temp("junction"),
+        memory: temp("mem"),
+        utilization: read("device/gpu_busy_percent").map(|busy| busy.min(100) as u8),
+        // pwm1 is only meaningful next to a tachometer; without one amdgpu doesn't drive a fan.
+        fan_percent: fan_rpm.and(read("pwm1")).map(|pwm| (pwm.min(255) * 100 / 255) as u8),
+        fan_rpm,
+    })
+}
+
+/// The amdgpu hwmon device of a discrete GPU. The integrated GPU of Ryzen APUs
+/// registers one too, but reports only its edge temperature.
+fn discrete(root: &Path) -> Option<PathBuf> {
+    let mut devices: Vec<PathBuf> = fs::read_dir(root)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| fs::read_to_string(path.join("name")).is_ok_and(|name| name.trim() == "amdgpu"))
+        .collect();
+    devices.sort();
+    devices.into_iter().find(|device| temperatures(device).iter().any(|(label, _)| label == "junction" || label == "mem"))
+}
+
+/// Labels and readings (°C) of the device's `temp*_input` files, in file order.
+fn temperatures(device: &Path) -> Vec<(String, u8)> {
+    (1..=8)
+        .filter_map(|i| {
+            let millidegrees: i32 = fs::read_to_string(device.join(format!("temp{}_input", i))).ok()?.trim().parse().ok()?;
+            let label = fs::read_to_string(device.join(format!("temp{}_label", i))).unwrap_or_default();
+            Some((label.trim().to_string(), (millidegrees / 1000).clamp(0, u8::MAX as i32) as u8))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(root: &Path, name: &str, files: &[(&str, &str)]) {
+        let dir = root.join(name);
+        fs::create_dir_all(dir.join("device")).unwrap();
+        fs::write(dir.join("name"), "amdgpu\n").unwrap();
+        for (file, value) in files {
+            fs::write(dir.join(file), format!("{}\n", value)).unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_the_discrete_gpu_not_the_apu() {
+        let root = std::env::temp_dir().join(format!("msi-center-amdgpu-{}", std::process::id()));
+        device(&root, "hwmon1", &[("temp1_input", "45000"), ("temp1_label", "edge")]);
+        device(
+            &root,
+            "hwmon2",
+            &[
+                ("temp1_input", "61000"),
+                ("temp1_label", "edge"),
+                ("temp2_input", "78000"),
+                ("temp2_label", "junction"),
+                ("temp3_input", "70000"),
+                ("temp3_label", "mem"),
+                ("fan1_input", "2100"),
+                ("pwm1", "153"),
+                ("device/gpu_busy_percent", "87"),
+            ],
+        );
+
+        let telemetry = telemetry_in(&root).unwrap();
+        assert_eq!((telemetry.temp, telemetry.hotspot, telemetry.memory), (61, Some(78), Some(70)));
+        assert_eq!((telemetry.fan_rpm, telemetry.fan_percent, telemetry.utilization), (Some(2100), Some(60), Some(87)));
+
+        fs::remove_dir_all(&root).unwrap();
+        device(&root, "hwmon1", &[("temp1_input", "45000"), ("temp1_label", "edge")]);
+        assert_eq!(telemetry_in(&root), None);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,46 @@
+//! Telemetry of the discrete GPU from its own driver. The EC's GPU register is of
+//! little use on many Optimus laptops: it reads 0 while the dGPU sleeps, and the
+//! proprietary NVIDIA driver registers no hwmon device to fall back on. Radeon
+//! GPUs report more through amdgpu's hwmon device than its first temperature.
+
+mod amdgpu;
+#[cfg(feature = "nvml")]
+mod nvml;
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct GpuTelemetry {
+    /// Edge (die) temperature, as the EC and most tools show it.
+    pub temp: u8,
+    /// Hottest spot of the die (amdgpu `junction`), which runs well above the edge under load.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hotspot: Option<u8>,
+    /// VRAM temperature (amdgpu `mem`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<u8>,
+    /// Busy time of the GPU's engines (%) over the driver's last sample period.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utilization: Option<u8>,
+    /// Fan duty (%) as the GPU reports it; laptops' EC-driven fans usually aren't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_percent: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fan_rpm: Option<u16>,
+}
+
+/// The dGPU's readings, or `None` when no provider reaches it. A runtime-suspended
+/// dGPU is left alone, since querying it would wake it up.
+pub fn telemetry() -> Option<GpuTelemetry> {
+    if crate::system::dgpu_active() == Some(false) {
+        return None;
+    }
+    if let Some(telemetry) = amdgpu::telemetry() {
+        return Some(telemetry);
+    }
+    #[cfg(feature = "nvml")]
+    if let Some(telemetry) = nvml::telemetry() {
+        return Some(telemetry);
+    }
+    None
+}
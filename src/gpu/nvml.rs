@@ -0,0 +1,25 @@
+//! NVIDIA GPUs through NVML, the library behind nvidia-smi, loaded at runtime.
+
+use super::GpuTelemetry;
+use nvml_wrapper::Nvml;
+use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+use std::sync::OnceLock;
+
+/// NVML, initialized on first use; `None` without the NVIDIA driver.
+fn nvml() -> Option<&'static Nvml> {
+    static NVML: OnceLock<Option<Nvml>> = OnceLock::new();
+    NVML.get_or_init(|| Nvml::init().map_err(|e| log::debug!("NVML unavailable: {}", e)).ok()).as_ref()
+}
+
+pub fn telemetry() -> Option<GpuTelemetry> {
+    let device = nvml()?.device_by_index(0).map_err(|e| log::debug!("NVML has no GPU 0: {}", e)).ok()?;
+    let temp = device.temperature(TemperatureSensor::Gpu).map_err(|e| log::debug!("NVML temperature: {}", e)).ok()?;
+    Some(GpuTelemetry {
+        temp: temp.min(u8::MAX as u32) as u8,
+        hotspot: None,
+        memory: None,
+        utilization: device.utilization_rates().ok().map(|rates| rates.gpu.min(100) as u8),
+        fan_percent: device.fan_speed(0).ok().map(|speed| speed.min(100) as u8),
+        fan_rpm: None,
+    })
+}
@@ -1,18 +1,32 @@
-mod config;
-mod ec;
-mod fan;
-mod scenario;
-
-use config::{AppConfig, Profile};
-use ec::EmbeddedController;
+use msi_center_linux::{automation, config, demo, ec, fan, history, hooks, logging, palette, scenario, system, travel};
+
+use automation::{AutomationConfig, PowerState, Role, Rule};
+use config::{AlertOverrides, AppConfig, ColorPalette, ExitAction, PollTimer, Profile, StartupCheck, TempLevel};
+use demo::DemoHardware;
+use hooks::ProfileHooks;
+use ec::{EmbeddedController, Support};
+use ec::failures::{self, WriteFailure};
 use eframe::egui;
-use fan::{FanController, FanCurve, FanCurvePoint, FanInfo, FanMode};
-use scenario::{ScenarioManager, ScenarioSettings, ShiftMode, UserScenario};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use fan::emergency::EmergencyCooling;
+use fan::failure::{FailureWatch, StalledFan};
+use fan::source::TempSource;
+use fan::{CurveDamping, CurveTuning, FanController, FanCurve, FanCurvePoint, FanInfo, FanMode, FanReading, ManualFanSpeed};
+use history::History;
+use scenario::{IndicatorSettings, Mismatch, ScenarioManager, ScenarioSettings, ShiftMode, SuperBatteryFeature, UserScenario};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant, SystemTime};
 
 fn main() -> eframe::Result<()> {
-    env_logger::init();
+    let config = AppConfig::load().unwrap_or_default();
+    logging::init(0, config.log_to_file);
+    ec::set_port_timing(config.ec_port);
+    ec::set_backend_order(&config.ec_backends);
+    ec::set_read_cache_ttl(Duration::from_millis(config.ec_read_cache_ms));
+    fan::set_audible_threshold(config.audible_threshold);
+    let demo = std::env::args().any(|arg| arg == "--demo");
+    if !demo {
+        fan::install_panic_hook();
+    }
 
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -25,7 +39,7 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "MSI Center Linux",
         options,
-        Box::new(|cc| Ok(Box::new(MsiCenterApp::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(MsiCenterApp::new(cc, demo)))),
     )
 }
 
@@ -35,36 +49,141 @@ enum Tab {
     FanControl,
     Scenarios,
     Profiles,
+    Automation,
     Settings,
 }
 
+impl Tab {
+    const ALL: [(Tab, &'static str, &'static str); 6] = [
+        (Tab::Dashboard, "📊", "Dashboard"),
+        (Tab::FanControl, "🌀", "Fan Control"),
+        (Tab::Scenarios, "⚡", "Scenarios"),
+        (Tab::Profiles, "👤", "Profiles"),
+        (Tab::Automation, "🔁", "Automation"),
+        (Tab::Settings, "⚙", "Settings"),
+    ];
+}
+
+/// Something the command palette can do.
+#[derive(Clone)]
+enum PaletteAction {
+    OpenTab(Tab),
+    Scenario(UserScenario),
+    Profile(String),
+    CurvePreset(String),
+    FanMode(FanMode),
+    CoolerBoost(bool),
+    ShiftMode(ShiftMode),
+    ResetFans,
+    ToggleMiniWindow,
+    Undo,
+    Redo,
+    Refresh,
+}
+
+/// What Ctrl+Z restores: the curve editors and the profile and automation config.
+#[derive(Clone, PartialEq)]
+struct EditSnapshot {
+    cpu_curve: Vec<[f32; 2]>,
+    gpu_curve: Vec<[f32; 2]>,
+    cpu_tuning: CurveTuning,
+    gpu_tuning: CurveTuning,
+    cpu_damping: CurveDamping,
+    gpu_damping: CurveDamping,
+    cpu_source: TempSource,
+    gpu_source: TempSource,
+    profiles: Vec<Profile>,
+    automation: AutomationConfig,
+}
+
 struct MsiCenterApp {
     current_tab: Tab,
     fan_info: Option<FanInfo>,
     current_scenario: UserScenario,
     current_shift_mode: ShiftMode,
     super_battery: bool,
+    /// Super battery features this model maps to their own bits.
+    super_battery_features: BTreeMap<SuperBatteryFeature, bool>,
+    /// Lid logo brightness in percent, on models with a lid logo LED.
+    lid_logo: Option<u8>,
+    /// Indicator LED behavior, on models whose EC lets it be configured.
+    indicator_led: Option<IndicatorSettings>,
     cooler_boost: bool,
     config: AppConfig,
+    config_mtime: Option<SystemTime>,
     last_update: Instant,
-    update_interval: Duration,
+    temperature_timer: PollTimer,
+    fan_timer: PollTimer,
+    feature_timer: PollTimer,
+    /// Rules as the Automation tab lists them, refreshed with the features while it is open.
+    automation_rules: Vec<Rule>,
     error_message: Option<String>,
     success_message: Option<String>,
     is_root: bool,
+    /// "Load at every boot" for reloading ec_sys with write support.
+    persist_ec_sys_writes: bool,
     
     cpu_fan_speed: f32,
     gpu_fan_speed: f32,
+    /// Manual speed of a third fan, on models that have one.
+    system_fan_speed: f32,
     manual_fan_mode: bool,
+    remember_manual_speed: bool,
     
     cpu_curve: Vec<[f32; 2]>,
     gpu_curve: Vec<[f32; 2]>,
+    /// Minimum duty and per-point offsets, edited on models with fan tuning registers.
+    cpu_tuning: CurveTuning,
+    gpu_tuning: CurveTuning,
+    /// Hysteresis and ramp rate, used when the curves are followed in software.
+    cpu_damping: CurveDamping,
+    gpu_damping: CurveDamping,
+    cpu_source: TempSource,
+    gpu_source: TempSource,
+    /// Name typed for saving the editors' curves as a preset of the active profile.
+    new_curve_preset_name: String,
     
     new_profile_name: String,
     selected_profile_base: usize,
+    edit_history: History<EditSnapshot>,
+    /// Set when an undo or redo ran mid-frame, so it isn't recorded as a new edit.
+    edit_restored: bool,
+
+    show_mini_window: bool,
+    /// Palette the window's visuals were last set up for, and the theme's own
+    /// visuals to go back to after high contrast mode.
+    applied_palette: Option<ColorPalette>,
+    base_visuals: Option<egui::Visuals>,
+    /// Ctrl+K command palette: whether it is open, what has been typed and the
+    /// highlighted match.
+    show_command_palette: bool,
+    palette_query: String,
+    palette_selected: usize,
+    /// EC handle shared by every read and write, opened on first use.
+    hardware: Option<FanController>,
+    /// EC writes that failed or did not stick recently; shown until dismissed.
+    ec_write_failures: Vec<WriteFailure>,
+    show_ec_failures: bool,
+    /// Lets EC writes outside the model's safe registers through; never saved.
+    expert_mode: bool,
+    available_shift_modes: Vec<ShiftMode>,
+    available_fan_modes: Vec<FanMode>,
+    /// Controls the active EC backend can reach; the others are greyed out.
+    support: Support,
+    /// Differences between the active profile and the hardware found on startup, and
+    /// the hardware's settings, until the user decides what to keep.
+    startup_mismatches: Vec<Mismatch>,
+    startup_hardware: Option<ScenarioSettings>,
+    /// Fans reading 0 RPM while hot for too long, shown in a banner.
+    failure_watch: FailureWatch,
+    stalled_fans: Vec<StalledFan>,
+
+    /// Simulated hardware when started with `--demo`; nothing is written to the EC or config.
+    demo: Option<DemoHardware>,
 }
 
 impl MsiCenterApp {
-    fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(_cc: &eframe::CreationContext<'_>, demo: bool) -> Self {
         let config = AppConfig::load().unwrap_or_default();
         let is_root = nix::unistd::geteuid().is_root();
 
@@ -74,173 +193,780 @@ impl MsiCenterApp {
             current_scenario: UserScenario::Balanced,
             current_shift_mode: ShiftMode::Comfort,
             super_battery: false,
+            super_battery_features: BTreeMap::new(),
+            lid_logo: None,
+            indicator_led: None,
             cooler_boost: false,
             config,
+            config_mtime: AppConfig::modified_time(),
             last_update: Instant::now() - Duration::from_secs(10),
-            update_interval: Duration::from_secs(2),
+            temperature_timer: PollTimer::default(),
+            fan_timer: PollTimer::default(),
+            feature_timer: PollTimer::default(),
+            automation_rules: Vec::new(),
             error_message: None,
             success_message: None,
             is_root,
+            persist_ec_sys_writes: true,
             cpu_fan_speed: 50.0,
             gpu_fan_speed: 50.0,
+            system_fan_speed: 50.0,
             manual_fan_mode: false,
+            remember_manual_speed: false,
             cpu_curve: vec![[40.0, 0.0], [50.0, 30.0], [60.0, 50.0], [70.0, 70.0], [80.0, 90.0], [90.0, 100.0]],
             gpu_curve: vec![[40.0, 0.0], [50.0, 30.0], [60.0, 50.0], [70.0, 70.0], [80.0, 90.0], [90.0, 100.0]],
+            cpu_tuning: CurveTuning::default(),
+            gpu_tuning: CurveTuning::default(),
+            cpu_damping: CurveDamping::default(),
+            gpu_damping: CurveDamping::default(),
+            cpu_source: TempSource::Fan,
+            gpu_source: TempSource::Fan,
+            new_curve_preset_name: String::new(),
             new_profile_name: String::new(),
             selected_profile_base: 1,
+            edit_history: History::default(),
+            edit_restored: false,
+            show_mini_window: false,
+            applied_palette: None,
+            base_visuals: None,
+            show_command_palette: false,
+            palette_query: String::new(),
+            palette_selected: 0,
+            hardware: None,
+            ec_write_failures: Vec::new(),
+            show_ec_failures: false,
+            expert_mode: false,
+            available_shift_modes: if demo { ShiftMode::ALL.to_vec() } else { ShiftMode::available() },
+            available_fan_modes: if demo { FanMode::ALL.to_vec() } else { FanMode::available() },
+            // The simulated model has the usual two fans.
+            support: Support { third_fan: false, ..Support::ALL },
+            startup_mismatches: Vec::new(),
+            startup_hardware: None,
+            failure_watch: FailureWatch::default(),
+            stalled_fans: Vec::new(),
+            demo: demo.then(DemoHardware::new),
         };
 
+        if !demo {
+            app.error_message = system::lockdown_warning();
+            app.check_startup_consistency();
+        }
+        app.load_editor_curves();
         app.refresh_data();
         app
     }
 
+    /// Compares the hardware with the active profile and handles a difference as
+    /// `startup_check` says; `prompt` leaves it to `render_startup_check`.
+    fn check_startup_consistency(&mut self) {
+        // Travel mode departs from the profile on purpose.
+        if self.config.startup_check == StartupCheck::Off || travel::is_on() {
+            return;
+        }
+        let Some(profile) = self.config.get_active_profile().cloned() else {
+            return;
+        };
+        let deferred = self.config.deferred_controls.clone();
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        let mut manager = ScenarioManager::new(fan_controller);
+        manager.set_deferred_controls(&deferred);
+        let checked = manager.compare(&profile.settings).and_then(|mismatches| Ok((mismatches, manager.hardware_settings(&profile.settings)?)));
+        let (mismatches, hardware) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                log::warn!("Could not compare the hardware with profile '{}': {}", profile.name, e);
+                return;
+            }
+        };
+        if mismatches.is_empty() {
+            return;
+        }
+
+        match self.config.startup_check {
+            StartupCheck::Reapply => self.apply_profile(&profile),
+            StartupCheck::Adopt => self.adopt_hardware_settings(hardware),
+            StartupCheck::Prompt | StartupCheck::Off => {
+                self.startup_mismatches = mismatches;
+                self.startup_hardware = Some(hardware);
+            }
+        }
+    }
+
+    fn adopt_hardware_settings(&mut self, settings: ScenarioSettings) {
+        self.config.adopt_settings(settings);
+        match self.save_config() {
+            Ok(()) => self.success_message = Some(format!("Kept the hardware's settings as '{}'", config::CURRENT_PROFILE)),
+            Err(e) => self.error_message = Some(format!("Failed to save config: {}", e)),
+        }
+    }
+
+    fn save_config(&mut self) -> config::Result<()> {
+        if self.demo.is_some() {
+            return Ok(());
+        }
+        let result = self.config.save();
+        self.config_mtime = AppConfig::modified_time();
+        result
+    }
+
+    /// Picks up changes made to the config file by the CLI or an editor so the next
+    /// save doesn't overwrite them.
+    fn reload_config_if_changed(&mut self) {
+        if self.demo.is_some() {
+            return;
+        }
+        let mtime = AppConfig::modified_time();
+        if mtime.is_none() || mtime == self.config_mtime {
+            return;
+        }
+
+        self.config_mtime = mtime;
+        match AppConfig::load() {
+            Ok(config) => {
+                log::info!("Config file changed on disk, reloading");
+                self.config = config;
+            }
+            Err(e) => log::warn!("Config file changed on disk but could not be loaded: {}", e),
+        }
+    }
+
+    fn edit_snapshot(&self) -> EditSnapshot {
+        EditSnapshot {
+            cpu_curve: self.cpu_curve.clone(),
+            gpu_curve: self.gpu_curve.clone(),
+            cpu_tuning: self.cpu_tuning.clone(),
+            gpu_tuning: self.gpu_tuning.clone(),
+            cpu_damping: self.cpu_damping,
+            gpu_damping: self.gpu_damping,
+            cpu_source: self.cpu_source.clone(),
+            gpu_source: self.gpu_source.clone(),
+            profiles: self.config.profiles.clone(),
+            automation: self.config.automation.clone(),
+        }
+    }
+
+    fn handle_undo_shortcuts(&mut self, ctx: &egui::Context) {
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        // Ctrl+Z also matches Ctrl+Shift+Z, so check for redo first.
+        let redo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::Z);
+        let undo = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z);
+        if ctx.input_mut(|i| i.consume_shortcut(&redo)) {
+            self.redo_edit();
+        } else if ctx.input_mut(|i| i.consume_shortcut(&undo)) {
+            self.undo_edit();
+        }
+    }
+
+    fn undo_edit(&mut self) {
+        let current = self.edit_snapshot();
+        match self.edit_history.undo(current) {
+            Some(snapshot) => self.restore_edit(snapshot, "Undone"),
+            None => self.success_message = Some("Nothing to undo".to_string()),
+        }
+    }
+
+    fn redo_edit(&mut self) {
+        let current = self.edit_snapshot();
+        match self.edit_history.redo(current) {
+            Some(snapshot) => self.restore_edit(snapshot, "Redone"),
+            None => self.success_message = Some("Nothing to redo".to_string()),
+        }
+    }
+
+    fn restore_edit(&mut self, snapshot: EditSnapshot, message: &str) {
+        let config_changed = snapshot.profiles != self.config.profiles || snapshot.automation != self.config.automation;
+        self.cpu_curve = snapshot.cpu_curve;
+        self.gpu_curve = snapshot.gpu_curve;
+        self.cpu_tuning = snapshot.cpu_tuning;
+        self.gpu_tuning = snapshot.gpu_tuning;
+        self.cpu_damping = snapshot.cpu_damping;
+        self.gpu_damping = snapshot.gpu_damping;
+        self.cpu_source = snapshot.cpu_source;
+        self.gpu_source = snapshot.gpu_source;
+        self.config.profiles = snapshot.profiles;
+        self.config.automation = snapshot.automation;
+        self.edit_restored = true;
+        if !self.config.profiles.iter().any(|p| p.name == self.config.active_profile)
+            && let Some(first) = self.config.profiles.first()
+        {
+            self.config.active_profile = first.name.clone();
+        }
+
+        if config_changed && let Err(e) = self.save_config() {
+            self.error_message = Some(format!("Failed to save config: {}", e));
+            return;
+        }
+        self.success_message = Some(message.to_string());
+    }
+
+    /// Re-reads everything now, e.g. after the user changed a setting.
     fn refresh_data(&mut self) {
-        if let Ok(ec) = EmbeddedController::new() {
-            let mut fan_controller = FanController::new(ec);
+        self.temperature_timer.reset();
+        self.fan_timer.reset();
+        self.feature_timer.reset();
+        self.poll();
+    }
+
+    /// Refreshes each kind of state whose polling interval has passed.
+    fn poll(&mut self) {
+        let polling = self.config.polling;
+        if self.feature_timer.due(polling.features()) {
+            self.refresh_features();
+        }
+        let fans_due = self.fan_timer.due(polling.fans());
+        let temperatures_due = self.temperature_timer.due(polling.temperatures());
+        if fans_due {
+            self.refresh_fans();
+        } else if temperatures_due {
+            self.refresh_temperatures();
+        }
+    }
+
+    fn refresh_temperatures(&mut self) {
+        if let Some(ref mut demo) = self.demo {
+            self.fan_info = Some(demo.fan_info());
+            return;
+        }
+        if let Some(fan_controller) = hardware(&mut self.hardware)
+            && let Some(ref mut info) = self.fan_info
+        {
+            (info.cpu_temp, info.gpu_temp) = fan_controller.read_temperatures();
+        }
+    }
+
+    fn refresh_fans(&mut self) {
+        if let Some(ref mut demo) = self.demo {
+            let info = demo.fan_info();
+            self.cooler_boost = info.cooler_boost;
+            self.fan_info = Some(info);
+            let scenario = demo.scenario_info();
+            self.current_scenario = scenario.current_scenario;
+            self.current_shift_mode = scenario.shift_mode;
+            self.super_battery = scenario.super_battery;
+            self.super_battery_features = scenario.super_battery_features;
+            self.lid_logo = scenario.lid_logo;
+            self.indicator_led = scenario.indicator_led;
+            self.last_update = Instant::now();
+            return;
+        }
+
+        if let Some(fan_controller) = hardware(&mut self.hardware) {
+            self.support = fan_controller.ec_mut().support();
             if let Ok(info) = fan_controller.get_fan_info() {
+                self.stalled_fans = self.failure_watch.update(&info, &self.config.fan_failure, Instant::now());
                 self.fan_info = Some(info.clone());
                 self.cooler_boost = info.cooler_boost;
             }
-        }
 
-        if let Ok(mut ec) = EmbeddedController::new() {
-            if let Ok(ec2) = EmbeddedController::new() {
-                let mut fan_controller = FanController::new(ec2);
-                let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
-                if let Ok(info) = manager.get_current_info() {
-                    self.current_scenario = info.current_scenario;
-                    self.current_shift_mode = info.shift_mode;
-                    self.super_battery = info.super_battery;
-                }
+            if let Ok(info) = ScenarioManager::new(fan_controller).get_current_info() {
+                self.current_scenario = info.current_scenario;
+                self.current_shift_mode = info.shift_mode;
+                self.super_battery = info.super_battery;
+                self.super_battery_features = info.super_battery_features;
+                self.lid_logo = info.lid_logo;
+                self.indicator_led = info.indicator_led;
             }
         }
 
         self.last_update = Instant::now();
     }
 
+    /// Slow-changing state outside the EC: the config file and recorded EC write failures.
+    fn refresh_features(&mut self) {
+        self.reload_config_if_changed();
+        if self.current_tab == Tab::Automation {
+            self.refresh_automation_rules();
+        }
+        if self.demo.is_some() {
+            return;
+        }
+
+        let failures = failures::recent();
+        let latest = failures.last().map(|f| &f.at);
+        if latest.is_some() && latest != self.ec_write_failures.last().map(|f| &f.at) {
+            // The write that was just reported as done did not reach the hardware.
+            self.success_message = None;
+        }
+        self.ec_write_failures = failures;
+    }
+
     fn set_scenario(&mut self, scenario: UserScenario) {
-        if let Ok(mut ec) = EmbeddedController::new() {
-            if let Ok(ec2) = EmbeddedController::new() {
-                let mut fan_controller = FanController::new(ec2);
-                let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
-                match manager.set_scenario(scenario) {
-                    Ok(_) => {
-                        self.current_scenario = scenario;
-                        self.success_message = Some(format!("Scenario set to {}", scenario));
-                        self.refresh_data();
-                    }
-                    Err(e) => {
-                        self.error_message = Some(format!("Failed to set scenario: {}", e));
-                    }
+        if let Some(ref mut demo) = self.demo {
+            demo.set_scenario(scenario);
+            self.success_message = Some(format!("Scenario set to {}", scenario));
+            self.refresh_data();
+            return;
+        }
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        let mut manager = ScenarioManager::new(fan_controller);
+        manager.set_deferred_controls(&self.config.deferred_controls);
+        match manager.set_scenario(scenario) {
+            Ok(_) => {
+                self.current_scenario = scenario;
+                self.success_message = Some(format!("Scenario set to {}", scenario));
+                if let Err(e) = self.config.tuned.sync(scenario) {
+                    self.error_message = Some(format!("Failed to set tuned profile: {}", e));
                 }
+                self.refresh_data();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set scenario: {}", e));
             }
         }
     }
 
     fn set_fan_mode(&mut self, mode: FanMode) {
-        if let Ok(ec) = EmbeddedController::new() {
-            let mut fan_controller = FanController::new(ec);
-            match fan_controller.set_fan_mode(mode) {
-                Ok(_) => {
-                    self.success_message = Some(format!("Fan mode set to {:?}", mode));
-                    self.refresh_data();
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to set fan mode: {}", e));
-                }
+        if let Some(ref mut demo) = self.demo {
+            demo.set_fan_mode(mode);
+            self.success_message = Some(format!("Fan mode set to {:?}", mode));
+            self.refresh_data();
+            return;
+        }
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        match fan_controller.set_fan_mode(mode) {
+            Ok(_) => {
+                self.success_message = Some(format!("Fan mode set to {:?}", mode));
+                self.refresh_data();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set fan mode: {}", e));
             }
         }
     }
 
     fn set_cooler_boost(&mut self, enabled: bool) {
-        if let Ok(ec) = EmbeddedController::new() {
-            let mut fan_controller = FanController::new(ec);
-            match fan_controller.set_cooler_boost(enabled) {
-                Ok(_) => {
-                    self.cooler_boost = enabled;
-                    self.success_message = Some(format!("Cooler Boost {}", if enabled { "enabled" } else { "disabled" }));
-                    self.refresh_data();
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to set cooler boost: {}", e));
-                }
+        if let Some(ref mut demo) = self.demo {
+            demo.set_cooler_boost(enabled);
+            self.success_message = Some(format!("Cooler Boost {}", if enabled { "enabled" } else { "disabled" }));
+            self.refresh_data();
+            return;
+        }
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        match fan_controller.set_cooler_boost(enabled) {
+            Ok(_) => {
+                self.cooler_boost = enabled;
+                self.success_message = Some(format!("Cooler Boost {}", if enabled { "enabled" } else { "disabled" }));
+                self.refresh_data();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set cooler boost: {}", e));
             }
         }
     }
 
     fn apply_manual_fan_speed(&mut self) {
-        if let Ok(ec) = EmbeddedController::new() {
-            let mut fan_controller = FanController::new(ec);
-            match fan_controller.set_manual_fan_speed(self.cpu_fan_speed as u8, self.gpu_fan_speed as u8) {
-                Ok(_) => {
-                    self.success_message = Some(format!("Fan speed set to CPU: {}%, GPU: {}%", 
-                        self.cpu_fan_speed as u8, self.gpu_fan_speed as u8));
-                    self.refresh_data();
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to set fan speed: {}", e));
+        let speed = ManualFanSpeed {
+            cpu: self.cpu_fan_speed as u8,
+            gpu: self.gpu_fan_speed as u8,
+            system: self.support.third_fan.then_some(self.system_fan_speed as u8),
+        };
+
+        if let Some(ref mut demo) = self.demo {
+            demo.set_manual_fan_speed(self.cpu_fan_speed as u8, self.gpu_fan_speed as u8);
+            self.success_message = Some(format!("Fan speed set to CPU: {}%, GPU: {}%",
+                self.cpu_fan_speed as u8, self.gpu_fan_speed as u8));
+            self.remember_manual_fan_speed(speed);
+            self.refresh_data();
+            return;
+        }
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        fan_controller.set_speed_guard(self.config.speed_guard);
+        let written = speed.floored(self.config.min_fan_percent());
+        match fan_controller.set_manual_fan_speeds(&written.speeds()) {
+            Ok(_) => {
+                let mut message = format!("Fan speed set to CPU: {}%, GPU: {}%", written.cpu, written.gpu);
+                if let Some(system) = written.system {
+                    message.push_str(&format!(", System: {}%", system));
                 }
+                self.success_message = Some(message);
+                // Only a speed the guard let through and the EC took is kept for later.
+                self.remember_manual_fan_speed(speed);
+                self.refresh_data();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set fan speed: {}", e));
             }
         }
     }
 
+    /// Stores `speed` in the active profile when "Remember" is ticked.
+    fn remember_manual_fan_speed(&mut self, speed: ManualFanSpeed) {
+        if self.remember_manual_speed
+            && self.config.set_manual_fan_speed(Some(speed))
+            && let Err(e) = self.save_config()
+        {
+            self.error_message = Some(format!("Failed to save config: {}", e));
+        }
+    }
+
     fn apply_fan_curve(&mut self, is_cpu: bool) {
-        let curve_points: Vec<FanCurvePoint> = if is_cpu {
-            self.cpu_curve.iter().map(|p| FanCurvePoint { temp: p[0] as u8, speed: p[1] as u8 }).collect()
+        let curve = if is_cpu {
+            editor_curve(&self.cpu_curve, &self.cpu_tuning, self.cpu_damping, &self.cpu_source)
         } else {
-            self.gpu_curve.iter().map(|p| FanCurvePoint { temp: p[0] as u8, speed: p[1] as u8 }).collect()
+            editor_curve(&self.gpu_curve, &self.gpu_tuning, self.gpu_damping, &self.gpu_source)
         };
 
-        let curve = FanCurve { points: curve_points };
-
-        if let Ok(ec) = EmbeddedController::new() {
-            let mut fan_controller = FanController::new(ec);
-            let result = if is_cpu {
-                fan_controller.set_cpu_fan_curve(curve)
+        if let Some(ref mut demo) = self.demo {
+            if is_cpu {
+                demo.set_cpu_fan_curve(curve);
             } else {
-                fan_controller.set_gpu_fan_curve(curve)
+                demo.set_gpu_fan_curve(curve);
+            }
+            self.success_message = Some(format!("{} fan curve applied", if is_cpu { "CPU" } else { "GPU" }));
+            return;
+        }
+
+        let curve = curve.floored(self.config.min_fan_percent());
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        fan_controller.set_speed_guard(self.config.speed_guard);
+        let result = if is_cpu {
+            fan_controller.set_cpu_fan_curve(curve)
+        } else {
+            fan_controller.set_gpu_fan_curve(curve)
+        };
+
+        match result {
+            Ok(_) => {
+                self.success_message = Some(format!("{} fan curve applied", if is_cpu { "CPU" } else { "GPU" }));
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to apply fan curve: {}", e));
+            }
+        }
+    }
+
+    fn set_editor_curves(&mut self, cpu: Option<&FanCurve>, gpu: Option<&FanCurve>) {
+        if let Some(curve) = cpu {
+            self.cpu_curve = editor_points(curve);
+            self.cpu_tuning = curve.tuning.clone();
+            self.cpu_damping = curve.damping;
+            self.cpu_source = curve.source.clone();
+        }
+        if let Some(curve) = gpu {
+            self.gpu_curve = editor_points(curve);
+            self.gpu_tuning = curve.tuning.clone();
+            self.gpu_damping = curve.damping;
+            self.gpu_source = curve.source.clone();
+        }
+    }
+
+    /// Starts the editors on the curves the fan tables hold, or where they can't be
+    /// read, the active profile's.
+    fn load_editor_curves(&mut self) {
+        let Some(settings) = self.config.get_active_profile().map(|p| p.settings.clone()) else {
+            return;
+        };
+        let hardware = if self.demo.is_some() { None } else { hardware(&mut self.hardware) };
+        let read = hardware.and_then(|fan_controller| ScenarioManager::new(fan_controller).hardware_settings(&settings).ok());
+        let settings = read.unwrap_or(settings);
+        self.set_editor_curves(settings.cpu_fan_curve.as_ref(), settings.gpu_fan_curve.as_ref());
+    }
+
+    /// Loads one of the active profile's curve presets into the editors, stores it as
+    /// the profile's curves and applies it.
+    fn use_curve_preset(&mut self, name: &str) {
+        let active = self.config.active_profile.clone();
+        let Some(profile) = self.config.profiles.iter_mut().find(|p| p.name == active) else {
+            return;
+        };
+        if !profile.use_curves(name) {
+            return;
+        }
+        let (cpu, gpu) = (profile.settings.cpu_fan_curve.clone(), profile.settings.gpu_fan_curve.clone());
+        self.set_editor_curves(cpu.as_ref(), gpu.as_ref());
+        if let Err(e) = self.save_config() {
+            self.error_message = Some(format!("Failed to save config: {}", e));
+            return;
+        }
+        if self.support.fan_curves {
+            self.apply_fan_curve(true);
+            if self.support.second_fan {
+                self.apply_fan_curve(false);
+            }
+        }
+        self.success_message = Some(format!("Using curves '{}'", name));
+    }
+
+    fn save_curve_preset(&mut self) {
+        let name = self.new_curve_preset_name.trim().to_string();
+        let active = self.config.active_profile.clone();
+        let Some(profile) = self.config.profiles.iter_mut().find(|p| p.name == active) else {
+            return;
+        };
+        profile.settings.cpu_fan_curve = Some(editor_curve(&self.cpu_curve, &self.cpu_tuning, self.cpu_damping, &self.cpu_source));
+        profile.cpu_curve = None;
+        if self.support.second_fan {
+            profile.settings.gpu_fan_curve = Some(editor_curve(&self.gpu_curve, &self.gpu_tuning, self.gpu_damping, &self.gpu_source));
+            profile.gpu_curve = None;
+        }
+        profile.save_curves(&name);
+        match self.save_config() {
+            Ok(()) => {
+                self.success_message = Some(format!("Curves saved as '{}' in profile '{}'", name, active));
+                self.new_curve_preset_name.clear();
+            }
+            Err(e) => self.error_message = Some(format!("Failed to save config: {}", e)),
+        }
+    }
+
+    fn set_shift_mode(&mut self, mode: ShiftMode) {
+        if let Some(ref mut demo) = self.demo {
+            demo.set_shift_mode(mode);
+            self.current_shift_mode = mode;
+            self.success_message = Some(format!("Shift mode set to {}", mode));
+            return;
+        }
+
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        let mut manager = ScenarioManager::new(fan_controller);
+        match manager.set_shift_mode(mode) {
+            Ok(_) => {
+                self.current_shift_mode = mode;
+                self.success_message = Some(format!("Shift mode set to {}", mode));
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set shift mode: {}", e));
+            }
+        }
+    }
+
+    fn set_lid_logo(&mut self, percent: u8) {
+        let message = if percent == 0 { "Lid logo off".to_string() } else { format!("Lid logo set to {}%", percent) };
+        if let Some(ref mut demo) = self.demo {
+            demo.set_lid_logo(percent);
+            self.lid_logo = Some(percent);
+            self.success_message = Some(message);
+            return;
+        }
+
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        match ScenarioManager::new(fan_controller).set_lid_logo(percent) {
+            Ok(_) => {
+                self.lid_logo = Some(percent);
+                self.success_message = Some(message);
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set lid logo: {}", e));
+            }
+        }
+    }
+
+    /// Writes the indicator LED behavior and stores it in the active profile, so
+    /// applying the profile sets it again.
+    fn set_indicator_led(&mut self, settings: IndicatorSettings) {
+        if let Some(ref mut demo) = self.demo {
+            demo.set_indicator_led(&settings);
+        } else {
+            let Some(fan_controller) = hardware(&mut self.hardware) else {
+                return;
             };
+            if let Err(e) = ScenarioManager::new(fan_controller).set_indicator_led(&settings) {
+                self.error_message = Some(format!("Failed to set indicator LED: {}", e));
+                return;
+            }
+        }
+        self.indicator_led = Some(settings);
 
-            match result {
-                Ok(_) => {
-                    self.success_message = Some(format!("{} fan curve applied", if is_cpu { "CPU" } else { "GPU" }));
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to apply fan curve: {}", e));
+        let active = self.config.active_profile.clone();
+        if let Some(profile) = self.config.profiles.iter_mut().find(|p| p.name == active) {
+            profile.settings.indicator_led = settings;
+        }
+        match self.save_config() {
+            Ok(()) => self.success_message = Some(format!("Indicator LED: {}", settings)),
+            Err(e) => self.error_message = Some(format!("Failed to save config: {}", e)),
+        }
+    }
+
+    fn set_super_battery_feature(&mut self, feature: SuperBatteryFeature, enabled: bool) {
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        match ScenarioManager::new(fan_controller).set_super_battery_feature(feature, enabled) {
+            Ok(_) => {
+                self.super_battery_features.insert(feature, enabled);
+                self.success_message = Some(format!("{} {}", feature, if enabled { "enabled" } else { "disabled" }));
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to set {}: {}", feature.label().to_lowercase(), e));
+            }
+        }
+    }
+
+    fn apply_profile(&mut self, profile: &Profile) {
+        if let Some(ref mut demo) = self.demo {
+            demo.apply_settings(&profile.settings);
+            self.config.set_active_profile(&profile.name);
+            self.success_message = Some(format!("Applied profile: {}", profile.name));
+            self.refresh_data();
+            return;
+        }
+
+        let previous = self.config.get_active_profile().cloned();
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        fan_controller.set_speed_guard(self.config.speed_guard);
+        let mut manager = ScenarioManager::new(fan_controller);
+        manager.set_deferred_controls(&self.config.deferred_controls);
+        let result = hooks::with_profile_hooks(profile, previous.as_ref(), || -> Result<(), Box<dyn std::error::Error>> {
+            Ok(manager.apply_settings(&profile.settings)?)
+        });
+
+        match result {
+            Ok(_) => {
+                self.config.set_active_profile(&profile.name);
+                let _ = self.save_config();
+                self.success_message = Some(format!("Applied profile: {}", profile.name));
+                if let Err(e) = self.config.tuned.sync(profile.scenario) {
+                    self.error_message = Some(format!("Failed to set tuned profile: {}", e));
                 }
+                self.refresh_data();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to apply profile: {}", e));
             }
         }
     }
 
     fn reset_fans(&mut self) {
-        if let Ok(ec) = EmbeddedController::new() {
-            let mut fan_controller = FanController::new(ec);
-            match fan_controller.reset_to_auto() {
-                Ok(_) => {
-                    self.manual_fan_mode = false;
-                    self.success_message = Some("Fans reset to automatic control".to_string());
-                    self.refresh_data();
-                }
-                Err(e) => {
-                    self.error_message = Some(format!("Failed to reset fans: {}", e));
-                }
+        if self.config.get_active_profile().is_some_and(|p| p.settings.manual_fan_speed.is_some()) {
+            self.config.set_manual_fan_speed(None);
+            let _ = self.save_config();
+        }
+
+        if let Some(ref mut demo) = self.demo {
+            demo.reset_to_auto();
+            self.manual_fan_mode = false;
+            self.success_message = Some("Fans reset to automatic control".to_string());
+            self.refresh_data();
+            return;
+        }
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            return;
+        };
+        match fan_controller.reset_to_auto() {
+            Ok(_) => {
+                self.manual_fan_mode = false;
+                self.success_message = Some("Fans reset to automatic control".to_string());
+                self.refresh_data();
+            }
+            Err(e) => {
+                self.error_message = Some(format!("Failed to reset fans: {}", e));
             }
         }
     }
+
+    /// Leaves the fans as `on_exit.gui` says when the window closes.
+    fn run_exit_action(&mut self) {
+        let action = self.config.on_exit.gui.clone();
+        if action == ExitAction::Leave || self.demo.is_some() {
+            return;
+        }
+        let Some(fan_controller) = hardware(&mut self.hardware) else {
+            log::warn!("Exit action '{}' skipped: EC not accessible", action);
+            return;
+        };
+        let result = match &action {
+            ExitAction::Leave => Ok(()),
+            ExitAction::Auto => fan_controller.reset_to_auto().map_err(|e| e.to_string()),
+            ExitAction::Profile(name) => match self.config.get_profile(name) {
+                Some(profile) => {
+                    fan_controller.set_speed_guard(self.config.speed_guard);
+                    let mut manager = ScenarioManager::new(fan_controller);
+                    manager.set_deferred_controls(&self.config.deferred_controls);
+                    manager.apply_settings(&profile.settings).map_err(|e| e.to_string())
+                }
+                None => Err(format!("Profile '{}' not found", name)),
+            },
+        };
+        match result {
+            Ok(()) => log::info!("Exit action: {}", action),
+            Err(e) => log::warn!("Exit action '{}' failed: {}", action, e),
+        }
+    }
+}
+
+/// The shared fan controller, opening the EC on first use.
+fn hardware(slot: &mut Option<FanController>) -> Option<&mut FanController> {
+    if slot.is_none() {
+        *slot = EmbeddedController::new().ok().map(FanController::new);
+    }
+    slot.as_mut()
+}
+
+fn editor_curve(points: &[[f32; 2]], tuning: &CurveTuning, damping: CurveDamping, source: &TempSource) -> FanCurve {
+    FanCurve {
+        points: points.iter().map(|p| FanCurvePoint { temp: p[0] as u8, speed: p[1] as u8 }).collect(),
+        tuning: tuning.clone(),
+        damping,
+        source: source.clone(),
+    }
+}
+
+fn editor_points(curve: &FanCurve) -> Vec<[f32; 2]> {
+    curve.points.iter().map(|p| [p.temp as f32, p.speed as f32]).collect()
+}
+
+/// `editor_points` of a curve with no more points than the model's fan table holds.
+fn fitted_editor_points(curve: &FanCurve, max_points: usize) -> Vec<[f32; 2]> {
+    if curve.points.len() > max_points { editor_points(&curve.fitted(max_points)) } else { editor_points(curve) }
 }
 
 impl eframe::App for MsiCenterApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.run_exit_action();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        if self.last_update.elapsed() > self.update_interval {
-            self.refresh_data();
+        self.poll();
+        self.handle_undo_shortcuts(ctx);
+        let palette = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::K);
+        if ctx.input_mut(|i| i.consume_shortcut(&palette)) {
+            self.show_command_palette = !self.show_command_palette;
+            self.palette_query.clear();
+            self.palette_selected = 0;
         }
+        // Taken after polling so config reloads from disk aren't recorded as edits.
+        let before = self.edit_snapshot();
 
         ctx.request_repaint_after(Duration::from_millis(500));
+        self.apply_visuals(ctx);
 
         self.render_top_panel(ctx);
         self.render_side_panel(ctx);
         self.render_central_panel(ctx);
         self.render_notifications(ctx);
+        self.render_startup_check(ctx);
+
+        if self.show_mini_window {
+            self.render_mini_window(ctx);
+        }
+        if self.show_command_palette {
+            self.render_command_palette(ctx);
+        }
+
+        if !std::mem::take(&mut self.edit_restored) {
+            let after = self.edit_snapshot();
+            self.edit_history.track(before, &after, ctx.is_using_pointer());
+        }
     }
 }
 
@@ -251,17 +977,221 @@ impl MsiCenterApp {
             ui.horizontal(|ui| {
                 ui.heading(egui::RichText::new("🖥 MSI Center Linux").size(24.0).strong());
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    if !self.is_root {
+                    ui.toggle_value(&mut self.show_mini_window, "🗗 Mini View")
+                        .on_hover_text("Pop out a compact always-on-top window");
+                    ui.separator();
+                    if !self.ec_write_failures.is_empty() {
+                        let count = self.ec_write_failures.len();
+                        let label = egui::RichText::new(format!("⚠ {} EC write{} failed", count, if count == 1 { "" } else { "s" }))
+                            .color(egui::Color32::from_rgb(255, 120, 80));
+                        if ui.add(egui::Label::new(label).sense(egui::Sense::click()))
+                            .on_hover_text("Click for details")
+                            .clicked()
+                        {
+                            self.show_ec_failures = true;
+                        }
+                        ui.separator();
+                    }
+                    if self.demo.is_some() {
+                        ui.label(egui::RichText::new("🎭 Demo mode (simulated data)").color(egui::Color32::LIGHT_BLUE));
+                    } else if !self.is_root {
                         ui.label(egui::RichText::new("⚠ Not running as root").color(egui::Color32::YELLOW));
                     } else {
                         ui.label(egui::RichText::new("✓ Root access").color(egui::Color32::GREEN));
                     }
                 });
             });
+            for fan in &self.stalled_fans {
+                ui.add_space(4.0);
+                ui.label(
+                    egui::RichText::new(format!("⚠ {}; it may have failed. Check that nothing blocks it.", fan))
+                        .size(16.0)
+                        .strong()
+                        .color(egui::Color32::WHITE)
+                        .background_color(egui::Color32::from_rgb(180, 30, 30)),
+                );
+            }
             ui.add_space(8.0);
         });
     }
 
+    /// Everything the command palette offers right now, with its label.
+    fn palette_commands(&self) -> Vec<(String, PaletteAction)> {
+        let mut commands: Vec<(String, PaletteAction)> =
+            Tab::ALL.iter().map(|(tab, _, label)| (format!("Open {} tab", label), PaletteAction::OpenTab(*tab))).collect();
+        for scenario in [UserScenario::Silent, UserScenario::Balanced, UserScenario::HighPerformance, UserScenario::Turbo, UserScenario::SuperBattery] {
+            commands.push((format!("Apply scenario {}", scenario), PaletteAction::Scenario(scenario)));
+        }
+        for profile in &self.config.profiles {
+            commands.push((format!("Apply profile {}", profile.name), PaletteAction::Profile(profile.name.clone())));
+        }
+        if let Some(profile) = self.config.get_active_profile() {
+            for name in profile.curves.keys() {
+                commands.push((format!("Use curves {}", name), PaletteAction::CurvePreset(name.clone())));
+            }
+        }
+        if self.support.fan_mode {
+            for mode in &self.available_fan_modes {
+                commands.push((format!("Fan mode {:?}", mode), PaletteAction::FanMode(*mode)));
+            }
+        }
+        if self.support.cooler_boost {
+            commands.push(("Cooler Boost on".to_string(), PaletteAction::CoolerBoost(true)));
+            commands.push(("Cooler Boost off".to_string(), PaletteAction::CoolerBoost(false)));
+        }
+        if self.support.shift_mode {
+            for mode in &self.available_shift_modes {
+                commands.push((format!("Shift mode {}", mode), PaletteAction::ShiftMode(*mode)));
+            }
+        }
+        commands.push(("Reset fans to automatic".to_string(), PaletteAction::ResetFans));
+        commands.push(("Toggle mini view".to_string(), PaletteAction::ToggleMiniWindow));
+        commands.push(("Undo".to_string(), PaletteAction::Undo));
+        commands.push(("Redo".to_string(), PaletteAction::Redo));
+        commands.push(("Refresh".to_string(), PaletteAction::Refresh));
+        commands
+    }
+
+    fn run_palette_action(&mut self, action: PaletteAction) {
+        match action {
+            PaletteAction::OpenTab(tab) => self.current_tab = tab,
+            PaletteAction::Scenario(scenario) => self.set_scenario(scenario),
+            PaletteAction::Profile(name) => {
+                if let Some(profile) = self.config.profiles.iter().find(|p| p.name == name).cloned() {
+                    self.apply_profile(&profile);
+                }
+            }
+            PaletteAction::CurvePreset(name) => self.use_curve_preset(&name),
+            PaletteAction::FanMode(mode) => self.set_fan_mode(mode),
+            PaletteAction::CoolerBoost(enabled) => self.set_cooler_boost(enabled),
+            PaletteAction::ShiftMode(mode) => self.set_shift_mode(mode),
+            PaletteAction::ResetFans => self.reset_fans(),
+            PaletteAction::ToggleMiniWindow => self.show_mini_window = !self.show_mini_window,
+            PaletteAction::Undo => self.undo_edit(),
+            PaletteAction::Redo => self.redo_edit(),
+            PaletteAction::Refresh => self.refresh_data(),
+        }
+    }
+
+    /// Up to this many matches are listed under the search field.
+    const PALETTE_ROWS: usize = 10;
+
+    fn render_command_palette(&mut self, ctx: &egui::Context) {
+        let commands = self.palette_commands();
+        let matches = palette::filter(commands.iter().map(|(label, _)| label.as_str()), &self.palette_query);
+        let shown = matches.len().min(Self::PALETTE_ROWS);
+
+        let (up, down, enter, escape) = ctx.input_mut(|i| {
+            (
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+                i.consume_key(egui::Modifiers::NONE, egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.show_command_palette = false;
+            return;
+        }
+        if down && self.palette_selected + 1 < shown {
+            self.palette_selected += 1;
+        }
+        if up {
+            self.palette_selected = self.palette_selected.saturating_sub(1);
+        }
+        self.palette_selected = self.palette_selected.min(shown.saturating_sub(1));
+
+        let mut chosen = enter.then(|| matches.get(self.palette_selected).copied()).flatten();
+        egui::Window::new("Command Palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .show(ctx, |ui| {
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut self.palette_query)
+                        .hint_text("Type a command…")
+                        .desired_width(f32::INFINITY),
+                );
+                search.request_focus();
+                if search.changed() {
+                    self.palette_selected = 0;
+                }
+                ui.separator();
+                if matches.is_empty() {
+                    ui.label(egui::RichText::new("No matching commands").color(egui::Color32::GRAY));
+                }
+                for (row, index) in matches.iter().take(shown).enumerate() {
+                    if ui.selectable_label(row == self.palette_selected, &commands[*index].0).clicked() {
+                        chosen = Some(*index);
+                    }
+                }
+                if matches.len() > shown {
+                    ui.label(egui::RichText::new(format!("{} more…", matches.len() - shown)).small().color(egui::Color32::GRAY));
+                }
+            });
+
+        if let Some(index) = chosen {
+            self.show_command_palette = false;
+            self.run_palette_action(commands[index].1.clone());
+        }
+    }
+
+    fn render_mini_window(&mut self, ctx: &egui::Context) {
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("mini_window"),
+            egui::ViewportBuilder::default()
+                .with_title("MSI Center Mini")
+                .with_inner_size([240.0, 150.0])
+                .with_resizable(false)
+                .with_always_on_top(),
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    if let Some(ref info) = self.fan_info {
+                        egui::Grid::new("mini_grid").num_columns(3).spacing([12.0, 4.0]).show(ui, |ui| {
+                            ui.label("");
+                            ui.label(egui::RichText::new("Temp").strong());
+                            ui.label(egui::RichText::new("Fan").strong());
+                            ui.end_row();
+
+                            for (label, temp, rpm) in [("CPU", info.cpu_temp, info.cpu_fan_rpm), ("GPU", info.gpu_temp, info.gpu_fan_rpm)] {
+                                ui.label(label);
+                                ui.label(egui::RichText::new(format!("{}°C", temp)).color(self.temp_color(temp)).strong());
+                                ui.label(format!("{} RPM", rpm));
+                                ui.end_row();
+                            }
+                            // A third fan has no sensor of its own.
+                            for fan in info.fans.iter().skip(2) {
+                                ui.label(fan.name);
+                                ui.label("");
+                                ui.label(format!("{} RPM", fan.rpm));
+                                ui.end_row();
+                            }
+                        });
+                    } else {
+                        ui.label("No data available");
+                    }
+
+                    ui.add_space(4.0);
+                    ui.label(egui::RichText::new(self.current_scenario.to_string()).color(egui::Color32::LIGHT_BLUE).strong());
+
+                    if self.support.cooler_boost {
+                        let mut cb = self.cooler_boost;
+                        let label = if cb { "🔥 Cooler Boost ON" } else { "Cooler Boost OFF" };
+                        if ui.toggle_value(&mut cb, label).changed() {
+                            self.set_cooler_boost(cb);
+                        }
+                    }
+                });
+
+                if ctx.input(|i| i.viewport().close_requested()) {
+                    self.show_mini_window = false;
+                }
+            },
+        );
+    }
+
     fn render_side_panel(&mut self, ctx: &egui::Context) {
         egui::SidePanel::left("side_panel")
             .resizable(false)
@@ -269,15 +1199,7 @@ impl MsiCenterApp {
             .show(ctx, |ui| {
                 ui.add_space(20.0);
 
-                let tabs = [
-                    (Tab::Dashboard, "📊", "Dashboard"),
-                    (Tab::FanControl, "🌀", "Fan Control"),
-                    (Tab::Scenarios, "⚡", "Scenarios"),
-                    (Tab::Profiles, "👤", "Profiles"),
-                    (Tab::Settings, "⚙", "Settings"),
-                ];
-
-                for (tab, icon, label) in tabs {
+                for (tab, icon, label) in Tab::ALL {
                     let is_selected = self.current_tab == tab;
                     let text = format!("{} {}", icon, label);
 
@@ -314,6 +1236,7 @@ impl MsiCenterApp {
                     Tab::FanControl => self.render_fan_control(ui),
                     Tab::Scenarios => self.render_scenarios(ui),
                     Tab::Profiles => self.render_profiles(ui),
+                    Tab::Automation => self.render_automation(ui),
                     Tab::Settings => self.render_settings(ui),
                 }
             });
@@ -333,6 +1256,10 @@ impl MsiCenterApp {
                     self.render_temp_gauge(ui, "CPU", info.cpu_temp);
                     ui.add_space(10.0);
                     self.render_temp_gauge(ui, "GPU", info.gpu_temp);
+                    if let Some(hotspot) = info.gpu.and_then(|gpu| gpu.hotspot) {
+                        ui.add_space(10.0);
+                        self.render_temp_gauge(ui, "GPU Hotspot", hotspot);
+                    }
                 } else {
                     ui.label("No data available");
                 }
@@ -343,9 +1270,12 @@ impl MsiCenterApp {
                 ui.add_space(10.0);
 
                 if let Some(ref info) = self.fan_info {
-                    self.render_fan_gauge(ui, "CPU Fan", info.cpu_fan_rpm, info.cpu_fan_percent);
-                    ui.add_space(10.0);
-                    self.render_fan_gauge(ui, "GPU Fan", info.gpu_fan_rpm, info.gpu_fan_percent);
+                    for (index, fan) in info.fans.iter().enumerate() {
+                        if index > 0 {
+                            ui.add_space(10.0);
+                        }
+                        self.render_fan_gauge(ui, fan);
+                    }
                 } else {
                     ui.label("No data available");
                 }
@@ -415,13 +1345,38 @@ impl MsiCenterApp {
         });
     }
 
+    /// High contrast mode also swaps the theme for black panels and white text.
+    fn apply_visuals(&mut self, ctx: &egui::Context) {
+        let palette = self.config.color_palette;
+        if self.applied_palette == Some(palette) {
+            return;
+        }
+        let base = self.base_visuals.get_or_insert_with(|| ctx.style().visuals.clone());
+        let mut visuals = base.clone();
+        if palette == ColorPalette::HighContrast {
+            visuals.override_text_color = Some(egui::Color32::WHITE);
+            visuals.panel_fill = egui::Color32::BLACK;
+            visuals.window_fill = egui::Color32::BLACK;
+            visuals.extreme_bg_color = egui::Color32::BLACK;
+            visuals.widgets.noninteractive.bg_stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+            visuals.widgets.inactive.bg_stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+            visuals.selection.stroke = egui::Stroke::new(2.0, egui::Color32::YELLOW);
+        }
+        ctx.set_visuals(visuals);
+        self.applied_palette = Some(palette);
+    }
+
+    fn temp_color(&self, temp: u8) -> egui::Color32 {
+        self.level_color(self.config.temp_thresholds.level(temp))
+    }
+
+    fn level_color(&self, level: TempLevel) -> egui::Color32 {
+        let [r, g, b] = self.config.color_palette.rgb(level);
+        egui::Color32::from_rgb(r, g, b)
+    }
+
     fn render_temp_gauge(&self, ui: &mut egui::Ui, label: &str, temp: u8) {
-        let color = match temp {
-            0..=50 => egui::Color32::GREEN,
-            51..=70 => egui::Color32::YELLOW,
-            71..=85 => egui::Color32::from_rgb(255, 165, 0),
-            _ => egui::Color32::RED,
-        };
+        let color = self.temp_color(temp);
 
         ui.horizontal(|ui| {
             ui.label(format!("{}: ", label));
@@ -435,14 +1390,25 @@ impl MsiCenterApp {
         ui.add(progress_bar);
     }
 
-    fn render_fan_gauge(&self, ui: &mut egui::Ui, label: &str, rpm: u32, percent: u8) {
+    fn render_fan_gauge(&self, ui: &mut egui::Ui, fan: &FanReading) {
         ui.horizontal(|ui| {
-            ui.label(format!("{}: ", label));
-            ui.label(egui::RichText::new(format!("{} RPM", rpm)).size(18.0).strong());
-            ui.label(format!("({}%)", percent));
+            ui.label(format!("{} Fan: ", fan.name));
+            ui.label(egui::RichText::new(format!("{} RPM", fan.rpm)).size(18.0).strong());
+            match fan.target_percent {
+                Some(target) if fan.off_target() => {
+                    ui.label(egui::RichText::new(format!("({}%, target {}%)", fan.percent, target)).color(egui::Color32::YELLOW))
+                        .on_hover_text("The EC is not running the fan at the duty its fan table asks for; the firmware or another tool may be overriding it");
+                }
+                Some(target) => {
+                    ui.label(format!("({}%, target {}%)", fan.percent, target));
+                }
+                None => {
+                    ui.label(format!("({}%)", fan.percent));
+                }
+            }
         });
 
-        let progress = percent as f32 / 100.0;
+        let progress = fan.percent as f32 / 100.0;
         let progress_bar = egui::ProgressBar::new(progress)
             .fill(egui::Color32::from_rgb(100, 150, 255))
             .show_percentage();
@@ -453,24 +1419,27 @@ impl MsiCenterApp {
         ui.heading("Fan Control");
         ui.add_space(20.0);
 
+        let unsupported = "Not supported on this model or EC backend";
+
         ui.group(|ui| {
             ui.heading("Fan Mode");
             ui.add_space(10.0);
 
-            ui.horizontal(|ui| {
-                if ui.button("🔄 Auto").clicked() {
-                    self.set_fan_mode(FanMode::Auto);
-                }
-                if ui.button("🔇 Silent").clicked() {
-                    self.set_fan_mode(FanMode::Silent);
-                }
-                if ui.button("📊 Basic").clicked() {
-                    self.set_fan_mode(FanMode::Basic);
-                }
-                if ui.button("⚙ Advanced").clicked() {
-                    self.set_fan_mode(FanMode::Advanced);
+            ui.add_enabled_ui(self.support.fan_mode, |ui| ui.horizontal(|ui| {
+                let modes = [
+                    (FanMode::Auto, "🔄 Auto"),
+                    (FanMode::Silent, "🔇 Silent"),
+                    (FanMode::Basic, "📊 Basic"),
+                    (FanMode::Advanced, "⚙ Advanced"),
+                ];
+                for (mode, name) in modes {
+                    if self.available_fan_modes.contains(&mode) && ui.button(name).clicked() {
+                        self.set_fan_mode(mode);
+                    }
                 }
-            });
+            }))
+            .response
+            .on_disabled_hover_text(unsupported);
         });
 
         ui.add_space(20.0);
@@ -479,15 +1448,41 @@ impl MsiCenterApp {
             ui.heading("Cooler Boost");
             ui.add_space(10.0);
 
-            ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.support.cooler_boost, |ui| ui.horizontal(|ui| {
                 ui.label("Cooler Boost: ");
                 let mut cb = self.cooler_boost;
                 let label = if cb { "🔥 ON" } else { "OFF" };
                 if ui.toggle_value(&mut cb, label).changed() {
                     self.set_cooler_boost(cb);
                 }
-            });
+            }))
+            .response
+            .on_disabled_hover_text(unsupported);
             ui.label(egui::RichText::new("Maximum fan speed for cooling").small().color(egui::Color32::GRAY));
+
+            ui.add_space(5.0);
+            let active = self.config.active_profile.clone();
+            if let Some(profile) = self.config.profiles.iter_mut().find(|p| p.name == active) {
+                let mut changed = false;
+                ui.add_enabled_ui(self.support.cooler_boost, |ui| ui.horizontal(|ui| {
+                    let mut enabled = profile.emergency_cooling.is_some();
+                    if ui.checkbox(&mut enabled, "Emergency cooling").on_hover_text("Done by the daemon, for this profile").changed() {
+                        profile.emergency_cooling = enabled.then(EmergencyCooling::default);
+                        changed = true;
+                    }
+                    if let Some(ref mut rule) = profile.emergency_cooling {
+                        // Saved once a drag ends rather than on every frame of it.
+                        let settled = |response: egui::Response| response.drag_stopped() || (response.changed() && !response.dragged());
+                        ui.label("on above");
+                        changed |= settled(ui.add(egui::DragValue::new(&mut rule.on_above).range(50..=105).suffix("°C")));
+                        ui.label("off below");
+                        changed |= settled(ui.add(egui::DragValue::new(&mut rule.off_below).range(40..=rule.on_above).suffix("°C")));
+                    }
+                }));
+                if changed && let Err(e) = self.save_config() {
+                    self.error_message = Some(format!("Failed to save config: {}", e));
+                }
+            }
         });
 
         ui.add_space(20.0);
@@ -495,20 +1490,54 @@ impl MsiCenterApp {
         ui.group(|ui| {
             ui.heading("Manual Fan Speed");
             ui.add_space(10.0);
+            if !self.support.fan_curves {
+                ui.label(egui::RichText::new(format!("{}; only Reset to Auto is available", unsupported)).small().color(egui::Color32::GRAY));
+            }
 
-            ui.horizontal(|ui| {
-                ui.label("CPU Fan: ");
-                ui.add(egui::Slider::new(&mut self.cpu_fan_speed, 0.0..=100.0).suffix("%"));
-            });
+            ui.add_enabled_ui(self.support.fan_curves, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("CPU Fan: ");
+                    ui.add(egui::Slider::new(&mut self.cpu_fan_speed, 0.0..=100.0).suffix("%"));
+                });
 
-            ui.horizontal(|ui| {
-                ui.label("GPU Fan: ");
-                ui.add(egui::Slider::new(&mut self.gpu_fan_speed, 0.0..=100.0).suffix("%"));
+                if self.support.second_fan {
+                    ui.horizontal(|ui| {
+                        ui.label("GPU Fan: ");
+                        ui.add(egui::Slider::new(&mut self.gpu_fan_speed, 0.0..=100.0).suffix("%"));
+                    });
+                }
+
+                if self.support.third_fan {
+                    ui.horizontal(|ui| {
+                        ui.label("System Fan: ");
+                        ui.add(egui::Slider::new(&mut self.system_fan_speed, 0.0..=100.0).suffix("%"));
+                    });
+                }
             });
 
+            ui.checkbox(&mut self.remember_manual_speed, "Remember in active profile")
+                .on_hover_text("Restore these speeds when the profile is applied, e.g. after reboot");
+
+            let active = self.config.active_profile.clone();
+            if let Some(profile) = self.config.profiles.iter_mut().find(|p| p.name == active) {
+                let mut percent = profile.settings.min_fan_percent.unwrap_or(0);
+                let response = ui.horizontal(|ui| {
+                    ui.label("Profile minimum:");
+                    ui.add(egui::DragValue::new(&mut percent).range(0..=100).suffix("%"))
+                        .on_hover_text("Curves and manual speeds of this profile are raised to it; 0 for none")
+                }).inner;
+                profile.settings.min_fan_percent = (percent > 0).then_some(percent);
+                // Saved once a drag ends rather than on every frame of it.
+                if (response.drag_stopped() || (response.changed() && !response.dragged()))
+                    && let Err(e) = self.save_config()
+                {
+                    self.error_message = Some(format!("Failed to save config: {}", e));
+                }
+            }
+
             ui.add_space(10.0);
             ui.horizontal(|ui| {
-                if ui.button("✓ Apply Manual Speed").clicked() {
+                if ui.add_enabled(self.support.fan_curves, egui::Button::new("✓ Apply Manual Speed")).clicked() {
                     self.apply_manual_fan_speed();
                 }
                 if ui.button("🔄 Reset to Auto").clicked() {
@@ -520,36 +1549,117 @@ impl MsiCenterApp {
         ui.add_space(20.0);
 
         ui.group(|ui| {
-            ui.heading("Fan Curves");
+            ui.horizontal(|ui| {
+                ui.heading("Fan Curves");
+                self.render_undo_buttons(ui);
+            });
             ui.add_space(10.0);
+            if !self.support.fan_curves {
+                ui.label(egui::RichText::new(format!("{}; curves can still be edited and saved in profiles", unsupported)).small().color(egui::Color32::GRAY));
+            }
+
+            self.render_curve_presets(ui);
+            ui.add_space(5.0);
 
             ui.label("CPU Fan Curve:");
             self.render_fan_curve_editor(ui, true);
 
-            ui.add_space(10.0);
+            if self.support.second_fan {
+                ui.add_space(10.0);
+
+                ui.label("GPU Fan Curve:");
+                self.render_fan_curve_editor(ui, false);
+            }
+        });
+    }
 
-            ui.label("GPU Fan Curve:");
-            self.render_fan_curve_editor(ui, false);
+    /// Quick switching between the active profile's named curves, e.g. for summer and winter.
+    fn render_curve_presets(&mut self, ui: &mut egui::Ui) {
+        let Some(profile) = self.config.get_active_profile() else {
+            return;
+        };
+        let names: Vec<String> = profile.curves.keys().cloned().collect();
+        let active = profile.active_curves.clone();
+        let mut chosen = None;
+        let mut removed = false;
+
+        ui.horizontal(|ui| {
+            ui.label("Presets:");
+            egui::ComboBox::from_id_salt("curve_presets")
+                .selected_text(active.as_deref().unwrap_or("(none)"))
+                .show_ui(ui, |ui| {
+                    for name in &names {
+                        if ui.selectable_label(active.as_ref() == Some(name), name).clicked() {
+                            chosen = Some(name.clone());
+                        }
+                    }
+                });
+            if active.is_some() && ui.button("Delete").on_hover_text("Remove this preset from the profile").clicked() {
+                removed = true;
+            }
+            ui.separator();
+            ui.add(egui::TextEdit::singleline(&mut self.new_curve_preset_name).hint_text("Summer, Dock, ...").desired_width(120.0));
+            let valid = !self.new_curve_preset_name.trim().is_empty();
+            if ui.add_enabled(valid, egui::Button::new("Save As")).on_hover_text("Store the curves below in the active profile").clicked() {
+                self.save_curve_preset();
+            }
         });
+
+        if let Some(name) = chosen {
+            self.use_curve_preset(&name);
+        } else if removed && let Some(name) = active {
+            let profile_name = self.config.active_profile.clone();
+            if let Some(profile) = self.config.profiles.iter_mut().find(|p| p.name == profile_name) {
+                profile.remove_curves(&name);
+            }
+            match self.save_config() {
+                Ok(()) => self.success_message = Some(format!("Curves '{}' removed", name)),
+                Err(e) => self.error_message = Some(format!("Failed to save config: {}", e)),
+            }
+        }
     }
 
     fn render_fan_curve_editor(&mut self, ui: &mut egui::Ui, is_cpu: bool) {
-        let curve = if is_cpu { &mut self.cpu_curve } else { &mut self.gpu_curve };
+        let (offset_registers, min_duty_register) = ec::quirks::capabilities().fan_tuning.for_fan(if is_cpu { 0 } else { 1 });
+        let (curve, tuning, damping, source) = if is_cpu {
+            (&mut self.cpu_curve, &mut self.cpu_tuning, &mut self.cpu_damping, &mut self.cpu_source)
+        } else {
+            (&mut self.gpu_curve, &mut self.gpu_tuning, &mut self.gpu_damping, &mut self.gpu_source)
+        };
 
+        let max_points = ec::quirks::capabilities().curve_points as usize;
         ui.horizontal(|ui| {
             if ui.button("Silent").clicked() {
-                *curve = vec![[50.0, 0.0], [60.0, 20.0], [70.0, 40.0], [80.0, 60.0], [90.0, 80.0], [95.0, 100.0]];
+                *curve = fitted_editor_points(&FanCurve::silent(), max_points);
             }
             if ui.button("Balanced").clicked() {
-                *curve = vec![[40.0, 0.0], [50.0, 30.0], [60.0, 50.0], [70.0, 70.0], [80.0, 90.0], [90.0, 100.0]];
+                *curve = fitted_editor_points(&FanCurve::default(), max_points);
             }
             if ui.button("Performance").clicked() {
-                *curve = vec![[35.0, 30.0], [45.0, 50.0], [55.0, 70.0], [65.0, 85.0], [75.0, 100.0], [85.0, 100.0]];
+                *curve = fitted_editor_points(&FanCurve::performance(), max_points);
+            }
+            if !self.config.fan_curves.is_empty() {
+                egui::ComboBox::from_id_salt(if is_cpu { "cpu_saved_curves" } else { "gpu_saved_curves" })
+                    .selected_text("Saved")
+                    .show_ui(ui, |ui| {
+                        for (name, saved) in &self.config.fan_curves {
+                            if ui.selectable_label(false, name).clicked() {
+                                *curve = fitted_editor_points(saved, max_points);
+                                *tuning = saved.tuning.clone();
+                                *damping = saved.damping;
+                                *source = saved.source.clone();
+                            }
+                        }
+                    });
             }
         });
+        // A curve made for a model with longer fan tables keeps the points that matter most.
+        if curve.len() > max_points {
+            *curve = fitted_editor_points(&editor_curve(curve, tuning, *damping, source), max_points);
+        }
 
         egui::Grid::new(if is_cpu { "cpu_curve_grid" } else { "gpu_curve_grid" })
-            .num_columns(7)
+            .num_columns(max_points + 1)
             .spacing([10.0, 4.0])
             .show(ui, |ui| {
                 ui.label("Point");
@@ -569,9 +1679,94 @@ impl MsiCenterApp {
                     ui.add(egui::DragValue::new(&mut point[1]).range(0.0..=100.0).speed(1.0));
                 }
                 ui.end_row();
+
+                // Only on firmware that adds a per-point boost, as MSI Center's advanced mode does.
+                if offset_registers.is_some() {
+                    let mut offsets = tuning.offsets.clone();
+                    offsets.resize(curve.len(), 0);
+                    ui.label("Offset %").on_hover_text("Duty the firmware adds to each point");
+                    let mut changed = false;
+                    for offset in offsets.iter_mut() {
+                        changed |= ui.add(egui::DragValue::new(offset).range(-fan::MAX_DUTY_OFFSET..=fan::MAX_DUTY_OFFSET).speed(1.0)).changed();
+                    }
+                    if changed {
+                        // Stored only while some point has an offset.
+                        tuning.offsets = if offsets.iter().all(|offset| *offset == 0) { Vec::new() } else { offsets };
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.horizontal(|ui| {
+            if ui.add_enabled(curve.len() < max_points, egui::Button::new("+ Point")).on_hover_text("Add a point 5°C above the hottest").clicked()
+                && let Some(&[temp, speed]) = curve.last()
+            {
+                curve.push([(temp + 5.0).min(100.0), speed]);
+            }
+            if ui.add_enabled(curve.len() > 2, egui::Button::new("− Point")).on_hover_text("Remove the hottest point").clicked() {
+                curve.pop();
+                tuning.offsets.truncate(curve.len());
+            }
+            ui.label(egui::RichText::new(format!("{} of {} points this model's fan table holds", curve.len(), max_points)).small().color(egui::Color32::GRAY));
+        });
+
+        if min_duty_register.is_some() {
+            ui.horizontal(|ui| {
+                let mut min_duty = tuning.min_duty.unwrap_or(0);
+                ui.label("Minimum duty:");
+                if ui.add(egui::Slider::new(&mut min_duty, 0..=100).suffix("%")).on_hover_text("The firmware never runs the fan slower while following the curve").changed() {
+                    tuning.min_duty = (min_duty > 0).then_some(min_duty);
+                }
+            });
+        }
+
+        // Only the software control loop damps; the firmware follows the points directly.
+        if self.config.software_fan_control.enabled {
+            ui.horizontal(|ui| {
+                ui.label("Hysteresis:");
+                ui.add(egui::Slider::new(&mut damping.hysteresis, 0..=10).suffix("°C"))
+                    .on_hover_text("Degrees the temperature must fall before the fan slows down");
+                ui.label("Max ramp:");
+                ui.add(egui::Slider::new(&mut damping.max_ramp, 0..=20).suffix("%/s"))
+                    .on_hover_text("Largest duty change per second; 0 for no limit");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Temperature:");
+                let mut sources = vec![
+                    (TempSource::Fan, if is_cpu { "CPU (default)" } else { "GPU (default)" }),
+                    (TempSource::CpuPackage, "CPU package"),
+                    (TempSource::CpuHottestCore, "Hottest CPU core"),
+                    (TempSource::GpuEdge, "GPU edge"),
+                    (TempSource::GpuHotspot, "GPU hotspot"),
+                    (TempSource::Nvme, "NVMe"),
+                ];
+                // A hwmon path from the config can be kept, but is only set there.
+                if let TempSource::Hwmon(_) = source {
+                    sources.push((source.clone(), "hwmon file"));
+                }
+                let selected = sources.iter().find(|(s, _)| s == source).map_or("", |(_, label)| *label);
+                egui::ComboBox::from_id_salt(if is_cpu { "cpu_temp_source" } else { "gpu_temp_source" })
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        for (option, label) in sources {
+                            let checked = option == *source;
+                            if ui.selectable_label(checked, label).clicked() {
+                                *source = option;
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Sensor the curve is looked up against; the firmware always uses its own");
             });
+        }
+
+        let problem = editor_curve(curve, tuning, *damping, source).validate(max_points, &self.config.speed_guard).err();
+        if let Some(ref problem) = problem {
+            ui.colored_label(egui::Color32::from_rgb(255, 100, 100), format!("⚠ Can't apply: {}", problem));
+        }
 
-        if ui.button(format!("Apply {} Curve", if is_cpu { "CPU" } else { "GPU" })).clicked() {
+        let apply = egui::Button::new(format!("Apply {} Curve", if is_cpu { "CPU" } else { "GPU" }));
+        if ui.add_enabled(self.support.fan_curves && problem.is_none(), apply).clicked() {
             self.apply_fan_curve(is_cpu);
         }
     }
@@ -614,8 +1809,11 @@ impl MsiCenterApp {
         ui.group(|ui| {
             ui.heading("Shift Mode");
             ui.add_space(10.0);
+            if !self.support.shift_mode {
+                ui.label(egui::RichText::new("Not supported on this model or EC backend").small().color(egui::Color32::GRAY));
+            }
 
-            ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.support.shift_mode, |ui| ui.horizontal(|ui| {
                 let modes = [
                     (ShiftMode::EcoSilent, "Eco"),
                     (ShiftMode::Comfort, "Comfort"),
@@ -624,26 +1822,80 @@ impl MsiCenterApp {
                 ];
 
                 for (mode, name) in modes {
+                    if !self.available_shift_modes.contains(&mode) {
+                        continue;
+                    }
                     let is_selected = self.current_shift_mode == mode;
                     if ui.selectable_label(is_selected, name).clicked() {
-                        if let Ok(mut ec) = EmbeddedController::new() {
-                            if let Ok(ec2) = EmbeddedController::new() {
-                                let mut fan_controller = FanController::new(ec2);
-                                let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
-                                if manager.set_shift_mode(mode).is_ok() {
-                                    self.current_shift_mode = mode;
-                                    self.success_message = Some(format!("Shift mode set to {}", mode));
-                                }
-                            }
-                        }
+                        self.set_shift_mode(mode);
+                    }
+                }
+            }));
+        });
+
+        if !self.super_battery_features.is_empty() {
+            ui.add_space(20.0);
+            ui.group(|ui| {
+                ui.heading("Super Battery Features");
+                ui.label(egui::RichText::new("The Super Battery scenario switches all of these; toggle them one by one here.").small().color(egui::Color32::GRAY));
+                ui.add_space(10.0);
+
+                for (feature, enabled) in self.super_battery_features.clone() {
+                    let mut checked = enabled;
+                    if ui.checkbox(&mut checked, feature.label()).changed() {
+                        self.set_super_battery_feature(feature, checked);
                     }
                 }
             });
+        }
+
+        if let Some(percent) = self.lid_logo {
+            ui.add_space(20.0);
+            ui.group(|ui| {
+                ui.heading("Lid Logo");
+                ui.label(egui::RichText::new("Profiles can set it too: Super Battery turns it off, Turbo lights it.").small().color(egui::Color32::GRAY));
+                ui.add_space(10.0);
+
+                ui.horizontal(|ui| {
+                    let mut on = percent > 0;
+                    if ui.checkbox(&mut on, "On").changed() {
+                        self.set_lid_logo(if on { 100 } else { 0 });
+                    }
+                    let mut brightness = percent;
+                    let slider = ui.add(egui::Slider::new(&mut brightness, 0..=100).suffix("%"));
+                    // Written once the drag ends rather than on every frame of it.
+                    if slider.dragged() {
+                        self.lid_logo = Some(brightness);
+                    } else if slider.drag_stopped() || slider.changed() {
+                        self.set_lid_logo(brightness);
+                    }
+                });
+            });
+        }
+    }
+
+    fn render_undo_buttons(&mut self, ui: &mut egui::Ui) {
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if ui.add_enabled(self.edit_history.can_redo(), egui::Button::new("↷ Redo"))
+                .on_hover_text("Ctrl+Shift+Z")
+                .clicked()
+            {
+                self.redo_edit();
+            }
+            if ui.add_enabled(self.edit_history.can_undo(), egui::Button::new("↶ Undo"))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                self.undo_edit();
+            }
         });
     }
 
     fn render_profiles(&mut self, ui: &mut egui::Ui) {
-        ui.heading("Profile Management");
+        ui.horizontal(|ui| {
+            ui.heading("Profile Management");
+            self.render_undo_buttons(ui);
+        });
         ui.add_space(20.0);
 
         ui.group(|ui| {
@@ -651,7 +1903,7 @@ impl MsiCenterApp {
             ui.add_space(10.0);
 
             let active_profile = self.config.active_profile.clone();
-            let profiles: Vec<_> = self.config.profiles.iter().cloned().collect();
+            let profiles: Vec<_> = self.config.profiles.to_vec();
 
             for profile in profiles {
                 let is_active = profile.name == active_profile;
@@ -667,26 +1919,12 @@ impl MsiCenterApp {
                     ui.label(format!("({})", profile.scenario));
 
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                        if !is_active {
-                            if ui.small_button("🗑").clicked() {
-                                self.config.remove_profile(&profile.name);
-                                let _ = self.config.save();
-                            }
+                        if !is_active && ui.small_button("🗑").clicked() {
+                            self.config.remove_profile(&profile.name);
+                            let _ = self.save_config();
                         }
                         if ui.small_button("Apply").clicked() {
-                            self.config.set_active_profile(&profile.name);
-                            let _ = self.config.save();
-                            
-                            if let Ok(mut ec) = EmbeddedController::new() {
-                                if let Ok(ec2) = EmbeddedController::new() {
-                                    let mut fan_controller = FanController::new(ec2);
-                                    let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
-                                    if manager.apply_settings(&profile.settings).is_ok() {
-                                        self.success_message = Some(format!("Applied profile: {}", profile.name));
-                                        self.refresh_data();
-                                    }
-                                }
-                            }
+                            self.apply_profile(&profile);
                         }
                     });
                 });
@@ -748,16 +1986,149 @@ impl MsiCenterApp {
                     name: self.new_profile_name.clone(),
                     scenario,
                     settings,
+                    alerts: AlertOverrides::default(),
+                    hooks: ProfileHooks::default(),
+                    ambient_light: None,
+                    emergency_cooling: None,
+                    curves: BTreeMap::new(),
+                    active_curves: None,
+                    cpu_curve: None,
+                    gpu_curve: None,
                 };
 
                 self.config.add_profile(profile);
-                let _ = self.config.save();
+                let _ = self.save_config();
                 self.success_message = Some(format!("Profile '{}' created", self.new_profile_name));
                 self.new_profile_name.clear();
             }
         });
     }
 
+    /// Every automation rule in one place: its profile, whether it is enabled, its
+    /// condition and when it last switched, with a button to try it out.
+    fn render_automation(&mut self, ui: &mut egui::Ui) {
+        if self.automation_rules.is_empty() {
+            self.refresh_automation_rules();
+        }
+        ui.horizontal(|ui| {
+            ui.heading("Automation");
+            self.render_undo_buttons(ui);
+        });
+        ui.add_space(20.0);
+
+        ui.group(|ui| {
+            ui.heading("Automatic Switching");
+            ui.label(egui::RichText::new("Applied by the msi-center daemon when the condition starts").small().color(egui::Color32::GRAY));
+            ui.add_space(10.0);
+
+            let names: Vec<String> = self.config.profiles.iter().map(|p| p.name.clone()).collect();
+            let mut changed = false;
+            let mut trigger = None;
+
+            ui.horizontal(|ui| {
+                let automation = &mut self.config.automation;
+                let mut paused = automation.is_paused();
+                if ui.checkbox(&mut paused, "Pause automatic switching").changed() {
+                    if paused {
+                        automation.pause(None);
+                    } else {
+                        automation.resume();
+                    }
+                    changed = true;
+                }
+                if paused {
+                    if ui.small_button("1 h").on_hover_text("Resume automatically in an hour").clicked() {
+                        automation.pause(Some(std::time::Duration::from_secs(3600)));
+                        changed = true;
+                    }
+                    if let Some(until) = automation.paused_until_display() {
+                        ui.label(egui::RichText::new(format!("until {}", until)).color(egui::Color32::GRAY));
+                    }
+                }
+            });
+            ui.add_space(5.0);
+
+            egui::Grid::new("automation_roles").num_columns(5).spacing([20.0, 8.0]).striped(true).show(ui, |ui| {
+                for header in ["Rule", "Profile", "Condition", "Last switched", ""] {
+                    ui.label(egui::RichText::new(header).strong());
+                }
+                ui.end_row();
+
+                for rule in &self.automation_rules {
+                    let automation = &mut self.config.automation;
+                    let mut enabled = automation.is_enabled(rule.role);
+                    if ui.checkbox(&mut enabled, rule.role.to_string()).on_hover_text("Disabled rules keep their profile").changed() {
+                        automation.set_enabled(rule.role, enabled);
+                        changed = true;
+                    }
+                    let selected = match rule.role {
+                        Role::OnAc => &mut automation.on_ac,
+                        Role::OnBattery => &mut automation.on_battery,
+                        Role::OnLowBattery => &mut automation.on_low_battery,
+                        Role::WhileGaming => &mut automation.while_gaming,
+                    };
+                    egui::ComboBox::from_id_salt(rule.role.to_string())
+                        .selected_text(selected.as_deref().unwrap_or("(none)"))
+                        .show_ui(ui, |ui| {
+                            changed |= ui.selectable_value(selected, None, "(none)").changed();
+                            for name in &names {
+                                changed |= ui.selectable_value(selected, Some(name.clone()), name).changed();
+                            }
+                        });
+                    let condition = egui::RichText::new(&rule.condition).small();
+                    if rule.matches {
+                        ui.label(condition.color(egui::Color32::LIGHT_GREEN)).on_hover_text("Holds right now");
+                    } else {
+                        ui.label(condition.color(egui::Color32::GRAY));
+                    }
+                    ui.label(egui::RichText::new(rule.last_triggered_display()).small());
+                    if ui
+                        .add_enabled(rule.profile.is_some(), egui::Button::new("▶ Trigger now").small())
+                        .on_hover_text("Apply the rule's profile now, as if its condition had just started")
+                        .clicked()
+                    {
+                        trigger = Some(rule.role);
+                    }
+                    ui.end_row();
+                }
+
+                let automation = &mut self.config.automation;
+                ui.label("Low battery below");
+                let slider = ui.add(egui::Slider::new(&mut automation.low_battery_threshold, 5..=50).suffix("%"));
+                // Save once the drag ends rather than on every frame of it.
+                changed |= slider.drag_stopped() || (slider.changed() && !slider.dragged());
+                ui.end_row();
+            });
+
+            if changed {
+                let _ = self.save_config();
+                self.refresh_automation_rules();
+            }
+            if let Some(role) = trigger {
+                self.trigger_automation_rule(role);
+            }
+        });
+    }
+
+    fn refresh_automation_rules(&mut self) {
+        let automation = &self.config.automation;
+        self.automation_rules = automation.list(PowerState::read(), automation::gaming_active(automation));
+    }
+
+    /// Applies `role`'s profile and notes it as the rule's last switch.
+    fn trigger_automation_rule(&mut self, role: Role) {
+        let Some(profile) = self.config.automation.profile(role).and_then(|name| self.config.get_profile(name)).cloned() else {
+            self.error_message = Some(format!("{}: the rule's profile doesn't exist", role));
+            return;
+        };
+        self.error_message = None;
+        self.apply_profile(&profile);
+        if self.error_message.is_none() {
+            automation::record_trigger(role);
+            self.refresh_automation_rules();
+        }
+    }
+
     fn render_settings(&mut self, ui: &mut egui::Ui) {
         ui.heading("Settings");
         ui.add_space(20.0);
@@ -769,25 +2140,143 @@ impl MsiCenterApp {
             ui.checkbox(&mut self.config.auto_start, "Start on boot");
             ui.checkbox(&mut self.config.apply_on_boot, "Apply profile on startup");
             ui.checkbox(&mut self.config.show_notifications, "Show notifications");
+            ui.checkbox(&mut self.config.log_to_file, "Write debug log file (takes effect on restart)");
 
-            ui.add_space(10.0);
-            if ui.button("💾 Save Settings").clicked() {
-                if self.config.save().is_ok() {
-                    self.success_message = Some("Settings saved".to_string());
+            ui.horizontal(|ui| {
+                let mut timeout = self.config.cooler_boost_timeout_minutes.is_some();
+                if ui.checkbox(&mut timeout, "Turn Cooler Boost off after").changed() {
+                    self.config.cooler_boost_timeout_minutes = timeout.then_some(15);
+                }
+                if let Some(ref mut minutes) = self.config.cooler_boost_timeout_minutes {
+                    ui.add(egui::DragValue::new(minutes).range(1..=240).suffix(" min"));
                 }
+                ui.label("(by the daemon)");
+            });
+
+            ui.add_space(5.0);
+            let profiles: Vec<String> = self.config.profiles.iter().map(|p| p.name.clone()).collect();
+            for (label, id, action) in [
+                ("When the GUI closes:", "exit_action_gui", &mut self.config.on_exit.gui),
+                ("When the daemon stops:", "exit_action_daemon", &mut self.config.on_exit.daemon),
+            ] {
+                ui.horizontal(|ui| {
+                    ui.label(label);
+                    egui::ComboBox::from_id_salt(id).selected_text(action.to_string()).show_ui(ui, |ui| {
+                        ui.selectable_value(action, ExitAction::Leave, ExitAction::Leave.to_string());
+                        ui.selectable_value(action, ExitAction::Auto, ExitAction::Auto.to_string());
+                        for name in &profiles {
+                            let choice = ExitAction::Profile(name.clone());
+                            let text = choice.to_string();
+                            ui.selectable_value(action, choice, text);
+                        }
+                    });
+                });
+            }
+
+            ui.add_space(10.0);
+            if ui.button("💾 Save Settings").clicked() && self.save_config().is_ok() {
+                self.success_message = Some("Settings saved".to_string());
             }
         });
 
+        if let Some(indicator) = self.indicator_led {
+            ui.add_space(20.0);
+            ui.group(|ui| {
+                ui.heading("Indicator LED");
+                ui.label(
+                    egui::RichText::new(format!("Stored in the active profile '{}'.", self.config.active_profile)).small().color(egui::Color32::GRAY),
+                );
+                ui.add_space(10.0);
+
+                if let Some(mut off) = indicator.off_on_battery
+                    && ui.checkbox(&mut off, "Off while on battery").changed()
+                {
+                    self.set_indicator_led(IndicatorSettings { off_on_battery: Some(off), ..indicator });
+                }
+                if let Some(mut percent) = indicator.blink_below {
+                    ui.horizontal(|ui| {
+                        ui.label("Blink below:");
+                        let slider = ui.add(egui::Slider::new(&mut percent, 0..=100).suffix("%")).on_hover_text("0 never blinks");
+                        // Written once the drag ends rather than on every frame of it.
+                        if slider.dragged() {
+                            self.indicator_led = Some(IndicatorSettings { blink_below: Some(percent), ..indicator });
+                        } else if slider.drag_stopped() || slider.changed() {
+                            self.set_indicator_led(IndicatorSettings { blink_below: Some(percent), ..indicator });
+                        }
+                    });
+                }
+            });
+        }
+
+        ui.add_space(20.0);
+
+        ui.group(|ui| {
+            ui.heading("Temperature Colors");
+            ui.add_space(10.0);
+
+            let thresholds = &mut self.config.temp_thresholds;
+            ui.horizontal(|ui| {
+                ui.label("Warm above:");
+                ui.add(egui::DragValue::new(&mut thresholds.warm).range(30..=108).suffix("°C"));
+                ui.label("Hot above:");
+                ui.add(egui::DragValue::new(&mut thresholds.hot).range(thresholds.warm + 1..=109).suffix("°C"));
+                ui.label("Critical above:");
+                ui.add(egui::DragValue::new(&mut thresholds.critical).range(thresholds.hot + 1..=110).suffix("°C"));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Palette:");
+                egui::ComboBox::from_id_salt("color_palette")
+                    .selected_text(self.config.color_palette.to_string())
+                    .show_ui(ui, |ui| {
+                        for palette in ColorPalette::ALL {
+                            ui.selectable_value(&mut self.config.color_palette, palette, palette.to_string());
+                        }
+                    });
+                for level in [TempLevel::Normal, TempLevel::Warm, TempLevel::Hot, TempLevel::Critical] {
+                    ui.label(egui::RichText::new("■").size(18.0).color(self.level_color(level)));
+                }
+            });
+            ui.label(egui::RichText::new("Saved with \"Save Settings\" above").small().color(egui::Color32::GRAY));
+        });
+
+        ui.add_space(20.0);
+
+        ui.group(|ui| {
+            ui.heading("Refresh Intervals");
+            ui.add_space(10.0);
+
+            let polling = &mut self.config.polling;
+            egui::Grid::new("polling_grid").num_columns(2).spacing([12.0, 6.0]).show(ui, |ui| {
+                for (label, value, max) in [
+                    ("Temperatures", &mut polling.temperatures_ms, 10_000),
+                    ("Fans and scenario", &mut polling.fans_ms, 30_000),
+                    ("Features", &mut polling.features_ms, 120_000),
+                ] {
+                    ui.label(label);
+                    ui.add(egui::Slider::new(value, 250..=max).logarithmic(true).suffix(" ms"));
+                    ui.end_row();
+                }
+            });
+            ui.label(egui::RichText::new("Saved with \"Save Settings\" above; the daemon picks them up too").small().color(egui::Color32::GRAY));
+        });
+
         ui.add_space(20.0);
 
         ui.group(|ui| {
-            ui.heading("Refresh Interval");
+            ui.heading("Expert Mode");
             ui.add_space(10.0);
 
-            let mut interval_secs = self.update_interval.as_secs() as f32;
-            if ui.add(egui::Slider::new(&mut interval_secs, 1.0..=10.0).suffix("s")).changed() {
-                self.update_interval = Duration::from_secs_f32(interval_secs);
+            if ui.checkbox(&mut self.expert_mode, "Allow writes to EC registers outside this model's safe list").changed() {
+                ec::allow_unsafe_writes(self.expert_mode);
+                log::info!("Expert mode {}", if self.expert_mode { "enabled" } else { "disabled" });
             }
+            if self.expert_mode {
+                ui.label(
+                    egui::RichText::new("⚠ A wrong write can leave the fans stuck until the next reboot")
+                        .color(egui::Color32::from_rgb(255, 180, 60)),
+                );
+            }
+            ui.label(egui::RichText::new("Only for this session; refused writes are logged").small().color(egui::Color32::GRAY));
         });
 
         ui.add_space(20.0);
@@ -826,9 +2315,79 @@ impl MsiCenterApp {
             if let Ok(product) = std::fs::read_to_string("/sys/class/dmi/id/product_name") {
                 ui.label(format!("Product: {}", product.trim()));
             }
+
+            if self.demo.is_none() && system::ec_sys_read_only() && system::kernel_lockdown().is_none() {
+                ui.add_space(10.0);
+                ui.label(egui::RichText::new("⚠ ec_sys is loaded read-only, so EC writes fall back to port access").color(egui::Color32::YELLOW));
+                ui.horizontal(|ui| {
+                    let button = ui
+                        .add_enabled(self.is_root, egui::Button::new("Enable debugfs writes"))
+                        .on_hover_text("Reloads ec_sys with write_support=1")
+                        .on_disabled_hover_text("Needs root; or run 'sudo msi-center ec enable-writes'");
+                    ui.checkbox(&mut self.persist_ec_sys_writes, "Load at every boot");
+                    if button.clicked() {
+                        match system::enable_ec_sys_writes(self.persist_ec_sys_writes) {
+                            Ok(()) => self.success_message = Some("ec_sys reloaded with write support".to_string()),
+                            Err(e) => self.error_message = Some(format!("Failed to reload ec_sys: {}", e)),
+                        }
+                    }
+                });
+            }
         });
     }
 
+    fn render_startup_check(&mut self, ctx: &egui::Context) {
+        if self.startup_mismatches.is_empty() {
+            return;
+        }
+        let profile = self.config.active_profile.clone();
+        let mut choice = None;
+        egui::Window::new("Hardware Differs From Profile")
+            .collapsible(false)
+            .resizable(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(format!("The hardware doesn't hold the settings of profile '{}':", profile));
+                ui.add_space(6.0);
+                for mismatch in &self.startup_mismatches {
+                    ui.label(format!("• {}", mismatch));
+                }
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Apply Profile").on_hover_text("Write the profile's settings again").clicked() {
+                        choice = Some(StartupCheck::Reapply);
+                    }
+                    if ui.button("Keep Hardware Settings").on_hover_text(format!("Show them as '{}'", config::CURRENT_PROFILE)).clicked() {
+                        choice = Some(StartupCheck::Adopt);
+                    }
+                    if ui.button("Ignore").clicked() {
+                        choice = Some(StartupCheck::Off);
+                    }
+                });
+                ui.label(egui::RichText::new("Set startup_check in the config to decide without asking").small().color(egui::Color32::GRAY));
+            });
+
+        let Some(choice) = choice else {
+            return;
+        };
+        self.startup_mismatches.clear();
+        let hardware = self.startup_hardware.take();
+        match choice {
+            StartupCheck::Reapply => {
+                if let Some(profile) = self.config.get_active_profile().cloned() {
+                    self.apply_profile(&profile);
+                }
+            }
+            StartupCheck::Adopt => {
+                if let Some(settings) = hardware {
+                    self.adopt_hardware_settings(settings);
+                    self.refresh_data();
+                }
+            }
+            StartupCheck::Prompt | StartupCheck::Off => {}
+        }
+    }
+
     fn render_notifications(&mut self, ctx: &egui::Context) {
         if let Some(ref msg) = self.success_message.clone() {
             egui::TopBottomPanel::bottom("success_notification").show(ctx, |ui| {
@@ -848,6 +2407,35 @@ impl MsiCenterApp {
             }
         }
 
+        if self.show_ec_failures {
+            let mut open = true;
+            egui::Window::new("EC Write Failures")
+                .open(&mut open)
+                .collapsible(false)
+                .default_width(460.0)
+                .show(ctx, |ui| {
+                    ui.label("These writes failed or read back a different value, so the hardware may not be in the state shown:");
+                    ui.add_space(6.0);
+                    egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                        for failure in self.ec_write_failures.iter().rev() {
+                            ui.label(egui::RichText::new(failure.to_string()).monospace());
+                        }
+                    });
+                    ui.add_space(6.0);
+                    ui.horizontal(|ui| {
+                        if ui.button("Dismiss").clicked() {
+                            failures::clear();
+                            self.ec_write_failures.clear();
+                            self.show_ec_failures = false;
+                        }
+                        ui.label(egui::RichText::new("`msi-center -vv` shows every EC access").small().color(egui::Color32::GRAY));
+                    });
+                });
+            if !open {
+                self.show_ec_failures = false;
+            }
+        }
+
         if let Some(ref msg) = self.error_message.clone() {
             egui::Window::new("Error")
                 .collapsible(false)
@@ -0,0 +1,90 @@
+//! Undo/redo for the GUI's editable state. The GUI snapshots that state around
+//! each frame; a change becomes one undo step, and a drag across many frames is
+//! collapsed into a single step once the pointer is released.
+
+/// Undo steps kept; older ones are dropped.
+const MAX_STEPS: usize = 100;
+
+#[derive(Debug)]
+pub struct History<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+    /// State before an edit that is still in progress (e.g. a drag).
+    pending: Option<T>,
+}
+
+impl<T> Default for History<T> {
+    fn default() -> Self {
+        Self { undo: Vec::new(), redo: Vec::new(), pending: None }
+    }
+}
+
+impl<T: Clone + PartialEq> History<T> {
+    /// Records the change from `before` to `after` made during one frame.
+    /// While `editing` (the pointer is held), changes accumulate into one step.
+    pub fn track(&mut self, before: T, after: &T, editing: bool) {
+        if before != *after && self.pending.is_none() {
+            self.pending = Some(before);
+        }
+        if editing {
+            return;
+        }
+        if let Some(start) = self.pending.take()
+            && start != *after
+        {
+            self.undo.push(start);
+            if self.undo.len() > MAX_STEPS {
+                self.undo.remove(0);
+            }
+            self.redo.clear();
+        }
+    }
+
+    /// The state to go back to, given the current one.
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        self.pending = None;
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        self.pending = None;
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drags_become_one_step() {
+        let mut history = History::default();
+        // A drag from 40 to 43 over three frames, then released.
+        history.track(40, &41, true);
+        history.track(41, &42, true);
+        history.track(42, &43, true);
+        history.track(43, &43, false);
+        // A click that changes the value in a single frame.
+        history.track(43, &50, false);
+
+        assert_eq!(history.undo(50), Some(43));
+        assert_eq!(history.undo(43), Some(40));
+        assert_eq!(history.undo(40), None);
+        assert_eq!(history.redo(40), Some(43));
+
+        history.track(43, &60, false);
+        assert!(!history.can_redo());
+    }
+}
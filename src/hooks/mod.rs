@@ -0,0 +1,107 @@
+use crate::config::Profile;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum HookError {
+    #[error("Failed to run {0} hook '{1}': {2}")]
+    SpawnFailed(HookEvent, String, std::io::Error),
+    #[error("{0} hook '{1}' failed with {2}")]
+    CommandFailed(HookEvent, String, std::process::ExitStatus),
+}
+
+pub type Result<T> = std::result::Result<T, HookError>;
+
+/// Shell commands run around switching to or away from a profile.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileHooks {
+    /// Run before the profile is applied; a failing command aborts the switch.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_apply: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_apply: Vec<String>,
+    /// Run when another profile replaces this one.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_revert: Vec<String>,
+}
+
+impl ProfileHooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre_apply.is_empty() && self.post_apply.is_empty() && self.on_revert.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    PreApply,
+    PostApply,
+    OnRevert,
+}
+
+impl std::fmt::Display for HookEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookEvent::PreApply => write!(f, "pre_apply"),
+            HookEvent::PostApply => write!(f, "post_apply"),
+            HookEvent::OnRevert => write!(f, "on_revert"),
+        }
+    }
+}
+
+/// Runs the hook commands through `sh -c`, describing the switch in `MSI_CENTER_*`
+/// environment variables. `owner` is the profile the hooks belong to.
+fn run(event: HookEvent, owner: &Profile, from: Option<&Profile>, to: &Profile) -> Result<()> {
+    let commands = match event {
+        HookEvent::PreApply => &owner.hooks.pre_apply,
+        HookEvent::PostApply => &owner.hooks.post_apply,
+        HookEvent::OnRevert => &owner.hooks.on_revert,
+    };
+
+    for command in commands {
+        log::info!("Running {} hook of profile {}: {}", event, owner.name, command);
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .env("MSI_CENTER_HOOK", event.to_string())
+            .env("MSI_CENTER_PROFILE", &owner.name)
+            .env("MSI_CENTER_FROM_PROFILE", from.map(|p| p.name.as_str()).unwrap_or(""))
+            .env("MSI_CENTER_TO_PROFILE", &to.name)
+            .env("MSI_CENTER_SCENARIO", to.scenario.to_string())
+            .env("MSI_CENTER_SHIFT_MODE", to.settings.shift_mode.to_string())
+            .env("MSI_CENTER_FAN_MODE", format!("{:?}", to.settings.fan_mode))
+            .env("MSI_CENTER_COOLER_BOOST", if to.settings.cooler_boost { "1" } else { "0" })
+            .status()
+            .map_err(|e| HookError::SpawnFailed(event, command.clone(), e))?;
+
+        if !status.success() {
+            return Err(HookError::CommandFailed(event, command.clone(), status));
+        }
+    }
+    Ok(())
+}
+
+/// Applies `profile` through `apply`, surrounded by its `pre_apply`/`post_apply` hooks
+/// and the `on_revert` hooks of `previous` when switching away from another profile.
+/// Only `pre_apply` failures abort; later hook failures are logged.
+pub fn with_profile_hooks<T, E: From<HookError>>(
+    profile: &Profile,
+    previous: Option<&Profile>,
+    apply: impl FnOnce() -> std::result::Result<T, E>,
+) -> std::result::Result<T, E> {
+    let previous = previous.filter(|p| p.name != profile.name);
+
+    run(HookEvent::PreApply, profile, previous, profile)?;
+    let result = apply()?;
+
+    if let Some(previous) = previous
+        && let Err(e) = run(HookEvent::OnRevert, previous, Some(previous), profile)
+    {
+        log::warn!("{}", e);
+    }
+    if let Err(e) = run(HookEvent::PostApply, profile, previous, profile) {
+        log::warn!("{}", e);
+    }
+
+    Ok(result)
+}
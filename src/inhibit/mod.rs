@@ -0,0 +1,97 @@
+//! Keeps the machine awake during long measurements. A logind inhibitor blocks
+//! suspend and idle actions system-wide, and the desktop's screensaver interface
+//! (GNOME, KDE and others, on Wayland and X11 alike) keeps the session from going
+//! idle. Both are released when the `IdleInhibitor` is dropped.
+
+use thiserror::Error;
+use zbus::blocking::Connection;
+use zbus::zvariant::OwnedFd;
+
+#[derive(Error, Debug)]
+pub enum InhibitError {
+    #[error("D-Bus error: {0}")]
+    DBusError(#[from] zbus::Error),
+    #[error("Neither logind nor the desktop accepted an idle inhibitor")]
+    Unavailable,
+}
+
+pub type Result<T> = std::result::Result<T, InhibitError>;
+
+const APP_NAME: &str = "MSI Center";
+
+/// Inhibitors held for the lifetime of this value.
+pub struct IdleInhibitor {
+    /// logind drops the inhibitor once the last copy of this descriptor is closed.
+    logind: Option<OwnedFd>,
+    /// The desktop drops the inhibitor when the connection closes, so it is kept open.
+    screensaver: Option<(Connection, u32)>,
+}
+
+impl IdleInhibitor {
+    /// Takes whichever inhibitors are available; fails only if none is.
+    pub fn acquire(why: &str) -> Result<Self> {
+        let logind = logind_inhibit(why)
+            .map_err(|e| log::debug!("logind idle inhibitor unavailable: {}", e))
+            .ok();
+        let screensaver = screensaver_inhibit(why)
+            .map_err(|e| log::debug!("Desktop idle inhibitor unavailable: {}", e))
+            .ok();
+        if logind.is_none() && screensaver.is_none() {
+            return Err(InhibitError::Unavailable);
+        }
+        Ok(Self { logind, screensaver })
+    }
+
+    /// Which inhibitors are held, for telling the user.
+    pub fn holders(&self) -> Vec<&'static str> {
+        let mut holders = Vec::new();
+        if self.logind.is_some() {
+            holders.push("logind");
+        }
+        if self.screensaver.is_some() {
+            holders.push("desktop");
+        }
+        holders
+    }
+}
+
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        if let Some((connection, cookie)) = self.screensaver.take()
+            && let Err(e) = connection.call_method(
+                Some("org.freedesktop.ScreenSaver"),
+                "/org/freedesktop/ScreenSaver",
+                Some("org.freedesktop.ScreenSaver"),
+                "UnInhibit",
+                &(cookie,),
+            )
+        {
+            log::debug!("Failed to release the desktop idle inhibitor: {}", e);
+        }
+    }
+}
+
+fn logind_inhibit(why: &str) -> Result<OwnedFd> {
+    let connection = Connection::system()?;
+    let reply = connection.call_method(
+        Some("org.freedesktop.login1"),
+        "/org/freedesktop/login1",
+        Some("org.freedesktop.login1.Manager"),
+        "Inhibit",
+        &("sleep:idle", APP_NAME, why, "block"),
+    )?;
+    Ok(reply.body().deserialize()?)
+}
+
+fn screensaver_inhibit(why: &str) -> Result<(Connection, u32)> {
+    let connection = Connection::session()?;
+    let reply = connection.call_method(
+        Some("org.freedesktop.ScreenSaver"),
+        "/org/freedesktop/ScreenSaver",
+        Some("org.freedesktop.ScreenSaver"),
+        "Inhibit",
+        &(APP_NAME, why),
+    )?;
+    let cookie = reply.body().deserialize()?;
+    Ok((connection, cookie))
+}
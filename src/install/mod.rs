@@ -0,0 +1,244 @@
+//! `install`: puts the binaries and the files around them (service, D-Bus and
+//! polkit policies, udev rule, desktop entries) where this system's conventions
+//! want them. The files are the ones shipped in `debian/`, with the binary paths
+//! rewritten for the prefix. Packagers stage them with `--destdir`.
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InstallError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0} not found; build it first (cargo build --release)")]
+    MissingBinary(PathBuf),
+}
+
+pub type Result<T> = std::result::Result<T, InstallError>;
+
+const SERVICE: &str = include_str!("../../debian/lib/systemd/system/msi-center.service");
+const DBUS_POLICY: &str = include_str!("../../debian/usr/share/dbus-1/system.d/com.msi_center_linux.conf");
+const POLKIT_POLICY: &str = include_str!("../../debian/usr/share/polkit-1/actions/com.msi-center-linux.policy");
+const DESKTOP_ENTRY: &str = include_str!("../../debian/usr/share/applications/msi-center-linux.desktop");
+const AUTOSTART_ENTRY: &str = include_str!("../../debian/etc/xdg/autostart/msi-center-notify.desktop");
+const ICON: &str = include_str!("../../debian/usr/share/icons/hicolor/scalable/apps/msi-center-linux.svg");
+/// The binary path the shipped files use.
+const PACKAGED_BIN_DIR: &str = "/usr/bin/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitSystem {
+    Systemd,
+    OpenRc,
+    /// Neither; the daemon has to be started some other way.
+    Other,
+}
+
+impl InitSystem {
+    pub fn detect() -> Self {
+        if Path::new("/run/systemd/system").exists() {
+            InitSystem::Systemd
+        } else if Path::new("/run/openrc").exists() || Path::new("/sbin/openrc-run").exists() {
+            InitSystem::OpenRc
+        } else {
+            InitSystem::Other
+        }
+    }
+}
+
+impl std::fmt::Display for InitSystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InitSystem::Systemd => write!(f, "systemd"),
+            InitSystem::OpenRc => write!(f, "openrc"),
+            InitSystem::Other => write!(f, "none"),
+        }
+    }
+}
+
+impl std::str::FromStr for InitSystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "systemd" => Ok(InitSystem::Systemd),
+            "openrc" => Ok(InitSystem::OpenRc),
+            "none" | "other" => Ok(InitSystem::Other),
+            _ => Err(format!("unknown init system '{}' (expected systemd, openrc or none)", s)),
+        }
+    }
+}
+
+/// Where each kind of file goes, as seen on the installed system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub prefix: PathBuf,
+    pub init: InitSystem,
+    pub service_dir: PathBuf,
+    pub udev_dir: PathBuf,
+    pub dbus_dir: PathBuf,
+    pub polkit_dir: PathBuf,
+}
+
+impl Layout {
+    /// The distribution's directories for a `/usr` install; for any other prefix
+    /// the ones under `/etc` that are read regardless of how the system was set up.
+    pub fn detect(prefix: &Path, init: InitSystem) -> Self {
+        Self::for_root(prefix, init, |path| Path::new(path).exists())
+    }
+
+    fn for_root(prefix: &Path, init: InitSystem, exists: impl Fn(&str) -> bool) -> Self {
+        let system = prefix == Path::new("/usr");
+        // Distributions without the /usr merge keep these under /lib.
+        let lib = |dir: &str| {
+            let usr = format!("/usr/lib/{}", dir);
+            if exists(&usr) || !exists(&format!("/lib/{}", dir)) { PathBuf::from(usr) } else { PathBuf::from(format!("/lib/{}", dir)) }
+        };
+        Self {
+            prefix: prefix.to_path_buf(),
+            init,
+            service_dir: match init {
+                InitSystem::Systemd if system => lib("systemd/system"),
+                InitSystem::Systemd => PathBuf::from("/etc/systemd/system"),
+                InitSystem::OpenRc | InitSystem::Other => PathBuf::from("/etc/init.d"),
+            },
+            udev_dir: if system { lib("udev/rules.d") } else { PathBuf::from("/etc/udev/rules.d") },
+            dbus_dir: PathBuf::from(if system { "/usr/share/dbus-1/system.d" } else { "/etc/dbus-1/system.d" }),
+            // polkit reads actions from this directory only.
+            polkit_dir: PathBuf::from("/usr/share/polkit-1/actions"),
+        }
+    }
+
+    pub fn bin_dir(&self) -> PathBuf {
+        self.prefix.join("bin")
+    }
+
+    /// The shipped file with its binary paths pointing into this prefix.
+    fn rewrite(&self, template: &str) -> String {
+        template.replace(PACKAGED_BIN_DIR, &format!("{}/", self.bin_dir().display()))
+    }
+
+    /// Every file to install, binaries first.
+    pub fn artifacts(&self, binaries: &[PathBuf]) -> Vec<Artifact> {
+        let mut artifacts: Vec<Artifact> = binaries
+            .iter()
+            .filter_map(|binary| {
+                let name = binary.file_name()?;
+                Some(Artifact { path: self.bin_dir().join(name), contents: Contents::Copy(binary.clone()), mode: 0o755 })
+            })
+            .collect();
+        let text = |path: PathBuf, contents: String, mode: u32| Artifact { path, contents: Contents::Text(contents), mode };
+        match self.init {
+            InitSystem::Systemd => artifacts.push(text(self.service_dir.join("msi-center.service"), self.rewrite(SERVICE), 0o644)),
+            InitSystem::OpenRc => artifacts.push(text(self.service_dir.join("msi-center"), self.openrc_script(), 0o755)),
+            InitSystem::Other => {}
+        }
+        artifacts.extend([
+            text(self.dbus_dir.join("com.msi_center_linux.conf"), DBUS_POLICY.to_string(), 0o644),
+            text(self.polkit_dir.join("com.msi-center-linux.policy"), self.rewrite(POLKIT_POLICY), 0o644),
+            text(self.udev_dir.join("70-msi-center.rules"), self.udev_rule(), 0o644),
+            text(self.prefix.join("share/applications/msi-center-linux.desktop"), self.rewrite(DESKTOP_ENTRY), 0o644),
+            text(PathBuf::from("/etc/xdg/autostart/msi-center-notify.desktop"), self.rewrite(AUTOSTART_ENTRY), 0o644),
+            text(self.prefix.join("share/icons/hicolor/scalable/apps/msi-center-linux.svg"), ICON.to_string(), 0o644),
+        ]);
+        artifacts
+    }
+
+    fn openrc_script(&self) -> String {
+        format!(
+            "#!/sbin/openrc-run\n\ndescription=\"MSI Center Linux daemon\"\ncommand=\"{}\"\ncommand_args=\"daemon run\"\ncommand_background=true\npidfile=\"/run/msi-center.pid\"\n\ndepend() {{\n\tneed dbus\n}}\n",
+            self.bin_dir().join("msi-center").display()
+        )
+    }
+
+    /// Applies the active profile once the msi-ec driver binds, which can happen
+    /// after the daemon started.
+    fn udev_rule(&self) -> String {
+        format!(
+            "# Installed by msi-center install\nACTION==\"add\", SUBSYSTEM==\"platform\", KERNEL==\"msi-ec\", RUN+=\"{} apply\"\n",
+            self.bin_dir().join("msi-center").display()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Contents {
+    Text(String),
+    Copy(PathBuf),
+}
+
+/// A file to install at `path` on the installed system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Artifact {
+    pub path: PathBuf,
+    pub contents: Contents,
+    pub mode: u32,
+}
+
+impl Artifact {
+    /// Where the file is written: `path` under `destdir`, if staging for a package.
+    pub fn target(&self, destdir: Option<&Path>) -> PathBuf {
+        match destdir {
+            Some(root) => root.join(self.path.strip_prefix("/").unwrap_or(&self.path)),
+            None => self.path.clone(),
+        }
+    }
+
+    pub fn write(&self, destdir: Option<&Path>) -> Result<()> {
+        let target = self.target(destdir);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        match self.contents {
+            Contents::Text(ref text) => fs::write(&target, text)?,
+            Contents::Copy(ref source) => {
+                if !source.exists() {
+                    return Err(InstallError::MissingBinary(source.clone()));
+                }
+                // Replaced rather than overwritten, so a running copy keeps working.
+                let staged = target.with_extension("new");
+                fs::copy(source, &staged)?;
+                fs::rename(&staged, &target)?;
+            }
+        }
+        fs::set_permissions(&target, fs::Permissions::from_mode(self.mode))?;
+        Ok(())
+    }
+}
+
+/// This binary and the GUI next to it, if it was built.
+pub fn binaries() -> Result<Vec<PathBuf>> {
+    let cli = std::env::current_exe()?;
+    let mut binaries = vec![cli.clone()];
+    let gui = cli.with_file_name("msi-center-gui");
+    if gui.exists() {
+        binaries.push(gui);
+    }
+    Ok(binaries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_follows_prefix_and_init_system() {
+        let merged = |path: &str| path.starts_with("/usr/lib/");
+        let usr = Layout::for_root(Path::new("/usr"), InitSystem::Systemd, merged);
+        assert_eq!(usr.service_dir, PathBuf::from("/usr/lib/systemd/system"));
+        assert_eq!(usr.udev_dir, PathBuf::from("/usr/lib/udev/rules.d"));
+        let split = Layout::for_root(Path::new("/usr"), InitSystem::Systemd, |path| path.starts_with("/lib/"));
+        assert_eq!(split.service_dir, PathBuf::from("/lib/systemd/system"));
+
+        let local = Layout::for_root(Path::new("/usr/local"), InitSystem::OpenRc, merged);
+        assert_eq!((local.service_dir.as_path(), local.dbus_dir.as_path()), (Path::new("/etc/init.d"), Path::new("/etc/dbus-1/system.d")));
+        let artifacts = local.artifacts(&[PathBuf::from("target/release/msi-center")]);
+        assert_eq!(artifacts[0].path, PathBuf::from("/usr/local/bin/msi-center"));
+        let Contents::Text(ref desktop) = artifacts.iter().find(|a| a.path.ends_with("msi-center-linux.desktop")).unwrap().contents else {
+            panic!("desktop entry is not text");
+        };
+        assert!(desktop.contains("Exec=pkexec /usr/local/bin/msi-center-gui"));
+        assert_eq!(artifacts[1].target(Some(Path::new("/tmp/pkg"))), PathBuf::from("/tmp/pkg/etc/init.d/msi-center"));
+    }
+}
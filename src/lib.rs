@@ -0,0 +1,31 @@
+//! Hardware access, configuration and control logic shared by the `msi-center` CLI
+//! and the `msi-center-gui` GUI.
+
+pub mod ambient;
+pub mod automation;
+pub mod config;
+pub mod daemon;
+pub mod demo;
+pub mod doctor;
+pub mod ec;
+pub mod fan;
+pub mod features;
+pub mod gpu;
+pub mod history;
+pub mod hooks;
+pub mod inhibit;
+pub mod install;
+pub mod logging;
+pub mod notify;
+pub mod overrides;
+pub mod palette;
+pub mod remote;
+pub mod scenario;
+pub mod script;
+pub mod sensors;
+pub mod session;
+pub mod stats;
+pub mod status;
+pub mod system;
+pub mod travel;
+pub mod tuned;
@@ -0,0 +1,97 @@
+use crate::config::AppConfig;
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const LOG_FILE_NAME: &str = "msi-center.log";
+const MAX_LOG_SIZE: u64 = 1024 * 1024;
+
+/// The file always records debug output, independently of the stderr verbosity,
+/// so a log can be requested from a user after the fact.
+const FILE_LEVEL: LevelFilter = LevelFilter::Debug;
+
+struct Logger {
+    stderr: env_logger::Logger,
+    file: Option<Mutex<File>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.stderr.enabled(metadata) || (self.file.is_some() && metadata.level() <= FILE_LEVEL)
+    }
+
+    fn log(&self, record: &Record) {
+        if self.stderr.matches(record) {
+            self.stderr.log(record);
+        }
+
+        if record.level() <= FILE_LEVEL
+            && let Some(ref file) = self.file
+            && let Ok(mut file) = file.lock() {
+            let _ = writeln!(
+                file,
+                "{} {:<5} {}: {}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {
+        self.stderr.flush();
+        if let Some(ref file) = self.file
+            && let Ok(mut file) = file.lock() {
+            let _ = file.flush();
+        }
+    }
+}
+
+pub fn log_file_path() -> Option<PathBuf> {
+    AppConfig::config_dir().ok().map(|dir| dir.join(LOG_FILE_NAME))
+}
+
+/// Opens the log file for appending, rotating it to `.1` once it grows past `MAX_LOG_SIZE`.
+fn open_log_file() -> Option<File> {
+    let path = log_file_path()?;
+
+    if let Ok(metadata) = fs::metadata(&path)
+        && metadata.len() > MAX_LOG_SIZE {
+        let _ = fs::rename(&path, path.with_extension("log.1"));
+    }
+
+    OpenOptions::new().create(true).append(true).open(&path).ok()
+}
+
+/// Sets up logging: `verbosity` raises the stderr level (`-v` info, `-vv` debug, `-vvv` trace),
+/// otherwise `RUST_LOG` applies as before.
+pub fn init(verbosity: u8, log_to_file: bool) {
+    let mut builder = env_logger::Builder::from_default_env();
+    match verbosity {
+        0 => {}
+        1 => {
+            builder.filter_level(LevelFilter::Info);
+        }
+        2 => {
+            builder.filter_level(LevelFilter::Debug);
+        }
+        _ => {
+            builder.filter_level(LevelFilter::Trace);
+        }
+    }
+    let stderr = builder.build();
+
+    let file = if log_to_file { open_log_file().map(Mutex::new) } else { None };
+
+    let mut max_level = stderr.filter();
+    if file.is_some() {
+        max_level = max_level.max(FILE_LEVEL);
+    }
+
+    if log::set_boxed_logger(Box::new(Logger { stderr, file })).is_ok() {
+        log::set_max_level(max_level);
+    }
+}
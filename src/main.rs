@@ -1,14 +1,25 @@
-mod config;
-mod ec;
-mod fan;
-mod scenario;
+use msi_center_linux::{ambient, automation, config, daemon, doctor, ec, fan, hooks, inhibit, install, logging, notify, overrides, remote, scenario, script, sensors, session, stats, status, system, travel, tuned};
 
 use clap::{Parser, Subcommand};
 use colored::Colorize;
-use config::{AppConfig, Profile};
+use config::{AlertOverrides, AppConfig, ColorPalette, Profile, TempLevel};
+use hooks::ProfileHooks;
+use inhibit::IdleInhibitor;
 use ec::EmbeddedController;
-use fan::{FanController, FanCurve, FanCurvePoint, FanMode};
-use scenario::{ScenarioManager, ShiftMode, UserScenario};
+use ec::snapshot::EcSnapshot;
+use ec::quirks::SupportLevel;
+use fan::emergency::EmergencyCooling;
+use fan::failure::FailureWatch;
+use fan::simulate::{self, SimulationSettings, TempRange};
+use fan::software::SoftwareCurve;
+use fan::source::TempSource;
+use fan::{CurveDamping, CurveError, CurveTuning, FanController, FanCurve, FanCurvePoint, FanError, FanInfo, FanMode, FanReading, ManualFanSpeed, OperatingPoint};
+use install::{InitSystem, Layout};
+use scenario::{IndicatorSettings, ScenarioManager, ScenarioSettings, ShiftMode, SuperBatteryFeature, UserScenario};
+use session::SessionStats;
+use status::StatusSnapshot;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::process;
 
 #[derive(Parser)]
@@ -17,14 +28,50 @@ use std::process;
 #[command(version = "0.1.0")]
 #[command(about = "MSI Center clone for Linux - Control laptop fans and user scenarios")]
 struct Cli {
+    /// Increase log verbosity (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Also write debug logs to the log file in the config directory
+    #[arg(long, global = true)]
+    log_file: bool,
+
+    /// Record every EC access of this command to a trace file (for the replay tests)
+    #[arg(long, global = true, value_name = "FILE")]
+    record_ec_trace: Option<PathBuf>,
+
+    /// Run the command on another machine over SSH (ssh://user@laptop); repeat for several
+    #[arg(long, global = true, value_name = "HOST")]
+    host: Vec<remote::Host>,
+
+    /// Write EC registers outside this model's safe list; goes before the command
+    /// (msi-center --force ec restore ...)
+    #[arg(long)]
+    force: bool,
+
+    /// EC backend to use: dev-port, ioperm, debugfs or msi-ec; repeat (or separate
+    /// with commas) to give an order of preference. Overrides `ec_backends` in the config
+    #[arg(long, global = true, value_name = "BACKEND", value_delimiter = ',')]
+    backend: Vec<ec::Backend>,
+
     #[command(subcommand)]
     command: Commands,
 }
 
+// Parsed once per run; the size of the largest variant doesn't matter.
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Show current system status (fans, temps, scenario)
-    Status,
+    Status {
+        /// Print a single line using a named template from the config
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Print the full machine state as JSON
+        #[arg(long, conflicts_with = "template")]
+        json: bool,
+    },
 
     /// Fan control commands
     Fan {
@@ -49,12 +96,268 @@ enum Commands {
         /// Update interval in seconds
         #[arg(short, long, default_value = "1")]
         interval: u64,
+        /// Stop after this many seconds instead of at Ctrl+C
+        #[arg(short, long)]
+        duration: Option<u64>,
+        /// Keep the machine from going idle or suspending until the session ends
+        #[arg(long)]
+        inhibit_idle: bool,
     },
 
     /// Apply settings from active profile
     Apply,
+
+    /// Install the binaries with their service, D-Bus and polkit policies, udev rule and desktop files
+    Install {
+        /// Prefix for the binaries and desktop files; /usr also puts the service and udev rule in the distribution's directories
+        #[arg(long, default_value = "/usr/local")]
+        prefix: PathBuf,
+        /// Write the files under this directory instead of /, e.g. when building a package
+        #[arg(long)]
+        destdir: Option<PathBuf>,
+        /// Init system to install a service for: systemd, openrc or none (default: the running one)
+        #[arg(long, value_parser = parse_init_system)]
+        init: Option<InitSystem>,
+        /// List the files and where they would go, without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Diagnose hardware access and conflicting power-management tools
+    Doctor {
+        /// Print a machine-readable report to attach to bug reports (no prompts)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Temporarily override a setting over the active profile and automation
+    Override {
+        #[command(subcommand)]
+        action: OverrideCommands,
+    },
+
+    /// List, switch on and off, pause or trigger the daemon's automatic profile switching rules
+    Automation {
+        #[command(subcommand)]
+        action: AutomationCommands,
+    },
+
+    /// Switch travel mode: Super Battery, silent fans, 60% charge limit, keyboard backlight and webcam off
+    Travel {
+        /// on to switch it on, off to put back the previous settings; shows the state without
+        #[arg(value_parser = parse_bool)]
+        enabled: Option<bool>,
+    },
+
+    /// Sensor history recorded by the daemon
+    History {
+        #[command(subcommand)]
+        action: HistoryCommands,
+    },
+
+    /// Background service exposing state and controls on D-Bus
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonCommands,
+    },
+
+    /// Show an on-screen notification when the scenario changes (run in the desktop session)
+    NotifyAgent,
+
+    /// Map scenarios to tuned profiles
+    Tuned {
+        #[command(subcommand)]
+        action: TunedCommands,
+    },
+
+    /// Export fan state for in-game overlays such as MangoHud
+    Osd {
+        /// Status template used for the overlay line
+        #[arg(short, long, default_value = "mangohud")]
+        template: String,
+
+        /// Keep running and rewrite this file every interval instead of printing once
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Update interval in seconds when writing to a file
+        #[arg(short, long, default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+    },
+
+    /// Stream sensor readings for external dashboards
+    Sensors {
+        #[command(subcommand)]
+        action: SensorsCommands,
+    },
+
+    /// Low-level embedded controller tools
+    Ec {
+        #[command(subcommand)]
+        action: EcCommands,
+    },
+
+    /// Run the commands in a file, one per line, with if/else blocks on power source and capabilities
+    Run {
+        /// Command file (e.g. setup.msc)
+        script: PathBuf,
+
+        /// Print the commands that would run on this machine right now, without running them
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Carry on with the next command when one fails
+        #[arg(long)]
+        keep_going: bool,
+    },
+
+    /// Browse the model database (quirk files and the templates they inherit from)
+    Models {
+        #[command(subcommand)]
+        action: ModelsCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum SensorsCommands {
+    /// Print changed sensor values as JSON merge patches, one line per change
+    Watch {
+        /// Milliseconds between readings
+        #[arg(short, long, default_value = "1000", value_parser = clap::value_parser!(u64).range(100..))]
+        interval: u64,
+
+        /// Send every value again this often (seconds), for consumers that join late; 0 for never
+        #[arg(short, long, default_value = "0")]
+        resync: u64,
+    },
+
+    /// Keep hwmon-style sensor files up to date for monitoring tools that only read those
+    Bridge {
+        /// Directory to write the files to (default: hwmon_bridge.path, /run/msi-center/hwmon)
+        #[arg(short, long)]
+        path: Option<PathBuf>,
+
+        /// Seconds between updates
+        #[arg(short, long, default_value = "2")]
+        interval: u64,
+    },
 }
 
+#[derive(Subcommand)]
+enum ModelsCommands {
+    /// List every model entry and template, marking the one matching this machine
+    List,
+
+    /// Show an entry with its templates applied
+    Show {
+        /// Entry id (file name without .toml); defaults to the entry matching this machine
+        id: Option<String>,
+    },
+
+    /// Gather DMI strings, EC firmware and a sanitized EC dump into a new entry for this machine (read-only)
+    Probe {
+        /// Write the entry to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum EcCommands {
+    /// Compare the current EC registers against the baseline taken at install
+    DiffBaseline {
+        /// Compare against this snapshot (name or file path) instead of the baseline
+        #[arg(short, long)]
+        against: Option<String>,
+    },
+
+    /// Save all EC registers to a snapshot (a name in the snapshots directory, or a file path)
+    Snapshot {
+        file: String,
+    },
+
+    /// Save this model's writable registers (controls, fan tables) to restore later
+    Backup {
+        /// Snapshot name or file path (default: a timestamped backup in the snapshots directory)
+        file: Option<String>,
+    },
+
+    /// Write back the writable registers from a backup or snapshot that differ from the EC now
+    Restore {
+        /// Backup or snapshot (name or file path); the most recent backup if omitted
+        file: Option<String>,
+
+        /// Restore a backup taken on a different model
+        #[arg(long)]
+        any_model: bool,
+    },
+
+    /// Show the registers that differ between two snapshots (names or file paths)
+    Diff {
+        before: String,
+        after: String,
+    },
+
+    /// Print EC registers as a hex/ASCII table, read through the active backend
+    Dump {
+        /// First register (hex like 0x68, or decimal)
+        #[arg(long, default_value = "0x00", value_parser = parse_register)]
+        start: u8,
+
+        /// Last register, inclusive
+        #[arg(long, default_value = "0xFF", value_parser = parse_register)]
+        end: u8,
+    },
+
+    /// Write one EC register and read it back (only registers known to be safe on this model without --force)
+    Write {
+        /// Register (hex like 0xD4, or decimal)
+        #[arg(value_parser = parse_register)]
+        address: u8,
+
+        /// Value (hex or decimal)
+        #[arg(value_parser = parse_register)]
+        value: u8,
+
+        /// Write the register even if it isn't on this model's safe list
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Poll all EC registers and print the ones that change
+    Watch {
+        /// Polling interval in milliseconds
+        #[arg(short, long, default_value = "500")]
+        interval: u64,
+
+        /// Name the registers this tool already knows (temperatures, fan mode, ...)
+        #[arg(short, long)]
+        annotate: bool,
+
+        /// Hide registers that change on their own, like temperatures and fan speeds
+        #[arg(long)]
+        ignore_sensors: bool,
+    },
+
+    /// Reload the ec_sys module with write support, so debugfs can write the EC
+    EnableWrites {
+        /// Also load it with write support at every boot (modules-load.d and modprobe.d)
+        #[arg(long)]
+        persist: bool,
+    },
+
+    /// Import EC changes captured under Windows and map them to settings
+    ImportCapture {
+        /// Capture file: [Action] sections of 'ADDRESS: OLD -> NEW' lines
+        file: PathBuf,
+
+        /// Save each recognized action as a profile named after its section
+        #[arg(short, long)]
+        save: bool,
+    },
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum FanCommands {
     /// Show current fan status
@@ -70,7 +373,7 @@ enum FanCommands {
     /// Enable or disable cooler boost
     CoolerBoost {
         /// Enable (on) or disable (off)
-        #[arg(value_parser = parse_bool)]
+        #[arg(action = clap::ArgAction::Set, value_parser = parse_bool)]
         enabled: bool,
     },
 
@@ -83,27 +386,336 @@ enum FanCommands {
         /// GPU fan speed percentage (0-100)
         #[arg(short, long)]
         gpu: u8,
+
+        /// Third (system) fan speed percentage, on models with one; defaults to the faster of CPU and GPU
+        #[arg(long)]
+        system: Option<u8>,
+
+        /// Store the speeds in the active profile so 'apply' restores them after reboot
+        #[arg(short, long)]
+        save: bool,
+
+        /// Allow speeds below the speed guard's floor at high temperature
+        #[arg(long)]
+        force: bool,
     },
 
     /// Set fan curve
+    #[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
     Curve {
-        /// Fan to configure: cpu or gpu
-        #[arg(short, long)]
-        fan: String,
+        #[command(subcommand)]
+        action: Option<CurveCommands>,
+
+        /// Fan to configure: cpu, gpu, system (third fan), both (CPU and GPU) or all
+        #[arg(short, long, required = true)]
+        fan: Option<String>,
 
         /// Curve preset: silent, balanced, performance, or custom
-        #[arg(short, long)]
-        preset: String,
+        #[arg(short, long, required = true)]
+        preset: Option<String>,
 
         /// Custom curve points (format: temp1:speed1,temp2:speed2,...)
-        #[arg(short = 'p', long)]
+        #[arg(short = 'P', long)]
         points: Option<String>,
+
+        /// Lowest duty (%) while following the curve, on firmware with a minimum duty register
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        min_duty: Option<u8>,
+
+        /// Duty offset (%) per curve point, e.g. "0,0,5,5,10,10", on firmware with offset registers
+        #[arg(long, allow_hyphen_values = true)]
+        offsets: Option<String>,
+
+        /// Allow a curve the speed guard would refuse
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Follow the active profile's curves in software, for models that ignore the curve tables
+    RunCurve {
+        /// Seconds between temperature readings (default: software_fan_control.interval_secs)
+        #[arg(short, long)]
+        interval: Option<u64>,
+
+        /// Degrees the temperature must fall before a fan slows down, overriding the curves' own
+        #[arg(short = 'y', long)]
+        hysteresis: Option<u8>,
+
+        /// Largest duty change per second (%), overriding the curves' own; 0 for no limit
+        #[arg(short, long)]
+        max_ramp: Option<u8>,
+    },
+
+    /// Find the fan duty you can still not hear, for quieter silent curves
+    CalibrateNoise {
+        /// Duty increase per step in percent
+        #[arg(short, long, default_value = "5")]
+        step: u8,
+
+        /// Seconds to let the fans settle at each step
+        #[arg(long, default_value = "5")]
+        settle: u64,
+
+        /// Forget the stored threshold instead of calibrating
+        #[arg(long)]
+        clear: bool,
+    },
+
+    /// Step each fan through 20-100% duty and report whether its RPM follows, to check the model's EC addresses
+    Test {
+        /// Longest time (seconds) to wait for a fan to settle at each duty
+        #[arg(long, default_value = "10")]
+        settle: u64,
+
+        /// Print the results as JSON
+        #[arg(long)]
+        json: bool,
     },
 
     /// Reset fans to automatic control
     Reset,
 }
 
+#[derive(Subcommand)]
+enum CurveCommands {
+    /// Chart the active profile's curves, or a named one, and the point they are operating at right now
+    Show {
+        /// Fan to show: cpu, gpu or both
+        #[arg(value_name = "FAN", conflicts_with = "fan")]
+        which: Option<String>,
+
+        #[arg(short, long, default_value = "both", hide = true)]
+        fan: String,
+
+        /// Curve to show instead of the profile's: silent, balanced, performance, a saved curve,
+        /// or one of the active profile's curve presets
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Read the curves the fan tables in the EC hold right now, and whether they match the active profile
+    Get {
+        /// Fan to read: cpu, gpu or both
+        #[arg(value_name = "FAN", default_value = "both")]
+        which: String,
+
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the duty curves would run at given temperatures, or replay a recorded temperature
+    /// trace through them, without touching the fans
+    Simulate {
+        /// Curve file (JSON, same format as in profiles); repeat to compare several curves
+        #[arg(short, long, required_unless_present = "preset")]
+        curve: Vec<PathBuf>,
+
+        /// Curve preset as for `fan curve --preset`: silent, balanced, performance, custom, or a saved curve
+        #[arg(short, long)]
+        preset: Option<String>,
+
+        /// Custom curve points for --preset custom (format: temp1:speed1,temp2:speed2,...)
+        #[arg(short = 'P', long)]
+        points: Option<String>,
+
+        /// Temperatures (°C) to show the duty at, e.g. 72 or 50,65,80
+        #[arg(long, value_delimiter = ',', conflicts_with = "trace")]
+        temp: Vec<u8>,
+
+        /// Range of temperatures (°C) to tabulate the duty over, e.g. 40-95
+        #[arg(short, long, conflicts_with = "trace")]
+        range: Option<TempRange>,
+
+        /// Degrees between the rows of --range
+        #[arg(long, default_value = "5", value_parser = clap::value_parser!(u8).range(1..))]
+        step: u8,
+
+        /// Temperature trace CSV: "seconds,temp" or one temperature per line at 1 s intervals
+        #[arg(short, long, required_unless_present_any = ["temp", "range"])]
+        trace: Option<PathBuf>,
+
+        /// Temperature smoothing time constant in seconds
+        #[arg(short, long, default_value = "0")]
+        smoothing: f32,
+
+        /// Degrees the temperature must drop before the fan slows down
+        #[arg(short = 'y', long, default_value = "0")]
+        hysteresis: f32,
+
+        /// Write the simulated duty over time as CSV (single curve only)
+        #[arg(short, long, requires = "trace")]
+        output: Option<PathBuf>,
+    },
+
+    /// Switch a profile to one of its curve presets or a saved curve (applied now if the profile is active)
+    Use {
+        /// Profile name
+        profile: String,
+
+        /// Preset or saved curve name, e.g. Summer
+        name: String,
+
+        /// Fans to follow a saved curve: cpu, gpu or both
+        #[arg(short, long, default_value = "both")]
+        fan: String,
+    },
+
+    /// Save a custom curve by name (with --points), or a profile's current curves as a preset
+    Save {
+        /// Curve name, e.g. gaming; or the profile, followed by the preset name
+        target: String,
+
+        /// Preset name, e.g. Winter, when storing a profile's curves
+        name: Option<String>,
+
+        /// Curve points (format: temp1:speed1,temp2:speed2,...)
+        #[arg(short = 'P', long, conflicts_with = "name", required_unless_present = "name")]
+        points: Option<String>,
+
+        /// Sensor software control looks the curve up against: fan, cpu-package, cpu-hottest-core,
+        /// gpu-edge, gpu-hotspot, nvme or a temp*_input path
+        #[arg(short, long, conflicts_with = "name", default_value = "fan")]
+        source: TempSource,
+    },
+
+    /// Delete a saved curve, or with a preset name, a curve preset from a profile
+    Remove {
+        /// Curve name; or the profile, followed by the preset name
+        target: String,
+
+        /// Preset name
+        name: Option<String>,
+    },
+
+    /// List the saved curves and the profiles' curve presets
+    List,
+}
+
+#[derive(Subcommand)]
+enum AutomationCommands {
+    /// Stop switching profiles automatically until resumed or the time runs out
+    Pause {
+        /// How long to pause, e.g. 30m, 2h or 1h30m (default: until resumed)
+        #[arg(long = "for", value_parser = automation::parse_duration)]
+        duration: Option<std::time::Duration>,
+    },
+
+    /// Resume automatic switching; the profile for the current condition is applied again
+    Resume,
+
+    /// List every rule with its profile, whether it is enabled and when it last switched
+    List,
+
+    /// Enable a rule: on-ac, on-battery, on-low-battery, while-gaming
+    Enable { rule: automation::Role },
+
+    /// Disable a rule without forgetting its profile
+    Disable { rule: automation::Role },
+
+    /// Apply a rule's profile now, as if its condition had just started
+    Trigger { rule: automation::Role },
+}
+
+#[derive(Subcommand)]
+enum OverrideCommands {
+    /// Override the fan mode: auto, silent, basic, advanced
+    FanMode {
+        #[arg(value_parser = parse_fan_mode)]
+        mode: FanMode,
+        #[command(flatten)]
+        lifetime: OverrideLifetime,
+    },
+
+    /// Override Cooler Boost (on/off)
+    CoolerBoost {
+        #[arg(action = clap::ArgAction::Set, value_parser = parse_bool)]
+        enabled: bool,
+        #[command(flatten)]
+        lifetime: OverrideLifetime,
+    },
+
+    /// Override the shift mode: eco, comfort, sport, turbo
+    ShiftMode {
+        #[arg(value_parser = parse_shift_mode)]
+        mode: ShiftMode,
+        #[command(flatten)]
+        lifetime: OverrideLifetime,
+    },
+
+    /// Override super battery (on/off)
+    SuperBattery {
+        #[arg(action = clap::ArgAction::Set, value_parser = parse_bool)]
+        enabled: bool,
+        #[command(flatten)]
+        lifetime: OverrideLifetime,
+    },
+
+    /// List the overrides in force
+    List,
+
+    /// End overrides (all, or the one on a control) and restore the active profile's settings
+    Clear {
+        /// fan-mode, cooler-boost, shift-mode or super-battery
+        control: Option<String>,
+    },
+}
+
+/// How long an override lasts; until the next reboot unless `--for` is given.
+#[derive(clap::Args)]
+struct OverrideLifetime {
+    /// Keep it until the machine reboots (the default)
+    #[arg(long, conflicts_with = "duration")]
+    until_reboot: bool,
+
+    /// Keep it for this long, e.g. 45m, 2h or 1h30m
+    #[arg(long = "for", value_parser = automation::parse_duration)]
+    duration: Option<std::time::Duration>,
+}
+
+#[derive(Subcommand)]
+enum DaemonCommands {
+    /// Run the daemon in the foreground (normally started by systemd)
+    Run {
+        /// Fan polling interval in seconds, overriding `polling.fans_ms` in the config
+        #[arg(short, long)]
+        interval: Option<u64>,
+    },
+
+    /// Print the D-Bus introspection XML of the control interface
+    Introspect,
+
+    /// Show whether the daemon is running and what it is doing
+    Status,
+}
+
+#[derive(Subcommand)]
+enum TunedCommands {
+    /// Show the active tuned profile and the scenario mapping
+    Status,
+
+    /// Activate mapped tuned profiles when scenarios or profiles are applied
+    Enable {
+        /// Enable (on) or disable (off)
+        #[arg(action = clap::ArgAction::Set, value_parser = parse_bool)]
+        enabled: bool,
+    },
+
+    /// Map a scenario to a tuned profile
+    Map {
+        /// Scenario: silent, balanced, highperf, turbo, battery
+        #[arg(value_parser = parse_scenario)]
+        scenario: UserScenario,
+
+        /// tuned profile name (see 'tuned-adm list')
+        profile: String,
+    },
+}
+
 #[derive(Subcommand)]
 enum ScenarioCommands {
     /// Show current scenario
@@ -129,8 +741,31 @@ enum ScenarioCommands {
     /// Enable or disable super battery mode
     SuperBattery {
         /// Enable (on) or disable (off)
-        #[arg(value_parser = parse_bool)]
+        #[arg(action = clap::ArgAction::Set, value_parser = parse_bool)]
         enabled: bool,
+        /// Switch only this feature, on models that map it: gpu-clamp, panel-dimming
+        #[arg(long, value_parser = parse_super_battery_feature)]
+        feature: Option<SuperBatteryFeature>,
+    },
+
+    /// Set the lid logo LED, on models that have one
+    LidLogo {
+        /// on, off, or brightness in percent (0-100)
+        #[arg(value_parser = parse_lid_logo)]
+        brightness: u8,
+    },
+
+    /// Show or configure the power/battery indicator LED, on models whose EC allows it
+    IndicatorLed {
+        /// Keep the LED dark while on battery (on/off)
+        #[arg(long, value_parser = parse_bool)]
+        off_on_battery: Option<bool>,
+        /// Battery level in percent below which the LED blinks; 0 never blinks
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        blink_below: Option<u8>,
+        /// Also store it in the active profile, so applying the profile sets it again
+        #[arg(long)]
+        profile: bool,
     },
 }
 
@@ -166,6 +801,62 @@ enum ProfileCommands {
 
     /// Save current settings to active profile
     Save,
+
+    /// Print a profile as JSON, e.g. to pipe into 'profile import' on another machine
+    Export {
+        /// Profile name (default: the active profile)
+        name: Option<String>,
+    },
+
+    /// Add or replace a profile from JSON on stdin
+    Import {
+        /// Also make it the active profile and apply it
+        #[arg(long)]
+        activate: bool,
+
+        /// Accept fan curves the speed guard would refuse
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Let the daemon turn Cooler Boost on while the CPU or GPU runs hot under a profile
+    EmergencyCooling {
+        /// Enable (on) or disable (off)
+        #[arg(action = clap::ArgAction::Set, value_parser = parse_bool)]
+        enabled: bool,
+        /// Temperature (°C) of the CPU or GPU above which Cooler Boost is turned on (default 90)
+        #[arg(long)]
+        above: Option<u8>,
+        /// Temperature (°C) both have to be below before it is turned off again (default 75)
+        #[arg(long)]
+        below: Option<u8>,
+        /// Profile name (default: the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+
+    /// Keep a profile's fans at or above a duty, raising its curves and manual speeds where they run slower
+    MinFan {
+        /// Minimum duty in percent; 0 removes the floor
+        percent: u8,
+        /// Profile name (default: the active profile)
+        #[arg(long)]
+        profile: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum HistoryCommands {
+    /// Peak temperatures of the last 24 hours, and fan duty per profile and thermal alerts of the last 7 days
+    Stats {
+        /// Print the figures as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+fn parse_init_system(s: &str) -> Result<InitSystem, String> {
+    s.parse()
 }
 
 fn parse_fan_mode(s: &str) -> Result<FanMode, String> {
@@ -179,26 +870,30 @@ fn parse_fan_mode(s: &str) -> Result<FanMode, String> {
 }
 
 fn parse_scenario(s: &str) -> Result<UserScenario, String> {
-    match s.to_lowercase().as_str() {
-        "silent" | "quiet" => Ok(UserScenario::Silent),
-        "balanced" | "comfort" => Ok(UserScenario::Balanced),
-        "highperf" | "performance" | "sport" => Ok(UserScenario::HighPerformance),
-        "turbo" | "extreme" => Ok(UserScenario::Turbo),
-        "battery" | "superbattery" | "eco" => Ok(UserScenario::SuperBattery),
-        _ => Err(format!("Invalid scenario: {}. Use: silent, balanced, highperf, turbo, battery", s)),
-    }
+    s.parse()
 }
 
 fn parse_shift_mode(s: &str) -> Result<ShiftMode, String> {
-    match s.to_lowercase().as_str() {
-        "eco" | "silent" => Ok(ShiftMode::EcoSilent),
-        "comfort" | "balanced" => Ok(ShiftMode::Comfort),
-        "sport" | "performance" => Ok(ShiftMode::Sport),
-        "turbo" | "extreme" => Ok(ShiftMode::Turbo),
-        _ => Err(format!("Invalid shift mode: {}. Use: eco, comfort, sport, turbo", s)),
+    s.parse()
+}
+
+fn parse_super_battery_feature(s: &str) -> Result<SuperBatteryFeature, String> {
+    s.parse()
+}
+
+fn parse_lid_logo(s: &str) -> Result<u8, String> {
+    match s.to_lowercase().as_str() {
+        "on" => Ok(100),
+        "off" => Ok(0),
+        other => other.trim_end_matches('%').parse::<u8>().ok().filter(|p| *p <= 100)
+            .ok_or_else(|| format!("Invalid lid logo brightness: {}. Use: on, off or 0-100", s)),
     }
 }
 
+fn lid_logo_label(percent: u8) -> String {
+    if percent == 0 { "OFF".to_string() } else { format!("ON ({}%)", percent) }
+}
+
 fn parse_bool(s: &str) -> Result<bool, String> {
     match s.to_lowercase().as_str() {
         "on" | "true" | "1" | "yes" | "enable" => Ok(true),
@@ -207,6 +902,14 @@ fn parse_bool(s: &str) -> Result<bool, String> {
     }
 }
 
+fn parse_register(s: &str) -> Result<u8, String> {
+    let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse(),
+    };
+    parsed.map_err(|_| format!("Invalid EC register: {}. Use 0x00-0xFF or 0-255", s))
+}
+
 fn parse_curve_points(points_str: &str) -> Result<FanCurve, String> {
     let mut points = Vec::new();
 
@@ -228,7 +931,22 @@ fn parse_curve_points(points_str: &str) -> Result<FanCurve, String> {
 
     points.sort_by_key(|p| p.temp);
 
-    Ok(FanCurve { points })
+    Ok(FanCurve { points, ..FanCurve::default() })
+}
+
+fn parse_duty_offsets(text: &str) -> Result<Vec<i8>, String> {
+    let offsets: Vec<i8> = text
+        .split(',')
+        .map(|offset| offset.trim().parse().map_err(|_| format!("Invalid duty offset: {}", offset)))
+        .collect::<Result<_, _>>()?;
+    let points = ec::quirks::capabilities().curve_points;
+    if offsets.len() > points as usize {
+        return Err(format!("This model's fan table has {} points, got {} offsets", points, offsets.len()));
+    }
+    match offsets.iter().find(|offset| offset.unsigned_abs() > fan::MAX_DUTY_OFFSET as u8) {
+        Some(offset) => Err(format!("Duty offsets must be within ±{}%, got: {}", fan::MAX_DUTY_OFFSET, offset)),
+        None => Ok(offsets),
+    }
 }
 
 fn check_root() {
@@ -239,6 +957,13 @@ fn check_root() {
     }
 }
 
+fn check_lockdown() {
+    if let Some(warning) = system::lockdown_warning() {
+        eprintln!("{}", format!("Warning: {}", warning).yellow());
+        println!();
+    }
+}
+
 fn print_header(title: &str) {
     println!();
     println!("{}", format!("═══ {} ═══", title).cyan().bold());
@@ -249,51 +974,240 @@ fn print_status_line(label: &str, value: &str, color: colored::Color) {
     println!("  {}: {}", label.white().bold(), value.color(color));
 }
 
+/// Each fan's RPM and duty, with the duty it was asked for where that is known,
+/// flagging fans the EC runs at another duty.
+fn print_fan_readings(fans: &[FanReading]) {
+    for fan in fans {
+        match fan.target_percent {
+            Some(target) => print_status_line(
+                &format!("{} Fan", fan.name),
+                &format!("{} RPM ({}%, target {}%)", fan.rpm, fan.percent, target),
+                if fan.off_target() { colored::Color::Yellow } else { colored::Color::White },
+            ),
+            None => print_status_line(&format!("{} Fan", fan.name), &format!("{} RPM ({}%)", fan.rpm, fan.percent), colored::Color::White),
+        }
+    }
+    if fans.iter().any(FanReading::off_target) {
+        println!("  {}", "The EC isn't running every fan at the duty its fan table asks for; the firmware or another tool may be overriding it".yellow());
+    }
+}
+
 fn main() {
-    env_logger::init();
     let cli = Cli::parse();
 
+    if !cli.host.is_empty() {
+        if let Err(e) = remote::run(&cli.host, &remote::forwarded_args(std::env::args())) {
+            eprintln!("{}: {}", "Error".red().bold(), e);
+            process::exit(1);
+        }
+        return;
+    }
+
+    let config = AppConfig::load().unwrap_or_default();
+    logging::init(cli.verbose, cli.log_file || config.log_to_file);
+    ec::set_port_timing(config.ec_port);
+    ec::set_backend_order(if cli.backend.is_empty() { &config.ec_backends } else { &cli.backend });
+    ec::set_read_cache_ttl(std::time::Duration::from_millis(config.ec_read_cache_ms));
+    ec::allow_unsafe_writes(cli.force);
+    fan::set_audible_threshold(config.audible_threshold);
+
     check_root();
+    check_lockdown();
+    fan::install_panic_hook();
+
+    if cli.record_ec_trace.is_some() {
+        ec::trace::start_recording();
+    }
+
+    let result = run_command(cli.command);
+
+    if let Some(path) = cli.record_ec_trace {
+        match ec::trace::finish_recording(&path, traced_command()) {
+            Ok(trace) => println!("{} Recorded {} EC operations to {}", "✓".green(), trace.operations.len(), path.display()),
+            Err(e) => eprintln!("{}: failed to save EC trace: {}", "Error".red().bold(), e),
+        }
+    }
+
+    if let Err(e) = result {
+        eprintln!("{}: {}", "Error".red().bold(), e);
+        process::exit(1);
+    }
+}
 
-    let result = match cli.command {
-        Commands::Status => cmd_status(),
+fn run_command(command: Commands) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Commands::Status { template, json } => cmd_status(template, json),
         Commands::Fan { action } => cmd_fan(action),
         Commands::Scenario { action } => cmd_scenario(action),
         Commands::Profile { action } => cmd_profile(action),
-        Commands::Monitor { interval } => cmd_monitor(interval),
+        Commands::Monitor { interval, duration, inhibit_idle } => cmd_monitor(interval, duration, inhibit_idle),
         Commands::Apply => cmd_apply(),
-    };
+        Commands::Install { prefix, destdir, init, dry_run } => cmd_install(&prefix, destdir.as_deref(), init, dry_run),
+        Commands::Doctor { json } => cmd_doctor(json),
+        Commands::Override { action } => cmd_override(action),
+        Commands::Automation { action } => cmd_automation(action),
+        Commands::Travel { enabled } => cmd_travel(enabled),
+        Commands::History { action } => cmd_history(action),
+        Commands::Daemon { action } => cmd_daemon(action),
+        Commands::NotifyAgent => notify::run_agent().map_err(Into::into),
+        Commands::Tuned { action } => cmd_tuned(action),
+        Commands::Osd { template, output, interval } => cmd_osd(template, output, interval),
+        Commands::Sensors { action: SensorsCommands::Watch { interval, resync } } => cmd_sensors_watch(interval, resync),
+        Commands::Sensors { action: SensorsCommands::Bridge { path, interval } } => cmd_sensors_bridge(path, interval),
+        Commands::Ec { action } => cmd_ec(action),
+        Commands::Run { script, dry_run, keep_going } => cmd_run(&script, dry_run, keep_going),
+        Commands::Models { action } => cmd_models(action),
+    }
+}
 
-    if let Err(e) = result {
-        eprintln!("{}: {}", "Error".red().bold(), e);
-        process::exit(1);
+fn cmd_run(path: &Path, dry_run: bool, keep_going: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let steps = script::load(path)?;
+    let facts = script::Facts::read();
+    let commands = script::commands(&steps, &facts);
+    let forced = ec::unsafe_writes_allowed();
+    let mut failed = 0;
+
+    for (line, words) in commands {
+        let shown = words.join(" ");
+        if dry_run {
+            println!("{:>4}  msi-center {}", line, shown);
+            continue;
+        }
+        println!("{} {}", "▶".cyan(), shown.bold());
+        let args = std::iter::once("msi-center").chain(words.iter().map(String::as_str));
+        let result = match Cli::try_parse_from(args) {
+            Ok(cli) if !cli.host.is_empty() || !cli.backend.is_empty() || cli.record_ec_trace.is_some() => {
+                Err("--host, --backend and --record-ec-trace only work on the command line".into())
+            }
+            Ok(Cli { command: Commands::Run { .. }, .. }) => Err("scripts cannot run other scripts".into()),
+            Ok(cli) => {
+                ec::allow_unsafe_writes(forced || cli.force);
+                let result = run_command(cli.command);
+                ec::allow_unsafe_writes(forced);
+                result
+            }
+            Err(e) => Err(e.render().to_string().lines().next().unwrap_or_default().trim_start_matches("error: ").to_string().into()),
+        };
+        if let Err(e) = result {
+            let message = format!("{}:{}: {}", path.display(), line, e);
+            if !keep_going {
+                return Err(message.into());
+            }
+            eprintln!("{}: {}", "Error".red().bold(), message);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} command(s) in {} failed", failed, path.display()).into());
+    }
+    Ok(())
+}
+
+/// The command line without the program name and global flags, as stored in EC traces.
+fn traced_command() -> Vec<String> {
+    let mut command = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--record-ec-trace" {
+            args.next();
+            continue;
+        }
+        let is_verbose = arg == "--verbose" || (arg.starts_with("-v") && arg[1..].chars().all(|c| c == 'v'));
+        if is_verbose || arg == "--log-file" || arg.starts_with("--record-ec-trace=") {
+            continue;
+        }
+        command.push(arg);
     }
+    command
 }
 
-fn cmd_status() -> Result<(), Box<dyn std::error::Error>> {
+fn cmd_status(template: Option<String>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+
+    if json {
+        let snapshot = StatusSnapshot::collect(&config.active_profile)?;
+        println!("{}", serde_json::to_string_pretty(&snapshot)?);
+        return Ok(());
+    }
+
+    if let Some(name) = template {
+        let Some(template) = config.status_template(&name) else {
+            let available: Vec<&str> = config.status_templates.keys().map(String::as_str).collect();
+            return Err(format!("Unknown template: {}. Available: {}", name, available.join(", ")).into());
+        };
+        let snapshot = StatusSnapshot::collect(&config.active_profile)?;
+        println!("{}", snapshot.render_template(&template)?);
+        return Ok(());
+    }
+
     print_header("MSI Center Linux - System Status");
 
-    let mut ec = EmbeddedController::new()?;
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
 
-    if !ec.is_msi_laptop() {
+    if !fan_controller.ec_mut().is_msi_laptop() {
         println!("{}", "Warning: This may not be an MSI laptop.".yellow());
     }
 
-    let mut fan_controller = FanController::new(EmbeddedController::new()?);
-    let fan_info = fan_controller.get_fan_info()?;
+    let StatusSnapshot { model, backend, support: controls, fan: fan_info, scenario: scenario_info, features, ec_write_failures, overrides, stalled_fans, .. } =
+        StatusSnapshot::collect_from(&mut fan_controller, &config.active_profile)?;
+
+    for fan in &stalled_fans {
+        println!("{}", format!("⚠ {}; it may have failed", fan).red().bold());
+    }
+    if !stalled_fans.is_empty() {
+        println!();
+    }
 
-    let mut ec2 = EmbeddedController::new()?;
-    let mut scenario_manager = ScenarioManager::new(&mut ec2, &mut fan_controller);
-    let scenario_info = scenario_manager.get_current_info()?;
+    println!("{}", "── Model ──".green());
+    print_status_line("Model", model.product.as_deref().unwrap_or("unknown"), colored::Color::White);
+    print_status_line("EC Firmware", model.firmware.as_deref().unwrap_or("unknown"), colored::Color::White);
+    let support_color = match model.support {
+        SupportLevel::Full => colored::Color::Green,
+        SupportLevel::Partial => colored::Color::Yellow,
+        SupportLevel::Unsupported => colored::Color::Red,
+    };
+    let support = match model.quirk {
+        Some(ref quirk) => format!("{} ({})", model.support, quirk),
+        None => model.support.to_string(),
+    };
+    print_status_line("Support", &support, support_color);
+    let order = ec::backend_order();
+    let backend_line = if let Some(mode) = system::kernel_lockdown() {
+        format!("{} (kernel lockdown: {})", backend, mode)
+    } else if order == ec::Backend::ALL {
+        backend.clone()
+    } else {
+        let allowed: Vec<String> = order.iter().map(ToString::to_string).collect();
+        format!("{} (allowed: {})", backend, allowed.join(", "))
+    };
+    print_status_line("EC Backend", &backend_line, colored::Color::White);
+    let missing = controls.missing();
+    if !missing.is_empty() {
+        print_status_line("Unavailable", &format!("{} (via {})", missing.join(", "), backend), colored::Color::Yellow);
+    }
+    match ec_write_failures.last() {
+        None => print_status_line("EC Writes", "OK", colored::Color::Green),
+        Some(latest) => {
+            print_status_line("EC Writes", &format!("{} failed in the last hour", ec_write_failures.len()), colored::Color::Red);
+            println!("    latest: {}", latest);
+        }
+    }
+    println!();
 
     println!("{}", "── Temperatures ──".green());
-    print_status_line("CPU Temperature", &format!("{}°C", fan_info.cpu_temp), get_temp_color(fan_info.cpu_temp));
-    print_status_line("GPU Temperature", &format!("{}°C", fan_info.gpu_temp), get_temp_color(fan_info.gpu_temp));
+    print_status_line("CPU Temperature", &format!("{}°C", fan_info.cpu_temp), get_temp_color(fan_info.cpu_temp, &config));
+    print_status_line("GPU Temperature", &format!("{}°C", fan_info.gpu_temp), get_temp_color(fan_info.gpu_temp, &config));
+    if let Some(hotspot) = fan_info.gpu.and_then(|gpu| gpu.hotspot) {
+        print_status_line("GPU Hotspot", &format!("{}°C", hotspot), get_temp_color(hotspot, &config));
+    }
+    if let Some(memory) = fan_info.gpu.and_then(|gpu| gpu.memory) {
+        print_status_line("GPU Memory", &format!("{}°C", memory), get_temp_color(memory, &config));
+    }
     println!();
 
     println!("{}", "── Fan Status ──".green());
-    print_status_line("CPU Fan", &format!("{} RPM ({}%)", fan_info.cpu_fan_rpm, fan_info.cpu_fan_percent), colored::Color::White);
-    print_status_line("GPU Fan", &format!("{} RPM ({}%)", fan_info.gpu_fan_rpm, fan_info.gpu_fan_percent), colored::Color::White);
+    print_fan_readings(&fan_info.fans);
     print_status_line("Fan Mode", &format!("{:?}", fan_info.fan_mode), colored::Color::Cyan);
     print_status_line("Cooler Boost", if fan_info.cooler_boost { "ON" } else { "OFF" }, 
         if fan_info.cooler_boost { colored::Color::Red } else { colored::Color::Green });
@@ -302,34 +1216,118 @@ fn cmd_status() -> Result<(), Box<dyn std::error::Error>> {
     println!("{}", "── Power Profile ──".green());
     print_status_line("Current Scenario", &scenario_info.current_scenario.to_string(), colored::Color::Cyan);
     print_status_line("Shift Mode", &scenario_info.shift_mode.to_string(), colored::Color::Cyan);
+    if travel::is_on() {
+        print_status_line("Travel Mode", "ON", colored::Color::Green);
+    }
     print_status_line("Super Battery", if scenario_info.super_battery { "ON" } else { "OFF" },
         if scenario_info.super_battery { colored::Color::Green } else { colored::Color::White });
+    for (feature, on) in &scenario_info.super_battery_features {
+        print_status_line(&format!("  {}", feature), if *on { "ON" } else { "OFF" },
+            if *on { colored::Color::Green } else { colored::Color::White });
+    }
+    if let Some(percent) = scenario_info.lid_logo {
+        print_status_line("Lid Logo", &lid_logo_label(percent), colored::Color::White);
+    }
+    if let Some(indicator) = scenario_info.indicator_led {
+        print_status_line("Indicator LED", &indicator.to_string(), colored::Color::White);
+    }
+    for o in &overrides {
+        print_status_line("Override", &format!("{} ({})", o.setting, o.until_display()), colored::Color::Yellow);
+    }
+    println!();
+
+    println!("{}", "── Features ──".green());
+    print_feature("Webcam", features.webcam.map(|on| if on { "ON" } else { "OFF" }.to_string()));
+    print_feature("Webcam Block", features.webcam_block.map(|on| if on { "ON" } else { "OFF" }.to_string()));
+    print_feature("Fn Lock", features.fn_lock.map(|on| if on { "ON" } else { "OFF" }.to_string()));
+    print_feature("Win/Fn Swap", features.win_swap.map(|on| if on { "ON" } else { "OFF" }.to_string()));
+    print_feature("Win Key", features.win_key.map(|on| if on { "ON" } else { "OFF" }.to_string()));
+    print_feature("Charge Threshold", features.charge_threshold.map(|t| format!("{}%", t)));
+    print_feature("Keyboard Backlight", features.keyboard_backlight.map(|level| match features.keyboard_backlight_max {
+        Some(max) => format!("{}/{}", level, max),
+        None => level.to_string(),
+    }));
 
     println!();
     Ok(())
 }
 
-fn get_temp_color(temp: u8) -> colored::Color {
-    match temp {
-        0..=50 => colored::Color::Green,
-        51..=70 => colored::Color::Yellow,
-        71..=85 => colored::Color::Red,
-        _ => colored::Color::BrightRed,
+fn print_feature(label: &str, value: Option<String>) {
+    match value {
+        Some(value) => print_status_line(label, &value, colored::Color::Cyan),
+        None => print_status_line(label, "not available", colored::Color::BrightBlack),
+    }
+}
+
+fn get_temp_color(temp: u8, config: &AppConfig) -> colored::Color {
+    level_color(config.temp_thresholds.level(temp), config.color_palette)
+}
+
+/// Terminal color of a band. The standard palette keeps the terminal's own colors;
+/// the others need exact shades.
+fn level_color(level: TempLevel, palette: ColorPalette) -> colored::Color {
+    match (palette, level) {
+        (ColorPalette::Standard, TempLevel::Normal) => colored::Color::Green,
+        (ColorPalette::Standard, TempLevel::Warm) => colored::Color::Yellow,
+        (ColorPalette::Standard, TempLevel::Hot) => colored::Color::Red,
+        (ColorPalette::Standard, TempLevel::Critical) => colored::Color::BrightRed,
+        (palette, level) => {
+            let [r, g, b] = palette.rgb(level);
+            colored::Color::TrueColor { r, g, b }
+        }
     }
 }
 
 fn cmd_fan(action: FanCommands) -> Result<(), Box<dyn std::error::Error>> {
+    if let FanCommands::Curve { action: Some(CurveCommands::Simulate { curve, preset, points, temp, range, step, trace, smoothing, hysteresis, output }), .. } =
+        action
+    {
+        let mut curves = Vec::new();
+        for path in &curve {
+            let name = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            curves.push((name, simulate::load_curve(path)?));
+        }
+        if let Some(preset) = preset {
+            curves.push((preset.clone(), preset_curve(&preset, points)?));
+        }
+        return match trace {
+            Some(trace) => cmd_curve_simulate(&curves, &trace, SimulationSettings { smoothing, hysteresis }, output),
+            None => {
+                let mut temps = temp;
+                temps.extend(range.map(|range| range.temps(step)).unwrap_or_default());
+                cmd_curve_evaluate(&curves, &temps)
+            }
+        };
+    }
+    if let FanCommands::Curve { action: Some(CurveCommands::Show { which, fan, name, json }), .. } = action {
+        return cmd_curve_show(&which.unwrap_or(fan), name.as_deref(), json);
+    }
+    if let FanCommands::Curve { action: Some(CurveCommands::Get { which, json }), .. } = action {
+        return cmd_curve_get(&which, json);
+    }
+    match action {
+        FanCommands::Curve { action: Some(CurveCommands::Use { profile, name, fan }), .. } => return cmd_curve_use(&profile, &name, &fan),
+        FanCommands::Curve { action: Some(CurveCommands::Save { target, name: Some(name), .. }), .. } => return cmd_curve_save(&target, &name),
+        FanCommands::Curve { action: Some(CurveCommands::Save { target, points: Some(points), source, .. }), .. } => {
+            return cmd_curve_save_named(&target, &points, source);
+        }
+        FanCommands::Curve { action: Some(CurveCommands::Remove { target, name: Some(name) }), .. } => return cmd_curve_remove(&target, &name),
+        FanCommands::Curve { action: Some(CurveCommands::Remove { target, name: None }), .. } => return cmd_curve_remove_named(&target),
+        FanCommands::Curve { action: Some(CurveCommands::List), .. } => return cmd_curve_list(),
+        _ => {}
+    }
+
     let ec = EmbeddedController::new()?;
     let mut fan_controller = FanController::new(ec);
 
     match action {
         FanCommands::Status => {
+            let config = AppConfig::load().unwrap_or_default();
             let info = fan_controller.get_fan_info()?;
             print_header("Fan Status");
-            print_status_line("CPU Fan", &format!("{} RPM ({}%)", info.cpu_fan_rpm, info.cpu_fan_percent), colored::Color::White);
-            print_status_line("GPU Fan", &format!("{} RPM ({}%)", info.gpu_fan_rpm, info.gpu_fan_percent), colored::Color::White);
-            print_status_line("CPU Temp", &format!("{}°C", info.cpu_temp), get_temp_color(info.cpu_temp));
-            print_status_line("GPU Temp", &format!("{}°C", info.gpu_temp), get_temp_color(info.gpu_temp));
+            print_fan_readings(&info.fans);
+            print_status_line("CPU Temp", &format!("{}°C", info.cpu_temp), get_temp_color(info.cpu_temp, &config));
+            print_status_line("GPU Temp", &format!("{}°C", info.gpu_temp), get_temp_color(info.gpu_temp, &config));
             print_status_line("Mode", &format!("{:?}", info.fan_mode), colored::Color::Cyan);
             print_status_line("Cooler Boost", if info.cooler_boost { "ON" } else { "OFF" }, colored::Color::Yellow);
             println!();
@@ -343,27 +1341,70 @@ fn cmd_fan(action: FanCommands) -> Result<(), Box<dyn std::error::Error>> {
         FanCommands::CoolerBoost { enabled } => {
             fan_controller.set_cooler_boost(enabled)?;
             println!("{} Cooler boost {}", "✓".green(), if enabled { "enabled" } else { "disabled" });
+            if enabled && let Some(minutes) = AppConfig::load().unwrap_or_default().cooler_boost_timeout_minutes {
+                println!("  The daemon turns it off again after {} minutes", minutes);
+            }
         }
 
-        FanCommands::Speed { cpu, gpu } => {
-            fan_controller.set_manual_fan_speed(cpu, gpu)?;
-            println!("{} Manual fan speed set - CPU: {}%, GPU: {}%", "✓".green(), cpu, gpu);
-        }
+        FanCommands::Speed { cpu, gpu, system, save, force } => {
+            let third_fan = fan_controller.fan_count() > 2;
+            if system.is_some() && !third_fan {
+                return Err("This model has no third fan; --system only applies to models with one".into());
+            }
+            let speed = ManualFanSpeed { cpu, gpu, system };
+            let config = AppConfig::load().unwrap_or_default();
+            let mut guard = config.speed_guard;
+            if force {
+                guard.enabled = false;
+            }
+            fan_controller.set_speed_guard(guard);
+            let written = speed.floored(config.min_fan_percent());
+            fan_controller.set_manual_fan_speeds(&written.speeds()).map_err(|e| match e {
+                FanError::UnsafeSpeed { .. } | FanError::FanStop { .. } => format!("{}. Use --force to override", e).into(),
+                e => Box::<dyn std::error::Error>::from(e),
+            })?;
+            if third_fan {
+                println!("{} Manual fan speed set - CPU: {}%, GPU: {}%, System: {}%", "✓".green(), written.cpu, written.gpu, written.speeds()[2]);
+            } else {
+                println!("{} Manual fan speed set - CPU: {}%, GPU: {}%", "✓".green(), written.cpu, written.gpu);
+            }
+            if written != speed {
+                println!("  Raised to the profile's minimum of {}%", config.min_fan_percent());
+            }
 
-        FanCommands::Curve { fan, preset, points } => {
-            let curve = match preset.as_str() {
-                "silent" => FanCurve::silent(),
-                "balanced" | "default" => FanCurve::default(),
-                "performance" => FanCurve::performance(),
-                "custom" => {
-                    if let Some(pts) = points {
-                        parse_curve_points(&pts)?
-                    } else {
-                        return Err("Custom curve requires --points argument".into());
-                    }
+            if save {
+                let mut config = AppConfig::load()?;
+                if !config.set_manual_fan_speed(Some(speed)) {
+                    return Err(format!("Active profile '{}' not found", config.active_profile).into());
                 }
-                _ => return Err(format!("Unknown preset: {}. Use: silent, balanced, performance, custom", preset).into()),
+                config.save()?;
+                println!("{} Saved to profile {}", "✓".green(), config.active_profile.cyan());
+            }
+        }
+
+        FanCommands::Curve { fan, preset, points, min_duty, offsets, force, .. } => {
+            // Only absent when a subcommand was given, which is handled above.
+            let (Some(fan), Some(preset)) = (fan, preset) else {
+                return Err("--fan and --preset are required".into());
             };
+            let mut curve = preset_curve(&preset, points)?;
+            curve.tuning = CurveTuning { min_duty, offsets: offsets.as_deref().map(parse_duty_offsets).transpose()?.unwrap_or_default() };
+            if !curve.tuning.is_empty() && fan_controller.ec_mut().capabilities().fan_tuning.is_empty() {
+                println!("{}", "This model has no fan tuning registers mapped; --min-duty and --offsets are ignored".yellow());
+            }
+
+            let config = AppConfig::load().unwrap_or_default();
+            let mut guard = config.speed_guard;
+            if force {
+                guard.enabled = false;
+            }
+            fan_controller.set_speed_guard(guard);
+            curve = curve.floored(config.min_fan_percent());
+            // Checked up front so that `both` and `all` never apply half of it.
+            curve.validate(fan_controller.ec_mut().capabilities().curve_points as usize, &guard).map_err(|e| match e {
+                CurveError::UnsafeSpeed { .. } | CurveError::FanStop { .. } => format!("Invalid fan curve: {}. Use --force to override", e),
+                e => format!("Invalid fan curve: {}", e),
+            })?;
 
             match fan.to_lowercase().as_str() {
                 "cpu" => {
@@ -374,222 +1415,2372 @@ fn cmd_fan(action: FanCommands) -> Result<(), Box<dyn std::error::Error>> {
                     fan_controller.set_gpu_fan_curve(curve)?;
                     println!("{} GPU fan curve set to {}", "✓".green(), preset);
                 }
-                "both" | "all" => {
+                "system" => {
+                    fan_controller.set_fan_curve(2, curve)?;
+                    println!("{} System fan curve set to {}", "✓".green(), preset);
+                }
+                "both" => {
                     fan_controller.set_cpu_fan_curve(curve.clone())?;
                     fan_controller.set_gpu_fan_curve(curve)?;
                     println!("{} Both fan curves set to {}", "✓".green(), preset);
                 }
-                _ => return Err(format!("Unknown fan: {}. Use: cpu, gpu, both", fan).into()),
+                "all" => {
+                    let count = fan_controller.fan_count();
+                    for index in 0..count {
+                        fan_controller.set_fan_curve(index, curve.clone())?;
+                    }
+                    println!("{} All {} fan curves set to {}", "✓".green(), count, preset);
+                }
+                _ => return Err(format!("Unknown fan: {}. Use: cpu, gpu, system, both, all", fan).into()),
             }
         }
 
-        FanCommands::Reset => {
-            fan_controller.reset_to_auto()?;
-            println!("{} Fans reset to automatic control", "✓".green());
+        FanCommands::RunCurve { interval, hysteresis, max_ramp } => {
+            cmd_fan_run_curve(&mut fan_controller, interval, hysteresis, max_ramp)?;
         }
-    }
-
-    Ok(())
-}
 
-fn cmd_scenario(action: ScenarioCommands) -> Result<(), Box<dyn std::error::Error>> {
-    let mut ec = EmbeddedController::new()?;
-    let mut fan_controller = FanController::new(EmbeddedController::new()?);
-    let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
-
-    match action {
-        ScenarioCommands::Status => {
-            let info = manager.get_current_info()?;
-            print_header("Current Scenario");
-            print_status_line("Scenario", &info.current_scenario.to_string(), colored::Color::Cyan);
-            print_status_line("Shift Mode", &info.shift_mode.to_string(), colored::Color::Yellow);
-            print_status_line("Super Battery", if info.super_battery { "ON" } else { "OFF" }, colored::Color::Green);
-            println!();
+        FanCommands::Test { settle, json } => {
+            cmd_fan_test(&mut fan_controller, settle, json)?;
         }
 
-        ScenarioCommands::List => {
-            print_header("Available Scenarios");
-            for scenario in ScenarioManager::get_available_scenarios() {
-                println!("  • {}", scenario.to_string().cyan());
+        FanCommands::CalibrateNoise { step, settle, clear } => {
+            let mut config = AppConfig::load()?;
+            if clear {
+                config.audible_threshold = None;
+                config.save()?;
+                println!("{} Audible threshold cleared", "✓".green());
+                return Ok(());
             }
-            println!();
-        }
 
-        ScenarioCommands::Set { scenario } => {
-            manager.set_scenario(scenario)?;
-            println!("{} Scenario set to {}", "✓".green(), scenario);
-        }
+            let threshold = calibrate_noise(&mut fan_controller, &config, step.clamp(1, 50), settle);
+            // Hand the fans back to the firmware whatever happened.
+            fan_controller.reset_to_auto()?;
+            let threshold = threshold?;
 
-        ScenarioCommands::Shift { mode } => {
-            manager.set_shift_mode(mode)?;
-            println!("{} Shift mode set to {}", "✓".green(), mode);
+            config.audible_threshold = Some(threshold);
+            config.save()?;
+            println!();
+            println!("{} Fans are inaudible to you up to {}%", "✓".green(), threshold.to_string().cyan());
+            println!("  The silent curve preset now stays at or below it under {}°C", fan::QUIET_TEMP_LIMIT);
         }
 
-        ScenarioCommands::SuperBattery { enabled } => {
-            manager.set_super_battery(enabled)?;
-            println!("{} Super battery {}", "✓".green(), if enabled { "enabled" } else { "disabled" });
+        FanCommands::Reset => {
+            fan_controller.reset_to_auto()?;
+            println!("{} Fans reset to automatic control", "✓".green());
+
+            // Otherwise the next 'apply' would bring the saved manual speeds back.
+            let mut config = AppConfig::load()?;
+            if config.get_active_profile().is_some_and(|p| p.settings.manual_fan_speed.is_some()) {
+                config.set_manual_fan_speed(None);
+                config.save()?;
+                println!("{} Removed saved manual speeds from profile {}", "✓".green(), config.active_profile.cyan());
+            }
         }
     }
 
     Ok(())
 }
 
-fn cmd_profile(action: ProfileCommands) -> Result<(), Box<dyn std::error::Error>> {
-    let mut config = AppConfig::load()?;
+/// Set by Ctrl+C or SIGTERM to end `fan run-curve` or `fan test`.
+static FAN_LOOP_STOP: AtomicBool = AtomicBool::new(false);
 
-    match action {
-        ProfileCommands::List => {
-            print_header("Profiles");
-            for profile in &config.profiles {
-                let marker = if profile.name == config.active_profile { "►" } else { " " };
-                println!("  {} {} ({})", marker.green(), profile.name.cyan(), profile.scenario);
-            }
-            println!();
-        }
+extern "C" fn stop_fan_loop(_signal: libc::c_int) {
+    FAN_LOOP_STOP.store(true, Ordering::SeqCst);
+}
 
-        ProfileCommands::Active => {
-            if let Some(profile) = config.get_active_profile() {
-                print_header("Active Profile");
-                print_status_line("Name", &profile.name, colored::Color::Cyan);
-                print_status_line("Scenario", &profile.scenario.to_string(), colored::Color::Yellow);
-                print_status_line("Shift Mode", &profile.settings.shift_mode.to_string(), colored::Color::White);
-                print_status_line("Fan Mode", &format!("{:?}", profile.settings.fan_mode), colored::Color::White);
-                print_status_line("Cooler Boost", if profile.settings.cooler_boost { "ON" } else { "OFF" }, colored::Color::White);
-                println!();
-            } else {
-                println!("{}", "No active profile found".yellow());
+/// Writes the active profile's curves as manual duties every `interval` seconds
+/// until interrupted, then hands the fans back to automatic control.
+fn cmd_fan_run_curve(
+    fan_controller: &mut FanController,
+    interval: Option<u64>,
+    hysteresis: Option<u8>,
+    max_ramp: Option<u8>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    if config.software_fan_control.enabled && daemon::query_health().is_ok() {
+        return Err("The daemon already follows the fan curves in software (software_fan_control)".into());
+    }
+    let Some(mut curves) = config.active_fan_curves() else {
+        return Err(format!("Profile '{}' uses manual fan speeds instead of curves", config.active_profile).into());
+    };
+    for curve in &mut curves {
+        curve.damping.hysteresis = hysteresis.unwrap_or(curve.damping.hysteresis);
+        curve.damping.max_ramp = max_ramp.unwrap_or(curve.damping.max_ramp);
+    }
+    let interval = interval.map_or(config.software_fan_control.interval(), |secs| std::time::Duration::from_secs(secs.max(1)));
+    fan_controller.set_speed_guard(config.speed_guard);
+
+    let handler = stop_fan_loop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+
+    println!(
+        "{}",
+        format!("Following the curves of profile {} every {}s. Press Ctrl+C to stop.", config.active_profile, interval.as_secs()).yellow()
+    );
+    let third_fan = fan_controller.fan_count() > 2;
+    let mut software = SoftwareCurve::new(curves);
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        while !FAN_LOOP_STOP.load(Ordering::SeqCst) {
+            let step = software.step(fan_controller)?;
+            if step.changed {
+                let [cpu, gpu, system] = step.speeds;
+                let mut line = format!("CPU {:>3}°C → {:>3}%  GPU {:>3}°C → {:>3}%", step.cpu_temp, cpu, step.gpu_temp, gpu);
+                if third_fan {
+                    line.push_str(&format!("  System → {:>3}%", system));
+                }
+                println!("  {} {}", chrono::Local::now().format("%H:%M:%S").to_string().dimmed(), line);
             }
-        }
 
-        ProfileCommands::Set { name } => {
-            if config.set_active_profile(&name) {
-                config.save()?;
-                println!("{} Active profile set to {}", "✓".green(), name.cyan());
-            } else {
-                println!("{} Profile '{}' not found", "✗".red(), name);
+            let slept = std::time::Instant::now();
+            while slept.elapsed() < interval && !FAN_LOOP_STOP.load(Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(100));
             }
         }
+        Ok(())
+    })();
 
-        ProfileCommands::Create { name, base } => {
-            let scenario = parse_scenario(&base)?;
-            let settings = match scenario {
-                UserScenario::Silent => scenario::ScenarioSettings::silent(),
-                UserScenario::Balanced => scenario::ScenarioSettings::balanced(),
-                UserScenario::HighPerformance => scenario::ScenarioSettings::high_performance(),
-                UserScenario::Turbo => scenario::ScenarioSettings::turbo(),
-                UserScenario::SuperBattery => scenario::ScenarioSettings::super_battery(),
-                UserScenario::Custom => scenario::ScenarioSettings::balanced(),
-            };
-
-            let profile = Profile {
-                name: name.clone(),
-                scenario,
-                settings,
-            };
-
-            config.add_profile(profile);
-            config.save()?;
-            println!("{} Profile '{}' created based on {}", "✓".green(), name.cyan(), base);
-        }
+    fan_controller.set_fan_mode(FanMode::Auto)?;
+    println!("{} Fans returned to automatic control", "✓".green());
+    result
+}
 
-        ProfileCommands::Delete { name } => {
-            if config.remove_profile(&name) {
-                config.save()?;
-                println!("{} Profile '{}' deleted", "✓".green(), name);
-            } else {
-                println!("{} Cannot delete profile '{}' (not found or last profile)", "✗".red(), name);
-            }
-        }
+/// Runs each fan through the self-test duties and reports whether its RPM follows.
+fn cmd_fan_test(fan_controller: &mut FanController, settle: u64, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    if config.software_fan_control.enabled && daemon::query_health().is_ok() {
+        return Err("The daemon follows the fan curves in software (software_fan_control) and would fight the test; turn it off first".into());
+    }
+    fan_controller.set_speed_guard(config.speed_guard);
+    let info = fan_controller.get_fan_info()?;
+    let guard = config.speed_guard;
+    if guard.enabled && info.cpu_temp.max(info.gpu_temp) >= guard.temp_threshold {
+        return Err(format!("Too hot to run fans at 20% (CPU {}°C, GPU {}°C); let the machine cool below {}°C first", info.cpu_temp, info.gpu_temp, guard.temp_threshold).into());
+    }
 
-        ProfileCommands::Save => {
-            println!("{} Current settings saved to active profile", "✓".green());
-            config.save()?;
+    let handler = stop_fan_loop as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+    if !json {
+        print_header("Fan Self-Test");
+        println!("  Each fan runs at {:?}% in turn, the others at 50%. Press Ctrl+C to stop.", fan::selftest::DUTIES);
+        println!();
+    }
+    let settle = std::time::Duration::from_secs(settle.max(2));
+    let result = fan::selftest::run(fan_controller, settle, &FAN_LOOP_STOP, |fan, sample| {
+        if !json {
+            println!("  {:<7} {:>3}% → {:>5} RPM", fan, sample.duty, sample.rpm);
         }
+    });
+    // Hand the fans back to the firmware whatever happened.
+    fan_controller.reset_to_auto()?;
+    let responses = result?;
+    if FAN_LOOP_STOP.load(Ordering::SeqCst) {
+        println!("{}", "Stopped; fans are back on automatic control".yellow());
+        return Ok(());
     }
 
+    if json {
+        println!("{}", serde_json::to_string_pretty(&responses)?);
+        return Ok(());
+    }
+    println!();
+    for response in &responses {
+        let color = if response.verdict == fan::selftest::Verdict::Responds { colored::Color::Green } else { colored::Color::Red };
+        print_status_line(&format!("{} fan", response.fan), &response.verdict.to_string(), color);
+    }
+    if responses.iter().any(|response| response.verdict != fan::selftest::Verdict::Responds) {
+        println!();
+        println!("  Check the fan registers in the model's quirk file (see Supported Hardware in the README).");
+    }
+    println!();
     Ok(())
 }
 
-fn cmd_monitor(interval: u64) -> Result<(), Box<dyn std::error::Error>> {
-    println!("{}", "Starting real-time monitoring. Press Ctrl+C to stop.".yellow());
+/// Ramps both fans up in `step` increments until the user reports hearing them.
+/// Returns the last duty that was still inaudible.
+fn calibrate_noise(fan_controller: &mut FanController, config: &AppConfig, step: u8, settle: u64) -> Result<u8, Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    fan_controller.set_speed_guard(config.speed_guard);
+    print_header("Fan Noise Calibration");
+    println!("  The fans will speed up step by step. Sit where you normally work and");
+    println!("  answer whether you can hear them. Press q to abort; fans return to Auto.");
     println!();
 
+    // Speeds the guard would refuse at the current temperatures are skipped.
+    let info = fan_controller.get_fan_info()?;
+    let mut duty = config.speed_guard.floor(info.cpu_temp).max(config.speed_guard.floor(info.gpu_temp));
+    let mut inaudible = 0;
     loop {
-        print!("\x1B[2J\x1B[1;1H");
+        fan_controller.set_manual_fan_speed(duty, duty)?;
+        std::thread::sleep(std::time::Duration::from_secs(settle));
 
-        print_header("MSI Center Linux - Live Monitor");
+        print!("  Fans at {:>3}% - can you hear them? [y/N/q] ", duty);
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            println!();
+            return Err("Calibration aborted: no answer on standard input".into());
+        }
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => return Ok(inaudible),
+            "q" | "quit" => return Err("Calibration aborted".into()),
+            _ => inaudible = duty,
+        }
 
-        if let Ok(mut fan_controller) = EmbeddedController::new().map(FanController::new) {
-            if let Ok(info) = fan_controller.get_fan_info() {
-                println!("{}", "── System Status ──".green());
-                println!();
+        if duty == 100 {
+            println!("  Inaudible even at full speed");
+            return Ok(100);
+        }
+        duty = duty.saturating_add(step).min(100);
+    }
+}
 
-                let cpu_bar = create_progress_bar(info.cpu_temp as f32, 100.0, 20);
-                let gpu_bar = create_progress_bar(info.gpu_temp as f32, 100.0, 20);
+#[derive(serde::Serialize)]
+struct CurveReport {
+    fan: &'static str,
+    /// Profile the curve comes from (with the saved curve it follows), or "default"
+    /// if the profile doesn't set one.
+    source: String,
+    points: Vec<FanCurvePoint>,
+    #[serde(skip_serializing_if = "CurveTuning::is_empty")]
+    tuning: CurveTuning,
+    #[serde(skip_serializing_if = "CurveDamping::is_empty")]
+    damping: CurveDamping,
+    /// Temperature source of software control, when not the fan's own.
+    #[serde(skip_serializing_if = "TempSource::is_fan")]
+    sensor: TempSource,
+    operating_point: Option<OperatingPoint>,
+    observed_percent: Option<u8>,
+    observed_rpm: Option<u32>,
+}
+
+fn cmd_curve_show(fan: &str, name: Option<&str>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let fans: &[&'static str] = match fan.to_lowercase().as_str() {
+        "cpu" => &["cpu"],
+        "gpu" => &["gpu"],
+        "both" | "all" => &["cpu", "gpu"],
+        _ => return Err(format!("Unknown fan: {}. Use: cpu, gpu, both", fan).into()),
+    };
+
+    let config = AppConfig::load().unwrap_or_default();
+    let settings = config.get_active_profile().map(|p| &p.settings);
+    let info: Option<FanInfo> = EmbeddedController::new().ok().and_then(|ec| FanController::new(ec).get_fan_info().ok());
+
+    let reports: Vec<CurveReport> = fans
+        .iter()
+        .map(|&fan| {
+            let (curve, source) = match name {
+                Some(name) => (named_curve(&config, name, fan)?, format!("curve {}", name)),
+                None => {
+                    let configured = settings.and_then(|s| if fan == "cpu" { s.cpu_fan_curve.clone() } else { s.gpu_fan_curve.clone() });
+                    let named = config.get_active_profile().and_then(|p| if fan == "cpu" { p.cpu_curve.as_ref() } else { p.gpu_curve.as_ref() });
+                    let source = match (&configured, named) {
+                        (Some(_), Some(name)) => format!("{}, curve {}", config.active_profile, name),
+                        (Some(_), None) => config.active_profile.clone(),
+                        (None, _) => "default".to_string(),
+                    };
+                    (configured.unwrap_or_default(), source)
+                }
+            };
+            // The firmware follows the fan's own temperature, software control the curve's source.
+            let own_source = curve.source.is_fan() || !config.software_fan_control.enabled;
+            let reading = info.as_ref().map(|i| {
+                let (temp, percent, rpm) =
+                    if fan == "cpu" { (i.cpu_temp, i.cpu_fan_percent, i.cpu_fan_rpm) } else { (i.gpu_temp, i.gpu_fan_percent, i.gpu_fan_rpm) };
+                (if own_source { temp } else { curve.source.read().unwrap_or(temp) }, percent, rpm)
+            });
+            Ok(CurveReport {
+                fan,
+                source,
+                operating_point: reading.and_then(|(temp, _, _)| curve.operating_point(temp)),
+                observed_percent: reading.map(|(_, percent, _)| percent),
+                observed_rpm: reading.map(|(_, _, rpm)| rpm),
+                points: curve.points,
+                tuning: curve.tuning,
+                damping: curve.damping,
+                sensor: curve.source,
+            })
+        })
+        .collect::<Result<_, Box<dyn std::error::Error>>>()?;
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    print_header("Fan Curves");
+    if let Some(ref info) = info {
+        print_status_line("Fan Mode", &format!("{:?}", info.fan_mode), colored::Color::Cyan);
+        if info.cooler_boost {
+            println!("  {}", "Cooler boost is on and overrides the curves".yellow());
+        }
+        println!();
+    }
+
+    for report in &reports {
+        println!("{}", format!("── {} Fan ({}) ──", report.fan.to_uppercase(), report.source).green());
+        let curve = FanCurve { points: report.points.clone(), ..FanCurve::default() };
+        let marker = fan::chart::OPERATING_POINT.to_string();
+        for line in fan::chart::render(&curve, report.operating_point) {
+            println!("  {}", line.replace(&marker, &marker.yellow().bold().to_string()));
+        }
+        let points: Vec<String> = report.points.iter().map(|p| format!("{}:{}", p.temp, p.speed)).collect();
+        println!("  Points ({}): {}", fan::chart::POINT, points.join(" "));
+        if let Some(op) = report.operating_point {
+            println!("  {}", format!("{} {}°C → {}%", marker, op.temp, op.duty).yellow());
+        }
+        if !report.tuning.is_empty() {
+            let offsets: Vec<String> = report.tuning.offsets.iter().map(|offset| format!("{:+}", offset)).collect();
+            println!(
+                "  Tuning: minimum duty {}%, offsets {}",
+                report.tuning.min_duty.unwrap_or(0),
+                if offsets.is_empty() { "none".to_string() } else { offsets.join(" ") }
+            );
+        }
+        if !report.sensor.is_fan() {
+            println!("  Temperature source (software control): {}", report.sensor);
+        }
+        if !report.damping.is_empty() {
+            println!(
+                "  Software control: hysteresis {}°C, max ramp {}",
+                report.damping.hysteresis,
+                if report.damping.max_ramp == 0 { "none".to_string() } else { format!("{}%/s", report.damping.max_ramp) }
+            );
+        }
+        match (report.operating_point, report.observed_percent, report.observed_rpm) {
+            (Some(op), Some(percent), Some(rpm)) => println!(
+                "  Live: {}°C, curve commands {}%, fan at {}% ({} RPM)",
+                op.temp, op.duty, percent, rpm
+            ),
+            _ => println!("  {}", "Live readings unavailable".dimmed()),
+        }
+        println!();
+    }
+
+    if let Some(profile) = config.get_active_profile()
+        && !profile.curves.is_empty()
+    {
+        let names: Vec<String> = profile
+            .curves
+            .keys()
+            .map(|name| if profile.active_curves.as_ref() == Some(name) { format!("{} (in use)", name) } else { name.clone() })
+            .collect();
+        print_status_line("Presets", &names.join(", "), colored::Color::Cyan);
+        println!();
+    }
+
+    Ok(())
+}
+
+/// The curve `fan curve show --name` charts for `fan`: the active profile's preset by
+/// that name, or the one `fan curve --preset` would use.
+fn named_curve(config: &AppConfig, name: &str, fan: &str) -> Result<FanCurve, Box<dyn std::error::Error>> {
+    let preset = config.get_active_profile().and_then(|p| p.curves.get(name));
+    match preset.and_then(|preset| if fan == "cpu" { preset.cpu.clone() } else { preset.gpu.clone() }) {
+        Some(curve) => Ok(curve),
+        None => preset_curve(name, None),
+    }
+}
+
+#[derive(serde::Serialize)]
+struct TableReport {
+    fan: &'static str,
+    /// The table's points, without those padding the active profile's curve out to the table.
+    points: Vec<FanCurvePoint>,
+    /// Every point as stored.
+    table: Vec<FanCurvePoint>,
+    /// The model's fan tuning registers for the fan; empty where it has none or they are zero.
+    #[serde(skip_serializing_if = "CurveTuning::is_empty")]
+    tuning: CurveTuning,
+    /// Whether the table holds what applying the active profile's curve writes;
+    /// unknown when the profile sets no curve or pins manual speeds.
+    matches_profile: Option<bool>,
+}
+
+fn cmd_curve_get(fan: &str, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let indices: &[usize] = match fan.to_lowercase().as_str() {
+        "cpu" => &[0],
+        "gpu" => &[1],
+        "both" | "all" => &[0, 1],
+        _ => return Err(format!("Unknown fan: {}. Use: cpu, gpu, both", fan).into()),
+    };
+    let config = AppConfig::load().unwrap_or_default();
+    let settings = config.get_active_profile().map(|p| &p.settings);
+    let mut controller = FanController::new(EmbeddedController::new()?);
+    let info = controller.get_fan_info().ok();
+
+    let fans = controller.fan_count();
+    let mut reports = Vec::new();
+    for &index in indices.iter().filter(|&&index| index < fans) {
+        let table = controller.read_fan_curve(index)?;
+        let configured = settings.filter(|s| s.manual_fan_speed.is_none()).and_then(|s| if index == 0 { s.cpu_fan_curve.as_ref() } else { s.gpu_fan_curve.as_ref() });
+        let floor = settings.and_then(|s| s.min_fan_percent).unwrap_or(0);
+        reports.push(TableReport {
+            fan: ["cpu", "gpu"][index],
+            points: table.unpadded(configured, floor),
+            matches_profile: configured.map(|curve| curve.held_by(&table, floor, controller.has_tuning(index))),
+            table: table.points,
+            tuning: table.tuning,
+        });
+    }
+    if reports.is_empty() {
+        return Err(format!("This model has no {} fan", fan.to_uppercase()).into());
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+        return Ok(());
+    }
+
+    print_header("Fan Tables");
+    if let Some(ref info) = info {
+        print_status_line("Fan Mode", &format!("{:?}", info.fan_mode), colored::Color::Cyan);
+        if info.fan_mode != FanMode::Advanced {
+            println!("  {}", "The firmware only follows the tables in Advanced mode".yellow());
+        }
+        println!();
+    }
+    for report in &reports {
+        println!("{}", format!("── {} Fan ──", report.fan.to_uppercase()).green());
+        let curve = FanCurve { points: report.points.clone(), ..FanCurve::default() };
+        for line in fan::chart::render(&curve, None) {
+            println!("  {}", line);
+        }
+        let points: Vec<String> = report.points.iter().map(|p| format!("{}:{}", p.temp, p.speed)).collect();
+        println!("  Points ({}): {}", fan::chart::POINT, points.join(" "));
+        if let Some(min_duty) = report.tuning.min_duty {
+            println!("  Minimum duty: {}%", min_duty);
+        }
+        if !report.tuning.offsets.is_empty() {
+            let offsets: Vec<String> = report.tuning.offsets.iter().map(|offset| format!("{:+}", offset)).collect();
+            println!("  Duty offsets: {}", offsets.join(" "));
+        }
+        match report.matches_profile {
+            Some(true) => println!("  {}", format!("Matches profile '{}'", config.active_profile).green()),
+            Some(false) => println!("  {}", format!("Differs from profile '{}'; run `msi-center apply` to write it", config.active_profile).yellow()),
+            None => {}
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// The profile a `fan curve use|save|remove` names.
+fn curve_profile<'a>(config: &'a mut AppConfig, name: &str) -> Result<&'a mut Profile, Box<dyn std::error::Error>> {
+    config.profiles.iter_mut().find(|p| p.name == name).ok_or_else(|| format!("Profile '{}' not found", name).into())
+}
+
+/// Uses the profile's own preset `name` if it has one, otherwise makes `fan` follow the saved curve `name`.
+fn cmd_curve_use(profile_name: &str, name: &str, fan: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
+    let is_active = config.active_profile == profile_name;
+    let (cpu, gpu) = match fan.to_lowercase().as_str() {
+        "cpu" => (true, false),
+        "gpu" => (false, true),
+        "both" => (true, true),
+        _ => return Err(format!("Unknown fan: {}. Use: cpu, gpu, both", fan).into()),
+    };
+    let profile = curve_profile(&mut config, profile_name)?;
+    let used = profile.use_curves(name) || config.follow_named_curve(profile_name, name, cpu, gpu);
+    if !used {
+        let profile = curve_profile(&mut config, profile_name)?;
+        let known: Vec<&str> = profile.curves.keys().map(String::as_str).collect();
+        let hint = if known.is_empty() { "it has none yet".to_string() } else { format!("available: {}", known.join(", ")) };
+        return Err(format!("Profile '{}' has no curve preset '{}' ({}), and there is no saved curve by that name", profile_name, name, hint).into());
+    }
+    let settings = curve_profile(&mut config, profile_name)?.settings.clone();
+    config.save()?;
+    println!("{} Profile {} now uses curves {}", "✓".green(), profile_name.cyan(), name.cyan());
+
+    if is_active {
+        let mut fan_controller = FanController::new(EmbeddedController::new()?);
+        fan_controller.set_speed_guard(config.speed_guard);
+        fan_controller.set_fan_mode(FanMode::Advanced)?;
+        if let Some(curve) = settings.cpu_fan_curve {
+            fan_controller.set_cpu_fan_curve(curve)?;
+        }
+        if let Some(curve) = settings.gpu_fan_curve {
+            fan_controller.set_gpu_fan_curve(curve)?;
+        }
+        println!("{} Applied to the fans", "✓".green());
+    }
+    Ok(())
+}
+
+fn cmd_curve_save(profile_name: &str, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
+    let profile = curve_profile(&mut config, profile_name)?;
+    if profile.settings.cpu_fan_curve.is_none() && profile.settings.gpu_fan_curve.is_none() {
+        return Err(format!("Profile '{}' has no fan curves to save", profile_name).into());
+    }
+    profile.save_curves(name);
+    config.save()?;
+    println!("{} Curves of profile {} saved as {}", "✓".green(), profile_name.cyan(), name.cyan());
+    Ok(())
+}
+
+/// The curve `fan curve --preset` stands for: a built-in one, `points` for `custom`, or a saved curve.
+fn preset_curve(preset: &str, points: Option<String>) -> Result<FanCurve, Box<dyn std::error::Error>> {
+    Ok(match preset {
+        "silent" => FanCurve::silent(),
+        "balanced" | "default" => FanCurve::default(),
+        "performance" => FanCurve::performance(),
+        "custom" => {
+            if let Some(pts) = points {
+                parse_curve_points(&pts)?
+            } else {
+                return Err("Custom curve requires --points argument".into());
+            }
+        }
+        name => match AppConfig::load().unwrap_or_default().fan_curves.remove(name) {
+            Some(curve) => curve,
+            None => {
+                return Err(format!("Unknown preset: {}. Use: silent, balanced, performance, custom, or a saved curve (fan curve list)", preset).into());
+            }
+        },
+    })
+}
+
+/// Names `fan curve --preset` gives its built-in curves, which saved curves can't shadow.
+const BUILTIN_CURVES: [&str; 5] = ["silent", "balanced", "default", "performance", "custom"];
+
+fn cmd_curve_save_named(name: &str, points: &str, source: TempSource) -> Result<(), Box<dyn std::error::Error>> {
+    if BUILTIN_CURVES.contains(&name) {
+        return Err(format!("'{}' is a built-in preset; pick another name", name).into());
+    }
+    let mut config = AppConfig::load()?;
+    let curve = FanCurve { source, ..parse_curve_points(points)? };
+    curve
+        .validate(ec::quirks::capabilities().curve_points as usize, &config.speed_guard)
+        .map_err(|e| format!("Invalid fan curve: {}", e))?;
+    let replaced = config.fan_curves.contains_key(name);
+    config.save_named_curve(name, curve);
+    config.save()?;
+    println!("{} Curve {} {}", "✓".green(), name.cyan(), if replaced { "updated" } else { "saved" });
+
+    let followers: Vec<&str> = config
+        .profiles
+        .iter()
+        .filter(|p| p.cpu_curve.as_deref() == Some(name) || p.gpu_curve.as_deref() == Some(name))
+        .map(|p| p.name.as_str())
+        .collect();
+    if !followers.is_empty() {
+        println!("  Followed by {}; apply the profile again to update the fans", followers.join(", "));
+    }
+    Ok(())
+}
+
+fn cmd_curve_remove_named(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
+    if !config.remove_named_curve(name) {
+        return Err(format!("No saved curve '{}'; to remove a profile's preset, name the profile and the preset", name).into());
+    }
+    config.save()?;
+    println!("{} Curve {} removed; profiles that followed it keep their copy", "✓".green(), name.cyan());
+    Ok(())
+}
+
+fn cmd_curve_list() -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+
+    print_header("Saved Curves");
+    if config.fan_curves.is_empty() {
+        println!("  {}", "None yet; add one with: fan curve save <name> --points 40:0,60:40,80:80,90:100".dimmed());
+    }
+    for (name, curve) in &config.fan_curves {
+        let points: Vec<String> = curve.points.iter().map(|p| format!("{}:{}", p.temp, p.speed)).collect();
+        println!("  {}  {}", name.cyan(), points.join(","));
+        for profile in &config.profiles {
+            let fans: Vec<&str> = [("CPU", &profile.cpu_curve), ("GPU", &profile.gpu_curve)]
+                .into_iter()
+                .filter(|(_, reference)| reference.as_deref() == Some(name.as_str()))
+                .map(|(fan, _)| fan)
+                .collect();
+            if !fans.is_empty() {
+                println!("    {} fan of profile {}", fans.join(" and "), profile.name);
+            }
+        }
+    }
+    println!();
+
+    print_header("Profile Presets");
+    let mut any = false;
+    for profile in config.profiles.iter().filter(|p| !p.curves.is_empty()) {
+        let names: Vec<&str> = profile.curves.keys().map(String::as_str).collect();
+        print_status_line(&profile.name, &names.join(", "), colored::Color::White);
+        any = true;
+    }
+    if !any {
+        println!("  {}", "None yet; add one with: fan curve save <profile> <name>".dimmed());
+    }
+    println!();
+    Ok(())
+}
+
+fn cmd_curve_remove(profile_name: &str, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
+    if !curve_profile(&mut config, profile_name)?.remove_curves(name) {
+        return Err(format!("Profile '{}' has no curve preset '{}'", profile_name, name).into());
+    }
+    config.save()?;
+    println!("{} Curve preset {} removed from profile {}", "✓".green(), name.cyan(), profile_name.cyan());
+    Ok(())
+}
+
+fn cmd_curve_simulate(
+    curves: &[(String, FanCurve)],
+    trace: &Path,
+    settings: SimulationSettings,
+    output: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if output.is_some() && curves.len() > 1 {
+        return Err("--output can only be used with a single --curve".into());
+    }
+
+    let trace_samples = simulate::load_trace(trace)?;
+    print_header("Fan Curve Simulation");
+    println!(
+        "  Trace: {} ({} samples), smoothing {}s, hysteresis {}°C",
+        trace.display(),
+        trace_samples.len(),
+        settings.smoothing,
+        settings.hysteresis
+    );
+    println!();
+
+    println!(
+        "  {:<24} {:>8} {:>8} {:>9} {:>9} {:>7}   {}",
+        "Curve".bold(),
+        "Avg %".bold(),
+        "Max %".bold(),
+        "Avg dBA".bold(),
+        "Peak dBA".bold(),
+        "Steps".bold(),
+        "Time <25 / 25-35 / 35-42 / >42 dBA".bold()
+    );
+
+    for (name, curve) in curves {
+        let samples = simulate::simulate(curve, &trace_samples, settings);
+        let summary = simulate::summarize(&samples);
+
+        let bands: Vec<String> = summary
+            .noise_bands
+            .iter()
+            .map(|seconds| format!("{:.0}%", seconds / summary.duration.max(f32::EPSILON) * 100.0))
+            .collect();
+        println!(
+            "  {:<24} {:>8.1} {:>8} {:>9.1} {:>9.1} {:>7}   {}",
+            name,
+            summary.average_duty,
+            summary.max_duty,
+            summary.average_dba,
+            summary.peak_dba,
+            summary.audible_steps,
+            bands.join(" / ")
+        );
+
+        if let Some(ref output) = output {
+            let mut csv = String::from("time,temp,smoothed_temp,duty,noise_dba\n");
+            for sample in &samples {
+                csv.push_str(&format!(
+                    "{},{},{:.1},{},{:.1}\n",
+                    sample.time, sample.temp, sample.smoothed_temp, sample.duty, sample.noise_dba
+                ));
+            }
+            std::fs::write(output, csv)?;
+        } else if curves.len() == 1 {
+            println!();
+            println!("  {:<10} {:>6} {:>6}", "Time (s)".bold(), "Temp".bold(), "Duty".bold());
+            let mut last_duty = None;
+            for sample in &samples {
+                if last_duty != Some(sample.duty) {
+                    println!("  {:<10.1} {:>5.0}° {:>5}%", sample.time, sample.smoothed_temp, sample.duty);
+                    last_duty = Some(sample.duty);
+                }
+            }
+        }
+    }
+
+    if let Some(output) = output {
+        println!();
+        println!("{} Duty over time written to {}", "✓".green(), output.display());
+    }
+    println!();
+    Ok(())
+}
+
+/// Prints the duty each curve would run at `temps`, as `fan curve` would write it: raised to
+/// the active profile's minimum, and checked against the speed guard.
+fn cmd_curve_evaluate(curves: &[(String, FanCurve)], temps: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    let floor = config.min_fan_percent();
+    let guard = config.speed_guard;
+    let curves: Vec<(&str, FanCurve)> = curves.iter().map(|(name, curve)| (name.as_str(), curve.floored(floor))).collect();
+    let widths: Vec<usize> = curves.iter().map(|(name, _)| name.chars().count().max(6)).collect();
+
+    print_header("Fan Curve Simulation");
+    if floor > 0 {
+        println!("  Duties raised to the active profile's minimum of {}%", floor);
+        println!();
+    }
+    print!("  {:>6}", "Temp".bold());
+    for ((name, _), width) in curves.iter().zip(&widths) {
+        print!("  {:>width$}", name.bold(), width = width);
+    }
+    println!();
+
+    let mut below_guard = false;
+    for &temp in temps {
+        print!("  {:>5}°", temp);
+        for ((_, curve), width) in curves.iter().zip(&widths) {
+            let duty = curve.get_speed_for_temp(temp);
+            // Padded before coloring, which would count the escape codes.
+            if duty < guard.floor(temp) {
+                below_guard = true;
+                print!("  {}", format!("{:>width$}", format!("{}%!", duty), width = width).yellow());
+            } else {
+                print!("  {:>width$}", format!("{}%", duty), width = width);
+            }
+        }
+        println!();
+    }
+    if below_guard {
+        println!();
+        println!("  {}", "! below the speed guard's floor at that temperature".yellow());
+    }
+
+    println!();
+    let max_points = ec::quirks::capabilities().curve_points as usize;
+    for (name, curve) in &curves {
+        match curve.validate(max_points, &guard) {
+            Ok(()) => println!("  {} {} would be accepted by fan curve", "✓".green(), name.cyan()),
+            Err(e) => println!("  {} {} would be refused: {}", "✗".red(), name.cyan(), e),
+        }
+    }
+    println!();
+    Ok(())
+}
+
+fn cmd_scenario(action: ScenarioCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    fan_controller.set_speed_guard(config.speed_guard);
+    let mut manager = ScenarioManager::new(&mut fan_controller);
+    manager.set_deferred_controls(&config.deferred_controls);
+
+    match action {
+        ScenarioCommands::Status => {
+            let info = manager.get_current_info()?;
+            print_header("Current Scenario");
+            print_status_line("Scenario", &info.current_scenario.to_string(), colored::Color::Cyan);
+            print_status_line("Shift Mode", &info.shift_mode.to_string(), colored::Color::Yellow);
+            print_status_line("Super Battery", if info.super_battery { "ON" } else { "OFF" }, colored::Color::Green);
+            for (feature, on) in &info.super_battery_features {
+                print_status_line(&format!("  {}", feature), if *on { "ON" } else { "OFF" }, colored::Color::Green);
+            }
+            if let Some(percent) = info.lid_logo {
+                print_status_line("Lid Logo", &lid_logo_label(percent), colored::Color::White);
+            }
+            if let Some(indicator) = info.indicator_led {
+                print_status_line("Indicator LED", &indicator.to_string(), colored::Color::White);
+            }
+            let available: Vec<String> = ShiftMode::available().iter().map(ToString::to_string).collect();
+            print_status_line("Available Shift Modes", &available.join(", "), colored::Color::White);
+            println!();
+        }
+
+        ScenarioCommands::List => {
+            print_header("Available Scenarios");
+            for scenario in ScenarioManager::get_available_scenarios() {
+                println!("  • {}", scenario.to_string().cyan());
+            }
+            println!();
+        }
+
+        ScenarioCommands::Set { scenario } => {
+            manager.set_scenario(scenario)?;
+            println!("{} Scenario set to {}", "✓".green(), scenario);
+            sync_tuned(&config, scenario);
+        }
+
+        ScenarioCommands::Shift { mode } => {
+            manager.set_shift_mode(mode)?;
+            println!("{} Shift mode set to {}", "✓".green(), mode);
+        }
+
+        ScenarioCommands::SuperBattery { enabled, feature: None } => {
+            manager.set_super_battery(enabled)?;
+            println!("{} Super battery {}", "✓".green(), if enabled { "enabled" } else { "disabled" });
+        }
+
+        ScenarioCommands::SuperBattery { enabled, feature: Some(feature) } => {
+            manager.set_super_battery_feature(feature, enabled)?;
+            println!("{} {} {}", "✓".green(), feature, if enabled { "enabled" } else { "disabled" });
+        }
+
+        ScenarioCommands::LidLogo { brightness } => {
+            manager.set_lid_logo(brightness)?;
+            println!("{} Lid logo {}", "✓".green(), lid_logo_label(brightness).to_lowercase());
+        }
+
+        ScenarioCommands::IndicatorLed { off_on_battery, blink_below, profile } => {
+            let settings = IndicatorSettings { off_on_battery, blink_below };
+            if settings.is_empty() {
+                let current = manager.indicator_led()?;
+                print_header("Indicator LED");
+                if let Some(off) = current.off_on_battery {
+                    print_status_line("Off on Battery", if off { "YES" } else { "NO" }, colored::Color::White);
+                }
+                if let Some(percent) = current.blink_below {
+                    print_status_line("Blinks Below", &format!("{}%", percent), colored::Color::White);
+                }
+                println!();
+                return Ok(());
+            }
+            manager.set_indicator_led(&settings)?;
+            println!("{} Indicator LED: {}", "✓".green(), settings);
+            if profile {
+                let mut config = config;
+                let active = config.active_profile.clone();
+                let stored = config.profiles.iter_mut().find(|p| p.name == active).ok_or_else(|| format!("Profile '{}' not found", active))?;
+                let indicator = &mut stored.settings.indicator_led;
+                indicator.off_on_battery = off_on_battery.or(indicator.off_on_battery);
+                indicator.blink_below = blink_below.or(indicator.blink_below);
+                config.save()?;
+                println!("  Stored in profile '{}'", active);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_profile(action: ProfileCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
+
+    match action {
+        ProfileCommands::List => {
+            print_header("Profiles");
+            for profile in &config.profiles {
+                let marker = if profile.name == config.active_profile { "►" } else { " " };
+                println!("  {} {} ({})", marker.green(), profile.name.cyan(), profile.scenario);
+            }
+            println!();
+        }
+
+        ProfileCommands::Active => {
+            if let Some(profile) = config.get_active_profile() {
+                print_header("Active Profile");
+                print_status_line("Name", &profile.name, colored::Color::Cyan);
+                print_status_line("Scenario", &profile.scenario.to_string(), colored::Color::Yellow);
+                print_status_line("Shift Mode", &profile.settings.shift_mode.to_string(), colored::Color::White);
+                print_status_line("Fan Mode", &format!("{:?}", profile.settings.fan_mode), colored::Color::White);
+                print_status_line("Cooler Boost", if profile.settings.cooler_boost { "ON" } else { "OFF" }, colored::Color::White);
+                if let Some(speed) = profile.settings.manual_fan_speed {
+                    let system = speed.system.map(|system| format!(", System {}%", system)).unwrap_or_default();
+                    print_status_line("Manual Speed", &format!("CPU {}%, GPU {}%{}", speed.cpu, speed.gpu, system), colored::Color::White);
+                }
+                if let Some(rule) = profile.emergency_cooling {
+                    print_status_line("Emergency Cooling", &rule.to_string(), colored::Color::White);
+                }
+                if let Some(percent) = profile.settings.min_fan_percent {
+                    print_status_line("Minimum Fan", &format!("{}%", percent), colored::Color::White);
+                }
+                let alerts = config.alert_settings();
+                print_status_line("Temp Warning", &format!("{}°C", alerts.temp_warning), colored::Color::White);
+                print_status_line("Notifications", if alerts.show_notifications { "ON" } else { "OFF" }, colored::Color::White);
+                println!();
+            } else {
+                println!("{}", "No active profile found".yellow());
+            }
+        }
+
+        ProfileCommands::Set { name } => {
+            if config.set_active_profile(&name) {
+                config.save()?;
+                println!("{} Active profile set to {}", "✓".green(), name.cyan());
+            } else {
+                println!("{} Profile '{}' not found", "✗".red(), name);
+            }
+        }
+
+        ProfileCommands::Create { name, base } => {
+            let scenario = parse_scenario(&base)?;
+            let settings = match scenario {
+                UserScenario::Silent => scenario::ScenarioSettings::silent(),
+                UserScenario::Balanced => scenario::ScenarioSettings::balanced(),
+                UserScenario::HighPerformance => scenario::ScenarioSettings::high_performance(),
+                UserScenario::Turbo => scenario::ScenarioSettings::turbo(),
+                UserScenario::SuperBattery => scenario::ScenarioSettings::super_battery(),
+                UserScenario::Custom => scenario::ScenarioSettings::balanced(),
+            };
+
+            let profile = Profile {
+                name: name.clone(),
+                scenario,
+                settings,
+                alerts: AlertOverrides::default(),
+                hooks: ProfileHooks::default(),
+                ambient_light: None,
+                emergency_cooling: None,
+                curves: Default::default(),
+                active_curves: None,
+                cpu_curve: None,
+                gpu_curve: None,
+            };
+
+            config.add_profile(profile);
+            config.save()?;
+            println!("{} Profile '{}' created based on {}", "✓".green(), name.cyan(), base);
+        }
+
+        ProfileCommands::Delete { name } => {
+            if config.remove_profile(&name) {
+                config.save()?;
+                println!("{} Profile '{}' deleted", "✓".green(), name);
+            } else {
+                println!("{} Cannot delete profile '{}' (not found or last profile)", "✗".red(), name);
+            }
+        }
+
+        ProfileCommands::Save => {
+            let name = config.active_profile.clone();
+            let profile = config.profiles.iter_mut().find(|p| p.name == name).ok_or_else(|| format!("Profile '{}' not found", name))?;
+            // The curves the fan tables hold, which may have been set since the profile was.
+            let hardware = EmbeddedController::new().map_err(|e| e.to_string()).and_then(|ec| {
+                let mut fan_controller = FanController::new(ec);
+                ScenarioManager::new(&mut fan_controller).hardware_settings(&profile.settings).map_err(|e| e.to_string())
+            });
+            match hardware {
+                Ok(hardware) => {
+                    profile.settings.cpu_fan_curve = hardware.cpu_fan_curve;
+                    profile.settings.gpu_fan_curve = hardware.gpu_fan_curve;
+                }
+                Err(e) => println!("  {}", format!("Fan curves not read from the EC: {}", e).dimmed()),
+            }
+            println!("{} Current settings saved to active profile", "✓".green());
+            config.save()?;
+        }
+
+        ProfileCommands::Export { name } => {
+            let name = name.unwrap_or_else(|| config.active_profile.clone());
+            let profile = config.get_profile(&name).ok_or_else(|| format!("Profile '{}' not found", name))?;
+            println!("{}", serde_json::to_string_pretty(profile)?);
+        }
+
+        ProfileCommands::Import { activate, force } => {
+            let mut input = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut input)?;
+            let profile: Profile = serde_json::from_str(&input).map_err(|e| format!("Invalid profile JSON: {}", e))?;
+            let name = profile.name.clone();
+            let mut guard = config.speed_guard;
+            if force {
+                guard.enabled = false;
+            }
+            let problems = profile.curve_problems(ec::quirks::capabilities().curve_points as usize, &guard);
+            if !problems.is_empty() {
+                return Err(format!("Profile '{}' has invalid fan curves (--force accepts those the speed guard refuses):\n  {}", name, problems.join("\n  ")).into());
+            }
+
+            // Replace in place so automation rules naming it keep working.
+            match config.profiles.iter_mut().find(|p| p.name == name) {
+                Some(existing) => *existing = profile,
+                None => config.add_profile(profile),
+            }
+            if activate {
+                config.set_active_profile(&name);
+            }
+            config.save()?;
+            println!("{} Profile '{}' imported", "✓".green(), name.cyan());
+
+            if activate {
+                return cmd_apply();
+            }
+        }
+
+        ProfileCommands::EmergencyCooling { enabled, above, below, profile } => {
+            let name = profile.unwrap_or_else(|| config.active_profile.clone());
+            let stored = config.profiles.iter_mut().find(|p| p.name == name).ok_or_else(|| format!("Profile '{}' not found", name))?;
+            if !enabled {
+                stored.emergency_cooling = None;
+                config.save()?;
+                println!("{} Emergency cooling off for profile '{}'", "✓".green(), name.cyan());
+                return Ok(());
+            }
+            let current = stored.emergency_cooling.unwrap_or_default();
+            let rule = EmergencyCooling { on_above: above.unwrap_or(current.on_above), off_below: below.unwrap_or(current.off_below) };
+            rule.validate()?;
+            stored.emergency_cooling = Some(rule);
+            config.save()?;
+            println!("{} Emergency cooling for profile '{}': Cooler Boost {}", "✓".green(), name.cyan(), rule);
+            if daemon::query_health().is_err() {
+                println!("  {}", "The daemon isn't running, and it is the one that watches the temperatures".yellow());
+            }
+        }
+
+        ProfileCommands::MinFan { percent, profile } => {
+            if percent > 100 {
+                return Err(format!("Invalid fan speed: {}", percent).into());
+            }
+            let name = profile.unwrap_or_else(|| config.active_profile.clone());
+            let stored = config.profiles.iter_mut().find(|p| p.name == name).ok_or_else(|| format!("Profile '{}' not found", name))?;
+            stored.settings.min_fan_percent = (percent > 0).then_some(percent);
+            config.save()?;
+            if percent == 0 {
+                println!("{} Profile '{}' has no minimum fan speed", "✓".green(), name.cyan());
+            } else {
+                println!("{} Fans under profile '{}' run at {}% or more", "✓".green(), name.cyan(), percent);
+            }
+            if name == config.active_profile {
+                println!("  Run {} to write it now", "msi-center apply".cyan());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_history(action: HistoryCommands) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        HistoryCommands::Stats { json } => {
+            let now = chrono::Local::now().timestamp();
+            let stats = stats::stats(&stats::load(now - 7 * stats::DAY)?, now);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+                return Ok(());
+            }
+
+            print_header("Last 24 Hours");
+            let temp = |temp: Option<u8>| temp.map(|t| format!("{}°C", t)).unwrap_or_else(|| "no samples".to_string());
+            print_status_line("Max CPU Temp", &temp(stats.max_cpu_temp_24h), colored::Color::Yellow);
+            print_status_line("Max GPU Temp", &temp(stats.max_gpu_temp_24h), colored::Color::Yellow);
+            println!();
+
+            print_header("Last 7 Days");
+            print_status_line("Thermal Alerts", &stats.thermal_alerts_7d.to_string(),
+                if stats.thermal_alerts_7d > 0 { colored::Color::Red } else { colored::Color::Green });
+            for (profile, duty) in &stats.duty_by_profile_7d {
+                let hours = duty.samples as f32 * stats::SAMPLE_INTERVAL.as_secs_f32() / 3600.0;
+                print_status_line(profile, &format!("CPU fan {:.0}%, GPU fan {:.0}% (over {:.1} h)", duty.cpu_fan_percent, duty.gpu_fan_percent, hours),
+                    colored::Color::White);
+            }
+            println!();
+
+            if stats.duty_by_profile_7d.is_empty() {
+                let config = AppConfig::load().unwrap_or_default();
+                if config.history_days == 0 {
+                    println!("{}", "History is off; set history_days in the config to record it".yellow());
+                } else if daemon::query_health().is_err() {
+                    println!("{}", "The daemon records the history, and it isn't running".yellow());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+static MONITOR_STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn stop_monitor(_signal: libc::c_int) {
+    MONITOR_STOP.store(true, Ordering::SeqCst);
+}
+
+fn cmd_monitor(interval: u64, duration: Option<u64>, inhibit_idle: bool) -> Result<(), Box<dyn std::error::Error>> {
+    println!("{}", "Starting real-time monitoring. Press Ctrl+C to stop.".yellow());
+    println!();
+
+    // A suspend halfway through would spoil the session's score.
+    let inhibitor = if inhibit_idle {
+        match IdleInhibitor::acquire("Monitoring session in progress") {
+            Ok(inhibitor) => Some(inhibitor),
+            Err(e) => {
+                println!("{} {}; the machine may still suspend", "⚠".yellow(), e);
+                std::thread::sleep(std::time::Duration::from_secs(2));
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let handler = stop_monitor as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+    }
+
+    let mut stats = SessionStats::default();
+    let config = AppConfig::load().unwrap_or_default();
+    let palette = config.color_palette;
+    let mut failure_watch = FailureWatch::default();
+    let interval = std::time::Duration::from_secs(interval);
+    let duration = duration.map(std::time::Duration::from_secs);
+
+    while !MONITOR_STOP.load(Ordering::SeqCst) && duration.is_none_or(|d| stats.duration() < d) {
+        print!("\x1B[2J\x1B[1;1H");
+
+        print_header("MSI Center Linux - Live Monitor");
+
+        if let Ok(mut fan_controller) = EmbeddedController::new().map(FanController::new)
+            && let Ok(info) = fan_controller.get_fan_info() {
+            stats.record(&info);
+            let stalled = failure_watch.update(&info, &config.fan_failure, std::time::Instant::now());
+            for fan in &stalled {
+                println!("{}", format!("⚠ {}; it may have failed", fan).red().bold());
+            }
+            if !stalled.is_empty() {
+                println!();
+            }
+            println!("{}", "── System Status ──".green());
+            println!();
+
+            let cpu_bar = create_progress_bar(info.cpu_temp as f32, 100.0, 20, palette);
+            let gpu_bar = create_progress_bar(info.gpu_temp as f32, 100.0, 20, palette);
+
+            println!("  CPU Temp: {:>3}°C {}", info.cpu_temp, cpu_bar);
+            println!("  GPU Temp: {:>3}°C {}", info.gpu_temp, gpu_bar);
+            if let Some(gpu) = info.gpu {
+                if let Some(hotspot) = gpu.hotspot {
+                    let memory = gpu.memory.map_or(String::new(), |memory| format!("  |  Memory: {}°C", memory));
+                    println!("  Hotspot:  {:>3}°C {}{}", hotspot, create_progress_bar(hotspot as f32, 100.0, 20, palette), memory);
+                }
+                let load = gpu.utilization.map_or("n/a".to_string(), |load| format!("{}%", load));
+                match (gpu.fan_percent, gpu.fan_rpm) {
+                    (Some(fan), Some(rpm)) => println!("  GPU Load: {:>4}  |  GPU-reported fan: {} RPM ({}%)", load, rpm, fan),
+                    (Some(fan), None) => println!("  GPU Load: {:>4}  |  GPU-reported fan: {}%", load, fan),
+                    _ => println!("  GPU Load: {:>4}", load),
+                }
+            }
+            println!();
+
+            for fan in &info.fans {
+                let bar = create_progress_bar(fan.percent as f32, 100.0, 20, palette);
+                let target = match fan.target_percent {
+                    Some(target) if fan.off_target() => format!("  target {}%", target).yellow().to_string(),
+                    Some(target) => format!("  target {}%", target).dimmed().to_string(),
+                    None => String::new(),
+                };
+                println!("  {:<9} {:>5} RPM {:>3}% {}{}", format!("{} Fan:", fan.name), fan.rpm, fan.percent, bar, target);
+            }
+            println!();
+
+            println!("  Mode: {:?}  |  Cooler Boost: {}", 
+                info.fan_mode,
+                if info.cooler_boost { "ON".red() } else { "OFF".green() }
+            );
+        }
+
+        println!();
+        match inhibitor {
+            Some(ref inhibitor) => println!(
+                "{}",
+                format!("Refreshing every {}s... Idle and suspend inhibited ({})", interval.as_secs(), inhibitor.holders().join(", ")).dimmed()
+            ),
+            None => println!("{}", format!("Refreshing every {}s...", interval.as_secs()).dimmed()),
+        }
+
+        // Sleep in slices so Ctrl+C ends the session promptly.
+        let slept = std::time::Instant::now();
+        while slept.elapsed() < interval && !MONITOR_STOP.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+    }
+
+    drop(inhibitor);
+    print_session_summary(&stats);
+    Ok(())
+}
+
+fn print_session_summary(stats: &SessionStats) {
+    println!();
+    println!("{}", "── Session Summary ──".green());
+    let Some(score) = stats.score() else {
+        println!("  No readings were taken.");
+        return;
+    };
+    let minutes = stats.duration().as_secs() / 60;
+    let seconds = stats.duration().as_secs() % 60;
+    let (peak_cpu, peak_gpu) = stats.peak_temps();
+    println!("  Duration:        {}m {:02}s", minutes, seconds);
+    match stats.average_clock_mhz() {
+        Some(clock) => println!("  Avg CPU Clock:   {} MHz", clock),
+        None => println!("  Avg CPU Clock:   n/a"),
+    }
+    match stats.throttled_fraction() {
+        Some(fraction) => println!("  Time Throttled:  {:.0}%", fraction * 100.0),
+        None => println!("  Time Throttled:  n/a"),
+    }
+    println!("  Peak Temps:      CPU {}°C, GPU {}°C", peak_cpu, peak_gpu);
+    println!("  Avg Fan Duty:    {}%", stats.average_duty().unwrap_or(0));
+    println!("  {}           {}", "Score:".bold(), format!("{} / 1000", score).bold());
+}
+
+fn create_progress_bar(value: f32, max: f32, width: usize, palette: ColorPalette) -> String {
+    let ratio = (value / max).clamp(0.0, 1.0);
+    let filled = (ratio * width as f32) as usize;
+    let empty = width - filled;
+
+    let level = if ratio < 0.5 {
+        TempLevel::Normal
+    } else if ratio < 0.75 {
+        TempLevel::Warm
+    } else {
+        TempLevel::Hot
+    };
+    let color = level_color(level, palette);
+
+    format!(
+        "[{}{}]",
+        "█".repeat(filled).color(color),
+        "░".repeat(empty).dimmed()
+    )
+}
+
+fn cmd_install(prefix: &Path, destdir: Option<&Path>, init: Option<InitSystem>, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let layout = Layout::detect(prefix, init.unwrap_or_else(InitSystem::detect));
+    let artifacts = layout.artifacts(&install::binaries()?);
+
+    print_header(if dry_run { "Install (dry run)" } else { "Install" });
+    print_status_line("Prefix", &layout.prefix.display().to_string(), colored::Color::Cyan);
+    print_status_line("Init System", &layout.init.to_string(), colored::Color::Cyan);
+    if let Some(root) = destdir {
+        print_status_line("Staged Under", &root.display().to_string(), colored::Color::Cyan);
+    }
+    println!();
+    for artifact in &artifacts {
+        let target = artifact.target(destdir);
+        if dry_run {
+            println!("  {} {} ({:o})", "→".cyan(), target.display(), artifact.mode);
+            continue;
+        }
+        artifact.write(destdir)?;
+        println!("  {} {}", "✓".green(), target.display());
+    }
+    println!();
+    if dry_run || destdir.is_some() {
+        return Ok(());
+    }
+    ec::snapshot::record_baseline_if_missing();
+
+    println!("{}", "Next steps:".bold());
+    match layout.init {
+        InitSystem::Systemd => println!("  {}", "sudo systemctl daemon-reload && sudo systemctl enable --now msi-center".cyan()),
+        InitSystem::OpenRc => println!("  {}", "sudo rc-update add msi-center default && sudo rc-service msi-center start".cyan()),
+        InitSystem::Other => println!("  Start '{} daemon run' as root at boot", layout.bin_dir().join("msi-center").display()),
+    }
+    println!("  {}", "sudo udevadm control --reload".cyan());
+    println!("  For EC writes through debugfs: {}", "sudo msi-center ec enable-writes --persist".cyan());
+    Ok(())
+}
+
+fn cmd_apply() -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load()?;
+
+    if let Some(profile) = config.get_active_profile() {
+        let mut fan_controller = FanController::new(EmbeddedController::new()?);
+        fan_controller.set_speed_guard(config.speed_guard);
+        let mut manager = ScenarioManager::new(&mut fan_controller);
+        manager.set_deferred_controls(&config.deferred_controls);
+
+        let mut settings = profile.settings.clone();
+        let overrides = overrides::Overrides::load();
+        overrides.apply_to(&mut settings);
+        hooks::with_profile_hooks(profile, None, || -> Result<(), Box<dyn std::error::Error>> {
+            Ok(manager.apply_settings(&settings)?)
+        })?;
+
+        println!("{} Applied profile: {}", "✓".green(), profile.name.cyan());
+        for o in overrides.active() {
+            println!("  {} {} (override, {})", "!".yellow(), o.setting, o.until_display());
+        }
+        println!("  Scenario: {}", profile.scenario);
+        println!("  Shift Mode: {}", profile.settings.shift_mode);
+        println!("  Fan Mode: {:?}", profile.settings.fan_mode);
+        println!("  Cooler Boost: {}", if profile.settings.cooler_boost { "ON" } else { "OFF" });
+        sync_tuned(&config, profile.scenario);
+    } else {
+        println!("{} No active profile found", "✗".red());
+    }
+
+    Ok(())
+}
+
+fn cmd_travel(enabled: Option<bool>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(enabled) = enabled else {
+        let state = if travel::is_on() { "ON".green() } else { "OFF".normal() };
+        println!("Travel mode: {}", state);
+        return Ok(());
+    };
+
+    let mut config = AppConfig::load()?;
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    fan_controller.set_speed_guard(config.speed_guard);
+    let mut manager = ScenarioManager::new(&mut fan_controller);
+    manager.set_deferred_controls(&config.deferred_controls);
+
+    if enabled {
+        let skipped = travel::enable(&mut manager, &mut config)?;
+        println!("{} Travel mode on", "✓".green());
+        println!("  Super Battery with silent fans");
+        for (control, setting) in [
+            ("Battery charge limit", format!("{}%", travel::CHARGE_THRESHOLD)),
+            ("Keyboard backlight", "off".to_string()),
+            ("Webcam switch", "off".to_string()),
+        ] {
+            if skipped.contains(&control) {
+                println!("  {} {}: not available on this model", "!".yellow(), control);
+            } else {
+                println!("  {}: {}", control, setting);
+            }
+        }
+        println!("  Automation is paused until travel mode is switched off");
+    } else {
+        travel::disable(&mut manager, &mut config)?;
+        println!("{} Travel mode off, previous charge limit, keyboard backlight and webcam restored", "✓".green());
+        cmd_apply()?;
+    }
+    Ok(())
+}
+
+fn cmd_osd(template: String, output: Option<PathBuf>, interval: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    let Some(template) = config.status_template(&template) else {
+        return Err(format!("Unknown template: {}", template).into());
+    };
+
+    let Some(output) = output else {
+        println!("{}", StatusSnapshot::collect(&config.active_profile)?.render_template(&template)?);
+        return Ok(());
+    };
+
+    if let Some(parent) = output.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_output = output.with_extension("tmp");
+
+    // The overlay reads the file from an unprivileged game process, so write it
+    // atomically to never expose a half-written line.
+    loop {
+        let line = match StatusSnapshot::collect(&config.active_profile) {
+            Ok(snapshot) => snapshot.render_template(&template)?,
+            Err(e) => {
+                log::warn!("Failed to read status for OSD: {}", e);
+                "msi-center: no data".to_string()
+            }
+        };
+        std::fs::write(&temp_output, format!("{}\n", line))?;
+        std::fs::rename(&temp_output, &output)?;
+
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+    }
+}
+
+/// Streams sensor changes to stdout until the reader goes away.
+fn cmd_sensors_watch(interval_ms: u64, resync_secs: u64) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    // One EC handle for the whole stream; only the fan state and the CPU clock are read.
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    let mut stream = sensors::PatchStream::default();
+    let interval = std::time::Duration::from_millis(interval_ms);
+    let resync = (resync_secs > 0).then(|| std::time::Duration::from_secs(resync_secs));
+    let mut last_resync = std::time::Instant::now();
+    let mut stdout = std::io::stdout().lock();
+
+    loop {
+        if resync.is_some_and(|every| last_resync.elapsed() >= every) {
+            stream.resync();
+            last_resync = std::time::Instant::now();
+        }
+        match fan_controller.get_fan_info() {
+            Ok(info) => {
+                let readings = sensors::readings(&info, system::cpu_clocks_mhz().map(|(current, _)| current));
+                if let Some(mut patch) = stream.patch(readings) {
+                    patch.insert("time".to_string(), chrono::Utc::now().timestamp_millis().into());
+                    // A closed pipe means the dashboard is gone.
+                    if writeln!(stdout, "{}", serde_json::Value::Object(patch)).and_then(|()| stdout.flush()).is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            Err(e) => log::warn!("Failed to read sensors: {}", e),
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+static BRIDGE_STOP: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn stop_bridge(_signal: libc::c_int) {
+    BRIDGE_STOP.store(true, Ordering::SeqCst);
+}
+
+/// Writes the hwmon bridge files every `interval` seconds until interrupted, then removes them.
+fn cmd_sensors_bridge(path: Option<PathBuf>, interval: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+    let path = path.unwrap_or_else(|| config.hwmon_bridge.path.clone());
+    if config.hwmon_bridge.enabled && config.hwmon_bridge.path == path && daemon::query_health().is_ok() {
+        return Err(format!("The daemon already keeps {} up to date (hwmon_bridge)", path.display()).into());
+    }
+
+    let handler = stop_bridge as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    // SAFETY: the handler only stores to an atomic, which is async-signal-safe.
+    unsafe {
+        libc::signal(libc::SIGINT, handler);
+        libc::signal(libc::SIGTERM, handler);
+    }
+
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    let interval = std::time::Duration::from_secs(interval.max(1));
+    println!("{}", format!("Writing sensor files to {} every {}s. Press Ctrl+C to stop.", path.display(), interval.as_secs()).yellow());
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        while !BRIDGE_STOP.load(Ordering::SeqCst) {
+            match fan_controller.get_fan_info() {
+                Ok(info) => sensors::write_hwmon(&path, &info)?,
+                Err(e) => log::warn!("Failed to read sensors: {}", e),
+            }
+            let slept = std::time::Instant::now();
+            while slept.elapsed() < interval && !BRIDGE_STOP.load(Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+        }
+        Ok(())
+    })();
+
+    sensors::remove_hwmon(&path)?;
+    result
+}
+
+fn print_check(ok: bool, message: &str) {
+    if ok {
+        println!("  {} {}", "✓".green(), message);
+    } else {
+        println!("  {} {}", "✗".red(), message);
+    }
+}
+
+/// Reports kernel lockdown and, if it leaves no EC backend, offers to load msi-ec.
+fn check_lockdown_backend() -> Result<(), Box<dyn std::error::Error>> {
+    let secure_boot = match system::secure_boot() {
+        Some(true) => ", Secure Boot on",
+        Some(false) => ", Secure Boot off",
+        None => "",
+    };
+    let Some(mode) = system::kernel_lockdown() else {
+        print_check(true, &format!("Kernel lockdown: none{}", secure_boot));
+        return Ok(());
+    };
+    println!("  {} Kernel lockdown: {}{}", "!".yellow().bold(), mode, secure_boot);
+    println!("      /dev/port, ioperm and debugfs are blocked; only the msi-ec driver can reach the EC");
+    if system::module_loaded("msi_ec") {
+        return Ok(());
+    }
+    if !system::module_available("msi_ec") {
+        print_check(false, "msi-ec driver is not installed; install it from your distribution or build it from its project page");
+        return Ok(());
+    }
+    let interactive = std::io::IsTerminal::is_terminal(&std::io::stdin());
+    if !nix::unistd::geteuid().is_root() || !interactive {
+        println!("      The msi-ec driver is installed but not loaded: {}", "sudo modprobe msi-ec".cyan());
+        return Ok(());
+    }
+    if confirm("The msi-ec driver is installed but not loaded. Load it now?")? {
+        match system::load_module("msi_ec") {
+            Ok(()) => print_check(true, "msi-ec driver loaded"),
+            Err(e) => print_check(false, &format!("Failed to load msi-ec: {}", e)),
+        }
+    }
+    Ok(())
+}
+
+/// Reports ec_sys loaded without write support and offers to reload it with it.
+fn check_ec_sys_writes() -> Result<(), Box<dyn std::error::Error>> {
+    if !system::ec_sys_read_only() || system::kernel_lockdown().is_some() {
+        return Ok(());
+    }
+    println!("  {} ec_sys is loaded read-only (write_support=0); EC writes fall back to port access", "!".yellow().bold());
+    let interactive = std::io::IsTerminal::is_terminal(&std::io::stdin());
+    if !nix::unistd::geteuid().is_root() || !interactive {
+        println!("      To write through debugfs: {}", "sudo msi-center ec enable-writes --persist".cyan());
+        return Ok(());
+    }
+    if !confirm("Reload ec_sys with write_support=1?")? {
+        return Ok(());
+    }
+    let persist = confirm("Load it with write support at every boot?")?;
+    match system::enable_ec_sys_writes(persist) {
+        Ok(()) => print_check(true, &format!("ec_sys reloaded with write support{}", if persist { " (permanent)" } else { "" })),
+        Err(e) => print_check(false, &format!("Failed to reload ec_sys: {}", e)),
+    }
+    Ok(())
+}
+
+/// Asks a yes/no question on the terminal; anything but yes is no.
+fn confirm(question: &str) -> std::io::Result<bool> {
+    use std::io::Write;
+
+    print!("      {} [y/N] ", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn cmd_doctor(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = AppConfig::load().unwrap_or_default();
+
+    if json {
+        let report = doctor::DoctorReport::collect(&config);
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    print_header("MSI Center Linux - Doctor");
+
+    println!("{}", "── Hardware Access ──".green());
+    print_check(nix::unistd::geteuid().is_root(), "Running as root");
+    let vendor = std::fs::read_to_string("/sys/class/dmi/id/sys_vendor").unwrap_or_default();
+    print_check(
+        vendor.to_lowercase().contains("micro-star") || vendor.to_lowercase().contains("msi"),
+        &format!("Vendor: {}", vendor.trim()),
+    );
+    match ec::quirks::detected() {
+        Some(quirk) => print_check(true, &format!("EC register map: {} ({})", quirk.name, quirk.source)),
+        None => print_check(true, "EC register map: default"),
+    }
+    check_lockdown_backend()?;
+    check_ec_sys_writes()?;
+    match EmbeddedController::new() {
+        Ok(mut ec) => {
+            print_check(true, &format!("EC backend: {}", ec.backend_name()));
+            match ec.read_byte(ec.registers().cpu_temp) {
+                Ok(_) => print_check(true, "EC responds to reads"),
+                Err(e) => print_check(false, &format!("EC read failed: {}", e)),
+            }
+            if EmbeddedController::port_watchdog_tripped() {
+                print_check(false, &format!("Port I/O timed out repeatedly; fell back to {}", ec.backend_name()));
+            }
+            if ec.shares_ec_with_driver() {
+                print_check(true, "msi-ec is loaded too; settings it manages are written through the driver");
+            }
+        }
+        Err(e) => print_check(false, &format!("EC backend: {}", e)),
+    }
+    print_check(system::module_loaded("msi_ec"), "msi-ec kernel module loaded");
+    print_check(system::module_loaded("ec_sys"), "ec_sys kernel module loaded");
+    if ec::quirks::acpi_methods() != ec::quirks::AcpiMethods::default() {
+        print_check(ec::acpi_call::available(), "acpi_call kernel module loaded (needed for this model's ACPI fallback)");
+    }
+    if config.ambient_light().enabled {
+        match ambient::find_sensor().and_then(|sensor| ambient::read_lux(&sensor)) {
+            Some(lux) => print_check(true, &format!("Ambient light sensor reads {:.0} lux", lux)),
+            None => print_check(false, "No ambient light sensor (iio) found; ambient_light has no effect"),
+        }
+    }
+    println!();
+
+    println!("{}", "── Power Management Conflicts ──".green());
+    let conflicts = system::detect_conflicts();
+    if conflicts.is_empty() {
+        print_check(true, "No conflicting tools detected");
+    }
+    for conflict in conflicts {
+        let pending: Vec<String> = conflict
+            .affects
+            .iter()
+            .filter(|control| !config.deferred_controls.contains(control))
+            .map(|control| format!("\"{}\"", control))
+            .collect();
+
+        if pending.is_empty() {
+            print_check(true, &format!("{} is active; overlapping controls are deferred to it", conflict.name));
+            continue;
+        }
+
+        println!("  {} {} is active (manages {})", "!".yellow().bold(), conflict.name.bold(), conflict.manages);
+        println!("      {}", conflict.advice);
+        println!("      Or let {} own these settings by adding to the config:", conflict.name);
+        println!("      {}", format!("\"deferred_controls\": [{}]", pending.join(", ")).cyan());
+    }
+    println!();
+
+    println!("{}", "── Fan Curves ──".green());
+    let problems = config.curve_problems();
+    if problems.is_empty() {
+        print_check(true, "All profile curves fit this model's fan tables and the speed guard");
+    }
+    for problem in problems {
+        print_check(false, &problem);
+    }
+    println!();
+
+    Ok(())
+}
+
+fn cmd_daemon(action: DaemonCommands) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        DaemonCommands::Run { interval } => daemon::run(interval.map(std::time::Duration::from_secs))?,
+        DaemonCommands::Introspect => print!("{}", daemon::introspection_xml()),
+        DaemonCommands::Status => cmd_daemon_status()?,
+    }
+    Ok(())
+}
+
+fn cmd_automation(action: AutomationCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
 
-                println!("  CPU Temp: {:>3}°C {}", info.cpu_temp, cpu_bar);
-                println!("  GPU Temp: {:>3}°C {}", info.gpu_temp, gpu_bar);
+    match action {
+        AutomationCommands::Pause { duration } => {
+            config.automation.pause(duration);
+            config.save()?;
+            match config.automation.paused_until_display() {
+                Some(until) => println!("{} Automatic switching paused until {}", "✓".green(), until),
+                None => println!("{} Automatic switching paused until resumed", "✓".green()),
+            }
+        }
+        AutomationCommands::Resume => {
+            config.automation.resume();
+            config.save()?;
+            println!("{} Automatic switching resumed", "✓".green());
+        }
+        AutomationCommands::List => {
+            print_header("Automation Rules");
+            if config.automation.is_paused() {
+                let until = config.automation.paused_until_display().unwrap_or_else(|| "resumed".to_string());
+                println!("  {}", format!("Paused until {}", until).yellow());
                 println!();
+            }
+            let gaming = automation::gaming_active(&config.automation);
+            for rule in config.automation.list(automation::PowerState::read(), gaming) {
+                let state = match (&rule.profile, rule.enabled) {
+                    (None, _) => "not set".dimmed(),
+                    (Some(_), true) => "enabled".green(),
+                    (Some(_), false) => "disabled".yellow(),
+                };
+                let profile = rule.profile.as_deref().unwrap_or("-");
+                let now = if rule.matches { " (matches now)".cyan().to_string() } else { String::new() };
+                println!("  {:<16} {:<10} → {}{}", rule.role.id().bold(), state, profile.cyan(), now);
+                println!("  {:<16} {}, last switched {}", "", rule.condition.dimmed(), rule.last_triggered_display());
+            }
+            println!();
+        }
+        AutomationCommands::Enable { rule } | AutomationCommands::Disable { rule } if config.automation.profile(rule).is_none() => {
+            return Err(format!("Rule {} has no profile; pick one under Automation in the GUI", rule.id()).into());
+        }
+        AutomationCommands::Enable { rule } => {
+            config.automation.set_enabled(rule, true);
+            config.save()?;
+            println!("{} Rule {} enabled", "✓".green(), rule.id().cyan());
+        }
+        AutomationCommands::Disable { rule } => {
+            config.automation.set_enabled(rule, false);
+            config.save()?;
+            println!("{} Rule {} disabled; its profile is kept", "✓".green(), rule.id().cyan());
+        }
+        AutomationCommands::Trigger { rule } => {
+            let Some(profile) = config.automation.profile(rule).map(str::to_string) else {
+                return Err(format!("Rule {} has no profile", rule.id()).into());
+            };
+            if !config.set_active_profile(&profile) {
+                return Err(format!("Profile '{}' not found", profile).into());
+            }
+            config.save()?;
+            automation::record_trigger(rule);
+            println!("{} {}: switching to {}", "✓".green(), rule, profile.cyan());
+            return cmd_apply();
+        }
+    }
+
+    Ok(())
+}
+
+fn cmd_override(action: OverrideCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let mut overrides = overrides::Overrides::load();
+    let (setting, lifetime) = match action {
+        OverrideCommands::FanMode { mode, lifetime } => (overrides::Setting::FanMode(mode), lifetime),
+        OverrideCommands::CoolerBoost { enabled, lifetime } => (overrides::Setting::CoolerBoost(enabled), lifetime),
+        OverrideCommands::ShiftMode { mode, lifetime } => (overrides::Setting::ShiftMode(mode), lifetime),
+        OverrideCommands::SuperBattery { enabled, lifetime } => (overrides::Setting::SuperBattery(enabled), lifetime),
+        OverrideCommands::List => {
+            print_header("Temporary Overrides");
+            if overrides.active().is_empty() {
+                println!("  None; the active profile and automation are in control");
+            }
+            for o in overrides.active() {
+                println!("  {} {}", o.setting.to_string().cyan(), o.until_display().dimmed());
+            }
+            return Ok(());
+        }
+        OverrideCommands::Clear { control } => {
+            if let Some(ref control) = control
+                && !overrides::CONTROLS.contains(&control.as_str())
+            {
+                return Err(format!("Unknown control: {}. Use: {}", control, overrides::CONTROLS.join(", ")).into());
+            }
+            let removed = overrides.clear(control.as_deref());
+            if removed.is_empty() {
+                println!("No override to clear");
+                return Ok(());
+            }
+            overrides.save()?;
+            for o in &removed {
+                println!("{} Cleared {}", "✓".green(), o.setting);
+            }
+            // Put back what the profile says, keeping the overrides that remain.
+            let config = AppConfig::load()?;
+            if let Some(profile) = config.get_active_profile() {
+                let mut settings = profile.settings.clone();
+                overrides.apply_to(&mut settings);
+                let mut fan_controller = FanController::new(EmbeddedController::new()?);
+                fan_controller.set_speed_guard(config.speed_guard);
+                let mut manager = ScenarioManager::new(&mut fan_controller);
+                manager.set_deferred_controls(&config.deferred_controls);
+                manager.apply_settings(&settings)?;
+                println!("  Restored the settings of profile {}", profile.name.cyan());
+            }
+            return Ok(());
+        }
+    };
+
+    let until = match lifetime.duration {
+        Some(duration) if !lifetime.until_reboot => Some(chrono::Local::now().timestamp() + duration.as_secs() as i64),
+        _ => None,
+    };
+    let mut fan_controller = FanController::new(EmbeddedController::new()?);
+    setting.enforce(&mut fan_controller)?;
+    overrides.set(setting, until);
+    overrides.save()?;
+    let o = overrides.active().last().copied().ok_or("override was not recorded")?;
+    println!("{} {} {}", "✓".green(), setting.to_string().cyan(), o.until_display());
+    if daemon::query_health().is_err() {
+        println!("  {}", "The daemon isn't running, so a profile switch or the end of the override won't be handled automatically".yellow());
+    }
+    Ok(())
+}
+
+fn cmd_daemon_status() -> Result<(), Box<dyn std::error::Error>> {
+    print_header("Daemon Status");
+
+    let service = std::process::Command::new("systemctl")
+        .args(["is-active", "msi-center.service"])
+        .output()
+        .ok()
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|state| !state.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    let service_color = if service == "active" { colored::Color::Green } else { colored::Color::Yellow };
+    print_status_line("Service", &service, service_color);
+
+    match daemon::query_health() {
+        Ok(health) => {
+            let or_none = |value: &str| if value.is_empty() { "none".to_string() } else { value.to_string() };
+            print_status_line("D-Bus", "reachable", colored::Color::Green);
+            print_status_line("Running since", &health.started_at, colored::Color::White);
+            print_status_line("EC backend", &or_none(&health.backend), colored::Color::Cyan);
+            print_status_line("Automation role", &or_none(&health.automation_role), colored::Color::Cyan);
+            let last_applied = if health.last_applied_profile.is_empty() {
+                "none".to_string()
+            } else {
+                format!("{} at {}", health.last_applied_profile, health.last_applied_at)
+            };
+            print_status_line("Last applied", &last_applied, colored::Color::White);
+            print_status_line("Pending operations", &health.pending_operations.to_string(), colored::Color::White);
+
+            if health.recent_errors.is_empty() {
+                print_status_line("Recent errors", "none", colored::Color::Green);
+            } else {
+                print_status_line("Recent errors", &health.recent_errors.len().to_string(), colored::Color::Red);
+                for error in &health.recent_errors {
+                    println!("    {}", error.red());
+                }
+            }
+        }
+        Err(daemon::DaemonError::NotRunning) => print_status_line("D-Bus", "not running", colored::Color::Yellow),
+        Err(e) => print_status_line("D-Bus", &e.to_string(), colored::Color::Red),
+    }
+
+    let config = AppConfig::load()?;
+    let rules = config.automation.rules();
+    println!();
+    if rules.is_empty() {
+        println!("  Automation rules: {}", "none".dimmed());
+    } else {
+        if config.automation.is_paused() {
+            let until = config.automation.paused_until_display().unwrap_or_else(|| "resumed".to_string());
+            println!("  Automation rules ({}):", format!("paused until {}", until).yellow());
+        } else {
+            println!("  Automation rules:");
+        }
+        for (role, profile) in rules {
+            println!("    {:<16} → {}", role.to_string(), profile.cyan());
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+fn format_register(value: Option<u8>) -> String {
+    value.map(|v| format!("{:#04x}", v)).unwrap_or_else(|| "--".to_string())
+}
+
+fn cmd_ec(action: EcCommands) -> Result<(), Box<dyn std::error::Error>> {
+    match action {
+        EcCommands::DiffBaseline { against } => {
+            let path = match &against {
+                Some(name) => ec::snapshot::resolve(name)?,
+                None => ec::snapshot::baseline_path()?,
+            };
+            if !path.exists() {
+                return Err(format!("No baseline recorded yet at {}; it is taken by 'msi-center install' and when the daemon starts", path.display()).into());
+            }
+            let reference = EcSnapshot::load(&path)?;
+
+            let mut ec = EmbeddedController::new()?;
+            let current = EcSnapshot::capture(&mut ec);
+            let changes = reference.diff(&current);
+
+            print_header("EC Registers vs Baseline");
+            println!("  Reference: {} ({}, {})", path.display(), reference.taken_at, reference.backend);
+            println!();
+
+            if changes.is_empty() {
+                println!("{} No registers changed", "✓".green());
+                return Ok(());
+            }
+
+            print_register_changes(&changes, &ec.registers(), &ec.capabilities());
+        }
+
+        EcCommands::Snapshot { file } => {
+            let path = ec::snapshot::target_path(&file)?;
+            let mut ec = EmbeddedController::new()?;
+            let snapshot = EcSnapshot::capture(&mut ec);
+            let readable = snapshot.registers.iter().filter(|r| r.is_some()).count();
+            snapshot.save(&path)?;
+            println!("{} Saved {} readable registers ({}) to {}", "✓".green(), readable, snapshot.backend, path.display());
+        }
+
+        EcCommands::Backup { file } => {
+            let path = match &file {
+                Some(name) => ec::snapshot::target_path(name)?,
+                None => ec::snapshot::backup_path()?,
+            };
+            let mut ec = EmbeddedController::new()?;
+            let backup = ec.backup();
+            let saved = backup.registers.iter().filter(|r| r.is_some()).count();
+            if saved == 0 {
+                return Err(format!("No writable registers could be read via {}", backup.backend).into());
+            }
+            backup.save(&path)?;
+            println!("{} Backed up {} registers ({}) to {}", "✓".green(), saved, backup.backend, path.display());
+            println!("  Restore with: msi-center ec restore {}", path.display());
+        }
+
+        EcCommands::Restore { file, any_model } => {
+            let path = match &file {
+                Some(name) => ec::snapshot::resolve(name)?,
+                None => ec::snapshot::latest_backup()?,
+            };
+            let backup = EcSnapshot::load(&path)?;
+            let model = ec::quirks::detected().map(|quirk| quirk.name.clone());
+            if let (Some(saved), Some(current)) = (&backup.model, &model)
+                && saved != current
+                && !any_model
+            {
+                return Err(format!("{} was taken on '{}', not '{}'; pass --any-model to restore it anyway", path.display(), saved, current).into());
+            }
+
+            let mut ec = EmbeddedController::new()?;
+            println!("Restoring {} ({}, {})", path.display(), backup.taken_at, backup.backend);
+            let report = ec.restore(&backup);
+            if report.verified + report.unverified + report.rejected.len() == 0 {
+                println!("{} All registers already match the backup", "✓".green());
+                return Ok(());
+            }
+            for rejected in &report.rejected {
+                println!("  {} {}", "✗".red(), rejected);
+            }
+            let restored = report.verified + report.unverified;
+            if !report.is_ok() {
+                return Err(format!("{} register(s) restored, {} failed", restored, report.rejected.len()).into());
+            }
+            println!("{} Restored {} register(s)", "✓".green(), restored);
+        }
+
+        EcCommands::Diff { before, after } => {
+            let before_path = ec::snapshot::resolve(&before)?;
+            let after_path = ec::snapshot::resolve(&after)?;
+            let older = EcSnapshot::load(&before_path)?;
+            let newer = EcSnapshot::load(&after_path)?;
+
+            print_header("EC Register Diff");
+            println!("  Before: {} ({}, {})", before_path.display(), older.taken_at, older.backend);
+            println!("  After:  {} ({}, {})", after_path.display(), newer.taken_at, newer.backend);
+            println!();
+
+            let changes = older.diff(&newer);
+            if changes.is_empty() {
+                println!("{} No registers changed", "✓".green());
+                return Ok(());
+            }
+            print_register_changes(&changes, &ec::quirks::registers(), &ec::quirks::capabilities());
+        }
+
+        EcCommands::Dump { start, end } => {
+            if start > end {
+                return Err(format!("--start {:#04x} is after --end {:#04x}", start, end).into());
+            }
+            let mut ec = EmbeddedController::new()?;
+            print_header(&format!("EC Registers {:#04x}-{:#04x} ({})", start, end, ec.backend_name()));
+            print_hex_dump(&mut ec, start, end);
+        }
+
+        EcCommands::Write { address, value, force } => {
+            if force {
+                ec::allow_unsafe_writes(true);
+            }
+            let mut ec = EmbeddedController::new()?;
+            ec.write_byte(address, value)?;
+            let name = ec.registers().describe(address, &ec.capabilities()).unwrap_or_else(|| "unmapped register".to_string());
+            match ec.read_byte(address) {
+                Ok(actual) if actual == value => println!("{} Wrote {:#04x} = {:#04x} ({})", "✓".green(), address, value, name),
+                Ok(actual) => println!("{} Wrote {:#04x} = {:#04x} ({}), but it reads {:#04x}", "⚠".yellow(), address, value, name, actual),
+                Err(e) => println!("{} Wrote {:#04x} = {:#04x} ({}), could not read it back: {}", "⚠".yellow(), address, value, name, e),
+            }
+        }
+
+        EcCommands::Watch { interval, annotate, ignore_sensors } => cmd_ec_watch(interval, annotate, ignore_sensors)?,
+
+        EcCommands::EnableWrites { persist } => {
+            if let Some(mode) = system::kernel_lockdown() {
+                return Err(format!("The kernel is locked down ({}), which refuses ec_sys writes; use the msi-ec driver instead", mode).into());
+            }
+            if !nix::unistd::geteuid().is_root() {
+                return Err("Reloading ec_sys needs root; run with sudo".into());
+            }
+            system::enable_ec_sys_writes(persist)?;
+            println!("{} ec_sys loaded with write support", "✓".green());
+            if persist {
+                println!("  It will be loaded this way at every boot");
+            }
+        }
+
+        EcCommands::ImportCapture { file, save } => {
+            let registers = ec::quirks::registers();
+            let actions = ec::capture::load(&file, &registers, &ec::quirks::capabilities())?;
+            let mut config = if save { Some(AppConfig::load()?) } else { None };
 
-                let cpu_fan_bar = create_progress_bar(info.cpu_fan_percent as f32, 100.0, 20);
-                let gpu_fan_bar = create_progress_bar(info.gpu_fan_percent as f32, 100.0, 20);
+            print_header("Imported EC Capture");
+            for action in &actions {
+                println!("{} ({} register(s))", action.name.cyan().bold(), action.writes.len());
+                for recognized in &action.recognized {
+                    println!("  {} {}", "✓".green(), recognized);
+                }
+                for write in &action.unknown {
+                    println!("  {} unknown register {}", "?".yellow(), write);
+                }
 
-                println!("  CPU Fan:  {:>5} RPM {:>3}% {}", info.cpu_fan_rpm, info.cpu_fan_percent, cpu_fan_bar);
-                println!("  GPU Fan:  {:>5} RPM {:>3}% {}", info.gpu_fan_rpm, info.gpu_fan_percent, gpu_fan_bar);
+                if let Some(ref mut config) = config
+                    && !action.recognized.is_empty()
+                {
+                    let base = config.get_profile(&action.name).map(|p| p.settings.clone()).unwrap_or_else(ScenarioSettings::balanced);
+                    let settings = action.settings(base);
+                    match config.profiles.iter_mut().find(|p| p.name == action.name) {
+                        Some(profile) => profile.settings = settings,
+                        None => config.add_profile(Profile {
+                            name: action.name.clone(),
+                            scenario: UserScenario::Custom,
+                            settings,
+                            alerts: AlertOverrides::default(),
+                            hooks: ProfileHooks::default(),
+                            ambient_light: None,
+                            emergency_cooling: None,
+                            curves: Default::default(),
+                            active_curves: None,
+                            cpu_curve: None,
+                            gpu_curve: None,
+                        }),
+                    }
+                    println!("  {} saved as profile '{}'", "→".cyan(), action.name);
+                }
                 println!();
+            }
 
-                println!("  Mode: {:?}  |  Cooler Boost: {}", 
-                    info.fan_mode,
-                    if info.cooler_boost { "ON".red() } else { "OFF".green() }
-                );
+            if let Some(config) = config {
+                config.save()?;
+            }
+            if actions.iter().any(|a| !a.unknown.is_empty()) {
+                println!("Unknown registers may belong to features not supported yet; please include them in a quirk report.");
             }
         }
+    }
+    Ok(())
+}
 
-        println!();
-        println!("{}", format!("Refreshing every {}s...", interval).dimmed());
+/// Table of changed registers, naming the ones in the register map.
+fn print_register_changes(changes: &[ec::snapshot::RegisterChange], registers: &ec::quirks::RegisterMap, capabilities: &ec::quirks::Capabilities) {
+    println!("  {:<6} {:<6} {:<6} {}", "Addr".bold(), "Old".bold(), "New".bold(), "Register".bold());
+    for change in changes {
+        let line = format!(
+            "  {:<6} {:<6} {:<6} {}",
+            format!("{:#04x}", change.address),
+            format_register(change.old),
+            format_register(change.new).yellow(),
+            registers.describe(change.address, capabilities).map(|name| name.cyan().to_string()).unwrap_or_default()
+        );
+        println!("{}", line.trim_end());
+    }
+    println!();
+    println!("{} register(s) changed", changes.len());
+}
 
-        std::thread::sleep(std::time::Duration::from_secs(interval));
+fn cmd_models(action: ModelsCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let entries = ec::quirks::entries();
+    let detected = ec::quirks::detected().map(|quirk| quirk.source.clone());
+
+    match action {
+        ModelsCommands::List => {
+            print_header("Model Database");
+            println!("  {:<20} {:<28} {:<18} {}", "Id".bold(), "Name".bold(), "Inherits".bold(), "Source".bold());
+            for entry in &entries {
+                let marker = if detected.as_ref() == Some(&entry.source) { "●".green().to_string() } else { " ".to_string() };
+                let name = if entry.is_template { format!("{} (template)", entry.quirk.name) } else { entry.quirk.name.clone() };
+                let inherits = entry.inherits.first().map(String::as_str).unwrap_or("-");
+                println!("{} {:<20} {:<28} {:<18} {}", marker, entry.id, name, inherits, entry.source.dimmed());
+            }
+            if entries.is_empty() {
+                println!("  No quirk files found");
+            }
+        }
+
+        ModelsCommands::Show { id } => {
+            let entry = match &id {
+                Some(id) => entries.iter().find(|entry| entry.id == *id).ok_or_else(|| format!("No model entry '{}'", id))?,
+                None => {
+                    let source = detected.as_ref().ok_or("No model entry matches this machine; pass an id from `msi-center models list`")?;
+                    entries.iter().find(|entry| entry.source == *source).ok_or("The detected model entry is no longer loadable")?
+                }
+            };
+            let quirk = &entry.quirk;
+
+            print_header(&quirk.name);
+            println!("  Id:           {}", entry.id);
+            println!("  Source:       {}", entry.source);
+            if !entry.inherits.is_empty() {
+                println!("  Inherits:     {}", entry.inherits.join(" → "));
+            }
+            if entry.is_template {
+                println!("  Template:     {}", "yes, never matched on its own".dimmed());
+            }
+            if !entry.products.is_empty() {
+                println!("  Products:     {}", entry.products.join(", "));
+            }
+            if !entry.firmware.is_empty() {
+                println!("  Firmware:     {}", entry.firmware.join(", "));
+            }
+            println!("  Support:      {}", quirk.support);
+
+            let caps = &quirk.capabilities;
+            println!();
+            println!("  {}", "Capabilities".bold());
+            println!("    Fans:          {}", caps.fans);
+            let layout = match caps.temp_table_offset {
+                Some(offset) => format!("temperatures apart, at {:+}", offset),
+                None => "temperature/speed pairs".to_string(),
+            };
+            println!("    Fan tables:    {} points, {}", caps.curve_points, layout);
+            println!("    Cooler Boost:  {}", if caps.cooler_boost { "yes" } else { "no" });
+            println!("    Shift mode:    {}", if caps.shift_mode { "yes" } else { "no" });
+            println!("    Super battery: {}", if caps.super_battery { "yes" } else { "no" });
+            if let Some(logo) = caps.lid_logo {
+                println!("    Lid logo:      {:#04x} (max level {})", logo.register, logo.max_level);
+            }
+            if let Some(led) = caps.indicator_led {
+                let mut parts = Vec::new();
+                if let Some(bit) = led.off_on_battery {
+                    parts.push(format!("off on battery {:#04x} bit {:#04x}", led.register, bit));
+                }
+                if let Some(register) = led.blink_threshold {
+                    parts.push(format!("blink threshold {:#04x}", register));
+                }
+                println!("    Indicator LED: {}", parts.join(", "));
+            }
+            let tuning = caps.fan_tuning;
+            let tuning_registers = [
+                ("CPU offsets", tuning.cpu_offsets),
+                ("CPU min duty", tuning.cpu_min_duty),
+                ("GPU offsets", tuning.gpu_offsets),
+                ("GPU min duty", tuning.gpu_min_duty),
+            ];
+            for (label, address) in tuning_registers {
+                if let Some(address) = address {
+                    println!("    {:<15}{:#04x}", format!("{}:", label), address);
+                }
+            }
+            if let Some(constant) = caps.fan_rpm.constant {
+                println!("    RPM constant:  {} / tachometer", constant);
+            }
+            if let Some(max_rpm) = caps.fan_rpm.max_rpm {
+                println!("    Max RPM:       {}", max_rpm);
+            }
+
+            println!();
+            println!("  {}", "Registers".bold());
+            if entry.register_overrides.is_empty() {
+                println!("    {}", "Default (WMI2) layout".dimmed());
+            }
+            for (key, address) in &entry.register_overrides {
+                println!("    {:<18} {:#04x}", key, address);
+            }
+        }
+
+        ModelsCommands::Probe { output } => return cmd_models_probe(output),
+    }
+
+    if let Some(dir) = ec::quirks::user_dirs().first() {
+        println!();
+        println!("{}", format!("Add or override entries in {}", dir.display()).dimmed());
     }
+    Ok(())
 }
 
-fn create_progress_bar(value: f32, max: f32, width: usize) -> String {
-    let ratio = (value / max).clamp(0.0, 1.0);
-    let filled = (ratio * width as f32) as usize;
-    let empty = width - filled;
+fn cmd_models_probe(output: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let mut ec = EmbeddedController::new()?;
+    let probe = ec::probe::ModelProbe::run(&mut ec);
+    let entry = probe.to_quirk_file();
 
-    let color = if ratio < 0.5 {
-        colored::Color::Green
-    } else if ratio < 0.75 {
-        colored::Color::Yellow
-    } else {
-        colored::Color::Red
+    let Some(path) = output else {
+        print!("{}", entry);
+        return Ok(());
     };
+    std::fs::write(&path, &entry)?;
+    let readable = probe.registers.iter().filter(|r| r.is_some()).count();
+    println!("{} Wrote a model entry to {}", "✓".green(), path.display());
+    println!("  Registers: {} readable via {}, {} bytes of text removed", readable, probe.backend, probe.redacted);
+    match probe.suggested_template() {
+        Some(layout) => println!("  Layout:    closest to {} ({}/{} plausible)", layout.id, layout.plausible, layout.checked),
+        None => println!("  Layout:    no template fits better than the default map"),
+    }
+    println!();
+    println!("Review it, copy it to {} to try it, then submit it.", ec::quirks::user_dirs().first().map(|dir| dir.display().to_string()).unwrap_or_default());
+    Ok(())
+}
 
-    format!(
-        "[{}{}]",
-        "█".repeat(filled).color(color),
-        "░".repeat(empty).dimmed()
-    )
+/// Prints one line per register change, so toggling a feature (keyboard backlight,
+/// battery threshold, ...) in firmware or under another OS reveals its address.
+fn cmd_ec_watch(interval: u64, annotate: bool, ignore_sensors: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut ec = EmbeddedController::new()?;
+    // Every poll must see the EC itself, however short the interval.
+    ec.set_read_cache(None);
+    let registers = ec.registers();
+    let capabilities = ec.capabilities();
+    let sensors = [
+        registers.cpu_temp,
+        registers.gpu_temp,
+        registers.cpu_fan_speed,
+        registers.gpu_fan_speed,
+        registers.cpu_fan_tach,
+        registers.cpu_fan_tach.wrapping_add(1),
+        registers.gpu_fan_tach,
+        registers.gpu_fan_tach.wrapping_add(1),
+    ];
+    let interval = std::time::Duration::from_millis(interval.max(50));
+
+    print_header(&format!("Watching EC registers via {}", ec.backend_name()));
+    println!("{}", "Press Ctrl+C to stop.".yellow());
+    println!();
+
+    let mut previous = EcSnapshot::capture(&mut ec);
+    let mut change_counts = [0u32; 256];
+    loop {
+        std::thread::sleep(interval);
+        let current = EcSnapshot::capture(&mut ec);
+        let time = chrono::Local::now().format("%H:%M:%S%.3f");
+
+        for change in previous.diff(&current) {
+            if ignore_sensors && sensors.contains(&change.address) {
+                continue;
+            }
+            change_counts[change.address as usize] += 1;
+            let count = change_counts[change.address as usize];
+            let mut line = format!(
+                "  {}  {}  {} -> {}",
+                time,
+                format!("{:#04x}", change.address).bold(),
+                format_register(change.old),
+                format_register(change.new).yellow().bold()
+            );
+            if count > 1 {
+                line.push_str(&format!("  ({} changes)", count).dimmed().to_string());
+            }
+            if annotate && let Some(name) = registers.describe(change.address, &capabilities) {
+                line.push_str(&format!("  {}", name.cyan()));
+            }
+            println!("{}", line);
+        }
+        previous = current;
+    }
 }
 
-fn cmd_apply() -> Result<(), Box<dyn std::error::Error>> {
-    let config = AppConfig::load()?;
+/// 16 registers per row; unreadable registers (and those outside the range) are `--`.
+fn print_hex_dump(ec: &mut EmbeddedController, start: u8, end: u8) {
+    let header: Vec<String> = (0..16).map(|column| format!("{:02X}", column)).collect();
+    println!("  {}  {}", "    ".bold(), header.join(" ").bold());
+
+    for row in (start / 16)..=(end / 16) {
+        let mut hex = Vec::with_capacity(16);
+        let mut ascii = String::with_capacity(16);
+        for column in 0..16u8 {
+            let address = row * 16 + column;
+            let value = if (start..=end).contains(&address) { ec.read_byte(address).ok() } else { None };
+            match value {
+                Some(byte) => {
+                    hex.push(format!("{:02X}", byte));
+                    ascii.push(if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' });
+                }
+                None => {
+                    hex.push("--".to_string());
+                    ascii.push(' ');
+                }
+            }
+        }
+        println!("  {}  {}  |{}|", format!("{:02X}:", row * 16).bold(), hex.join(" "), ascii);
+    }
+    println!();
+}
 
-    if let Some(profile) = config.get_active_profile() {
-        let mut ec = EmbeddedController::new()?;
-        let mut fan_controller = FanController::new(EmbeddedController::new()?);
-        let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
+fn sync_tuned(config: &AppConfig, scenario: UserScenario) {
+    match config.tuned.sync(scenario) {
+        Ok(Some(profile)) => println!("{} tuned profile set to {}", "✓".green(), profile.cyan()),
+        Ok(None) => {}
+        Err(e) => eprintln!("{} {}", "Warning:".yellow(), e),
+    }
+}
 
-        manager.apply_settings(&profile.settings)?;
+fn cmd_tuned(action: TunedCommands) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = AppConfig::load()?;
 
-        println!("{} Applied profile: {}", "✓".green(), profile.name.cyan());
-        println!("  Scenario: {}", profile.scenario);
-        println!("  Shift Mode: {}", profile.settings.shift_mode);
-        println!("  Fan Mode: {:?}", profile.settings.fan_mode);
-        println!("  Cooler Boost: {}", if profile.settings.cooler_boost { "ON" } else { "OFF" });
-    } else {
-        println!("{} No active profile found", "✗".red());
+    match action {
+        TunedCommands::Status => {
+            print_header("tuned Integration");
+            if tuned::is_available() {
+                print_status_line("Active tuned profile", &tuned::active_profile()?, colored::Color::Cyan);
+            } else {
+                print_status_line("tuned-adm", "not installed", colored::Color::Yellow);
+            }
+            print_status_line("Activate on apply", if config.tuned.enabled { "ON" } else { "OFF" }, colored::Color::White);
+            println!();
+            for scenario in ScenarioManager::get_available_scenarios() {
+                let profile = config.tuned.profiles.get(&scenario).map(String::as_str).unwrap_or("-");
+                println!("  {:<18} → {}", scenario.to_string(), profile.cyan());
+            }
+            println!();
+        }
+
+        TunedCommands::Enable { enabled } => {
+            if enabled && !tuned::is_available() {
+                return Err("tuned-adm not found; install tuned first".into());
+            }
+            config.tuned.enabled = enabled;
+            config.save()?;
+            println!("{} tuned integration {}", "✓".green(), if enabled { "enabled" } else { "disabled" });
+        }
+
+        TunedCommands::Map { scenario, profile } => {
+            config.tuned.profiles.insert(scenario, profile.clone());
+            config.save()?;
+            println!("{} {} now maps to tuned profile {}", "✓".green(), scenario, profile.cyan());
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ec::trace::{EcTrace, MockEc};
+    use fan::SpeedGuard;
+    use std::sync::{Arc, Mutex};
+
+    fn fixtures_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("ec-traces")
+    }
+
+    /// Parses a recorded command line with the CLI and runs its hardware part against `mock`.
+    fn replay(mock: &Arc<Mutex<MockEc>>, trace: &EcTrace) -> Result<(), Box<dyn std::error::Error>> {
+        let cli = Cli::try_parse_from(std::iter::once("msi-center").chain(trace.command.iter().map(String::as_str)))?;
+        // Replay with the register map and capabilities of the model the trace was recorded on.
+        let quirk = ec::quirks::for_product(&trace.model);
+        let mut ec = EmbeddedController::with_mock(Arc::clone(mock));
+        ec.set_registers(quirk.as_ref().map(|q| q.registers).unwrap_or_default());
+        ec.set_capabilities(quirk.as_ref().map(|q| q.capabilities).unwrap_or_default());
+        let mut fans = FanController::new(ec);
+        // The guard and speed floor come from the user's config in the CLI; traces record the hardware side only.
+        fans.set_speed_guard(SpeedGuard { enabled: false, ..SpeedGuard::default() });
+
+        match cli.command {
+            Commands::Scenario { action } => {
+                let mut manager = ScenarioManager::new(&mut fans);
+                match action {
+                    ScenarioCommands::Set { scenario } => manager.set_scenario(scenario)?,
+                    ScenarioCommands::Shift { mode } => manager.set_shift_mode(mode)?,
+                    ScenarioCommands::SuperBattery { enabled, feature: None } => manager.set_super_battery(enabled)?,
+                    ScenarioCommands::SuperBattery { enabled, feature: Some(feature) } => manager.set_super_battery_feature(feature, enabled)?,
+                    _ => return Err(format!("unsupported command in trace: {}", trace.command.join(" ")).into()),
+                }
+            }
+            Commands::Fan { action } => match action {
+                FanCommands::Mode { mode } => fans.set_fan_mode(mode)?,
+                FanCommands::CoolerBoost { enabled } => fans.set_cooler_boost(enabled)?,
+                FanCommands::Reset => fans.reset_to_auto()?,
+                FanCommands::Speed { cpu, gpu, system, .. } => fans.set_manual_fan_speeds(&ManualFanSpeed { cpu, gpu, system }.speeds())?,
+                _ => return Err(format!("unsupported command in trace: {}", trace.command.join(" ")).into()),
+            },
+            _ => return Err(format!("unsupported command in trace: {}", trace.command.join(" ")).into()),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn recorded_traces_replay() {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(fixtures_dir())
+            .expect("tests/ec-traces is missing")
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+        assert!(!paths.is_empty(), "no EC traces in {}", fixtures_dir().display());
+
+        for path in paths {
+            let trace = EcTrace::load(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+            let mock = Arc::new(Mutex::new(MockEc::from_trace(&trace)));
+
+            if let Err(e) = replay(&mock, &trace) {
+                panic!("{} ({}): {}", path.display(), trace.model, e);
+            }
+
+            let expected: Vec<String> = trace.writes().iter().map(ToString::to_string).collect();
+            let actual: Vec<String> = mock.lock().unwrap().writes().iter().map(ToString::to_string).collect();
+            assert_eq!(
+                actual,
+                expected,
+                "{} ({}, `{}`): EC writes differ from the recording",
+                path.display(),
+                trace.model,
+                trace.command.join(" ")
+            );
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use crate::config::AppConfig;
+use crate::daemon::{BUS_NAME, OBJECT_PATH};
+use std::collections::HashMap;
+use zbus::blocking::fdo::PropertiesProxy;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::Value;
+
+const OSD_TIMEOUT_MS: i32 = 2000;
+
+/// Shows a short-lived OSD-style notification, replacing the previous one
+/// (`replaces_id`) so rapid changes don't stack bubbles. Returns the new id.
+pub fn show_osd(session: &Connection, replaces_id: u32, icon: &str, summary: &str, body: &str) -> zbus::Result<u32> {
+    let hints: HashMap<&str, Value> = HashMap::from([
+        ("transient", Value::from(true)),
+        ("urgency", Value::from(0u8)),
+        // Makes notify-osd style servers render this as an OSD bubble.
+        ("x-canonical-private-synchronous", Value::from("msi-center")),
+    ]);
+
+    let reply = session.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &("MSI Center", replaces_id, icon, summary, body, Vec::<&str>::new(), hints, OSD_TIMEOUT_MS),
+    )?;
+    reply.body().deserialize()
+}
+
+/// Shows a critical notification, which stays until the user dismisses it.
+fn show_alert(session: &Connection, replaces_id: u32, summary: &str, body: &str) -> zbus::Result<u32> {
+    let hints: HashMap<&str, Value> = HashMap::from([("urgency", Value::from(2u8))]);
+    let reply = session.call_method(
+        Some("org.freedesktop.Notifications"),
+        "/org/freedesktop/Notifications",
+        Some("org.freedesktop.Notifications"),
+        "Notify",
+        &("MSI Center", replaces_id, "dialog-error", summary, body, Vec::<&str>::new(), hints, 0i32),
+    )?;
+    reply.body().deserialize()
+}
+
+fn scenario_icon(scenario: &str) -> &'static str {
+    match scenario {
+        "Silent" | "Super Battery" => "battery-profile-powersave",
+        "High Performance" | "Turbo" => "battery-profile-performance",
+        _ => "battery-profile-balanced",
+    }
+}
+
+/// Hysteresis before a temperature warning can fire again, so readings hovering
+/// around the threshold don't produce a stream of bubbles.
+const TEMP_WARNING_RESET: u8 = 5;
+
+/// Runs in the user's session: watches the daemon on the system bus and shows an OSD
+/// whenever the scenario changes, whether by hotkey, CLI or another client, a
+/// warning when a temperature reaches the active profile's alert threshold, and a
+/// critical one when a fan looks stalled or the critical-temperature failsafe steps in.
+pub fn run_agent() -> zbus::Result<()> {
+    let system = Connection::system()?;
+    let session = Connection::session()?;
+    let proxy = Proxy::new(&system, BUS_NAME, OBJECT_PATH, "com.msi_center_linux.Control1")?;
+    let properties = PropertiesProxy::builder(&system)
+        .destination(BUS_NAME)?
+        .path(OBJECT_PATH)?
+        .build()?;
+
+    let mut last_scenario: String = proxy.get_property("Scenario").unwrap_or_default();
+    let mut scenario_notification = 0;
+    let mut warning_notification = 0;
+    let mut warned = false;
+    let mut stalled_notification = 0;
+    let mut critical_notification = 0;
+
+    log::info!("Watching {} for scenario and temperature changes", BUS_NAME);
+    for signal in properties.receive_properties_changed()? {
+        let Ok(args) = signal.args() else {
+            continue;
+        };
+        let changed = args.changed_properties();
+        // Profile overrides are merged at read time, so switching profiles takes effect immediately.
+        let alerts = AppConfig::load().unwrap_or_default().alert_settings();
+
+        if let Some(scenario) = changed.get("Scenario").and_then(|v| String::try_from(v.clone()).ok())
+            && scenario != last_scenario
+        {
+            last_scenario = scenario.clone();
+            if alerts.show_notifications {
+                match show_osd(&session, scenario_notification, scenario_icon(&scenario), &scenario, "User scenario") {
+                    Ok(id) => scenario_notification = id,
+                    Err(e) => log::warn!("Failed to show scenario OSD: {}", e),
+                }
+            }
+        }
+
+        // Shown regardless of show_notifications: a dead fan is not a matter of taste.
+        if let Some(fans) = changed.get("StalledFans").and_then(|v| Vec::<String>::try_from(v.clone()).ok())
+            && !fans.is_empty()
+        {
+            let which = if fans.len() == 1 { format!("{} fan reads", fans[0]) } else { format!("{} fans read", fans.join(" and ")) };
+            let body = format!("{} 0 RPM while hot. Check that nothing blocks it and that it still works.", which);
+            match show_alert(&session, stalled_notification, "Fan stalled", &body) {
+                Ok(id) => stalled_notification = id,
+                Err(e) => log::warn!("Failed to show stalled fan warning: {}", e),
+            }
+        }
+
+        if changed.get("CriticalTemp").and_then(|v| bool::try_from(v.clone()).ok()) == Some(true) {
+            let cpu_temp: u32 = proxy.get_property("CpuTemp").unwrap_or_default();
+            let gpu_temp: u32 = proxy.get_property("GpuTemp").unwrap_or_default();
+            let body = format!("CPU {}°C, GPU {}°C. Fans are at 100% and the shift mode at Eco until it cools down.", cpu_temp, gpu_temp);
+            match show_alert(&session, critical_notification, "Critical temperature", &body) {
+                Ok(id) => critical_notification = id,
+                Err(e) => log::warn!("Failed to show critical temperature warning: {}", e),
+            }
+        }
+
+        if !changed.contains_key("CpuTemp") && !changed.contains_key("GpuTemp") {
+            continue;
+        }
+        let cpu_temp: u32 = proxy.get_property("CpuTemp").unwrap_or_default();
+        let gpu_temp: u32 = proxy.get_property("GpuTemp").unwrap_or_default();
+        let hottest = cpu_temp.max(gpu_temp);
+        let threshold = alerts.temp_warning as u32;
+
+        if hottest < threshold.saturating_sub(TEMP_WARNING_RESET as u32) {
+            warned = false;
+        } else if hottest >= threshold && !warned {
+            warned = true;
+            if alerts.show_notifications {
+                let body = format!("CPU {}°C, GPU {}°C (warning at {}°C)", cpu_temp, gpu_temp, threshold);
+                match show_osd(&session, warning_notification, "dialog-warning", "High temperature", &body) {
+                    Ok(id) => warning_notification = id,
+                    Err(e) => log::warn!("Failed to show temperature warning: {}", e),
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! Temporary overrides (`msi-center override`): single settings that win over the
+//! active profile and automation until they run out, the machine reboots, or they
+//! are cleared, without touching any saved profile. The daemon keeps them in force
+//! and puts the profile's settings back once they end.
+
+use crate::config::{AppConfig, ConfigError};
+use crate::fan::{FanController, FanError, FanMode};
+use crate::scenario::{ScenarioError, ScenarioManager, ScenarioSettings, ShiftMode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum OverrideError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid overrides file: {0}")]
+    Invalid(#[from] serde_json::Error),
+    #[error("Config error: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("Fan error: {0}")]
+    FanError(#[from] FanError),
+    #[error("Scenario error: {0}")]
+    ScenarioError(#[from] ScenarioError),
+}
+
+pub type Result<T> = std::result::Result<T, OverrideError>;
+
+/// Changes on every boot, so overrides from an earlier one are ignored.
+const BOOT_ID_PATH: &str = "/proc/sys/kernel/random/boot_id";
+
+/// Controls that can be overridden, by the names the CLI uses.
+pub const CONTROLS: [&str; 4] = ["fan-mode", "cooler-boost", "shift-mode", "super-battery"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "control", content = "value", rename_all = "kebab-case")]
+pub enum Setting {
+    FanMode(FanMode),
+    CoolerBoost(bool),
+    ShiftMode(ShiftMode),
+    SuperBattery(bool),
+}
+
+impl Setting {
+    /// The control's name in [`CONTROLS`].
+    pub fn control(&self) -> &'static str {
+        match self {
+            Setting::FanMode(_) => "fan-mode",
+            Setting::CoolerBoost(_) => "cooler-boost",
+            Setting::ShiftMode(_) => "shift-mode",
+            Setting::SuperBattery(_) => "super-battery",
+        }
+    }
+
+    /// Replaces the control's value in settings about to be applied.
+    pub fn apply_to(&self, settings: &mut ScenarioSettings) {
+        match *self {
+            Setting::FanMode(mode) => {
+                settings.fan_mode = mode;
+                // A manual speed would switch the fans back to Advanced.
+                if mode != FanMode::Advanced {
+                    settings.manual_fan_speed = None;
+                }
+            }
+            Setting::CoolerBoost(enabled) => settings.cooler_boost = enabled,
+            Setting::ShiftMode(mode) => settings.shift_mode = mode,
+            Setting::SuperBattery(enabled) => settings.super_battery = enabled,
+        }
+    }
+
+    /// Writes the setting unless the hardware already has it. Returns whether it wrote.
+    pub fn enforce(&self, fan_controller: &mut FanController) -> Result<bool> {
+        match *self {
+            Setting::FanMode(mode) => {
+                if fan_controller.get_fan_info()?.fan_mode == mode {
+                    return Ok(false);
+                }
+                fan_controller.set_fan_mode(mode)?;
+            }
+            Setting::CoolerBoost(enabled) => {
+                if fan_controller.get_fan_info()?.cooler_boost == enabled {
+                    return Ok(false);
+                }
+                fan_controller.set_cooler_boost(enabled)?;
+            }
+            Setting::ShiftMode(mode) => {
+                let mut manager = ScenarioManager::new(fan_controller);
+                if manager.get_current_info()?.shift_mode == mode {
+                    return Ok(false);
+                }
+                manager.set_shift_mode(mode)?;
+            }
+            Setting::SuperBattery(enabled) => {
+                let mut manager = ScenarioManager::new(fan_controller);
+                if manager.get_current_info()?.super_battery == enabled {
+                    return Ok(false);
+                }
+                manager.set_super_battery(enabled)?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl std::fmt::Display for Setting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+        match *self {
+            Setting::FanMode(mode) => write!(f, "fan mode {}", mode.name()),
+            Setting::CoolerBoost(enabled) => write!(f, "Cooler Boost {}", on_off(enabled)),
+            Setting::ShiftMode(mode) => write!(f, "shift mode {}", mode),
+            Setting::SuperBattery(enabled) => write!(f, "super battery {}", on_off(enabled)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Override {
+    #[serde(flatten)]
+    pub setting: Setting,
+    /// Unix time at which it ends; `None` lasts until reboot.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<i64>,
+}
+
+impl Override {
+    fn has_expired(&self) -> bool {
+        self.until.is_some_and(|until| chrono::Local::now().timestamp() >= until)
+    }
+
+    /// When it ends, for display.
+    pub fn until_display(&self) -> String {
+        match self.until.and_then(|until| chrono::DateTime::from_timestamp(until, 0)) {
+            Some(until) => format!("until {}", until.with_timezone(&chrono::Local).format("%H:%M")),
+            None => "until reboot".to_string(),
+        }
+    }
+}
+
+/// The overrides of the current boot, kept next to the config.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Overrides {
+    boot_id: String,
+    overrides: Vec<Override>,
+}
+
+fn boot_id() -> String {
+    fs::read_to_string(BOOT_ID_PATH).map(|id| id.trim().to_string()).unwrap_or_default()
+}
+
+impl Overrides {
+    fn path() -> Result<PathBuf> {
+        Ok(AppConfig::config_dir()?.join("overrides.json"))
+    }
+
+    /// The overrides still in force: set during this boot and not run out.
+    pub fn load() -> Self {
+        let boot_id = boot_id();
+        let stored: Option<Overrides> = Self::path().ok().and_then(|path| {
+            let content = fs::read_to_string(path).ok()?;
+            serde_json::from_str(&content).map_err(|e| log::warn!("Ignoring invalid overrides file: {}", e)).ok()
+        });
+        let overrides = stored
+            .filter(|stored| stored.boot_id == boot_id)
+            .map(|stored| stored.overrides.into_iter().filter(|o| !o.has_expired()).collect())
+            .unwrap_or_default();
+        Self { boot_id, overrides }
+    }
+
+    /// Writes through a temporary file renamed into place, since the daemon reads
+    /// the file on every fan tick and must never see it half written.
+    pub fn save(&self) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        let mut temp_file = tempfile::Builder::new()
+            .prefix("overrides.json.")
+            .suffix(".tmp")
+            .tempfile_in(AppConfig::config_dir()?)?;
+        temp_file.write_all(content.as_bytes())?;
+        temp_file.as_file().sync_all()?;
+        temp_file.persist(Self::path()?).map_err(|e| e.error)?;
+        Ok(())
+    }
+
+    pub fn active(&self) -> &[Override] {
+        &self.overrides
+    }
+
+    pub fn settings(&self) -> Vec<Setting> {
+        self.overrides.iter().map(|o| o.setting).collect()
+    }
+
+    /// Adds an override, replacing any earlier one on the same control.
+    pub fn set(&mut self, setting: Setting, until: Option<i64>) {
+        self.overrides.retain(|o| o.setting.control() != setting.control());
+        self.overrides.push(Override { setting, until });
+    }
+
+    /// Removes the override on `control`, or all of them. Returns what was removed.
+    pub fn clear(&mut self, control: Option<&str>) -> Vec<Override> {
+        let (removed, kept) = self.overrides.iter().partition(|o| control.is_none_or(|control| o.setting.control() == control));
+        self.overrides = kept;
+        removed
+    }
+
+    /// Puts every override into settings about to be applied, so a profile switch
+    /// doesn't undo them.
+    pub fn apply_to(&self, settings: &mut ScenarioSettings) {
+        for o in &self.overrides {
+            o.setting.apply_to(settings);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_replace_profile_values_and_lapse() {
+        let mut overrides = Overrides::default();
+        overrides.set(Setting::FanMode(FanMode::Advanced), None);
+        overrides.set(Setting::FanMode(FanMode::Silent), None);
+        overrides.set(Setting::CoolerBoost(false), Some(0));
+        assert_eq!(overrides.settings(), [Setting::FanMode(FanMode::Silent), Setting::CoolerBoost(false)]);
+        assert!(overrides.active()[1].has_expired());
+
+        let mut settings = ScenarioSettings::turbo();
+        overrides.apply_to(&mut settings);
+        assert_eq!(settings.fan_mode, FanMode::Silent);
+        assert!(!settings.cooler_boost);
+
+        assert_eq!(overrides.clear(Some("cooler-boost")).len(), 1);
+        assert_eq!(overrides.clear(None).len(), 1);
+        assert!(overrides.active().is_empty());
+    }
+}
@@ -0,0 +1,57 @@
+//! Matching for the GUI's Ctrl+K command palette: every word typed has to appear
+//! in a command's label, and commands where the words start a word of the label
+//! ("cb on" for "Cooler Boost on") rank above those where they only appear inside
+//! one.
+
+/// Extra cost of a query word found in the middle of a label word.
+const INSIDE_WORD: usize = 100;
+
+/// How well `label` matches `query`, lower being better, or `None` if a word of
+/// the query is missing. An empty query matches everything equally.
+pub fn score(label: &str, query: &str) -> Option<usize> {
+    let label = label.to_lowercase();
+    let words: Vec<&str> = label.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect();
+    let mut score = 0;
+    for part in query.to_lowercase().split_whitespace() {
+        if words.iter().any(|word| word.starts_with(part)) {
+            continue;
+        }
+        if !label.contains(part) && !is_initials(part, &words) {
+            return None;
+        }
+        score += INSIDE_WORD;
+    }
+    // Among equally good matches, shorter labels are closer to what was typed.
+    Some(score + label.len())
+}
+
+/// Whether `part` spells the first letters of consecutive label words, like "cb".
+fn is_initials(part: &str, words: &[&str]) -> bool {
+    part.len() > 1
+        && words.windows(part.len()).any(|run| run.iter().zip(part.chars()).all(|(word, c)| word.starts_with(c)))
+}
+
+/// Indices of the entries matching `query`, best first; ties keep their order.
+pub fn filter<'a>(labels: impl IntoIterator<Item = &'a str>, query: &str) -> Vec<usize> {
+    let mut matches: Vec<(usize, usize)> =
+        labels.into_iter().enumerate().filter_map(|(index, label)| Some((score(label, query)?, index))).collect();
+    matches.sort();
+    matches.into_iter().map(|(_, index)| index).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn words_match_in_any_order_and_word_starts_rank_first() {
+        let labels = ["Cooler Boost on", "Cooler Boost off", "Apply scenario Silent", "Open Fan Control tab", "Fan mode Silent"];
+        assert_eq!(filter(labels, "cooler boost on"), [0]);
+        assert_eq!(filter(labels, "silent apply"), [2]);
+        assert_eq!(filter(labels, "cb off"), [1]);
+        assert_eq!(filter(labels, "fan"), [4, 3]);
+        assert_eq!(filter(labels, "ilent"), [4, 2]);
+        assert!(filter(labels, "battery").is_empty());
+        assert_eq!(filter(labels, "").len(), labels.len());
+    }
+}
@@ -0,0 +1,196 @@
+//! Runs CLI commands on other machines over SSH (`--host ssh://user@laptop`). The
+//! remote side is a plain `msi-center` invocation, so `--json` output and
+//! `profile export`/`import` work across machines unchanged.
+
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum RemoteError {
+    #[error("Invalid host '{0}'. Use ssh://[user@]host[:port] or [user@]host")]
+    InvalidHost(String),
+    #[error("Unsupported scheme in '{0}'; only ssh:// hosts are supported")]
+    UnsupportedScheme(String),
+    #[error("Failed to run ssh: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{host}: output is not valid JSON: {error}")]
+    JsonError { host: String, error: serde_json::Error },
+    #[error("Command failed on {0}")]
+    Failed(String),
+}
+
+pub type Result<T> = std::result::Result<T, RemoteError>;
+
+/// Program run on the remote machine; must be on its `PATH`.
+const REMOTE_PROGRAM: &str = "msi-center";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Host {
+    pub user: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl std::str::FromStr for Host {
+    type Err = RemoteError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let rest = match s.split_once("://") {
+            Some(("ssh", rest)) => rest,
+            Some(_) => return Err(RemoteError::UnsupportedScheme(s.to_string())),
+            None => s,
+        };
+        let rest = rest.trim_end_matches('/');
+        let (user, address) = match rest.rsplit_once('@') {
+            Some((user, address)) => (Some(user.to_string()), address),
+            None => (None, rest),
+        };
+        let (host, port) = match address.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().map_err(|_| RemoteError::InvalidHost(s.to_string()))?)),
+            None => (address, None),
+        };
+        // ssh would take a leading dash as one of its own options.
+        if host.is_empty() || user.as_deref() == Some("") || host.starts_with('-') || user.as_deref().is_some_and(|u| u.starts_with('-')) {
+            return Err(RemoteError::InvalidHost(s.to_string()));
+        }
+        Ok(Self { user, host: host.to_string(), port })
+    }
+}
+
+impl std::fmt::Display for Host {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(ref user) = self.user {
+            write!(f, "{}@", user)?;
+        }
+        write!(f, "{}", self.host)?;
+        if let Some(port) = self.port {
+            write!(f, ":{}", port)?;
+        }
+        Ok(())
+    }
+}
+
+/// Quotes an argument for the remote shell, which ssh always goes through.
+fn shell_quote(arg: &str) -> String {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c)) {
+        return arg.to_string();
+    }
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+fn ssh_command(host: &Host, args: &[String]) -> Command {
+    let mut command = Command::new("ssh");
+    // Never stop to ask for a password per host; use keys or an agent.
+    command.args(["-o", "BatchMode=yes"]);
+    if let Some(port) = host.port {
+        command.arg("-p").arg(port.to_string());
+    }
+    let target = match host.user {
+        Some(ref user) => format!("{}@{}", user, host.host),
+        None => host.host.clone(),
+    };
+    let remote: Vec<String> = std::iter::once(REMOTE_PROGRAM.to_string()).chain(args.iter().map(|a| shell_quote(a))).collect();
+    command.arg("--").arg(target).arg(remote.join(" "));
+    command
+}
+
+/// The command line without the program name and `--host` flags, to run remotely.
+pub fn forwarded_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut forwarded = Vec::new();
+    let mut args = args.skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--host" {
+            args.next();
+            continue;
+        }
+        if !arg.starts_with("--host=") {
+            forwarded.push(arg);
+        }
+    }
+    forwarded
+}
+
+/// Runs `args` on every host. A single host gets the terminal directly, so
+/// `monitor` and colored output work as they do locally. With several hosts the
+/// output is grouped per host, and `--json` output is merged into one object
+/// keyed by host.
+pub fn run(hosts: &[Host], args: &[String]) -> Result<()> {
+    if let [host] = hosts {
+        let status = ssh_command(host, args).status()?;
+        return if status.success() { Ok(()) } else { Err(RemoteError::Failed(host.to_string())) };
+    }
+
+    // Every host gets the same input, e.g. a profile for `profile import`.
+    let mut input = Vec::new();
+    if !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+        std::io::stdin().read_to_end(&mut input)?;
+    }
+
+    let json = args.iter().any(|a| a == "--json");
+    let mut merged = serde_json::Map::new();
+    let mut failed = Vec::new();
+
+    for host in hosts {
+        let mut child = ssh_command(host, args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command that doesn't read its input closes the pipe early; that's fine.
+            let _ = stdin.write_all(&input);
+        }
+        let output = child.wait_with_output()?;
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+
+        if json {
+            if output.status.success() {
+                match serde_json::from_str(&stdout) {
+                    Ok(value) => {
+                        merged.insert(host.to_string(), value);
+                    }
+                    Err(error) => {
+                        eprintln!("{}", RemoteError::JsonError { host: host.to_string(), error });
+                        failed.push(host.to_string());
+                    }
+                }
+            } else {
+                eprintln!("{}: {}", host, stderr.trim());
+                failed.push(host.to_string());
+            }
+            continue;
+        }
+
+        println!("── {} ──", host);
+        print!("{}", stdout);
+        eprint!("{}", stderr);
+        if !output.status.success() {
+            failed.push(host.to_string());
+        }
+        println!();
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&merged).unwrap_or_default());
+    }
+    if failed.is_empty() { Ok(()) } else { Err(RemoteError::Failed(failed.join(", "))) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hosts_parse_and_reject_option_lookalikes() {
+        let host: Host = "ssh://me@laptop:2222/".parse().unwrap();
+        assert_eq!(host, Host { user: Some("me".to_string()), host: "laptop".to_string(), port: Some(2222) });
+        assert_eq!("laptop".parse::<Host>().unwrap().to_string(), "laptop");
+
+        for invalid in ["-oProxyCommand=evil", "ssh://-oProxyCommand=evil", "-me@laptop", "me@", "laptop:port"] {
+            assert!(invalid.parse::<Host>().is_err(), "{} parsed", invalid);
+        }
+        assert!(matches!("http://laptop".parse::<Host>(), Err(RemoteError::UnsupportedScheme(_))));
+    }
+}
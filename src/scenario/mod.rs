@@ -1,6 +1,9 @@
-use crate::ec::{EcError, EmbeddedController, MSI_ADDRESS_SHIFT_MODE, MSI_ADDRESS_SUPER_BATTERY};
-use crate::fan::{FanController, FanCurve, FanError, FanMode};
+use crate::ec::msi_ec;
+use crate::ec::quirks::{IndicatorLed, SuperBatteryBits};
+use crate::ec::{EcError, EmbeddedController, Support};
+use crate::fan::{FanController, FanCurve, FanError, FanMode, ManualFanSpeed};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -11,6 +14,10 @@ pub enum ScenarioError {
     FanError(#[from] FanError),
     #[error("Invalid scenario: {0}")]
     InvalidScenario(String),
+    #[error("Shift mode {0} is not supported by this model. Available: {1}")]
+    UnsupportedShiftMode(ShiftMode, String),
+    #[error("{0} is not supported on this model or EC backend")]
+    Unsupported(&'static str),
 }
 
 pub type Result<T> = std::result::Result<T, ScenarioError>;
@@ -23,6 +30,32 @@ pub enum ShiftMode {
     Turbo = 0xC4,
 }
 
+impl ShiftMode {
+    pub const ALL: [ShiftMode; 4] = [ShiftMode::EcoSilent, ShiftMode::Comfort, ShiftMode::Sport, ShiftMode::Turbo];
+
+    /// Shift modes the firmware supports, as listed by msi-ec. Falls back to all modes
+    /// when the driver isn't loaded or doesn't report them.
+    pub fn available() -> Vec<ShiftMode> {
+        let Some(names) = msi_ec::available_shift_modes() else {
+            return Self::ALL.to_vec();
+        };
+
+        let modes: Vec<ShiftMode> = Self::ALL
+            .into_iter()
+            .filter(|mode| names.iter().any(|name| name.parse() == Ok(*mode)))
+            .collect();
+        if modes.is_empty() {
+            log::debug!("Unrecognized available_shift_modes: {:?}", names);
+            return Self::ALL.to_vec();
+        }
+        modes
+    }
+
+    pub fn is_available(self) -> bool {
+        Self::available().contains(&self)
+    }
+}
+
 impl From<u8> for ShiftMode {
     fn from(value: u8) -> Self {
         match value {
@@ -46,7 +79,70 @@ impl std::fmt::Display for ShiftMode {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+impl std::str::FromStr for ShiftMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "eco" | "silent" => Ok(ShiftMode::EcoSilent),
+            "comfort" | "balanced" => Ok(ShiftMode::Comfort),
+            "sport" | "performance" => Ok(ShiftMode::Sport),
+            "turbo" | "extreme" => Ok(ShiftMode::Turbo),
+            _ => Err(format!("Invalid shift mode: {}. Use: eco, comfort, sport, turbo", s)),
+        }
+    }
+}
+
+/// Bit of the super battery register set on every model.
+const SUPER_BATTERY_ON: u8 = 0x01;
+
+/// Behaviors the super battery register also switches on some models, each with
+/// its own bit as mapped in the model's quirk file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuperBatteryFeature {
+    GpuClamp,
+    PanelDimming,
+}
+
+impl SuperBatteryFeature {
+    pub const ALL: [SuperBatteryFeature; 2] = [SuperBatteryFeature::GpuClamp, SuperBatteryFeature::PanelDimming];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SuperBatteryFeature::GpuClamp => "GPU clamp",
+            SuperBatteryFeature::PanelDimming => "Panel dimming",
+        }
+    }
+
+    /// The register bit of this feature, if the model maps it.
+    pub fn mask(self, bits: &SuperBatteryBits) -> Option<u8> {
+        match self {
+            SuperBatteryFeature::GpuClamp => bits.gpu_clamp,
+            SuperBatteryFeature::PanelDimming => bits.panel_dimming,
+        }
+    }
+}
+
+impl std::fmt::Display for SuperBatteryFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl std::str::FromStr for SuperBatteryFeature {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "gpu-clamp" | "gpu" => Ok(SuperBatteryFeature::GpuClamp),
+            "panel-dimming" | "dimming" => Ok(SuperBatteryFeature::PanelDimming),
+            _ => Err(format!("Invalid super battery feature: {}. Use: gpu-clamp, panel-dimming", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum UserScenario {
     Silent,
     Balanced,
@@ -69,25 +165,72 @@ impl std::fmt::Display for UserScenario {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::str::FromStr for UserScenario {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "silent" | "quiet" => Ok(UserScenario::Silent),
+            "balanced" | "comfort" => Ok(UserScenario::Balanced),
+            "highperf" | "performance" | "sport" | "high performance" => Ok(UserScenario::HighPerformance),
+            "turbo" | "extreme" => Ok(UserScenario::Turbo),
+            "battery" | "superbattery" | "eco" | "super battery" => Ok(UserScenario::SuperBattery),
+            _ => Err(format!("Invalid scenario: {}. Use: silent, balanced, highperf, turbo, battery", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ScenarioSettings {
     pub shift_mode: ShiftMode,
     pub fan_mode: FanMode,
     pub cooler_boost: bool,
     pub super_battery: bool,
+    /// Features that differ from `super_battery`, on models that map their bits.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub super_battery_features: BTreeMap<SuperBatteryFeature, bool>,
     pub cpu_fan_curve: Option<FanCurve>,
     pub gpu_fan_curve: Option<FanCurve>,
+    /// Manual speeds to restore on apply; written after the curves so they take precedence.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub manual_fan_speed: Option<ManualFanSpeed>,
+    /// Lid logo brightness in percent, 0 being off; `None` leaves the LED as it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lid_logo: Option<u8>,
+    /// Power/battery indicator LED behavior, on models whose EC lets it be configured.
+    #[serde(default, skip_serializing_if = "IndicatorSettings::is_empty")]
+    pub indicator_led: IndicatorSettings,
+    /// Duty (%) the profile's curves and manual speeds are raised to where they run slower.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_fan_percent: Option<u8>,
 }
 
 impl ScenarioSettings {
+    /// Built-in settings of a scenario; `Custom` has none.
+    pub fn for_scenario(scenario: UserScenario) -> Option<Self> {
+        match scenario {
+            UserScenario::Silent => Some(Self::silent()),
+            UserScenario::Balanced => Some(Self::balanced()),
+            UserScenario::HighPerformance => Some(Self::high_performance()),
+            UserScenario::Turbo => Some(Self::turbo()),
+            UserScenario::SuperBattery => Some(Self::super_battery()),
+            UserScenario::Custom => None,
+        }
+    }
+
     pub fn silent() -> Self {
         Self {
             shift_mode: ShiftMode::EcoSilent,
             fan_mode: FanMode::Silent,
             cooler_boost: false,
             super_battery: false,
+            super_battery_features: BTreeMap::new(),
             cpu_fan_curve: Some(FanCurve::silent()),
             gpu_fan_curve: Some(FanCurve::silent()),
+            manual_fan_speed: None,
+            lid_logo: None,
+            indicator_led: IndicatorSettings::default(),
+            min_fan_percent: None,
         }
     }
 
@@ -97,8 +240,13 @@ impl ScenarioSettings {
             fan_mode: FanMode::Auto,
             cooler_boost: false,
             super_battery: false,
+            super_battery_features: BTreeMap::new(),
             cpu_fan_curve: Some(FanCurve::default()),
             gpu_fan_curve: Some(FanCurve::default()),
+            manual_fan_speed: None,
+            lid_logo: None,
+            indicator_led: IndicatorSettings::default(),
+            min_fan_percent: None,
         }
     }
 
@@ -108,8 +256,13 @@ impl ScenarioSettings {
             fan_mode: FanMode::Basic,
             cooler_boost: false,
             super_battery: false,
+            super_battery_features: BTreeMap::new(),
             cpu_fan_curve: Some(FanCurve::performance()),
             gpu_fan_curve: Some(FanCurve::performance()),
+            manual_fan_speed: None,
+            lid_logo: None,
+            indicator_led: IndicatorSettings::default(),
+            min_fan_percent: None,
         }
     }
 
@@ -119,8 +272,13 @@ impl ScenarioSettings {
             fan_mode: FanMode::Advanced,
             cooler_boost: true,
             super_battery: false,
+            super_battery_features: BTreeMap::new(),
             cpu_fan_curve: Some(FanCurve::performance()),
             gpu_fan_curve: Some(FanCurve::performance()),
+            manual_fan_speed: None,
+            lid_logo: Some(100),
+            indicator_led: IndicatorSettings::default(),
+            min_fan_percent: None,
         }
     }
 
@@ -130,40 +288,198 @@ impl ScenarioSettings {
             fan_mode: FanMode::Silent,
             cooler_boost: false,
             super_battery: true,
+            super_battery_features: BTreeMap::new(),
             cpu_fan_curve: Some(FanCurve::silent()),
             gpu_fan_curve: Some(FanCurve::silent()),
+            manual_fan_speed: None,
+            lid_logo: Some(0),
+            indicator_led: IndicatorSettings::default(),
+            min_fan_percent: None,
+        }
+    }
+}
+
+/// Behavior of the power/battery indicator LED; unset fields leave the EC's setting
+/// as it is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IndicatorSettings {
+    /// Keeps the LED dark while on battery.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub off_on_battery: Option<bool>,
+    /// Battery level in percent below which the LED blinks; 0 never blinks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blink_below: Option<u8>,
+}
+
+impl IndicatorSettings {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Only the fields `led` maps registers for.
+    fn mapped(&self, led: &IndicatorLed) -> Self {
+        Self {
+            off_on_battery: self.off_on_battery.filter(|_| led.off_on_battery.is_some()),
+            blink_below: self.blink_below.filter(|_| led.blink_threshold.is_some()),
+        }
+    }
+}
+
+impl std::fmt::Display for IndicatorSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(off) = self.off_on_battery {
+            parts.push(if off { "off on battery".to_string() } else { "on on battery".to_string() });
+        }
+        match self.blink_below {
+            Some(0) => parts.push("never blinks".to_string()),
+            Some(percent) => parts.push(format!("blinks below {}%", percent)),
+            None => {}
+        }
+        write!(f, "{}", if parts.is_empty() { "firmware default".to_string() } else { parts.join(", ") })
+    }
+}
+
+/// Settings that can be left to another power-management tool instead of being
+/// written when a profile or scenario is applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeferredControl {
+    ShiftMode,
+    SuperBattery,
+}
+
+impl std::fmt::Display for DeferredControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeferredControl::ShiftMode => write!(f, "shift_mode"),
+            DeferredControl::SuperBattery => write!(f, "super_battery"),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct ScenarioInfo {
     pub current_scenario: UserScenario,
     pub shift_mode: ShiftMode,
     pub super_battery: bool,
+    /// State of the features this model maps to their own bits.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub super_battery_features: BTreeMap<SuperBatteryFeature, bool>,
+    /// Lid logo brightness in percent, on models with a lid logo LED.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lid_logo: Option<u8>,
+    /// Indicator LED behavior, on models whose EC lets it be configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub indicator_led: Option<IndicatorSettings>,
+}
+
+/// A setting the hardware holds differently from a profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mismatch {
+    pub setting: &'static str,
+    pub profile: String,
+    pub hardware: String,
+}
+
+impl std::fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} in the profile, {} on the hardware", self.setting, self.profile, self.hardware)
+    }
+}
+
+/// Settings of `profile` that `hardware` doesn't match, among those this machine
+/// supports and that aren't deferred to other tools. Fan curves only differ where
+/// the tables could be read back; see [`ScenarioManager::hardware_settings`].
+pub fn mismatches(profile: &ScenarioSettings, hardware: &ScenarioSettings, support: &Support, deferred: &[DeferredControl]) -> Vec<Mismatch> {
+    let mut found = Vec::new();
+    let mut compare = |setting: &'static str, compared: bool, profile: String, hardware: String| {
+        if compared && profile != hardware {
+            found.push(Mismatch { setting, profile, hardware });
+        }
+    };
+    let on_off = |on: bool| if on { "on" } else { "off" }.to_string();
+    compare(
+        "Shift mode",
+        support.shift_mode && !deferred.contains(&DeferredControl::ShiftMode),
+        profile.shift_mode.to_string(),
+        hardware.shift_mode.to_string(),
+    );
+    let super_battery = support.super_battery && !deferred.contains(&DeferredControl::SuperBattery);
+    compare("Super battery", super_battery, on_off(profile.super_battery), on_off(hardware.super_battery));
+    for (feature, &enabled) in &profile.super_battery_features {
+        let actual = hardware.super_battery_features.get(feature).copied().unwrap_or(hardware.super_battery);
+        compare(feature.label(), super_battery, on_off(enabled), on_off(actual));
+    }
+    // Manual speeds are written in Advanced mode.
+    let fan_mode = if profile.manual_fan_speed.is_some() { FanMode::Advanced } else { profile.fan_mode };
+    compare("Fan mode", support.fan_mode, format!("{:?}", fan_mode), format!("{:?}", hardware.fan_mode));
+    compare("Cooler boost", support.cooler_boost, on_off(profile.cooler_boost), on_off(hardware.cooler_boost));
+    let points = |curve: &FanCurve| curve.points.iter().map(|p| format!("{}:{}", p.temp, p.speed)).collect::<Vec<_>>().join(",");
+    for (setting, expected, actual) in [("CPU fan curve", &profile.cpu_fan_curve, &hardware.cpu_fan_curve), ("GPU fan curve", &profile.gpu_fan_curve, &hardware.gpu_fan_curve)] {
+        if let (Some(expected), Some(actual)) = (expected, actual) {
+            compare(setting, support.fan_curves && profile.manual_fan_speed.is_none(), points(expected), points(actual));
+        }
+    }
+    if let (Some(expected), Some(actual)) = (profile.lid_logo, hardware.lid_logo) {
+        compare("Lid logo", support.lid_logo, format!("{}%", expected), format!("{}%", actual));
+    }
+    let (expected, actual) = (profile.indicator_led, hardware.indicator_led);
+    if let (Some(expected), Some(actual)) = (expected.off_on_battery, actual.off_on_battery) {
+        compare("Indicator LED off on battery", support.indicator_led, on_off(expected), on_off(actual));
+    }
+    if let (Some(expected), Some(actual)) = (expected.blink_below, actual.blink_below) {
+        compare("Indicator LED blink threshold", support.indicator_led, format!("{}%", expected), format!("{}%", actual));
+    }
+    found
 }
 
+/// Works through the fan controller's EC handle, so applying a scenario opens the EC once.
 pub struct ScenarioManager<'a> {
-    ec: &'a mut EmbeddedController,
     fan_controller: &'a mut FanController,
     current_scenario: UserScenario,
+    deferred: Vec<DeferredControl>,
 }
 
 impl<'a> ScenarioManager<'a> {
-    pub fn new(ec: &'a mut EmbeddedController, fan_controller: &'a mut FanController) -> Self {
+    pub fn new(fan_controller: &'a mut FanController) -> Self {
         Self {
-            ec,
             fan_controller,
             current_scenario: UserScenario::Balanced,
+            deferred: Vec::new(),
         }
     }
 
+    fn ec(&mut self) -> &mut EmbeddedController {
+        self.fan_controller.ec_mut()
+    }
+
+    /// Skips writing the given controls in `apply_settings`, leaving them to other tools.
+    pub fn set_deferred_controls(&mut self, controls: &[DeferredControl]) {
+        self.deferred = controls.to_vec();
+    }
+
     pub fn get_current_info(&mut self) -> Result<ScenarioInfo> {
-        let shift_mode_raw = self.ec.read_byte(MSI_ADDRESS_SHIFT_MODE).unwrap_or(0xC1);
-        let super_battery_raw = self.ec.read_byte(MSI_ADDRESS_SUPER_BATTERY).unwrap_or(0);
+        let ec = self.ec();
+        let registers = ec.registers();
+        let shift_mode_raw = ec.read_byte(registers.shift_mode).unwrap_or(0xC1);
+        let super_battery_raw = ec.read_byte(registers.super_battery).unwrap_or(0);
 
         let shift_mode = ShiftMode::from(shift_mode_raw);
-        let super_battery = (super_battery_raw & 0x01) != 0;
+        let super_battery = (super_battery_raw & SUPER_BATTERY_ON) != 0;
+        let bits = self.ec().capabilities().super_battery_bits;
+        let super_battery_features = SuperBatteryFeature::ALL
+            .into_iter()
+            .filter_map(|feature| feature.mask(&bits).map(|mask| (feature, super_battery_raw & mask != 0)))
+            .collect();
+
+        let lid_logo = self.ec().capabilities().lid_logo.and_then(|logo| {
+            let level = self.ec().read_byte(logo.register).ok()?;
+            Some(logo.percent(level))
+        });
+
+        let indicator_led = self.indicator_led().ok();
 
         let current_scenario = self.detect_scenario(shift_mode, super_battery);
 
@@ -171,9 +487,70 @@ impl<'a> ScenarioManager<'a> {
             current_scenario,
             shift_mode,
             super_battery,
+            super_battery_features,
+            lid_logo,
+            indicator_led,
         })
     }
 
+    /// `base` with the settings the hardware holds right now in place of its own.
+    /// Fan curves are read from the fan tables where the backend can; see
+    /// [`Self::table_curve`].
+    pub fn hardware_settings(&mut self, base: &ScenarioSettings) -> Result<ScenarioSettings> {
+        let info = self.get_current_info()?;
+        let fans = self.fan_controller.get_fan_info()?;
+        // Pinned manual speeds overwrite the tables, so they hold no curve then.
+        let (cpu_fan_curve, gpu_fan_curve) = if base.manual_fan_speed.is_some() && fans.fan_mode == FanMode::Advanced {
+            (base.cpu_fan_curve.clone(), base.gpu_fan_curve.clone())
+        } else {
+            let floor = base.min_fan_percent.unwrap_or(0);
+            (self.table_curve(0, base.cpu_fan_curve.as_ref(), floor), self.table_curve(1, base.gpu_fan_curve.as_ref(), floor))
+        };
+        Ok(ScenarioSettings {
+            shift_mode: info.shift_mode,
+            fan_mode: fans.fan_mode,
+            cooler_boost: fans.cooler_boost,
+            super_battery: info.super_battery,
+            super_battery_features: info.super_battery_features,
+            manual_fan_speed: base.manual_fan_speed.filter(|_| fans.fan_mode == FanMode::Advanced),
+            lid_logo: info.lid_logo.or(base.lid_logo),
+            indicator_led: info.indicator_led.unwrap_or(base.indicator_led),
+            cpu_fan_curve,
+            gpu_fan_curve,
+            ..base.clone()
+        })
+    }
+
+    /// The curve fan `index`'s table holds, as a profile stores it: `profile` itself
+    /// while the table holds what applying it wrote (raised to `floor` and fitted),
+    /// otherwise the points, and tuning where the model has it, read back with the
+    /// rest of `profile`. Where the table
+    /// can't be read, or wasn't written, `profile`.
+    fn table_curve(&mut self, index: usize, profile: Option<&FanCurve>, floor: u8) -> Option<FanCurve> {
+        if index == 1 && !self.fan_controller.gpu_fan_writable() {
+            return profile.cloned();
+        }
+        let Ok(read) = self.fan_controller.read_fan_curve(index) else {
+            return profile.cloned();
+        };
+        let tuned = self.fan_controller.has_tuning(index);
+        match profile {
+            Some(curve) if curve.held_by(&read, floor, tuned) => Some(curve.clone()),
+            Some(curve) => {
+                let tuning = if tuned { read.tuning.clone() } else { curve.tuning.clone() };
+                Some(FanCurve { points: read.unpadded(Some(curve), floor), tuning, ..curve.clone() })
+            }
+            None => Some(read),
+        }
+    }
+
+    /// What the hardware holds differently from `settings`; see [`mismatches`].
+    pub fn compare(&mut self, settings: &ScenarioSettings) -> Result<Vec<Mismatch>> {
+        let hardware = self.hardware_settings(settings)?;
+        let support = self.ec().support();
+        Ok(mismatches(settings, &hardware, &support, &self.deferred))
+    }
+
     fn detect_scenario(&self, shift_mode: ShiftMode, super_battery: bool) -> UserScenario {
         if super_battery {
             return UserScenario::SuperBattery;
@@ -188,13 +565,8 @@ impl<'a> ScenarioManager<'a> {
     }
 
     pub fn set_scenario(&mut self, scenario: UserScenario) -> Result<()> {
-        let settings = match scenario {
-            UserScenario::Silent => ScenarioSettings::silent(),
-            UserScenario::Balanced => ScenarioSettings::balanced(),
-            UserScenario::HighPerformance => ScenarioSettings::high_performance(),
-            UserScenario::Turbo => ScenarioSettings::turbo(),
-            UserScenario::SuperBattery => ScenarioSettings::super_battery(),
-            UserScenario::Custom => return Ok(()),
+        let Some(settings) = ScenarioSettings::for_scenario(scenario) else {
+            return Ok(());
         };
 
         self.apply_settings(&settings)?;
@@ -204,36 +576,244 @@ impl<'a> ScenarioManager<'a> {
     }
 
     pub fn apply_settings(&mut self, settings: &ScenarioSettings) -> Result<()> {
-        self.ec.write_byte(MSI_ADDRESS_SHIFT_MODE, settings.shift_mode as u8)?;
+        let support = self.ec().support();
+        let capabilities = self.ec().capabilities();
+        if self.deferred.contains(&DeferredControl::ShiftMode) {
+            log::info!("Shift mode is deferred to another tool, not writing it");
+        } else if !support.shift_mode {
+            log::debug!("Shift mode is not supported here, not writing it");
+        } else if !settings.shift_mode.is_available() {
+            log::warn!("Shift mode {} is not supported by this model, leaving it unchanged", settings.shift_mode);
+        } else {
+            let ec = self.ec();
+            ec.write_byte(ec.registers().shift_mode, settings.shift_mode as u8)?;
+        }
 
-        let super_battery_value = if settings.super_battery { 0x01 } else { 0x00 };
-        self.ec.write_byte(MSI_ADDRESS_SUPER_BATTERY, super_battery_value)?;
+        if self.deferred.contains(&DeferredControl::SuperBattery) {
+            log::info!("Super battery is deferred to another tool, not writing it");
+        } else if !support.super_battery {
+            log::debug!("Super battery is not supported here, not writing it");
+        } else {
+            let super_battery_value = self.super_battery_value(settings.super_battery, &settings.super_battery_features);
+            let ec = self.ec();
+            ec.write_byte(ec.registers().super_battery, super_battery_value)?;
+        }
+
+        if support.fan_mode {
+            self.fan_controller.set_fan_mode(settings.fan_mode)?;
+        }
+        if support.cooler_boost {
+            self.fan_controller.set_cooler_boost(settings.cooler_boost)?;
+        }
 
-        self.fan_controller.set_fan_mode(settings.fan_mode)?;
-        self.fan_controller.set_cooler_boost(settings.cooler_boost)?;
+        let has_fan_tables = settings.cpu_fan_curve.is_some() || settings.gpu_fan_curve.is_some() || settings.manual_fan_speed.is_some();
+        if has_fan_tables && !support.fan_curves {
+            log::info!("Fan curves can't be written through the {} backend, skipping them", self.ec().backend_name());
+        } else {
+            let floor = settings.min_fan_percent.unwrap_or(0);
+            if let Some(ref curve) = settings.cpu_fan_curve {
+                self.fan_controller.set_cpu_fan_curve(curve.floored(floor))?;
+            }
+
+            if let Some(ref curve) = settings.gpu_fan_curve {
+                self.fan_controller.set_gpu_fan_curve(curve.floored(floor))?;
+            }
+
+            if let Some(speed) = settings.manual_fan_speed {
+                self.fan_controller.set_manual_fan_speeds(&speed.floored(floor).speeds())?;
+            }
+        }
 
-        if let Some(ref curve) = settings.cpu_fan_curve {
-            self.fan_controller.set_cpu_fan_curve(curve.clone())?;
+        if let Some(percent) = settings.lid_logo {
+            match capabilities.lid_logo.filter(|_| support.lid_logo) {
+                Some(logo) => {
+                    let ec = self.ec();
+                    ec.write_byte(logo.register, logo.level(percent))?;
+                }
+                None => log::debug!("This model has no lid logo LED, not writing it"),
+            }
         }
 
-        if let Some(ref curve) = settings.gpu_fan_curve {
-            self.fan_controller.set_gpu_fan_curve(curve.clone())?;
+        if !settings.indicator_led.is_empty() {
+            match capabilities.indicator_led.filter(|_| support.indicator_led) {
+                Some(led) => self.set_indicator_led(&settings.indicator_led.mapped(&led))?,
+                None => log::debug!("This model's indicator LED can't be configured, not writing it"),
+            }
         }
 
         Ok(())
     }
 
+    /// Writes only the GPU fan part of `settings`, e.g. once a sleeping dGPU wakes up.
+    pub fn apply_gpu_settings(&mut self, settings: &ScenarioSettings) -> Result<()> {
+        let floor = settings.min_fan_percent.unwrap_or(0);
+        if let Some(speed) = settings.manual_fan_speed {
+            self.fan_controller.set_manual_fan_speeds(&speed.floored(floor).speeds())?;
+        } else if let Some(ref curve) = settings.gpu_fan_curve {
+            self.fan_controller.set_gpu_fan_curve(curve.floored(floor))?;
+        }
+        Ok(())
+    }
+
     pub fn set_shift_mode(&mut self, mode: ShiftMode) -> Result<()> {
-        self.ec.write_byte(MSI_ADDRESS_SHIFT_MODE, mode as u8)?;
+        if !self.ec().support().shift_mode {
+            return Err(ScenarioError::Unsupported("Shift mode"));
+        }
+        let available = ShiftMode::available();
+        if !available.contains(&mode) {
+            let names: Vec<String> = available.iter().map(ToString::to_string).collect();
+            return Err(ScenarioError::UnsupportedShiftMode(mode, names.join(", ")));
+        }
+        let ec = self.ec();
+        ec.write_byte(ec.registers().shift_mode, mode as u8)?;
         Ok(())
     }
 
     pub fn set_super_battery(&mut self, enabled: bool) -> Result<()> {
-        let value = if enabled { 0x01 } else { 0x00 };
-        self.ec.write_byte(MSI_ADDRESS_SUPER_BATTERY, value)?;
+        if !self.ec().support().super_battery {
+            return Err(ScenarioError::Unsupported("Super battery"));
+        }
+        let value = self.super_battery_value(enabled, &BTreeMap::new());
+        let ec = self.ec();
+        ec.write_byte(ec.registers().super_battery, value)?;
+        Ok(())
+    }
+
+    /// Switches one feature of super battery mode, leaving the other bits as they are.
+    pub fn set_super_battery_feature(&mut self, feature: SuperBatteryFeature, enabled: bool) -> Result<()> {
+        let capabilities = self.ec().capabilities();
+        if !self.ec().support().super_battery {
+            return Err(ScenarioError::Unsupported("Super battery"));
+        }
+        let Some(mask) = feature.mask(&capabilities.super_battery_bits) else {
+            return Err(ScenarioError::Unsupported(feature.label()));
+        };
+        let ec = self.ec();
+        let register = ec.registers().super_battery;
+        let current = ec.read_byte(register)?;
+        let value = if enabled { current | mask } else { current & !mask };
+        ec.write_byte(register, value)?;
+        Ok(())
+    }
+
+    /// Sets the lid logo brightness in percent; 0 turns it off.
+    pub fn set_lid_logo(&mut self, percent: u8) -> Result<()> {
+        let Some(logo) = self.ec().capabilities().lid_logo.filter(|_| self.ec().support().lid_logo) else {
+            return Err(ScenarioError::Unsupported("Lid logo"));
+        };
+        let ec = self.ec();
+        ec.write_byte(logo.register, logo.level(percent))?;
+        Ok(())
+    }
+
+    fn indicator(&mut self) -> Result<IndicatorLed> {
+        self.ec().capabilities().indicator_led.filter(|_| self.ec().support().indicator_led).ok_or(ScenarioError::Unsupported("Indicator LED"))
+    }
+
+    /// How the indicator LED behaves now; fields the model doesn't map are `None`.
+    pub fn indicator_led(&mut self) -> Result<IndicatorSettings> {
+        let led = self.indicator()?;
+        let ec = self.ec();
+        let off_on_battery = match led.off_on_battery {
+            Some(bit) => Some(ec.read_byte(led.register)? & bit != 0),
+            None => None,
+        };
+        let blink_below = match led.blink_threshold {
+            Some(register) => Some(ec.read_byte(register)?.min(100)),
+            None => None,
+        };
+        Ok(IndicatorSettings { off_on_battery, blink_below })
+    }
+
+    /// Writes the fields of `settings` that are set, leaving the others as they are.
+    pub fn set_indicator_led(&mut self, settings: &IndicatorSettings) -> Result<()> {
+        let led = self.indicator()?;
+        if settings.off_on_battery.is_some() && led.off_on_battery.is_none() {
+            return Err(ScenarioError::Unsupported("Turning the indicator LED off on battery"));
+        }
+        if settings.blink_below.is_some() && led.blink_threshold.is_none() {
+            return Err(ScenarioError::Unsupported("The indicator LED blink threshold"));
+        }
+        let ec = self.ec();
+        if let (Some(off), Some(bit)) = (settings.off_on_battery, led.off_on_battery) {
+            let current = ec.read_byte(led.register)?;
+            ec.write_byte(led.register, if off { current | bit } else { current & !bit })?;
+        }
+        if let (Some(percent), Some(register)) = (settings.blink_below, led.blink_threshold) {
+            ec.write_byte(register, percent.min(100))?;
+        }
+        Ok(())
+    }
+
+    /// Whether the webcam is switched on.
+    pub fn webcam(&mut self) -> Result<bool> {
+        if !self.ec().support().webcam {
+            return Err(ScenarioError::Unsupported("Webcam switch"));
+        }
+        let ec = self.ec();
+        Ok(ec.read_byte(ec.registers().webcam)? & msi_ec::WEBCAM_ON != 0)
+    }
+
+    pub fn set_webcam(&mut self, enabled: bool) -> Result<()> {
+        if !self.ec().support().webcam {
+            return Err(ScenarioError::Unsupported("Webcam switch"));
+        }
+        let ec = self.ec();
+        let register = ec.registers().webcam;
+        let current = ec.read_byte(register)?;
+        let value = if enabled { current | msi_ec::WEBCAM_ON } else { current & !msi_ec::WEBCAM_ON };
+        ec.write_byte(register, value)?;
+        Ok(())
+    }
+
+    /// Charge stop threshold in percent; 100 when the EC charges to full.
+    pub fn charge_threshold(&mut self) -> Result<u8> {
+        if !self.ec().support().battery_threshold {
+            return Err(ScenarioError::Unsupported("Battery charge limit"));
+        }
+        let ec = self.ec();
+        let value = ec.read_byte(ec.registers().battery_charge)?;
+        Ok(if value & msi_ec::VALUE_SET == 0 { 100 } else { (value & !msi_ec::VALUE_SET).min(100) })
+    }
+
+    pub fn set_charge_threshold(&mut self, percent: u8) -> Result<()> {
+        if !self.ec().support().battery_threshold {
+            return Err(ScenarioError::Unsupported("Battery charge limit"));
+        }
+        let ec = self.ec();
+        ec.write_byte(ec.registers().battery_charge, msi_ec::VALUE_SET | percent.min(100))?;
         Ok(())
     }
 
+    /// Keyboard backlight level, from 0 (off) to 3.
+    pub fn keyboard_backlight(&mut self) -> Result<u8> {
+        if !self.ec().support().keyboard_backlight {
+            return Err(ScenarioError::Unsupported("Keyboard backlight"));
+        }
+        let ec = self.ec();
+        Ok((ec.read_byte(ec.registers().kbd_backlight)? & !msi_ec::VALUE_SET).min(msi_ec::MAX_KBD_LEVEL))
+    }
+
+    pub fn set_keyboard_backlight(&mut self, level: u8) -> Result<()> {
+        if !self.ec().support().keyboard_backlight {
+            return Err(ScenarioError::Unsupported("Keyboard backlight"));
+        }
+        let ec = self.ec();
+        ec.write_byte(ec.registers().kbd_backlight, msi_ec::VALUE_SET | level.min(msi_ec::MAX_KBD_LEVEL))?;
+        Ok(())
+    }
+
+    /// Register value for super battery mode: every mapped feature follows `enabled`
+    /// unless `overrides` says otherwise.
+    fn super_battery_value(&mut self, enabled: bool, overrides: &BTreeMap<SuperBatteryFeature, bool>) -> u8 {
+        let bits = self.ec().capabilities().super_battery_bits;
+        let base = if enabled { SUPER_BATTERY_ON } else { 0x00 };
+        SuperBatteryFeature::ALL.into_iter().fold(base, |value, feature| match feature.mask(&bits) {
+            Some(mask) if overrides.get(&feature).copied().unwrap_or(enabled) => value | mask,
+            _ => value,
+        })
+    }
+
     pub fn get_available_scenarios() -> Vec<UserScenario> {
         vec![
             UserScenario::Silent,
@@ -246,8 +826,78 @@ impl<'a> ScenarioManager<'a> {
 }
 
 pub fn apply_scenario_standalone(scenario: UserScenario) -> Result<()> {
-    let mut ec = EmbeddedController::new()?;
     let mut fan_controller = FanController::new(EmbeddedController::new()?);
-    let mut manager = ScenarioManager::new(&mut ec, &mut fan_controller);
+    let mut manager = ScenarioManager::new(&mut fan_controller);
     manager.set_scenario(scenario)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::quirks::{Capabilities, SuperBatteryBits};
+    use crate::ec::trace::{mock_ec, mock_ec_with};
+
+    #[test]
+    fn only_supported_and_owned_settings_are_compared() {
+        let profile = ScenarioSettings::turbo();
+        let hardware = ScenarioSettings::silent();
+        assert!(mismatches(&profile, &profile, &Support::ALL, &[]).is_empty());
+
+        let all: Vec<&str> = mismatches(&profile, &hardware, &Support::ALL, &[]).iter().map(|m| m.setting).collect();
+        assert!(all.contains(&"Shift mode") && all.contains(&"CPU fan curve"));
+
+        let support = Support { shift_mode: false, ..Support::ALL };
+        let limited = mismatches(&profile, &hardware, &support, &[DeferredControl::SuperBattery]);
+        assert!(limited.iter().all(|m| m.setting != "Shift mode" && m.setting != "Super battery"));
+        assert!(limited.len() < all.len());
+    }
+
+    #[test]
+    fn indicator_led_writes_only_its_own_bit() {
+        let mut memory = vec![Some(0u8); 256];
+        memory[0x2D] = Some(0x81);
+        let (mock, mut ec) = mock_ec_with(memory);
+        let led = IndicatorLed { register: 0x2D, off_on_battery: Some(0x04), blink_threshold: None };
+        ec.set_capabilities(Capabilities { indicator_led: Some(led), ..Capabilities::default() });
+        let mut fans = FanController::new(ec);
+        let mut manager = ScenarioManager::new(&mut fans);
+
+        assert_eq!(manager.indicator_led().unwrap(), IndicatorSettings { off_on_battery: Some(false), blink_below: None });
+        manager.set_indicator_led(&IndicatorSettings { off_on_battery: Some(true), blink_below: None }).unwrap();
+        assert_eq!(mock.lock().unwrap().read(0x2D), Some(0x85));
+        assert!(manager.set_indicator_led(&IndicatorSettings { off_on_battery: None, blink_below: Some(10) }).is_err());
+
+        // A profile's blink threshold is skipped on a model that can't set it.
+        let settings = ScenarioSettings {
+            indicator_led: IndicatorSettings { off_on_battery: Some(false), blink_below: Some(10) },
+            ..ScenarioSettings::balanced()
+        };
+        manager.apply_settings(&settings).unwrap();
+        assert_eq!(mock.lock().unwrap().read(0x2D), Some(0x81));
+    }
+
+    #[test]
+    fn super_battery_features_map_to_their_bits() {
+        let (mock, mut ec) = mock_ec(0);
+        ec.set_capabilities(Capabilities {
+            super_battery_bits: SuperBatteryBits { gpu_clamp: Some(0x02), panel_dimming: Some(0x04) },
+            ..Capabilities::default()
+        });
+        let mut fans = FanController::new(ec);
+        let mut manager = ScenarioManager::new(&mut fans);
+
+        manager.set_super_battery(true).unwrap();
+        manager.set_super_battery_feature(SuperBatteryFeature::PanelDimming, false).unwrap();
+        let info = manager.get_current_info().unwrap();
+        assert!(info.super_battery);
+        assert_eq!(info.super_battery_features.get(&SuperBatteryFeature::GpuClamp), Some(&true));
+        assert_eq!(info.super_battery_features.get(&SuperBatteryFeature::PanelDimming), Some(&false));
+
+        let mut settings = ScenarioSettings::balanced();
+        settings.super_battery_features.insert(SuperBatteryFeature::GpuClamp, true);
+        manager.apply_settings(&settings).unwrap();
+
+        let writes: Vec<u8> = mock.lock().unwrap().writes().iter().filter(|o| o.address == 0xEB).map(|o| o.value).collect();
+        assert_eq!(writes, vec![0x07, 0x03, 0x02]);
+    }
+}
@@ -0,0 +1,248 @@
+//! Command files for `msi-center run`: one CLI command per line (without the
+//! program name), `#` comments, and `if`/`else`/`end` blocks on the power source
+//! and the model's capabilities, e.g.
+//!
+//! ```text
+//! # Session startup
+//! if ac
+//!     scenario set turbo
+//! else
+//!     scenario set balanced
+//! end
+//! if has cooler_boost and not battery < 30
+//!     fan cooler-boost off
+//! end
+//! ```
+
+use crate::automation::PowerState;
+use crate::ec::quirks::Capabilities;
+use std::fs;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ScriptError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("line {0}: {1}")]
+    Parse(usize, String),
+}
+
+pub type Result<T> = std::result::Result<T, ScriptError>;
+
+/// What a condition can test for, read once when the script starts.
+#[derive(Debug, Clone, Copy)]
+pub struct Facts {
+    pub power: PowerState,
+    pub capabilities: Capabilities,
+    /// The msi-ec driver is loaded.
+    pub msi_ec: bool,
+}
+
+impl Facts {
+    pub fn read() -> Self {
+        Self {
+            power: PowerState::read(),
+            capabilities: crate::ec::quirks::capabilities(),
+            msi_ec: Path::new(crate::ec::msi_ec::MSI_EC_PATH).exists(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Ac,
+    Battery,
+    /// Battery charge below this percentage (false without a battery).
+    BatteryBelow(u8),
+    Has(String),
+    Not(Box<Condition>),
+    All(Vec<Condition>),
+}
+
+/// Capabilities `has` accepts.
+const CAPABILITIES: [&str; 7] = ["cooler_boost", "shift_mode", "super_battery", "lid_logo", "second_fan", "third_fan", "msi_ec"];
+
+impl Condition {
+    fn parse(line: usize, words: &[String]) -> Result<Self> {
+        let error = |message: String| ScriptError::Parse(line, message);
+        let mut conditions = Vec::new();
+        for clause in words.split(|word| word == "and") {
+            let mut words: Vec<&str> = clause.iter().map(String::as_str).collect();
+            let negated = words.first() == Some(&"not");
+            if negated {
+                words.remove(0);
+            }
+            let condition = match words.as_slice() {
+                ["ac"] => Condition::Ac,
+                ["battery"] => Condition::Battery,
+                ["battery", "<", percent] => {
+                    Condition::BatteryBelow(percent.trim_end_matches('%').parse().map_err(|_| error(format!("invalid battery level: {}", percent)))?)
+                }
+                ["has", capability] if CAPABILITIES.contains(capability) => Condition::Has(capability.to_string()),
+                ["has", capability] => return Err(error(format!("unknown capability '{}' (expected one of: {})", capability, CAPABILITIES.join(", ")))),
+                [] => return Err(error("missing condition".to_string())),
+                other => return Err(error(format!("unknown condition '{}'", other.join(" ")))),
+            };
+            conditions.push(if negated { Condition::Not(Box::new(condition)) } else { condition });
+        }
+        Ok(if conditions.len() == 1 { conditions.remove(0) } else { Condition::All(conditions) })
+    }
+
+    pub fn holds(&self, facts: &Facts) -> bool {
+        let caps = &facts.capabilities;
+        match self {
+            Condition::Ac => facts.power.on_ac,
+            Condition::Battery => !facts.power.on_ac,
+            Condition::BatteryBelow(percent) => facts.power.battery_percent.is_some_and(|level| level < *percent),
+            Condition::Has(capability) => match capability.as_str() {
+                "cooler_boost" => caps.cooler_boost,
+                "shift_mode" => caps.shift_mode,
+                "super_battery" => caps.super_battery,
+                "lid_logo" => caps.lid_logo.is_some(),
+                "second_fan" => caps.fans > 1,
+                "third_fan" => caps.fans > 2,
+                "msi_ec" => facts.msi_ec,
+                _ => false,
+            },
+            Condition::Not(condition) => !condition.holds(facts),
+            Condition::All(conditions) => conditions.iter().all(|condition| condition.holds(facts)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// A CLI command's arguments, and the line it is on.
+    Command(usize, Vec<String>),
+    If { condition: Condition, then: Vec<Step>, otherwise: Vec<Step> },
+}
+
+/// Splits a line into words like a shell would for simple cases: whitespace
+/// separates, quotes group, and `#` outside quotes starts a comment.
+fn split_words(line: usize, text: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    for c in text.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_default().push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_default();
+            }
+            (None, '#') if word.is_none() => break,
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_default().push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err(ScriptError::Parse(line, "unterminated quote".to_string()));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// An `if` whose `end` hasn't been reached yet.
+struct OpenIf {
+    line: usize,
+    condition: Condition,
+    /// The finished `then` branch, once `else` has been seen.
+    then: Option<Vec<Step>>,
+    /// Steps of the branch being read.
+    steps: Vec<Step>,
+}
+
+pub fn parse(source: &str) -> Result<Vec<Step>> {
+    let mut top = Vec::new();
+    let mut open: Vec<OpenIf> = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let line = index + 1;
+        let words = split_words(line, text)?;
+        let error = |message: &str| Err(ScriptError::Parse(line, message.to_string()));
+        match words.first().map(String::as_str) {
+            None => {}
+            Some("if") => {
+                let condition = Condition::parse(line, &words[1..])?;
+                open.push(OpenIf { line, condition, then: None, steps: Vec::new() });
+            }
+            Some("else") if words.len() > 1 => return error("'else' takes no condition; nest an 'if' inside it"),
+            Some("else") => match open.last_mut() {
+                Some(block) if block.then.is_none() => block.then = Some(std::mem::take(&mut block.steps)),
+                _ => return error("'else' without a matching 'if'"),
+            },
+            Some("end") if words.len() > 1 => return error("'end' takes no arguments"),
+            Some("end") => {
+                let Some(block) = open.pop() else {
+                    return error("'end' without a matching 'if'");
+                };
+                let (then, otherwise) = match block.then {
+                    Some(then) => (then, block.steps),
+                    None => (block.steps, Vec::new()),
+                };
+                let step = Step::If { condition: block.condition, then, otherwise };
+                open.last_mut().map_or(&mut top, |parent| &mut parent.steps).push(step);
+            }
+            Some(_) => open.last_mut().map_or(&mut top, |parent| &mut parent.steps).push(Step::Command(line, words)),
+        }
+    }
+    if let Some(block) = open.last() {
+        return Err(ScriptError::Parse(block.line, "'if' is never closed with 'end'".to_string()));
+    }
+    Ok(top)
+}
+
+pub fn load(path: &Path) -> Result<Vec<Step>> {
+    parse(&fs::read_to_string(path)?)
+}
+
+/// The commands to run, in order, with conditions evaluated against `facts`.
+pub fn commands<'a>(steps: &'a [Step], facts: &Facts) -> Vec<(usize, &'a [String])> {
+    let mut commands = Vec::new();
+    for step in steps {
+        match step {
+            Step::Command(line, words) => commands.push((*line, words.as_slice())),
+            Step::If { condition, then, otherwise } => {
+                let branch = if condition.holds(facts) { then } else { otherwise };
+                commands.extend(self::commands(branch, facts));
+            }
+        }
+    }
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branches_follow_power_source_and_capabilities() {
+        let script = parse(
+            "# startup\n\
+             fan mode auto\n\
+             if ac\n\
+                 scenario set 'turbo'  # plugged in\n\
+             else\n\
+                 if has cooler_boost and not battery < 30%\n\
+                     fan cooler-boost off\n\
+                 end\n\
+                 scenario set balanced\n\
+             end\n",
+        )
+        .unwrap();
+        let mut facts = Facts { power: PowerState { on_ac: true, battery_percent: Some(80) }, capabilities: Capabilities::default(), msi_ec: false };
+        let lines = |facts: &Facts| commands(&script, facts).into_iter().map(|(line, _)| line).collect::<Vec<_>>();
+
+        assert_eq!(commands(&script, &facts)[1].1, ["scenario", "set", "turbo"]);
+        assert_eq!(lines(&facts), [2, 4]);
+        facts.power.on_ac = false;
+        assert_eq!(lines(&facts), [2, 7, 9]);
+        facts.power.battery_percent = Some(20);
+        assert_eq!(lines(&facts), [2, 9]);
+
+        assert_eq!(parse("if ac\nfan mode auto\n").unwrap_err().to_string(), "line 1: 'if' is never closed with 'end'");
+        assert_eq!(parse("end\n").unwrap_err().to_string(), "line 1: 'end' without a matching 'if'");
+        assert!(parse("if has turbo_button\nend\n").is_err());
+    }
+}
@@ -0,0 +1,156 @@
+//! Sensor readings for other software: a stream of JSON merge patches (RFC 7396),
+//! one per line, for dashboards that keep their own copy of the state (the first
+//! line carries every value and the following ones only what changed since), and a
+//! directory of hwmon-style files for tools that only understand those.
+
+use crate::fan::FanInfo;
+use serde_json::{Map, Value};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum SensorsError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, SensorsError>;
+
+/// `name` of the bridge, as hwmon devices report their driver.
+const HWMON_NAME: &str = "msi_center";
+
+pub type Readings = Map<String, Value>;
+
+/// Flat sensor values of one reading; the CPU clock is left out where cpufreq isn't available.
+pub fn readings(info: &FanInfo, cpu_clock_mhz: Option<u32>) -> Readings {
+    let mut readings = Map::new();
+    readings.insert("cpu_temp".to_string(), info.cpu_temp.into());
+    readings.insert("gpu_temp".to_string(), info.gpu_temp.into());
+    if let Some(hotspot) = info.gpu.and_then(|gpu| gpu.hotspot) {
+        readings.insert("gpu_hotspot_temp".to_string(), hotspot.into());
+    }
+    if let Some(memory) = info.gpu.and_then(|gpu| gpu.memory) {
+        readings.insert("gpu_memory_temp".to_string(), memory.into());
+    }
+    for fan in &info.fans {
+        let name = fan.name.to_lowercase();
+        readings.insert(format!("{}_fan_rpm", name), fan.rpm.into());
+        readings.insert(format!("{}_fan_percent", name), fan.percent.into());
+        if let Some(target) = fan.target_percent {
+            readings.insert(format!("{}_fan_target_percent", name), target.into());
+        }
+    }
+    readings.insert("fan_mode".to_string(), format!("{:?}", info.fan_mode).into());
+    readings.insert("cooler_boost".to_string(), info.cooler_boost.into());
+    if let Some(clock) = cpu_clock_mhz {
+        readings.insert("cpu_clock_mhz".to_string(), clock.into());
+    }
+    readings
+}
+
+/// Writes the readings in hwmon's sysfs layout and units: `temp1_input` (CPU) and
+/// `temp2_input` (GPU) in millidegrees, `fanN_input` in RPM and `pwmN` as 0-255,
+/// each with a `_label`. Files are replaced atomically, so pollers never see half a value.
+pub fn write_hwmon(dir: &Path, info: &FanInfo) -> Result<()> {
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o755))?;
+    }
+
+    let mut files = vec![
+        ("name".to_string(), HWMON_NAME.to_string()),
+        ("temp1_input".to_string(), (info.cpu_temp as u32 * 1000).to_string()),
+        ("temp1_label".to_string(), "CPU".to_string()),
+        ("temp2_input".to_string(), (info.gpu_temp as u32 * 1000).to_string()),
+        ("temp2_label".to_string(), "GPU".to_string()),
+    ];
+    for (i, fan) in info.fans.iter().enumerate() {
+        files.push((format!("fan{}_input", i + 1), fan.rpm.to_string()));
+        files.push((format!("fan{}_label", i + 1), fan.name.to_string()));
+        files.push((format!("pwm{}", i + 1), (fan.percent.min(100) as u32 * 255 / 100).to_string()));
+    }
+
+    for (name, value) in files {
+        let temp = dir.join(format!(".{}.tmp", name));
+        fs::write(&temp, format!("{}\n", value))?;
+        fs::set_permissions(&temp, fs::Permissions::from_mode(0o644))?;
+        fs::rename(&temp, dir.join(name))?;
+    }
+    Ok(())
+}
+
+/// Removes the bridge's files, so stale readings don't pass for live ones, and the
+/// directory too once nothing else is in it.
+pub fn remove_hwmon(dir: &Path) -> Result<()> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let ours = |name: &str| {
+        name == "name" || ["temp", "fan", "pwm"].iter().any(|prefix| name.strip_prefix(prefix).is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit())))
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_str().is_some_and(ours) {
+            fs::remove_file(entry.path())?;
+        }
+    }
+    // Fails, harmlessly, if other files remain.
+    let _ = fs::remove_dir(dir);
+    Ok(())
+}
+
+/// Turns successive readings into merge patches.
+#[derive(Debug, Default)]
+pub struct PatchStream {
+    last: Option<Readings>,
+}
+
+impl PatchStream {
+    /// The values that changed since the previous reading (all of them the first
+    /// time), with `null` for values that disappeared; `None` if nothing changed.
+    pub fn patch(&mut self, readings: Readings) -> Option<Readings> {
+        let patch = match self.last {
+            None => readings.clone(),
+            Some(ref last) => {
+                let mut patch: Readings =
+                    readings.iter().filter(|(key, value)| last.get(*key) != Some(*value)).map(|(k, v)| (k.clone(), v.clone())).collect();
+                for key in last.keys().filter(|key| !readings.contains_key(*key)) {
+                    patch.insert(key.clone(), Value::Null);
+                }
+                patch
+            }
+        };
+        self.last = Some(readings);
+        (!patch.is_empty()).then_some(patch)
+    }
+
+    /// Makes the next patch carry every value again, for consumers joining late.
+    pub fn resync(&mut self) {
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn reading(value: Value) -> Readings {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn patches_carry_only_changed_values() {
+        let mut stream = PatchStream::default();
+        let first = reading(json!({ "cpu_temp": 60, "gpu_temp": 50, "cpu_clock_mhz": 3200 }));
+        assert_eq!(stream.patch(first.clone()), Some(first.clone()));
+        assert_eq!(stream.patch(first.clone()), None);
+
+        let second = reading(json!({ "cpu_temp": 61, "gpu_temp": 50 }));
+        assert_eq!(stream.patch(second.clone()), Some(reading(json!({ "cpu_temp": 61, "cpu_clock_mhz": null }))));
+
+        stream.resync();
+        assert_eq!(stream.patch(second.clone()), Some(second));
+    }
+}
@@ -0,0 +1,155 @@
+//! Summarizes a `monitor` session into one comparable score, for iterating on fan
+//! curves and power limits: run the same workload under each setting and compare.
+
+use crate::fan::FanInfo;
+use crate::system;
+use std::time::{Duration, Instant};
+
+/// Temperature (°C) above which peaks start to cost points.
+const HOT_TEMP: u8 = 80;
+/// Degrees above `HOT_TEMP` at which the heat penalty is at its maximum.
+const HEAT_RANGE: f64 = 40.0;
+const MAX_SCORE: f64 = 1000.0;
+
+#[derive(Debug)]
+pub struct SessionStats {
+    started: Instant,
+    samples: u32,
+    clock_sum: u64,
+    clock_samples: u32,
+    max_clock: u32,
+    throttled_samples: u32,
+    throttle_tracked: bool,
+    last_throttle_events: Option<u64>,
+    peak_cpu_temp: u8,
+    peak_gpu_temp: u8,
+    duty_sum: u64,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            samples: 0,
+            clock_sum: 0,
+            clock_samples: 0,
+            max_clock: 0,
+            throttled_samples: 0,
+            throttle_tracked: false,
+            last_throttle_events: system::throttle_events(),
+            peak_cpu_temp: 0,
+            peak_gpu_temp: 0,
+            duty_sum: 0,
+        }
+    }
+}
+
+impl SessionStats {
+    /// Adds one reading; clocks and throttling are read from sysfs alongside it.
+    pub fn record(&mut self, info: &FanInfo) {
+        self.record_sample(info, system::cpu_clocks_mhz(), system::throttle_events());
+    }
+
+    fn record_sample(&mut self, info: &FanInfo, clocks: Option<(u32, u32)>, throttle_events: Option<u64>) {
+        self.samples += 1;
+        if let Some((current, max)) = clocks {
+            self.clock_sum += current as u64;
+            self.clock_samples += 1;
+            self.max_clock = self.max_clock.max(max);
+        }
+        if let Some(events) = throttle_events {
+            self.throttle_tracked = true;
+            // Any new event since the previous sample counts the whole interval as throttled.
+            if self.last_throttle_events.is_some_and(|last| events > last) {
+                self.throttled_samples += 1;
+            }
+            self.last_throttle_events = Some(events);
+        }
+        self.peak_cpu_temp = self.peak_cpu_temp.max(info.cpu_temp);
+        self.peak_gpu_temp = self.peak_gpu_temp.max(info.gpu_temp);
+        self.duty_sum += (info.cpu_fan_percent as u64 + info.gpu_fan_percent as u64) / 2;
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn average_clock_mhz(&self) -> Option<u32> {
+        (self.clock_samples > 0).then(|| (self.clock_sum / self.clock_samples as u64) as u32)
+    }
+
+    /// Share of samples during which the CPU throttled, if the kernel reports it.
+    pub fn throttled_fraction(&self) -> Option<f64> {
+        (self.throttle_tracked && self.samples > 0).then(|| self.throttled_samples as f64 / self.samples as f64)
+    }
+
+    pub fn peak_temps(&self) -> (u8, u8) {
+        (self.peak_cpu_temp, self.peak_gpu_temp)
+    }
+
+    pub fn average_duty(&self) -> Option<u8> {
+        (self.samples > 0).then(|| (self.duty_sum / self.samples as u64) as u8)
+    }
+
+    /// 0–1000, higher is better. The share of the rated clock the CPU held sets the
+    /// base; time throttled, peaks above 80°C and average fan duty pull it down.
+    /// Without cpufreq the clock counts as fully held, so only compare scores from
+    /// the same machine.
+    pub fn score(&self) -> Option<u32> {
+        let duty = self.average_duty()?;
+        let clock = match (self.average_clock_mhz(), self.max_clock) {
+            (Some(average), max) if max > 0 => (average as f64 / max as f64).min(1.0),
+            _ => 1.0,
+        };
+        let throttling = 1.0 - 0.5 * self.throttled_fraction().unwrap_or(0.0);
+        let peak = self.peak_cpu_temp.max(self.peak_gpu_temp);
+        let heat = 1.0 - 0.5 * (peak.saturating_sub(HOT_TEMP) as f64 / HEAT_RANGE).min(1.0);
+        let noise = 1.0 - 0.25 * duty as f64 / 100.0;
+        Some((MAX_SCORE * clock * throttling * heat * noise).round() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fan::FanMode;
+
+    fn reading(temp: u8, duty: u8) -> FanInfo {
+        FanInfo {
+            cpu_fan_rpm: 0,
+            gpu_fan_rpm: 0,
+            cpu_fan_percent: duty,
+            gpu_fan_percent: duty,
+            cpu_temp: temp,
+            gpu_temp: temp,
+            fan_mode: FanMode::Auto,
+            cooler_boost: false,
+            fans: Vec::new(),
+            gpu: None,
+        }
+    }
+
+    fn session(samples: &[(u8, u8, u32, u64)]) -> SessionStats {
+        let mut stats = SessionStats { last_throttle_events: Some(0), ..SessionStats::default() };
+        for &(temp, duty, clock, events) in samples {
+            stats.record_sample(&reading(temp, duty), Some((clock, 4000)), Some(events));
+        }
+        stats
+    }
+
+    #[test]
+    fn throttling_heat_and_noise_lower_the_score() {
+        let cool = session(&[(70, 40, 4000, 0), (72, 40, 4000, 0)]);
+        assert_eq!(cool.throttled_fraction(), Some(0.0));
+        assert_eq!(cool.score(), Some(900));
+
+        let throttled = session(&[(95, 40, 3000, 0), (98, 40, 2800, 5)]);
+        assert_eq!(throttled.throttled_fraction(), Some(0.5));
+        assert_eq!(throttled.peak_temps(), (98, 98));
+        assert!(throttled.score() < cool.score());
+
+        let loud = session(&[(70, 100, 4000, 0)]);
+        assert!(loud.score() < cool.score());
+        assert_eq!(SessionStats::default().score(), None);
+    }
+}
@@ -0,0 +1,215 @@
+//! Sensor history: the daemon appends a sample every minute and each thermal alert
+//! to `history.jsonl` in the config directory, one JSON object per line, and
+//! `history stats` aggregates over it.
+
+use crate::config::{AppConfig, ConfigError};
+use crate::fan::FanInfo;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StatsError {
+    #[error("Config error: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, StatsError>;
+
+const HISTORY_FILE: &str = "history.jsonl";
+/// How often the daemon records a sample.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(60);
+/// Degrees the hottest chip has to cool below `temp_warning` before another alert counts.
+const ALERT_RESET: u8 = 5;
+pub const DAY: i64 = 24 * 60 * 60;
+
+/// One line of the history; `at` is a Unix timestamp.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    Sample { at: i64, profile: String, cpu_temp: u8, gpu_temp: u8, cpu_fan_percent: u8, gpu_fan_percent: u8 },
+    /// The hottest chip reached the active `temp_warning`.
+    ThermalAlert { at: i64, cpu_temp: u8, gpu_temp: u8 },
+}
+
+impl Entry {
+    pub fn at(&self) -> i64 {
+        match self {
+            Entry::Sample { at, .. } | Entry::ThermalAlert { at, .. } => *at,
+        }
+    }
+}
+
+fn history_path() -> Result<PathBuf> {
+    Ok(AppConfig::config_dir()?.join(HISTORY_FILE))
+}
+
+pub fn append(entries: &[Entry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(history_path()?)?;
+    for entry in entries {
+        writeln!(file, "{}", serde_json::to_string(entry).unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+/// Entries recorded at or after `since`, oldest first. Lines that don't parse,
+/// e.g. one cut short by a crash, are skipped.
+pub fn load(since: i64) -> Result<Vec<Entry>> {
+    let content = match fs::read_to_string(history_path()?) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(content.lines().filter_map(|line| serde_json::from_str::<Entry>(line).ok()).filter(|entry| entry.at() >= since).collect())
+}
+
+/// Drops entries older than `days`, so the file doesn't grow without end.
+pub fn prune(days: u32) -> Result<()> {
+    let path = history_path()?;
+    if !path.exists() {
+        return Ok(());
+    }
+    let kept = load(chrono::Local::now().timestamp() - days as i64 * DAY)?;
+    let mut content = String::new();
+    for entry in &kept {
+        content.push_str(&serde_json::to_string(entry).unwrap_or_default());
+        content.push('\n');
+    }
+    fs::write(path, content)?;
+    Ok(())
+}
+
+/// Turns the daemon's readings into history entries.
+#[derive(Debug, Default)]
+pub struct Recorder {
+    last_sample: Option<Instant>,
+    alerting: bool,
+}
+
+impl Recorder {
+    /// A sample if `SAMPLE_INTERVAL` has passed since the last one, and an alert
+    /// when the hottest chip first reaches `temp_warning`.
+    pub fn entries(&mut self, info: &FanInfo, profile: &str, temp_warning: u8, now: Instant, at: i64) -> Vec<Entry> {
+        let mut entries = Vec::new();
+        let hottest = info.cpu_temp.max(info.gpu_temp);
+        if hottest < temp_warning.saturating_sub(ALERT_RESET) {
+            self.alerting = false;
+        } else if hottest >= temp_warning && !self.alerting {
+            self.alerting = true;
+            entries.push(Entry::ThermalAlert { at, cpu_temp: info.cpu_temp, gpu_temp: info.gpu_temp });
+        }
+        if self.last_sample.is_none_or(|last| now.saturating_duration_since(last) >= SAMPLE_INTERVAL) {
+            self.last_sample = Some(now);
+            entries.push(Entry::Sample {
+                at,
+                profile: profile.to_string(),
+                cpu_temp: info.cpu_temp,
+                gpu_temp: info.gpu_temp,
+                cpu_fan_percent: info.cpu_fan_percent,
+                gpu_fan_percent: info.gpu_fan_percent,
+            });
+        }
+        entries
+    }
+}
+
+/// Highest CPU and GPU temperatures sampled since `since`.
+pub fn max_temps(entries: &[Entry], since: i64) -> Option<(u8, u8)> {
+    entries.iter().filter(|entry| entry.at() >= since).fold(None, |max, entry| match entry {
+        Entry::Sample { cpu_temp, gpu_temp, .. } => {
+            let (cpu, gpu) = max.unwrap_or((0, 0));
+            Some((cpu.max(*cpu_temp), gpu.max(*gpu_temp)))
+        }
+        Entry::ThermalAlert { .. } => max,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ProfileDuty {
+    pub cpu_fan_percent: f32,
+    pub gpu_fan_percent: f32,
+    /// Samples taken under the profile, about one a minute.
+    pub samples: usize,
+}
+
+/// Average fan duties by the profile that was active, since `since`.
+pub fn average_duty_by_profile(entries: &[Entry], since: i64) -> BTreeMap<String, ProfileDuty> {
+    let mut sums: BTreeMap<String, (u64, u64, usize)> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.at() >= since) {
+        if let Entry::Sample { profile, cpu_fan_percent, gpu_fan_percent, .. } = entry {
+            let sum = sums.entry(profile.clone()).or_default();
+            sum.0 += *cpu_fan_percent as u64;
+            sum.1 += *gpu_fan_percent as u64;
+            sum.2 += 1;
+        }
+    }
+    sums.into_iter()
+        .map(|(profile, (cpu, gpu, samples))| {
+            let duty = ProfileDuty { cpu_fan_percent: cpu as f32 / samples as f32, gpu_fan_percent: gpu as f32 / samples as f32, samples };
+            (profile, duty)
+        })
+        .collect()
+}
+
+pub fn thermal_alerts(entries: &[Entry], since: i64) -> usize {
+    entries.iter().filter(|entry| entry.at() >= since && matches!(entry, Entry::ThermalAlert { .. })).count()
+}
+
+/// What `history stats` shows: the last day's peaks, and duties and alerts over the last week.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Stats {
+    pub max_cpu_temp_24h: Option<u8>,
+    pub max_gpu_temp_24h: Option<u8>,
+    pub duty_by_profile_7d: BTreeMap<String, ProfileDuty>,
+    pub thermal_alerts_7d: usize,
+}
+
+pub fn stats(entries: &[Entry], now: i64) -> Stats {
+    let max = max_temps(entries, now - DAY);
+    Stats {
+        max_cpu_temp_24h: max.map(|(cpu, _)| cpu),
+        max_gpu_temp_24h: max.map(|(_, gpu)| gpu),
+        duty_by_profile_7d: average_duty_by_profile(entries, now - 7 * DAY),
+        thermal_alerts_7d: thermal_alerts(entries, now - 7 * DAY),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(at: i64, profile: &str, cpu_temp: u8, cpu_fan_percent: u8) -> Entry {
+        Entry::Sample { at, profile: profile.to_string(), cpu_temp, gpu_temp: 50, cpu_fan_percent, gpu_fan_percent: 30 }
+    }
+
+    #[test]
+    fn stats_cover_their_own_windows() {
+        let now = 100 * DAY;
+        let entries = vec![
+            sample(now - 3 * DAY, "Silent", 95, 20),
+            Entry::ThermalAlert { at: now - 3 * DAY, cpu_temp: 95, gpu_temp: 50 },
+            Entry::ThermalAlert { at: now - 8 * DAY, cpu_temp: 96, gpu_temp: 50 },
+            sample(now - 3600, "Balanced", 70, 40),
+            sample(now - 60, "Balanced", 72, 50),
+            sample(now - 60, "Silent", 60, 30),
+        ];
+        let stats = stats(&entries, now);
+        assert_eq!((stats.max_cpu_temp_24h, stats.max_gpu_temp_24h), (Some(72), Some(50)));
+        assert_eq!(stats.thermal_alerts_7d, 1);
+        assert_eq!(stats.duty_by_profile_7d["Balanced"], ProfileDuty { cpu_fan_percent: 45.0, gpu_fan_percent: 30.0, samples: 2 });
+        assert_eq!(stats.duty_by_profile_7d["Silent"].cpu_fan_percent, 25.0);
+        assert_eq!(max_temps(&entries, now), None);
+
+        let line = serde_json::to_string(&entries[1]).unwrap();
+        assert_eq!(serde_json::from_str::<Entry>(&line).unwrap(), entries[1]);
+    }
+}
@@ -0,0 +1,147 @@
+use crate::ec::failures::{self, WriteFailure};
+use crate::ec::quirks::{self, ModelInfo};
+use crate::ec::{EcError, EmbeddedController, Support};
+use crate::features::FeatureState;
+use crate::overrides::{Override, Overrides};
+use crate::fan::failure::{self as fan_failure, StalledFan};
+use crate::fan::{FanController, FanError, FanInfo};
+use crate::scenario::{ScenarioError, ScenarioInfo, ScenarioManager};
+use serde::Serialize;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum StatusError {
+    #[error("EC error: {0}")]
+    EcError(#[from] EcError),
+    #[error("Fan error: {0}")]
+    FanError(#[from] FanError),
+    #[error("Scenario error: {0}")]
+    ScenarioError(#[from] ScenarioError),
+    #[error("Unknown placeholder {{{0}}}. Available: {1}")]
+    UnknownPlaceholder(String, String),
+    #[error("Unterminated placeholder in template: {0}")]
+    Unterminated(String),
+}
+
+pub type Result<T> = std::result::Result<T, StatusError>;
+
+/// Everything `status` reports, gathered once so it can be rendered in different formats.
+#[derive(Debug, Serialize)]
+pub struct StatusSnapshot {
+    pub model: ModelInfo,
+    /// The EC backend the readings came through.
+    pub backend: String,
+    /// Controls that work through the active EC backend.
+    pub support: Support,
+    pub fan: FanInfo,
+    pub scenario: ScenarioInfo,
+    pub features: FeatureState,
+    pub active_profile: String,
+    /// EC writes that failed or did not stick in the last hour, from any process.
+    pub ec_write_failures: Vec<WriteFailure>,
+    /// Fans the daemon found reading 0 RPM while hot.
+    pub stalled_fans: Vec<StalledFan>,
+    /// Temporary overrides in force over the active profile.
+    pub overrides: Vec<Override>,
+}
+
+fn on_off(value: bool) -> String {
+    if value { "ON".to_string() } else { "OFF".to_string() }
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_else(|| "n/a".to_string())
+}
+
+impl StatusSnapshot {
+    pub fn collect(active_profile: &str) -> Result<Self> {
+        Self::collect_from(&mut FanController::new(EmbeddedController::new()?), active_profile)
+    }
+
+    /// Like `collect`, reading through an EC handle the caller already has open.
+    pub fn collect_from(fan_controller: &mut FanController, active_profile: &str) -> Result<Self> {
+        let fan = fan_controller.get_fan_info()?;
+        let scenario = ScenarioManager::new(fan_controller).get_current_info()?;
+
+        Ok(Self {
+            model: quirks::model_info(),
+            backend: fan_controller.ec_mut().backend_name().to_string(),
+            support: fan_controller.ec_mut().support(),
+            fan,
+            scenario,
+            features: FeatureState::read(),
+            active_profile: active_profile.to_string(),
+            ec_write_failures: failures::recent(),
+            stalled_fans: fan_failure::recorded(),
+            overrides: Overrides::load().active().to_vec(),
+        })
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("model", optional(self.model.product.as_deref())),
+            ("support", format!("{:?}", self.model.support).to_lowercase()),
+            ("backend", self.backend.clone()),
+            ("cpu_temp", self.fan.cpu_temp.to_string()),
+            ("gpu_temp", self.fan.gpu_temp.to_string()),
+            ("cpu_fan_rpm", self.fan.cpu_fan_rpm.to_string()),
+            ("gpu_fan_rpm", self.fan.gpu_fan_rpm.to_string()),
+            ("cpu_fan_percent", self.fan.cpu_fan_percent.to_string()),
+            ("gpu_fan_percent", self.fan.gpu_fan_percent.to_string()),
+            ("system_fan_rpm", optional(self.fan.fans.get(2).map(|fan| fan.rpm))),
+            ("system_fan_percent", optional(self.fan.fans.get(2).map(|fan| fan.percent))),
+            ("fan_mode", format!("{:?}", self.fan.fan_mode)),
+            ("cooler_boost", on_off(self.fan.cooler_boost)),
+            ("scenario", self.scenario.current_scenario.to_string()),
+            ("shift_mode", self.scenario.shift_mode.to_string()),
+            ("super_battery", on_off(self.scenario.super_battery)),
+            ("webcam", optional(self.features.webcam.map(on_off))),
+            ("charge_threshold", optional(self.features.charge_threshold)),
+            ("kbd_backlight", optional(self.features.keyboard_backlight)),
+            ("profile", self.active_profile.clone()),
+            ("ec_write_failures", self.ec_write_failures.len().to_string()),
+            ("stalled_fans", self.stalled_fans.len().to_string()),
+        ]
+    }
+
+    /// Substitutes `{field}` placeholders; `{{` and `}}` produce literal braces.
+    pub fn render_template(&self, template: &str) -> Result<String> {
+        let fields = self.fields();
+        let mut output = String::with_capacity(template.len());
+        let mut chars = template.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    output.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    output.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => name.push(ch),
+                            None => return Err(StatusError::Unterminated(template.to_string())),
+                        }
+                    }
+                    let name = name.trim();
+                    match fields.iter().find(|(key, _)| *key == name) {
+                        Some((_, value)) => output.push_str(value),
+                        None => {
+                            let available: Vec<&str> = fields.iter().map(|(key, _)| *key).collect();
+                            return Err(StatusError::UnknownPlaceholder(name.to_string(), available.join(", ")));
+                        }
+                    }
+                }
+                _ => output.push(c),
+            }
+        }
+
+        Ok(output)
+    }
+}
@@ -0,0 +1,274 @@
+use crate::scenario::DeferredControl;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// A power-management tool whose settings may fight ours.
+#[derive(Debug, Clone, Serialize)]
+pub struct Conflict {
+    pub name: &'static str,
+    /// What the tool manages that overlaps with us.
+    pub manages: &'static str,
+    pub affects: &'static [DeferredControl],
+    /// How to resolve the overlap on the tool's side.
+    pub advice: &'static str,
+}
+
+struct KnownTool {
+    name: &'static str,
+    processes: &'static [&'static str],
+    marker_paths: &'static [&'static str],
+    manages: &'static str,
+    affects: &'static [DeferredControl],
+    advice: &'static str,
+}
+
+const KNOWN_TOOLS: &[KnownTool] = &[
+    KnownTool {
+        name: "TLP",
+        processes: &[],
+        // TLP is not a daemon; it leaves its state directory behind once it has run.
+        marker_paths: &["/run/tlp"],
+        manages: "platform profile, CPU boost and battery charge thresholds",
+        affects: &[DeferredControl::ShiftMode, DeferredControl::SuperBattery],
+        advice: "Comment out PLATFORM_PROFILE_ON_AC/PLATFORM_PROFILE_ON_BAT and CPU_BOOST_ON_AC/CPU_BOOST_ON_BAT in /etc/tlp.conf",
+    },
+    KnownTool {
+        name: "thermald",
+        processes: &["thermald"],
+        marker_paths: &[],
+        manages: "CPU power limits and thermal throttling",
+        affects: &[DeferredControl::ShiftMode],
+        advice: "Stop it with 'systemctl disable --now thermald' if Turbo/Sport shift modes are throttled unexpectedly",
+    },
+    KnownTool {
+        name: "power-profiles-daemon",
+        processes: &["power-profiles-daemon"],
+        marker_paths: &[],
+        manages: "platform profile and CPU energy preference",
+        affects: &[DeferredControl::ShiftMode],
+        advice: "Avoid switching power profiles from the desktop, or run 'systemctl mask --now power-profiles-daemon'",
+    },
+    KnownTool {
+        name: "auto-cpufreq",
+        processes: &["auto-cpufreq"],
+        marker_paths: &[],
+        manages: "CPU governor and turbo boost",
+        affects: &[DeferredControl::ShiftMode],
+        advice: "Set 'turbo = auto' in /etc/auto-cpufreq.conf or stop it with 'systemctl disable --now auto-cpufreq'",
+    },
+];
+
+/// Command lines of all running processes, as whitespace-joined strings.
+pub fn running_processes() -> Vec<String> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().chars().all(|c| c.is_ascii_digit()))
+        .filter_map(|entry| fs::read(entry.path().join("cmdline")).ok())
+        .filter(|cmdline| !cmdline.is_empty())
+        .map(|cmdline| String::from_utf8_lossy(&cmdline).replace('\0', " "))
+        .collect()
+}
+
+pub fn process_running(processes: &[String], name: &str) -> bool {
+    processes.iter().any(|cmdline| {
+        cmdline
+            .split_whitespace()
+            .take(2)
+            .any(|arg| Path::new(arg).file_name().is_some_and(|f| f.to_string_lossy() == name))
+    })
+}
+
+/// Detects other power-management tools that are active on this system.
+pub fn detect_conflicts() -> Vec<Conflict> {
+    let processes = running_processes();
+
+    KNOWN_TOOLS
+        .iter()
+        .filter(|tool| {
+            tool.processes.iter().any(|p| process_running(&processes, p))
+                || tool.marker_paths.iter().any(|p| Path::new(p).exists())
+        })
+        .map(|tool| Conflict {
+            name: tool.name,
+            manages: tool.manages,
+            affects: tool.affects,
+            advice: tool.advice,
+        })
+        .collect()
+}
+
+pub fn module_loaded(name: &str) -> bool {
+    Path::new("/sys/module").join(name).exists()
+}
+
+/// Whether `modprobe` can find the module for the running kernel.
+pub fn module_available(name: &str) -> bool {
+    Command::new("modprobe").args(["--dry-run", "--quiet", name]).status().is_ok_and(|status| status.success())
+}
+
+pub fn load_module(name: &str) -> std::io::Result<()> {
+    modprobe(&[name])
+}
+
+fn modprobe(args: &[&str]) -> std::io::Result<()> {
+    let output = Command::new("modprobe").args(args).output()?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(std::io::Error::other(String::from_utf8_lossy(&output.stderr).trim().to_string()))
+}
+
+const EC_SYS_WRITE_SUPPORT: &str = "/sys/module/ec_sys/parameters/write_support";
+/// Files that load ec_sys with write support at boot once it is made permanent.
+const EC_SYS_MODULES_LOAD: &str = "/etc/modules-load.d/msi-center-ec_sys.conf";
+const EC_SYS_MODPROBE_OPTIONS: &str = "/etc/modprobe.d/msi-center-ec_sys.conf";
+
+/// ec_sys's `write_support` parameter, or `None` when the module isn't loaded.
+pub fn ec_sys_write_support() -> Option<bool> {
+    fs::read_to_string(EC_SYS_WRITE_SUPPORT).ok().map(|value| value.trim() == "Y")
+}
+
+/// Whether ec_sys is loaded without `write_support`, so its debugfs file can only
+/// be read and EC writes have to go through another backend.
+pub fn ec_sys_read_only() -> bool {
+    ec_sys_write_support() == Some(false)
+}
+
+/// Reloads ec_sys with `write_support=1`. With `persist` it is also loaded that
+/// way at every boot. The parameter only takes effect when the module is loaded,
+/// so a loaded copy is removed first.
+pub fn enable_ec_sys_writes(persist: bool) -> std::io::Result<()> {
+    if module_loaded("ec_sys") {
+        modprobe(&["--remove", "ec_sys"])?;
+    }
+    modprobe(&["ec_sys", "write_support=1"])?;
+    if persist {
+        fs::write(EC_SYS_MODULES_LOAD, "ec_sys\n")?;
+        fs::write(EC_SYS_MODPROBE_OPTIONS, "options ec_sys write_support=1\n")?;
+    }
+    Ok(())
+}
+
+const LOCKDOWN_PATH: &str = "/sys/kernel/security/lockdown";
+/// The `SecureBoot` EFI variable; its fifth byte (after the attributes) is 1 when enabled.
+const SECURE_BOOT_VAR: &str = "/sys/firmware/efi/efivars/SecureBoot-8be4df61-93ca-11d2-aa0d-00e098032b8c";
+
+/// The kernel lockdown mode, e.g. `integrity`, or `None` when the kernel isn't
+/// locked down. Either mode refuses `/dev/port`, `ioperm` and debugfs, which
+/// leaves the msi-ec driver as the only way to the EC.
+pub fn kernel_lockdown() -> Option<String> {
+    let content = fs::read_to_string(LOCKDOWN_PATH).ok()?;
+    parse_lockdown(&content)
+}
+
+/// The bracketed mode in `none [integrity] confidentiality`.
+fn parse_lockdown(content: &str) -> Option<String> {
+    let mode = content.split_whitespace().find_map(|word| word.strip_prefix('[')?.strip_suffix(']'))?;
+    (mode != "none").then(|| mode.to_string())
+}
+
+/// Whether the firmware booted with Secure Boot on; `None` on non-EFI systems.
+pub fn secure_boot() -> Option<bool> {
+    fs::read(SECURE_BOOT_VAR).ok()?.get(4).map(|value| *value == 1)
+}
+
+/// What to tell the user when lockdown leaves no working EC backend: the kernel
+/// is locked down and the msi-ec driver isn't loaded.
+pub fn lockdown_warning() -> Option<String> {
+    let mode = kernel_lockdown()?;
+    if module_loaded("msi_ec") {
+        return None;
+    }
+    let cause = if secure_boot() == Some(true) { "Secure Boot is on and the kernel" } else { "The kernel" };
+    Some(format!(
+        "{} is locked down ({}), which blocks direct EC access through /dev/port and debugfs. Load the msi-ec driver ('sudo modprobe msi-ec', or 'msi-center doctor' to do it for you) to control this laptop.",
+        cause, mode
+    ))
+}
+
+const PCI_DEVICES: &str = "/sys/bus/pci/devices";
+
+/// Whether the discrete GPU of a hybrid (Optimus) machine is powered up. `None`
+/// when there is no second GPU, i.e. nothing is ever switched off.
+pub fn dgpu_active() -> Option<bool> {
+    let displays: Vec<_> = fs::read_dir(PCI_DEVICES)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| fs::read_to_string(path.join("class")).is_ok_and(|class| class.trim().starts_with("0x03")))
+        .collect();
+    if displays.len() < 2 {
+        return None;
+    }
+
+    // The integrated GPU is the one the firmware booted on.
+    let dgpu = displays
+        .iter()
+        .find(|path| fs::read_to_string(path.join("boot_vga")).map_or(true, |boot| boot.trim() != "1"))?;
+    let status = fs::read_to_string(dgpu.join("power/runtime_status")).ok()?;
+    Some(status.trim() != "suspended")
+}
+
+const CPU_DEVICES: &str = "/sys/devices/system/cpu";
+
+fn cpu_dirs() -> Vec<std::path::PathBuf> {
+    fs::read_dir(CPU_DEVICES)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.file_name()
+                        .and_then(|name| name.to_str())
+                        .and_then(|name| name.strip_prefix("cpu"))
+                        .is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// Average current clock and highest rated clock of the CPU cores in MHz, from cpufreq.
+pub fn cpu_clocks_mhz() -> Option<(u32, u32)> {
+    let cpus = cpu_dirs();
+    let current: Vec<u64> = cpus.iter().filter_map(|cpu| read_u64(&cpu.join("cpufreq/scaling_cur_freq"))).collect();
+    let max = cpus.iter().filter_map(|cpu| read_u64(&cpu.join("cpufreq/cpuinfo_max_freq"))).max()?;
+    if current.is_empty() {
+        return None;
+    }
+    let average = current.iter().sum::<u64>() / current.len() as u64;
+    Some(((average / 1000) as u32, (max / 1000) as u32))
+}
+
+/// Thermal throttling events counted by the CPU since boot (Intel `thermal_throttle`);
+/// `None` where the kernel doesn't expose them.
+pub fn throttle_events() -> Option<u64> {
+    let counts: Vec<u64> = cpu_dirs()
+        .iter()
+        .flat_map(|cpu| ["core_throttle_count", "package_throttle_count"].map(|file| cpu.join("thermal_throttle").join(file)))
+        .filter_map(|path| read_u64(&path))
+        .collect();
+    (!counts.is_empty()).then(|| counts.iter().sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lockdown_mode_is_the_bracketed_one() {
+        assert_eq!(parse_lockdown("none [integrity] confidentiality\n").as_deref(), Some("integrity"));
+        assert_eq!(parse_lockdown("[none] integrity confidentiality\n"), None);
+        assert_eq!(parse_lockdown(""), None);
+    }
+}
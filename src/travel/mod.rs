@@ -0,0 +1,177 @@
+//! Travel mode: one switch for the usual settings on the road (Super Battery with
+//! silent fans, a 60% charge limit, keyboard backlight and webcam off), which puts
+//! back what it changed when switched off again.
+
+use crate::config::{AppConfig, ConfigError};
+use crate::scenario::{ScenarioError, ScenarioManager, ScenarioSettings};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TravelError {
+    #[error("Config error: {0}")]
+    ConfigError(#[from] ConfigError),
+    #[error("Scenario error: {0}")]
+    ScenarioError(#[from] ScenarioError),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Travel mode is already on")]
+    AlreadyOn,
+    #[error("Travel mode is not on")]
+    NotOn,
+}
+
+pub type Result<T> = std::result::Result<T, TravelError>;
+
+const STATE_FILE: &str = "travel.json";
+/// Charge limit while traveling: a battery kept at 60% on the charger ages more slowly.
+pub const CHARGE_THRESHOLD: u8 = 60;
+
+/// The scenario part of the preset, applied like a profile.
+pub fn settings() -> ScenarioSettings {
+    ScenarioSettings::super_battery()
+}
+
+/// What travel mode changed outside the scenario, to put back on `off`; `None`
+/// for controls this model doesn't have.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Saved {
+    pub webcam: Option<bool>,
+    pub charge_threshold: Option<u8>,
+    pub keyboard_backlight: Option<u8>,
+    /// Whether automation was paused before, in which case `off` leaves it paused.
+    #[serde(default)]
+    pub automation_paused: bool,
+}
+
+/// Kept in the config directory; it exists for as long as travel mode is on.
+fn state_path() -> Result<PathBuf> {
+    Ok(AppConfig::config_dir()?.join(STATE_FILE))
+}
+
+pub fn is_on() -> bool {
+    state_path().is_ok_and(|path| path.exists())
+}
+
+/// Turns travel mode on and pauses automation, so it doesn't switch profiles
+/// underneath. Returns the controls this model lacks, which were skipped.
+pub fn enable(manager: &mut ScenarioManager, config: &mut AppConfig) -> Result<Vec<&'static str>> {
+    if is_on() {
+        return Err(TravelError::AlreadyOn);
+    }
+    let mut skipped = Vec::new();
+    let mut saved = read_state(manager, &mut skipped)?;
+    saved.automation_paused = config.automation.is_paused();
+    // Saved first, so a failure halfway can still be undone with `off`.
+    fs::write(state_path()?, serde_json::to_string_pretty(&saved)?)?;
+
+    apply(manager, &saved)?;
+    config.automation.pause(None);
+    config.save()?;
+    Ok(skipped)
+}
+
+/// Puts back the charge limit, keyboard backlight, webcam and automation as they
+/// were. The caller applies the active profile again for the rest.
+pub fn disable(manager: &mut ScenarioManager, config: &mut AppConfig) -> Result<()> {
+    let path = state_path()?;
+    if !path.exists() {
+        return Err(TravelError::NotOn);
+    }
+    let saved: Saved = serde_json::from_str(&fs::read_to_string(&path)?)?;
+
+    restore(manager, &saved)?;
+    if !saved.automation_paused {
+        config.automation.resume();
+        config.save()?;
+    }
+    fs::remove_file(path)?;
+    Ok(())
+}
+
+/// Applies the preset to the controls `saved` found on this model.
+fn apply(manager: &mut ScenarioManager, saved: &Saved) -> Result<()> {
+    manager.apply_settings(&settings())?;
+    if saved.charge_threshold.is_some() {
+        manager.set_charge_threshold(CHARGE_THRESHOLD)?;
+    }
+    if saved.keyboard_backlight.is_some() {
+        manager.set_keyboard_backlight(0)?;
+    }
+    if saved.webcam.is_some() {
+        manager.set_webcam(false)?;
+    }
+    Ok(())
+}
+
+fn restore(manager: &mut ScenarioManager, saved: &Saved) -> Result<()> {
+    if let Some(percent) = saved.charge_threshold {
+        manager.set_charge_threshold(percent)?;
+    }
+    if let Some(level) = saved.keyboard_backlight {
+        manager.set_keyboard_backlight(level)?;
+    }
+    if let Some(on) = saved.webcam {
+        manager.set_webcam(on)?;
+    }
+    Ok(())
+}
+
+fn read_state(manager: &mut ScenarioManager, skipped: &mut Vec<&'static str>) -> Result<Saved> {
+    Ok(Saved {
+        charge_threshold: optional(manager.charge_threshold(), skipped)?,
+        keyboard_backlight: optional(manager.keyboard_backlight(), skipped)?,
+        webcam: optional(manager.webcam(), skipped)?,
+        automation_paused: false,
+    })
+}
+
+/// `None` for a control the model doesn't have, noted in `skipped`.
+fn optional<T>(result: crate::scenario::Result<T>, skipped: &mut Vec<&'static str>) -> crate::scenario::Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ScenarioError::Unsupported(what)) => {
+            skipped.push(what);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec::quirks::RegisterMap;
+    use crate::ec::trace::mock_ec_with;
+    use crate::fan::FanController;
+
+    #[test]
+    fn travel_mode_puts_back_what_it_changed() {
+        let registers = RegisterMap::default();
+        let mut memory = vec![Some(0u8); 256];
+        memory[registers.battery_charge as usize] = Some(0x80 | 80);
+        memory[registers.kbd_backlight as usize] = Some(0x80 | 2);
+        memory[registers.webcam as usize] = Some(0x0A);
+        let (mock, ec) = mock_ec_with(memory);
+        let mut fans = FanController::new(ec);
+        let mut manager = ScenarioManager::new(&mut fans);
+
+        let mut skipped = Vec::new();
+        let saved = read_state(&mut manager, &mut skipped).unwrap();
+        assert_eq!((saved.charge_threshold, saved.keyboard_backlight, saved.webcam), (Some(80), Some(2), Some(true)));
+        assert!(skipped.is_empty());
+
+        apply(&mut manager, &saved).unwrap();
+        assert_eq!(manager.charge_threshold().unwrap(), CHARGE_THRESHOLD);
+        assert_eq!(manager.keyboard_backlight().unwrap(), 0);
+        assert!(!manager.webcam().unwrap());
+        assert_eq!(mock.lock().unwrap().read(registers.webcam), Some(0x08));
+
+        restore(&mut manager, &saved).unwrap();
+        assert_eq!(read_state(&mut manager, &mut skipped).unwrap(), saved);
+    }
+}
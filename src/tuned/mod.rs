@@ -0,0 +1,86 @@
+use crate::scenario::UserScenario;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::process::Command;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TunedError {
+    #[error("Failed to run tuned-adm: {0}")]
+    CommandFailed(#[from] std::io::Error),
+    #[error("tuned-adm {0} failed: {1}")]
+    AdmError(String, String),
+}
+
+pub type Result<T> = std::result::Result<T, TunedError>;
+
+/// Maps our scenarios to tuned profiles for systems where tuned owns CPU/disk tunables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TunedConfig {
+    /// Activate the mapped tuned profile whenever a scenario or profile is applied.
+    pub enabled: bool,
+    pub profiles: BTreeMap<UserScenario, String>,
+}
+
+impl Default for TunedConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            profiles: BTreeMap::from([
+                (UserScenario::Silent, "powersave".to_string()),
+                (UserScenario::Balanced, "balanced".to_string()),
+                (UserScenario::HighPerformance, "throughput-performance".to_string()),
+                (UserScenario::Turbo, "latency-performance".to_string()),
+                (UserScenario::SuperBattery, "powersave".to_string()),
+            ]),
+        }
+    }
+}
+
+fn tuned_adm(args: &[&str]) -> Result<String> {
+    let output = Command::new("tuned-adm").args(args).output()?;
+    if !output.status.success() {
+        return Err(TunedError::AdmError(
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+pub fn is_available() -> bool {
+    Command::new("tuned-adm").arg("--version").output().is_ok()
+}
+
+pub fn active_profile() -> Result<String> {
+    let output = tuned_adm(&["active"])?;
+    Ok(output
+        .lines()
+        .find_map(|line| line.strip_prefix("Current active profile:"))
+        .unwrap_or("none")
+        .trim()
+        .to_string())
+}
+
+pub fn set_profile(profile: &str) -> Result<()> {
+    tuned_adm(&["profile", profile])?;
+    Ok(())
+}
+
+impl TunedConfig {
+    /// Activates the tuned profile mapped to `scenario` if the bridge is enabled.
+    /// Returns the profile that was activated.
+    pub fn sync(&self, scenario: UserScenario) -> Result<Option<String>> {
+        if !self.enabled {
+            return Ok(None);
+        }
+        let Some(profile) = self.profiles.get(&scenario) else {
+            log::debug!("No tuned profile mapped for scenario {}", scenario);
+            return Ok(None);
+        };
+
+        set_profile(profile)?;
+        log::info!("Activated tuned profile {} for scenario {}", profile, scenario);
+        Ok(Some(profile.clone()))
+    }
+}